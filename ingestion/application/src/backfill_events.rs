@@ -0,0 +1,67 @@
+use chrono::NaiveDate;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::job_state::JobStatus;
+
+/// Structured progress emitted while a backfill runs, in the order the
+/// service reaches each step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackfillEvent {
+    JobInitialized {
+        job_key: String,
+        resume_from: NaiveDate,
+    },
+    /// Number of days scheduled for processing after gap detection.
+    GapsDetected(usize),
+    DayStarted(NaiveDate),
+    DayCompleted {
+        date: NaiveDate,
+        ticks: usize,
+    },
+    DayFailed {
+        date: NaiveDate,
+        error: String,
+    },
+    /// The gateway had no data for the day and the no-data policy did not
+    /// fail it.
+    DayNoData(NaiveDate),
+    /// A re-fetched day's ticks differ from what an earlier run stored.
+    SourceDataChanged {
+        date: NaiveDate,
+    },
+    Finalized {
+        status: JobStatus,
+        days_processed: usize,
+        total_ticks: usize,
+    },
+}
+
+/// Running count sent after each day a backfill finishes with, whether it
+/// stored ticks, had no data or failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfillProgress {
+    pub date: NaiveDate,
+    /// Ticks stored for the day; 0 unless it completed.
+    pub ticks: usize,
+    pub days_done: usize,
+    /// Days the run set out to process.
+    pub days_total: usize,
+}
+
+/// Optional event sender. Events are built lazily, so a run without a
+/// receiver does no extra work.
+#[derive(Clone, Default)]
+pub(crate) struct EventSink(Option<UnboundedSender<BackfillEvent>>);
+
+impl EventSink {
+    pub(crate) fn new(sender: Option<UnboundedSender<BackfillEvent>>) -> Self {
+        Self(sender)
+    }
+
+    pub(crate) fn emit(&self, event: impl FnOnce() -> BackfillEvent) {
+        if let Some(sender) = &self.0 {
+            // A dropped receiver just means nobody is listening any more.
+            let _ = sender.send(event());
+        }
+    }
+}
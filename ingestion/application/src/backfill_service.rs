@@ -1,16 +1,237 @@
 use async_trait::async_trait;
-use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
+use futures::StreamExt;
+use serde::Serialize;
 use shaku::{Component, Interface};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::sync::Arc;
+use std::time::{Duration as StdDuration, Instant};
+use tokio::sync::mpsc::{Sender, UnboundedSender};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use crate::historical_data::{GapDetector, HistoricalDataGateway};
-use crate::job_state::{JobInstanceId, JobState, JobStateRepository, JobStatus};
-use crate::ports::TickRepository;
-use ingestion_domain::DateRange;
+use crate::backfill_events::{BackfillEvent, BackfillProgress, EventSink};
+use crate::historical_data::{
+    ContractResolver, FetchedTicks, GapDetector, HistoricalDataError, HistoricalDataGateway,
+};
+use crate::job_state::{JobInstanceId, JobKey, JobState, JobStateRepository, JobStatus};
+use crate::ports::{DeadLetterSink, SaveStreamError, TickRepository};
+use ingestion_domain::{
+    coalesce_gaps, filter_min_gap_days, ContractSpan, DateRange, Tick, TicksChecksum,
+    TradingCalendar,
+};
 
 const HEARTBEAT_TIMEOUT: Duration = Duration::seconds(300);
+/// Cap on `min_heartbeat_interval`: a third of [`HEARTBEAT_TIMEOUT`], so a
+/// live job always heartbeats well before another instance may take over.
+const MAX_HEARTBEAT_INTERVAL: StdDuration = StdDuration::from_secs(100);
+
+#[derive(Debug, Clone)]
+pub struct BackfillConfig {
+    /// How many times a rate-limited fetch is retried before the day is failed.
+    pub max_rate_limit_retries: u32,
+    /// Backoff used when the gateway gives no retry-after hint; doubles per attempt.
+    pub rate_limit_backoff: StdDuration,
+    /// Upper bound applied to gateway-provided retry-after hints.
+    pub max_retry_after: StdDuration,
+    /// Gaps shorter than this many days are ignored. 1 keeps every gap.
+    pub min_gap_days: u32,
+    /// Days fetched at the same time. Whatever order fetches finish in,
+    /// days are handed to the writers in date order.
+    pub max_concurrent_days: usize,
+    /// Fetched days written to the repository at the same time, independent
+    /// of `max_concurrent_days`. Fetched days wait in a queue of
+    /// `max_concurrent_days` slots for a free writer. Writes may finish out
+    /// of order; the cursor only moves over the leading run of written days.
+    pub max_concurrent_writes: usize,
+    /// Gaps separated by fewer than this many present days are fetched as
+    /// one range, refetching the days between them. 0 never merges.
+    pub coalesce_gap_days: u32,
+    /// Gateway requests assumed per fetched day when estimating duration.
+    pub requests_per_day: u32,
+    /// Rate-limit windows the gateway is subject to, used only for estimates.
+    pub rate_budgets: Vec<RateBudget>,
+    /// Re-run gap detection over the written days after a run and fail any
+    /// day that is still missing. Off by default: it rescans the range.
+    pub verify_after_run: bool,
+    /// Most recent days whose tick checksums are kept in job state.
+    pub max_day_checksums: usize,
+    /// What to do when the gateway reports no data for a day.
+    pub no_data_policy: NoDataPolicy,
+    /// Heartbeats sent within this long of the previous one are skipped.
+    /// Capped at 100s so takeover (after 300s of silence) stays well clear.
+    pub min_heartbeat_interval: StdDuration,
+    /// How often a run started with [`BackfillOptions::wait_for_lock`]
+    /// re-checks a lock held by another instance.
+    pub lock_poll_interval: StdDuration,
+    /// UTC hours a complete day has ticks in. A fetched day covering fewer
+    /// is reported as [`DayOutcome::Partial`]. `None` treats any non-empty
+    /// day as full.
+    pub expected_hours_per_day: Option<u32>,
+    /// What to do with records the gateway returns that fail validation.
+    pub invalid_tick_policy: InvalidTickPolicy,
+    /// How many times an empty result for a trading day is refetched before
+    /// the day is accepted as empty. Unlike
+    /// [`HistoricalDataError::DataNotAvailable`], an empty day on the
+    /// calendar is more likely a transient backend issue. 0 never retries.
+    pub empty_day_retries: u32,
+    /// Wait before each empty-day refetch.
+    pub empty_day_retry_delay: StdDuration,
+    /// Decides which days [`Self::empty_day_retries`] applies to.
+    pub calendar: TradingCalendar,
+    /// Stream each day from the gateway into the repository this many ticks
+    /// at a time, with [`HistoricalDataGateway::fetch_historical_stream`]
+    /// and [`TickRepository::save_stream`], instead of holding the whole day
+    /// in memory. The writer then does the fetching, and a day that fails
+    /// part way keeps the chunks already saved rather than going to the
+    /// dead-letter sink. `force_overwrite` runs replace whole days and
+    /// ignore it. `None` buffers each day.
+    pub stream_chunk_ticks: Option<usize>,
+}
+
+/// Handling of [`HistoricalDataError::DataNotAvailable`] for a day in range,
+/// e.g. an exchange holiday missing from the calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoDataPolicy {
+    /// Record the day in `failed_days` and fail the job.
+    Fail,
+    /// List the day in `days_no_data`. The cursor stays before the day, so a
+    /// resumed run fetches it again, as does gap detection in a new run.
+    #[default]
+    Skip,
+    /// Like `Skip`, but also store an empty-day marker so gap detection
+    /// stops reporting the day.
+    FillEmpty,
+}
+
+/// Handling of records in a fetched day that fail [`Tick`] validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InvalidTickPolicy {
+    /// Store the valid ticks and count the rest in
+    /// [`BackfillReport::invalid_ticks`].
+    #[default]
+    Skip,
+    /// Fail the day without storing any of it.
+    Fail,
+}
+
+impl Default for BackfillConfig {
+    fn default() -> Self {
+        Self {
+            max_rate_limit_retries: 3,
+            rate_limit_backoff: StdDuration::from_secs(1),
+            // Long enough to honour IB's ten-minute pacing window.
+            max_retry_after: StdDuration::from_secs(10 * 60),
+            min_gap_days: 1,
+            coalesce_gap_days: 0,
+            max_concurrent_days: 1,
+            max_concurrent_writes: 1,
+            requests_per_day: 1,
+            // IB's per-contract and 10-minute historical data limits.
+            rate_budgets: vec![
+                RateBudget::new(6, StdDuration::from_secs(2)),
+                RateBudget::new(60, StdDuration::from_secs(600)),
+            ],
+            verify_after_run: false,
+            max_day_checksums: 366,
+            no_data_policy: NoDataPolicy::default(),
+            min_heartbeat_interval: StdDuration::from_secs(30),
+            lock_poll_interval: StdDuration::from_secs(5),
+            expected_hours_per_day: None,
+            invalid_tick_policy: InvalidTickPolicy::default(),
+            empty_day_retries: 0,
+            empty_day_retry_delay: StdDuration::from_secs(5),
+            calendar: TradingCalendar::default(),
+            stream_chunk_ticks: None,
+        }
+    }
+}
+
+/// At most `max_requests` requests per rolling `window`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateBudget {
+    pub max_requests: u32,
+    pub window: StdDuration,
+}
+
+impl RateBudget {
+    pub fn new(max_requests: u32, window: StdDuration) -> Self {
+        Self {
+            max_requests,
+            window,
+        }
+    }
+
+    /// Time to issue `requests` back to back: the first window's worth goes
+    /// out immediately, each further batch waits a full window.
+    fn time_for(&self, requests: u64) -> StdDuration {
+        let batches = requests.saturating_sub(1) / u64::from(self.max_requests.max(1));
+        self.window
+            .saturating_mul(u32::try_from(batches).unwrap_or(u32::MAX))
+    }
+}
+
+impl BackfillConfig {
+    /// Rough wall-clock time to fetch `days` days, bounded by the slowest
+    /// rate budget. Ignores fetch latency and retries.
+    pub fn estimate_duration(&self, days: usize) -> StdDuration {
+        let requests = days as u64 * u64::from(self.requests_per_day);
+        self.rate_budgets
+            .iter()
+            .map(|budget| budget.time_for(requests))
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn day_outcome(&self, result: &DayResult) -> DayOutcome {
+        match self.expected_hours_per_day {
+            _ if result.tick_count == 0 => DayOutcome::Empty,
+            Some(expected) if result.hours_present < expected => DayOutcome::Partial {
+                present: result.hours_present,
+                expected,
+            },
+            _ => DayOutcome::Full,
+        }
+    }
+
+    fn rate_limit_delay(&self, attempt: u32, retry_after: Option<StdDuration>) -> StdDuration {
+        match retry_after {
+            Some(hint) => hint.min(self.max_retry_after),
+            None => self
+                .rate_limit_backoff
+                .saturating_mul(1u32 << attempt.min(16)),
+        }
+    }
+}
+
+/// Per-run knobs for [`BackfillService::backfill_range_with_options`].
+#[derive(Clone, Default)]
+pub struct BackfillOptions {
+    /// Receives a [`BackfillEvent`] for each step of the run.
+    pub events: Option<UnboundedSender<BackfillEvent>>,
+    /// Refetch every day in the range, even ones gap detection considers
+    /// present, and replace their stored data. For vendor revisions. Days
+    /// from today (UTC) on belong to live ingestion and are refused.
+    pub force_overwrite: bool,
+    /// Stops the run when cancelled: days waiting on the gateway (e.g. on
+    /// a rate limiter) are abandoned, days being written finish, and the
+    /// partial report comes back with [`BackfillReport::cancelled`] set.
+    /// The job is marked cancelled and the next run resumes from its
+    /// cursor.
+    pub cancel: Option<CancellationToken>,
+    /// When another instance holds the job's lock, keep polling for up to
+    /// this long for it to free up (heartbeat goes stale or the job stops
+    /// running) instead of failing with [`BackfillError::JobAlreadyRunning`].
+    pub wait_for_lock: Option<StdDuration>,
+    /// Refuse with [`BackfillError::TooManyDays`], before fetching anything,
+    /// when the run would fetch more days than this. Guards against a
+    /// mistyped range.
+    pub max_days: Option<usize>,
+    /// Receives a [`BackfillProgress`] as each day finishes. A full channel
+    /// holds the run up until the receiver catches up.
+    pub progress: Option<Sender<BackfillProgress>>,
+}
 
 #[async_trait]
 pub trait BackfillService: Interface {
@@ -19,6 +240,50 @@ pub trait BackfillService: Interface {
         symbol: &str,
         range: DateRange,
     ) -> Result<BackfillReport, BackfillError>;
+
+    async fn backfill_range_with_options(
+        &self,
+        symbol: &str,
+        range: DateRange,
+        options: BackfillOptions,
+    ) -> Result<BackfillReport, BackfillError>;
+
+    /// Like `backfill_range`, sending a [`BackfillProgress`] to `progress`
+    /// after each day.
+    async fn backfill_range_with_progress(
+        &self,
+        symbol: &str,
+        range: DateRange,
+        progress: Sender<BackfillProgress>,
+    ) -> Result<BackfillReport, BackfillError> {
+        let options = BackfillOptions {
+            progress: Some(progress),
+            ..BackfillOptions::default()
+        };
+        self.backfill_range_with_options(symbol, range, options)
+            .await
+    }
+
+    /// Refetches exactly the given dates, skipping gap detection. Dates must
+    /// fall within the gateway's history window. The job is kept under
+    /// [`JobKey::dates_key`] and always starts from a fresh cursor.
+    async fn backfill_dates(
+        &self,
+        symbol: &str,
+        dates: Vec<NaiveDate>,
+    ) -> Result<BackfillReport, BackfillError>;
+
+    /// Works out which days `backfill_range` would fetch without fetching
+    /// anything or writing job state.
+    async fn plan(&self, symbol: &str, range: DateRange) -> Result<BackfillPlan, BackfillError>;
+
+    async fn estimate_duration(
+        &self,
+        symbol: &str,
+        range: DateRange,
+    ) -> Result<StdDuration, BackfillError> {
+        Ok(self.plan(symbol, range).await?.estimated_duration)
+    }
 }
 
 #[derive(Component)]
@@ -35,6 +300,20 @@ pub struct BackfillServiceImpl {
 
     #[shaku(inject)]
     job_state_repo: Arc<dyn JobStateRepository>,
+
+    #[shaku(default)]
+    config: BackfillConfig,
+
+    /// Receives a day's ticks when writing them fails. Without one the
+    /// ticks are dropped along with the failed day.
+    #[shaku(default)]
+    dead_letters: Option<Arc<dyn DeadLetterSink>>,
+
+    /// Picks the underlying contract to fetch for each day, e.g. across
+    /// futures rolls. Ticks are still stored under the requested symbol.
+    /// Without one every day is fetched as the requested symbol.
+    #[shaku(default)]
+    contract_resolver: Option<Arc<dyn ContractResolver>>,
 }
 
 impl BackfillServiceImpl {
@@ -49,34 +328,372 @@ impl BackfillServiceImpl {
             gap_detector,
             repository,
             job_state_repo,
+            config: BackfillConfig::default(),
+            dead_letters: None,
+            contract_resolver: None,
+        }
+    }
+
+    pub fn with_config(mut self, config: BackfillConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn with_dead_letter_sink(mut self, sink: Arc<dyn DeadLetterSink>) -> Self {
+        self.dead_letters = Some(sink);
+        self
+    }
+
+    pub fn with_contract_resolver(mut self, resolver: Arc<dyn ContractResolver>) -> Self {
+        self.contract_resolver = Some(resolver);
+        self
+    }
+
+    /// `range` split by the contract to fetch; empty without a resolver.
+    fn contract_spans(&self, symbol: &str, range: &DateRange) -> Vec<ContractSpan> {
+        let Some(resolver) = &self.contract_resolver else {
+            return Vec::new();
+        };
+        let spans = resolver.resolve(symbol, range);
+        for span in spans.iter().filter(|span| span.contract != symbol) {
+            info!(
+                "Fetching {} from {} to {} as {}",
+                symbol,
+                span.range.start(),
+                span.range.end(),
+                span.contract
+            );
         }
+        spans
     }
 
-    async fn backfill_single_day(
+    async fn fetch_with_retry(
         &self,
         symbol: &str,
+        contract: &str,
         date: NaiveDate,
-    ) -> Result<DayResult, BackfillError> {
-        let ticks = self
-            .gateway
-            .fetch_historical_ticks(symbol, date)
+        cancel: &CancellationToken,
+    ) -> Result<FetchedTicks, HistoricalDataError> {
+        let mut attempt = 0;
+        loop {
+            let fetched = if contract == symbol {
+                self.gateway
+                    .fetch_checked_ticks_cancellable(symbol, date, cancel)
+                    .await
+            } else {
+                self.gateway
+                    .fetch_contract_ticks_cancellable(contract, date, cancel)
+                    .await
+            };
+            match fetched {
+                Err(HistoricalDataError::RateLimitExceeded { retry_after })
+                    if attempt < self.config.max_rate_limit_retries =>
+                {
+                    let delay = self.config.rate_limit_delay(attempt, retry_after);
+                    warn!(
+                        "Rate limited fetching {} {} (attempt {}), retrying in {:?}",
+                        contract,
+                        date,
+                        attempt + 1,
+                        delay
+                    );
+                    tokio::select! {
+                        _ = cancel.cancelled() => return Err(HistoricalDataError::Cancelled),
+                        _ = tokio::time::sleep(delay) => {}
+                    }
+                    attempt += 1;
+                }
+                other => return other,
+            }
+        }
+    }
+
+    /// [`Self::fetch_with_retry`], refetching an empty trading day up to
+    /// [`BackfillConfig::empty_day_retries`] times.
+    async fn fetch_retrying_empty(
+        &self,
+        symbol: &str,
+        contract: &str,
+        date: NaiveDate,
+        cancel: &CancellationToken,
+    ) -> Result<FetchedTicks, HistoricalDataError> {
+        let retries = if self.config.calendar.is_trading_day(date) {
+            self.config.empty_day_retries
+        } else {
+            0
+        };
+        let mut attempt = 0;
+        loop {
+            let fetched = self
+                .fetch_with_retry(symbol, contract, date, cancel)
+                .await?;
+            if !fetched.ticks.is_empty() || !fetched.invalid.is_empty() || attempt >= retries {
+                return Ok(fetched);
+            }
+            attempt += 1;
+            warn!(
+                "Empty result for trading day {} {}, refetching ({}/{})",
+                contract, date, attempt, retries
+            );
+            tokio::select! {
+                _ = cancel.cancelled() => return Err(HistoricalDataError::Cancelled),
+                _ = tokio::time::sleep(self.config.empty_day_retry_delay) => {}
+            }
+        }
+    }
+
+    /// Fetches `date` of `contract` and applies the invalid-tick policy.
+    /// The ticks are filed under `symbol`.
+    async fn fetch_day(
+        &self,
+        symbol: &str,
+        contract: &str,
+        date: NaiveDate,
+        run: &RunOptions,
+    ) -> Result<FetchedDay, BackfillError> {
+        let FetchedTicks { mut ticks, invalid } = self
+            .fetch_retrying_empty(symbol, contract, date, &run.cancel)
             .await
             .map_err(BackfillError::GatewayError)?;
+        if contract != symbol {
+            ticks = ticks
+                .into_iter()
+                .map(|tick| tick.with_symbol(symbol.to_string()))
+                .collect();
+        }
+        if let Some(first) = invalid.first() {
+            if self.config.invalid_tick_policy == InvalidTickPolicy::Fail {
+                return Err(BackfillError::InvalidTicks {
+                    count: invalid.len(),
+                    first: first.to_string(),
+                });
+            }
+            warn!(
+                "Dropped {} invalid ticks for {} {} (first: {})",
+                invalid.len(),
+                symbol,
+                date,
+                first
+            );
+        }
+        Ok(FetchedDay::Buffered {
+            ticks,
+            invalid_ticks: invalid.len(),
+        })
+    }
 
-        let tick_count = ticks.len();
-        let last_timestamp = ticks.last().map(|tick| tick.timestamp().timestamp_millis());
+    /// Stores a fetched day, or streams it in when it was left to the
+    /// writer.
+    async fn write_day(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        fetched: FetchedDay,
+        run: &RunOptions,
+    ) -> Result<DayResult, BackfillError> {
+        let (ticks, invalid_ticks) = match fetched {
+            FetchedDay::Buffered {
+                ticks,
+                invalid_ticks,
+            } => (ticks, invalid_ticks),
+            FetchedDay::Streamed {
+                contract,
+                chunk_ticks,
+            } => {
+                return self
+                    .stream_day(symbol, &contract, date, chunk_ticks, run)
+                    .await
+            }
+        };
+        let replace = run.force_overwrite;
+        let mut stats = DayStats::new(date);
+        for tick in &ticks {
+            stats.add(tick);
+        }
 
-        if !ticks.is_empty() {
-            self.repository
-                .save_batch(ticks)
-                .await
-                .map_err(BackfillError::RepositoryError)?;
+        if ticks.is_empty() {
+            if replace {
+                warn!(
+                    "No ticks returned for {} {}, keeping existing data",
+                    symbol, date
+                );
+            }
+        } else {
+            // The repository consumes the batch, so keep a copy only when
+            // there is somewhere to put it on failure.
+            let kept = self.dead_letters.as_ref().map(|_| ticks.clone());
+            let written = if replace {
+                self.repository.replace_day(symbol, date, ticks).await
+            } else {
+                self.repository.save_batch(ticks).await
+            };
+            if let Err(err) = written {
+                if let (Some(sink), Some(ticks)) = (&self.dead_letters, kept) {
+                    self.dead_letter(sink.as_ref(), symbol, date, &ticks).await;
+                }
+                return Err(BackfillError::RepositoryError(err));
+            }
         }
 
-        Ok(DayResult {
-            tick_count,
-            last_timestamp,
-        })
+        Ok(stats.finish(invalid_ticks))
+    }
+
+    /// Streams `date` of `contract` from the gateway into the repository,
+    /// `chunk_ticks` at a time, filed under `symbol`. Rate limits and empty
+    /// trading days are retried as for buffered fetches, but only while
+    /// nothing has been saved; a failure after that fails the day with the
+    /// saved chunks kept.
+    async fn stream_day(
+        &self,
+        symbol: &str,
+        contract: &str,
+        date: NaiveDate,
+        chunk_ticks: usize,
+        run: &RunOptions,
+    ) -> Result<DayResult, BackfillError> {
+        let empty_retries = if self.config.calendar.is_trading_day(date) {
+            self.config.empty_day_retries
+        } else {
+            0
+        };
+        let (mut rate_limited, mut empty) = (0, 0);
+        loop {
+            if run.cancel.is_cancelled() {
+                return Err(BackfillError::GatewayError(HistoricalDataError::Cancelled));
+            }
+            let mut stats = DayStats::new(date);
+            let ticks = self
+                .gateway
+                .fetch_historical_stream(contract, date)
+                .map(|tick| match tick {
+                    Ok(tick) if contract != symbol => Ok(tick.with_symbol(symbol.to_string())),
+                    other => other,
+                })
+                .inspect(|tick| {
+                    if let Ok(tick) = tick {
+                        stats.add(tick);
+                    }
+                })
+                .boxed();
+            let saved = self.repository.save_stream(ticks, chunk_ticks).await;
+            let delay = match saved {
+                Ok(0) if empty < empty_retries => {
+                    empty += 1;
+                    warn!(
+                        "Empty result for trading day {} {}, refetching ({}/{})",
+                        contract, date, empty, empty_retries
+                    );
+                    self.config.empty_day_retry_delay
+                }
+                Ok(_) => return Ok(stats.finish(0)),
+                Err(SaveStreamError::Source(HistoricalDataError::RateLimitExceeded {
+                    retry_after,
+                })) if stats.tick_count == 0
+                    && rate_limited < self.config.max_rate_limit_retries =>
+                {
+                    let delay = self.config.rate_limit_delay(rate_limited, retry_after);
+                    rate_limited += 1;
+                    warn!(
+                        "Rate limited streaming {} {} (attempt {}), retrying in {:?}",
+                        contract, date, rate_limited, delay
+                    );
+                    delay
+                }
+                Err(SaveStreamError::Source(err)) => return Err(BackfillError::GatewayError(err)),
+                Err(SaveStreamError::Repository(err)) => {
+                    return Err(BackfillError::RepositoryError(err))
+                }
+            };
+            tokio::select! {
+                _ = run.cancel.cancelled() => {
+                    return Err(BackfillError::GatewayError(HistoricalDataError::Cancelled))
+                }
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+    }
+
+    async fn dead_letter(
+        &self,
+        sink: &dyn DeadLetterSink,
+        symbol: &str,
+        date: NaiveDate,
+        ticks: &[Tick],
+    ) {
+        match sink.write_batch(symbol, ticks).await {
+            Ok(()) => warn!(
+                "Saved {} unwritten ticks for {} {} to the dead-letter sink",
+                ticks.len(),
+                symbol,
+                date
+            ),
+            Err(e) => warn!(
+                "Could not dead-letter {} ticks for {} {}: {}",
+                ticks.len(),
+                symbol,
+                date,
+                e
+            ),
+        }
+    }
+
+    async fn detect_days(
+        &self,
+        symbol: &str,
+        effective_start: NaiveDate,
+        range_end: NaiveDate,
+    ) -> Result<Vec<PlannedDay>, BackfillError> {
+        let effective_range = DateRange::new(effective_start, range_end).map_err(|e| {
+            BackfillError::Internal(format!(
+                "invalid effective range starting {}: {}",
+                effective_start, e
+            ))
+        })?;
+
+        let gaps = self
+            .gap_detector
+            .detect_gaps(symbol, effective_range)
+            .await
+            .map_err(BackfillError::GapDetectionError)?;
+        let gaps = filter_min_gap_days(gaps, self.config.min_gap_days);
+        let gaps = coalesce_gaps(gaps, self.config.coalesce_gap_days);
+
+        Ok(plan_days_to_process(
+            effective_start,
+            range_end,
+            gaps.as_slice(),
+        ))
+    }
+
+    /// Days in `written_days` (ascending) that gap detection still reports
+    /// as missing.
+    async fn missing_after_run(
+        &self,
+        symbol: &str,
+        written_days: &[NaiveDate],
+    ) -> Result<Vec<NaiveDate>, BackfillError> {
+        let (first, last) = match (written_days.first(), written_days.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Ok(Vec::new()),
+        };
+        let range = DateRange::new(first, last)
+            .map_err(|e| BackfillError::Internal(format!("invalid verification range: {}", e)))?;
+
+        let missing: BTreeSet<NaiveDate> = self
+            .gap_detector
+            .detect_gaps(symbol, range)
+            .await
+            .map_err(BackfillError::GapDetectionError)?
+            .iter()
+            .flat_map(DateRange::split_by_days)
+            .map(|day| day.start())
+            .collect();
+
+        Ok(written_days
+            .iter()
+            .copied()
+            .filter(|date| missing.contains(date))
+            .collect())
     }
 
     async fn initialize_job(
@@ -84,34 +701,150 @@ impl BackfillServiceImpl {
         symbol: &str,
         range: &DateRange,
     ) -> Result<JobContext, BackfillError> {
-        let job_key = format!("ingest:job:{}:{}", symbol, range.start());
+        let job_key = JobKey::new(symbol, range.start()).to_string();
+        self.start_job(job_key, range, true).await
+    }
+
+    /// Claims `job_key` for `range`. With `resume`, a running or cancelled
+    /// job carries on from its cursor; otherwise the cursor starts over at
+    /// the range start.
+    async fn start_job(
+        &self,
+        job_key: String,
+        range: &DateRange,
+        resume: bool,
+    ) -> Result<JobContext, BackfillError> {
         let now = Utc::now();
-        if let Some(mut state) = self.job_state_repo.get(&job_key).await? {
-            if matches!(state.status, JobStatus::Running) {
+        let existing = self.job_state_repo.get(&job_key).await?;
+        if let Some(mut state) = existing.clone() {
+            if matches!(state.status, JobStatus::Running | JobStatus::Cancelled) {
                 let heartbeat_age = now.signed_duration_since(state.heartbeat_at);
-                if heartbeat_age <= HEARTBEAT_TIMEOUT {
+                if state.status == JobStatus::Running && heartbeat_age <= HEARTBEAT_TIMEOUT {
                     return Err(BackfillError::JobAlreadyRunning(job_key));
                 }
 
-                state.job_instance_id = Uuid::new_v4().to_string();
-                state.status = JobStatus::Running;
-                state.heartbeat_at = now;
-                self.job_state_repo.upsert(&job_key, &state).await?;
-                return Ok(JobContext { job_key, state });
+                if resume {
+                    state.job_instance_id = Uuid::new_v4().to_string();
+                    state.status = JobStatus::Running;
+                    state.heartbeat_at = now;
+                    self.job_state_repo.upsert(&job_key, &state).await?;
+                    return Ok(JobContext::new(job_key, state));
+                }
             }
         }
 
         let job_instance_id = Uuid::new_v4().to_string();
         let initial_cursor = start_of_day_ts(range.start()).saturating_sub(1);
-        let state = JobState::new(
+        let mut state = JobState::new(
             job_instance_id.clone(),
             JobStatus::Running,
             initial_cursor,
             end_of_day_ts(range.end()),
             now,
         );
+        // Keep checksums from earlier runs so re-fetched days can be compared,
+        // and the lifetime totals so they keep accumulating.
+        if let Some(previous) = existing {
+            state.day_checksums = previous.day_checksums;
+            state.total_ticks = previous.total_ticks;
+            state.last_processed_date = previous.last_processed_date;
+        }
         self.job_state_repo.upsert(&job_key, &state).await?;
-        Ok(JobContext { job_key, state })
+        Ok(JobContext::new(job_key, state))
+    }
+
+    /// Like `initialize_job`, but while the job is locked by another
+    /// instance retries every `lock_poll_interval` until `wait` elapses.
+    async fn initialize_job_waiting(
+        &self,
+        symbol: &str,
+        range: &DateRange,
+        wait: StdDuration,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<JobContext, BackfillError> {
+        let deadline = Instant::now() + wait;
+        loop {
+            match self.initialize_job(symbol, range).await {
+                Err(BackfillError::JobAlreadyRunning(job_key)) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Err(BackfillError::JobAlreadyRunning(job_key));
+                    }
+                    info!(
+                        "Job {} is locked by another instance, retrying for up to {}s",
+                        job_key,
+                        remaining.as_secs()
+                    );
+                    let pause = tokio::time::sleep(self.config.lock_poll_interval.min(remaining));
+                    match cancel {
+                        Some(cancel) => tokio::select! {
+                            _ = pause => {}
+                            _ = cancel.cancelled() => return Err(BackfillError::Cancelled),
+                        },
+                        None => pause.await,
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Stores the day's checksum, warning if it differs from an earlier run.
+    async fn record_checksum(
+        &self,
+        symbol: &str,
+        ctx: &mut JobContext,
+        date: NaiveDate,
+        checksum: u64,
+        events: &EventSink,
+    ) -> Result<(), BackfillError> {
+        let checksums = &mut ctx.state.day_checksums;
+        if let Some(previous) = checksums.insert(date, checksum) {
+            if previous != checksum {
+                warn!(
+                    "Source data for {} {} changed since it was last fetched \
+                     (checksum {:016x} -> {:016x})",
+                    symbol, date, previous, checksum
+                );
+                events.emit(|| BackfillEvent::SourceDataChanged { date });
+            }
+        }
+        while checksums.len() > self.config.max_day_checksums {
+            checksums.pop_first();
+        }
+
+        self.job_state_repo
+            .update_day_checksums(
+                ctx.job_key(),
+                ctx.job_instance_id(),
+                &ctx.state.day_checksums,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Adds a successfully processed day to the job's lifetime totals.
+    async fn record_progress(
+        &self,
+        ctx: &mut JobContext,
+        date: NaiveDate,
+        ticks: usize,
+    ) -> Result<(), BackfillError> {
+        let state = &mut ctx.state;
+        state.total_ticks += ticks as u64;
+        let last = state
+            .last_processed_date
+            .map_or(date, |last| last.max(date));
+        state.last_processed_date = Some(last);
+        self.job_state_repo
+            .update_progress(
+                ctx.job_key(),
+                ctx.job_instance_id(),
+                ctx.state.total_ticks,
+                last,
+            )
+            .await?;
+        Ok(())
     }
 
     async fn finalize_job(
@@ -123,9 +856,26 @@ impl BackfillServiceImpl {
             .update_status(ctx.job_key(), ctx.job_instance_id(), status.clone())
             .await?;
         ctx.state.status = status;
+        self.heartbeat(ctx).await
+    }
+
+    /// Sends a heartbeat unless one went out within the configured minimum
+    /// interval.
+    async fn heartbeat(&self, ctx: &mut JobContext) -> Result<(), BackfillError> {
+        let interval = self
+            .config
+            .min_heartbeat_interval
+            .min(MAX_HEARTBEAT_INTERVAL);
+        if ctx
+            .last_heartbeat
+            .is_some_and(|sent| sent.elapsed() < interval)
+        {
+            return Ok(());
+        }
         self.job_state_repo
             .heartbeat(ctx.job_key(), ctx.job_instance_id(), Utc::now())
             .await?;
+        ctx.last_heartbeat = Some(Instant::now());
         Ok(())
     }
 
@@ -136,6 +886,245 @@ impl BackfillServiceImpl {
         ctx.state.last_error_type = Some(message.to_string());
         Ok(())
     }
+
+    async fn process_days(
+        &self,
+        symbol: &str,
+        range: DateRange,
+        job_ctx: &mut JobContext,
+        days_to_process: Vec<NaiveDate>,
+        run: &RunOptions,
+        events: &EventSink,
+    ) -> Result<BackfillReport, BackfillError> {
+        let mut total_ticks = 0;
+        let mut days_processed = 0;
+        let mut failed_days = Vec::new();
+        let mut days_no_data = Vec::new();
+        let mut day_outcomes = BTreeMap::new();
+        let mut invalid_ticks = BTreeMap::new();
+        let mut job_failed = false;
+        let mut written_days = Vec::new();
+        let mut cancelled = false;
+        self.repository.set_source(self.gateway.source_id()).await;
+
+        let mut pending = Vec::new();
+        for date in days_to_process {
+            if end_of_day_ts(date) <= job_ctx.state.cursor {
+                day_outcomes.insert(date, DayOutcome::Skipped);
+            } else {
+                pending.push(date);
+            }
+        }
+        // The cursor only moves over the leading run of finished days, so a
+        // resumed job never skips a day that failed or is still in flight.
+        let mut cursor = CursorTracker::new(&pending);
+        let days_total = pending.len();
+        let mut days_done = 0;
+
+        // Fetches feed a bounded queue drained by a separate pool of
+        // writers, so gateway and disk concurrency are tuned independently.
+        let contracts = self.contract_spans(symbol, &range);
+        let contracts = &contracts;
+        let fetch_slots = self.config.max_concurrent_days.max(1);
+        let (fetched_tx, fetched_rx) = tokio::sync::mpsc::channel(fetch_slots);
+        let fetch_stage = async move {
+            let mut fetches = futures::stream::iter(pending)
+                .map(|date| async move {
+                    if run.cancel.is_cancelled() {
+                        return (
+                            date,
+                            Err(BackfillError::GatewayError(HistoricalDataError::Cancelled)),
+                        );
+                    }
+                    events.emit(|| BackfillEvent::DayStarted(date));
+                    let contract = contracts
+                        .iter()
+                        .find(|span| span.range.contains(date))
+                        .map_or(symbol, |span| span.contract.as_str());
+                    let streamed = self
+                        .config
+                        .stream_chunk_ticks
+                        .filter(|_| !run.force_overwrite);
+                    let fetched = match streamed {
+                        Some(chunk_ticks) => Ok(FetchedDay::Streamed {
+                            contract: contract.to_string(),
+                            chunk_ticks,
+                        }),
+                        None => self.fetch_day(symbol, contract, date, run).await,
+                    };
+                    (date, fetched)
+                })
+                .buffered(fetch_slots);
+            while let Some(fetched) = fetches.next().await {
+                // The writers stopped on an error; nobody is left to write.
+                if fetched_tx.send(fetched).await.is_err() {
+                    break;
+                }
+            }
+        };
+        let results = futures::stream::unfold(fetched_rx, |mut fetched_rx| async move {
+            fetched_rx.recv().await.map(|fetched| (fetched, fetched_rx))
+        })
+        .map(|(date, fetched)| async move {
+            let result = match fetched {
+                Ok(fetched) => self.write_day(symbol, date, fetched, run).await,
+                Err(err) => Err(err),
+            };
+            (date, result)
+        })
+        .buffer_unordered(self.config.max_concurrent_writes.max(1));
+
+        let write_stage = async {
+            // Owned here so an early error closes the queue and stops fetching.
+            let mut results = std::pin::pin!(results);
+            while let Some((date, result)) = results.next().await {
+                let day_end = end_of_day_ts(date);
+                let mut day_ticks = 0;
+                match result {
+                    Ok(result) => {
+                        day_ticks = result.tick_count;
+                        events.emit(|| BackfillEvent::DayCompleted {
+                            date,
+                            ticks: result.tick_count,
+                        });
+                        total_ticks += result.tick_count;
+                        days_processed += 1;
+                        day_outcomes.insert(date, self.config.day_outcome(&result));
+                        if result.invalid_ticks > 0 {
+                            invalid_ticks.insert(date, result.invalid_ticks);
+                        }
+                        if result.tick_count > 0 {
+                            written_days.push(date);
+                            self.record_checksum(symbol, job_ctx, date, result.checksum, events)
+                                .await?;
+                        }
+                        self.record_progress(job_ctx, date, result.tick_count)
+                            .await?;
+                        cursor.complete(date, result.last_timestamp.unwrap_or(day_end));
+                    }
+                    // Fetches waiting on the gateway give up at once; days
+                    // already being written finish first.
+                    Err(BackfillError::GatewayError(HistoricalDataError::Cancelled)) => {
+                        cancelled = true;
+                        continue;
+                    }
+                    Err(BackfillError::GatewayError(HistoricalDataError::DataNotAvailable(_)))
+                        if self.config.no_data_policy != NoDataPolicy::Fail =>
+                    {
+                        if self.config.no_data_policy == NoDataPolicy::FillEmpty {
+                            self.repository
+                                .mark_no_data(symbol, date)
+                                .await
+                                .map_err(BackfillError::RepositoryError)?;
+                            days_processed += 1;
+                            day_outcomes.insert(date, DayOutcome::Empty);
+                            cursor.complete(date, day_end);
+                        } else {
+                            // Left as a hole so a resumed run tries it again.
+                            day_outcomes.insert(date, DayOutcome::Skipped);
+                        }
+                        events.emit(|| BackfillEvent::DayNoData(date));
+                        days_no_data.push(date);
+                    }
+                    Err(e) => {
+                        job_failed = true;
+                        let msg = e.to_string();
+                        events.emit(|| BackfillEvent::DayFailed {
+                            date,
+                            error: msg.clone(),
+                        });
+                        self.record_error(job_ctx, &format!("{}: {}", date, msg))
+                            .await?;
+                        day_outcomes.insert(date, DayOutcome::Failed(msg.clone()));
+                        failed_days.push((date, msg));
+                    }
+                }
+
+                days_done += 1;
+                if let Some(progress) = &run.progress {
+                    // A dropped receiver just means nobody is listening any more.
+                    let _ = progress
+                        .send(BackfillProgress {
+                            date,
+                            ticks: day_ticks,
+                            days_done,
+                            days_total,
+                        })
+                        .await;
+                }
+
+                if let Some(cursor_ts) = cursor.advance() {
+                    if cursor_ts > job_ctx.state.cursor {
+                        self.job_state_repo
+                            .update_cursor(job_ctx.job_key(), job_ctx.job_instance_id(), cursor_ts)
+                            .await?;
+                        job_ctx.state.cursor = cursor_ts;
+                    }
+                }
+                self.heartbeat(job_ctx).await?;
+            }
+            Ok::<_, BackfillError>(())
+        };
+        let ((), written) = futures::join!(fetch_stage, write_stage);
+        written?;
+        failed_days.sort_by_key(|(date, _)| *date);
+        days_no_data.sort();
+        written_days.sort();
+
+        self.repository
+            .shutdown()
+            .await
+            .map_err(BackfillError::RepositoryError)?;
+
+        if cancelled {
+            warn!(
+                "Backfill of {} cancelled after {} of {} days",
+                symbol, days_done, days_total
+            );
+        } else if self.config.verify_after_run {
+            for date in self.missing_after_run(symbol, &written_days).await? {
+                job_failed = true;
+                let msg = "data missing on post-run verification".to_string();
+                warn!("{} {}: {}", symbol, date, msg);
+                events.emit(|| BackfillEvent::DayFailed {
+                    date,
+                    error: msg.clone(),
+                });
+                self.record_error(job_ctx, &format!("{}: {}", date, msg))
+                    .await?;
+                day_outcomes.insert(date, DayOutcome::Failed(msg.clone()));
+                failed_days.push((date, msg));
+            }
+        }
+
+        let final_status = if cancelled {
+            JobStatus::Cancelled
+        } else if job_failed {
+            JobStatus::Failed
+        } else {
+            JobStatus::Completed
+        };
+        self.finalize_job(job_ctx, final_status.clone()).await?;
+        events.emit(|| BackfillEvent::Finalized {
+            status: final_status,
+            days_processed,
+            total_ticks,
+        });
+
+        Ok(BackfillReport {
+            symbol: symbol.to_string(),
+            range,
+            days_processed,
+            total_ticks,
+            failed_days,
+            days_no_data,
+            day_outcomes,
+            invalid_ticks,
+            cancelled,
+            lifetime_ticks: job_ctx.state.total_ticks,
+            last_processed_date: job_ctx.state.last_processed_date,
+        })
+    }
 }
 
 #[async_trait]
@@ -145,93 +1134,258 @@ impl BackfillService for BackfillServiceImpl {
         symbol: &str,
         range: DateRange,
     ) -> Result<BackfillReport, BackfillError> {
-        let mut job_ctx = self.initialize_job(symbol, &range).await?;
+        self.backfill_range_with_options(symbol, range, BackfillOptions::default())
+            .await
+    }
+
+    async fn backfill_range_with_options(
+        &self,
+        symbol: &str,
+        range: DateRange,
+        options: BackfillOptions,
+    ) -> Result<BackfillReport, BackfillError> {
+        let events = EventSink::new(options.events);
+        if options.force_overwrite {
+            let today = Utc::now().date_naive();
+            if range.end() >= today {
+                return Err(BackfillError::OverlapsLiveWindow(range.end().max(today)));
+            }
+        }
+        if let Some(max_days) = options.max_days {
+            let plan = self.plan(symbol, range.clone()).await?;
+            let days = plan.days_to_fetch(options.force_overwrite);
+            if days > max_days {
+                return Err(BackfillError::TooManyDays { days, max_days });
+            }
+        }
+        let mut job_ctx = match options.wait_for_lock {
+            Some(wait) => {
+                self.initialize_job_waiting(symbol, &range, wait, options.cancel.as_ref())
+                    .await?
+            }
+            None => self.initialize_job(symbol, &range).await?,
+        };
         let effective_start = resume_start(range.start(), job_ctx.state.cursor);
+        events.emit(|| BackfillEvent::JobInitialized {
+            job_key: job_ctx.job_key.clone(),
+            resume_from: effective_start,
+        });
         if effective_start > range.end() {
             self.finalize_job(&mut job_ctx, JobStatus::Completed)
                 .await?;
+            events.emit(|| BackfillEvent::Finalized {
+                status: JobStatus::Completed,
+                days_processed: 0,
+                total_ticks: 0,
+            });
             return Ok(BackfillReport {
                 symbol: symbol.to_string(),
                 range,
                 days_processed: 0,
                 total_ticks: 0,
                 failed_days: Vec::new(),
+                days_no_data: Vec::new(),
+                day_outcomes: BTreeMap::new(),
+                invalid_ticks: BTreeMap::new(),
+                cancelled: false,
+                lifetime_ticks: job_ctx.state.total_ticks,
+                last_processed_date: job_ctx.state.last_processed_date,
             });
         }
-        let effective_range =
-            DateRange::new(effective_start, range.end()).expect("effective range must be valid");
+        let run = RunOptions {
+            force_overwrite: options.force_overwrite,
+            cancel: options.cancel.unwrap_or_default(),
+            progress: options.progress,
+        };
+        let planned = if options.force_overwrite {
+            effective_start
+                .iter_days()
+                .take_while(|date| *date <= range.end())
+                .map(|date| PlannedDay {
+                    date,
+                    reason: PlanReason::Forced,
+                })
+                .collect()
+        } else {
+            self.detect_days(symbol, effective_start, range.end())
+                .await?
+        };
+        for day in &planned {
+            debug!("Planned {} for {}: {}", day.date, symbol, day.reason);
+        }
+        let days_to_process: Vec<NaiveDate> = planned.iter().map(|day| day.date).collect();
+        events.emit(|| BackfillEvent::GapsDetected(days_to_process.len()));
 
-        let gaps = self
-            .gap_detector
-            .detect_gaps(symbol, effective_range.clone())
+        self.process_days(symbol, range, &mut job_ctx, days_to_process, &run, &events)
             .await
-            .map_err(BackfillError::GapDetectionError)?;
+    }
 
-        let days_to_process = plan_days_to_process(effective_start, range.end(), gaps.as_slice());
+    async fn backfill_dates(
+        &self,
+        symbol: &str,
+        dates: Vec<NaiveDate>,
+    ) -> Result<BackfillReport, BackfillError> {
+        let dates: Vec<NaiveDate> = dates
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let (first, last) = match (dates.first(), dates.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Err(BackfillError::NoDatesRequested),
+        };
 
-        let mut total_ticks = 0;
-        let mut days_processed = 0;
-        let mut failed_days = Vec::new();
-        let mut job_failed = false;
+        let max_history_days = self.gateway.max_history_days();
+        let today = Utc::now().date_naive();
+        if (today - first).num_days() > max_history_days as i64 {
+            return Err(BackfillError::OutsideHistoryWindow {
+                date: first,
+                max_history_days,
+            });
+        }
 
-        for date in days_to_process {
-            let day_end = end_of_day_ts(date);
-            if day_end <= job_ctx.state.cursor {
-                continue;
-            }
+        let range = DateRange::new(first, last)
+            .map_err(|e| BackfillError::Internal(format!("invalid date list range: {}", e)))?;
+        // Date lists get their own job, so the dates are not skipped as
+        // already done by a range job's cursor, nor by an earlier list's.
+        let job_key = JobKey::new(symbol, first).dates_key();
+        let mut job_ctx = self.start_job(job_key, &range, false).await?;
 
-            self.job_state_repo
-                .heartbeat(job_ctx.job_key(), job_ctx.job_instance_id(), Utc::now())
-                .await?;
+        self.process_days(
+            symbol,
+            range,
+            &mut job_ctx,
+            dates,
+            &RunOptions::default(),
+            &EventSink::default(),
+        )
+        .await
+    }
 
-            match self.backfill_single_day(symbol, date).await {
-                Ok(result) => {
-                    total_ticks += result.tick_count;
-                    days_processed += 1;
-                    let cursor_ts = result.last_timestamp.unwrap_or(day_end);
-                    self.job_state_repo
-                        .update_cursor(job_ctx.job_key(), job_ctx.job_instance_id(), cursor_ts)
-                        .await?;
-                    job_ctx.state.cursor = cursor_ts;
-                }
-                Err(e) => {
-                    job_failed = true;
-                    let msg = e.to_string();
-                    self.record_error(&mut job_ctx, &msg).await?;
-                    failed_days.push((date, msg));
-                }
+    async fn plan(&self, symbol: &str, range: DateRange) -> Result<BackfillPlan, BackfillError> {
+        // Mirrors `initialize_job`: only a job still marked running or
+        // cancelled resumes from its cursor; anything else starts over from
+        // the range start.
+        let job_key = JobKey::new(symbol, range.start()).to_string();
+        let resume_from = match self.job_state_repo.get(&job_key).await? {
+            Some(state) if matches!(state.status, JobStatus::Running | JobStatus::Cancelled) => {
+                resume_start(range.start(), state.cursor)
             }
-        }
-
-        self.repository
-            .shutdown()
-            .await
-            .map_err(BackfillError::RepositoryError)?;
+            _ => range.start(),
+        };
 
-        let final_status = if job_failed {
-            JobStatus::Failed
+        let days = if resume_from > range.end() {
+            Vec::new()
         } else {
-            JobStatus::Completed
+            self.detect_days(symbol, resume_from, range.end()).await?
         };
-        self.finalize_job(&mut job_ctx, final_status).await?;
+        let estimated_duration = self.config.estimate_duration(days.len());
 
-        Ok(BackfillReport {
+        Ok(BackfillPlan {
             symbol: symbol.to_string(),
             range,
-            days_processed,
-            total_ticks,
-            failed_days,
+            resume_from,
+            days,
+            estimated_duration,
+        })
+    }
+}
+
+/// What a backfill would do, as computed by [`BackfillService::plan`].
+#[derive(Debug, Clone)]
+pub struct BackfillPlan {
+    pub symbol: String,
+    pub range: DateRange,
+    pub resume_from: NaiveDate,
+    pub days: Vec<PlannedDay>,
+    pub estimated_duration: StdDuration,
+}
+
+impl BackfillPlan {
+    /// Days the run would fetch: the planned ones, or with
+    /// `force_overwrite` every day from `resume_from` to the range's end.
+    pub fn days_to_fetch(&self, force_overwrite: bool) -> usize {
+        if force_overwrite {
+            self.resume_from
+                .iter_days()
+                .take_while(|date| *date <= self.range.end())
+                .count()
+        } else {
+            self.days.len()
+        }
+    }
+}
+
+/// A day a backfill would fetch, and why.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PlannedDay {
+    pub date: NaiveDate,
+    pub reason: PlanReason,
+}
+
+/// Why a day was planned. A day with several reasons gets the strongest:
+/// `Forced`, then `Gap`, then `ResumeStart`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum PlanReason {
+    /// The day the run starts or resumes from. It is always refetched, as
+    /// an interrupted run may have stored only part of it, even when gap
+    /// detection finds nothing missing.
+    ResumeStart,
+    /// Gap detection reported the day missing.
+    Gap,
+    /// `force_overwrite` refetches every day of the range.
+    Forced,
+}
+
+impl std::fmt::Display for PlanReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PlanReason::ResumeStart => "resume start",
+            PlanReason::Gap => "gap",
+            PlanReason::Forced => "forced",
         })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct BackfillReport {
     pub symbol: String,
     pub range: DateRange,
     pub days_processed: usize,
     pub total_ticks: usize,
     pub failed_days: Vec<(NaiveDate, String)>,
+    /// Days the gateway had no data for, under a non-failing [`NoDataPolicy`].
+    pub days_no_data: Vec<NaiveDate>,
+    /// How each day this run looked at turned out.
+    pub day_outcomes: BTreeMap<NaiveDate, DayOutcome>,
+    /// Records dropped for failing validation under
+    /// [`InvalidTickPolicy::Skip`], by day. Days without any are absent.
+    pub invalid_ticks: BTreeMap<NaiveDate, usize>,
+    /// The run was cancelled; days it never reached appear nowhere above.
+    pub cancelled: bool,
+    /// Ticks written by every run of the job, this one included.
+    pub lifetime_ticks: u64,
+    /// Latest day any run of the job has processed.
+    pub last_processed_date: Option<NaiveDate>,
+}
+
+/// Result of backfilling one day, for callers that branch per day rather
+/// than reading counts off the report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum DayOutcome {
+    /// Ticks were stored and cover the expected hours.
+    Full,
+    /// Ticks were stored but only `present` of the `expected` UTC hours
+    /// have any; see [`BackfillConfig::expected_hours_per_day`].
+    Partial { present: u32, expected: u32 },
+    /// The gateway returned no ticks, or had no data and an empty-day
+    /// marker was stored.
+    Empty,
+    /// Not fetched this run: the gateway had no data under
+    /// [`NoDataPolicy::Skip`], or the job cursor already covered the day.
+    Skipped,
+    /// The day failed; the message matches its `failed_days` entry.
+    Failed(String),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -250,14 +1404,51 @@ pub enum BackfillError {
 
     #[error("Job already running: {0}")]
     JobAlreadyRunning(String),
+
+    #[error("Internal error: {0}")]
+    Internal(String),
+
+    #[error("No dates requested")]
+    NoDatesRequested,
+
+    #[error("{date} is older than the gateway's {max_history_days}-day history window")]
+    OutsideHistoryWindow {
+        date: NaiveDate,
+        max_history_days: u32,
+    },
+
+    /// Cancelled while still waiting for another instance's lock, before
+    /// the run started.
+    #[error("Backfill cancelled")]
+    Cancelled,
+
+    #[error("Refusing to overwrite {0}: it is inside the live ingestion window")]
+    OverlapsLiveWindow(NaiveDate),
+
+    #[error("Refusing to start: {days} days to fetch exceeds the limit of {max_days}")]
+    TooManyDays { days: usize, max_days: usize },
+
+    #[error("Gateway returned {count} invalid ticks (first: {first})")]
+    InvalidTicks { count: usize, first: String },
 }
 
 struct JobContext {
     job_key: String,
     state: JobState,
+    /// When this process last wrote `heartbeat_at`.
+    last_heartbeat: Option<Instant>,
 }
 
 impl JobContext {
+    /// `state` was just written with a fresh `heartbeat_at`.
+    fn new(job_key: String, state: JobState) -> Self {
+        Self {
+            job_key,
+            state,
+            last_heartbeat: Some(Instant::now()),
+        }
+    }
+
     fn job_key(&self) -> &str {
         &self.job_key
     }
@@ -267,21 +1458,133 @@ impl JobContext {
     }
 }
 
+/// Works out how far the cursor may move as days finish in any order: up
+/// to the last of the leading days that have all finished.
+struct CursorTracker {
+    /// Days not yet passed by the cursor, ascending.
+    remaining: VecDeque<NaiveDate>,
+    finished: BTreeMap<NaiveDate, i64>,
+}
+
+impl CursorTracker {
+    fn new(days: &[NaiveDate]) -> Self {
+        Self {
+            remaining: days.iter().copied().collect(),
+            finished: BTreeMap::new(),
+        }
+    }
+
+    /// `date` finished and may move the cursor to `cursor_ts`.
+    fn complete(&mut self, date: NaiveDate, cursor_ts: i64) {
+        self.finished.insert(date, cursor_ts);
+    }
+
+    /// The new cursor, if the leading days have moved on.
+    fn advance(&mut self) -> Option<i64> {
+        let mut cursor = None;
+        while let Some(cursor_ts) = self
+            .remaining
+            .front()
+            .and_then(|date| self.finished.remove(date))
+        {
+            self.remaining.pop_front();
+            cursor = Some(cursor_ts);
+        }
+        cursor
+    }
+}
+
+/// Per-run settings threaded down to each day.
+#[derive(Default)]
+struct RunOptions {
+    force_overwrite: bool,
+    cancel: CancellationToken,
+    progress: Option<Sender<BackfillProgress>>,
+}
+
+/// A day between the fetch and write stages.
+enum FetchedDay {
+    /// Fetched whole: its valid ticks, and how many records were dropped.
+    Buffered {
+        ticks: Vec<Tick>,
+        invalid_ticks: usize,
+    },
+    /// Left for the writer to stream from the gateway; see
+    /// [`BackfillConfig::stream_chunk_ticks`].
+    Streamed {
+        contract: String,
+        chunk_ticks: usize,
+    },
+}
+
+/// A day's [`DayResult`] figures, gathered tick by tick.
+struct DayStats {
+    date: NaiveDate,
+    tick_count: usize,
+    checksum: TicksChecksum,
+    hours: BTreeSet<u32>,
+    last_timestamp: Option<DateTime<Utc>>,
+}
+
+impl DayStats {
+    fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            tick_count: 0,
+            checksum: TicksChecksum::new(),
+            hours: BTreeSet::new(),
+            last_timestamp: None,
+        }
+    }
+
+    fn add(&mut self, tick: &Tick) {
+        self.tick_count += 1;
+        self.checksum.add(tick);
+        // Gateways may over-return, e.g. a futures session that spills into
+        // the neighbouring day. Those ticks are still saved (the repository
+        // files each tick by its own timestamp), but only in-day ticks may
+        // move the cursor, or the next day would be treated as done.
+        let timestamp = tick.timestamp();
+        if timestamp.date_naive() == self.date {
+            self.hours.insert(timestamp.hour());
+            self.last_timestamp = self.last_timestamp.max(Some(timestamp));
+        }
+    }
+
+    fn finish(self, invalid_ticks: usize) -> DayResult {
+        DayResult {
+            tick_count: self.tick_count,
+            invalid_ticks,
+            hours_present: self.hours.len() as u32,
+            last_timestamp: self
+                .last_timestamp
+                .map(|timestamp| timestamp.timestamp_millis()),
+            checksum: self.checksum.finish(),
+        }
+    }
+}
+
 struct DayResult {
     tick_count: usize,
+    /// Records dropped for failing validation.
+    invalid_ticks: usize,
+    /// Distinct UTC hours of the day that have at least one tick.
+    hours_present: u32,
     last_timestamp: Option<i64>,
+    checksum: u64,
 }
 
 fn start_of_day_ts(date: NaiveDate) -> i64 {
-    date.and_hms_opt(0, 0, 0)
-        .expect("valid midnight")
-        .and_utc()
+    DateRange::single_day(date)
+        .utc()
+        .start_utc()
         .timestamp_millis()
 }
 
 fn end_of_day_ts(date: NaiveDate) -> i64 {
-    date.and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap())
-        .and_utc()
+    DateRange::single_day(date)
+        .utc()
+        .end_utc()
         .timestamp_millis()
 }
 
@@ -297,10 +1600,14 @@ fn plan_days_to_process(
     effective_start: NaiveDate,
     range_end: NaiveDate,
     gaps: &[DateRange],
-) -> Vec<NaiveDate> {
-    let mut days = BTreeSet::new();
+) -> Vec<PlannedDay> {
+    let mut days = BTreeMap::new();
+    let mut plan = |date, reason: PlanReason| {
+        let planned = days.entry(date).or_insert(reason);
+        *planned = (*planned).max(reason);
+    };
     if effective_start <= range_end {
-        days.insert(effective_start);
+        plan(effective_start, PlanReason::ResumeStart);
     }
 
     for gap in gaps {
@@ -309,11 +1616,13 @@ fn plan_days_to_process(
             if date < effective_start || date > range_end {
                 continue;
             }
-            days.insert(date);
+            plan(date, PlanReason::Gap);
         }
     }
 
-    days.into_iter().collect()
+    days.into_iter()
+        .map(|(date, reason)| PlannedDay { date, reason })
+        .collect()
 }
 
 trait CursorExt {
@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+use ingestion_domain::DateRange;
+use shaku::Interface;
+
+/// Shared queue of gaps for a pool of backfill workers. A planner enqueues a
+/// symbol's gaps once; each worker then claims one at a time. A claimed gap
+/// that is not acknowledged within the queue's visibility timeout (the
+/// worker failed or died) becomes claimable again.
+#[async_trait]
+pub trait GapQueue: Interface {
+    /// Queues `gaps`, skipping any already pending or claimed, so replanning
+    /// a symbol does not hand the same gap out twice.
+    async fn enqueue_gaps(&self, symbol: &str, gaps: &[DateRange]) -> Result<(), GapQueueError>;
+
+    /// Atomically takes the next pending gap, so no two workers ever hold
+    /// the same one. `None` once nothing is pending.
+    async fn claim_next_gap(&self, symbol: &str) -> Result<Option<DateRange>, GapQueueError>;
+
+    /// Marks a claimed gap as filled so it is never handed out again, even if
+    /// its claim expired and it was requeued meanwhile.
+    async fn ack_gap(&self, symbol: &str, gap: &DateRange) -> Result<(), GapQueueError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GapQueueError {
+    #[error("Backend error: {0}")]
+    Backend(String),
+    /// The Redis endpoint is a cluster node that redirected the request.
+    #[error("Redis Cluster is not supported: {0}")]
+    ClusterNotSupported(String),
+    #[error("Invalid queued gap '{0}'")]
+    InvalidGap(String),
+}
@@ -1,7 +1,10 @@
 use async_trait::async_trait;
 use chrono::NaiveDate;
-use ingestion_domain::{DateRange, Tick};
+use futures::stream::{self, BoxStream, StreamExt};
+use ingestion_domain::{ContractSpan, DateRange, RollSchedule, Tick, TickValidationError};
 use shaku::Interface;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
 #[async_trait]
 pub trait HistoricalDataGateway: Interface {
@@ -11,9 +14,100 @@ pub trait HistoricalDataGateway: Interface {
         date: NaiveDate,
     ) -> Result<Vec<Tick>, HistoricalDataError>;
 
+    /// Like `fetch_historical_ticks`, but returns
+    /// [`HistoricalDataError::Cancelled`] promptly once `token` fires.
+    /// Gateways that wait on a rate limiter should pass the token down.
+    async fn fetch_historical_ticks_cancellable(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        token: &CancellationToken,
+    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        tokio::select! {
+            biased;
+            _ = token.cancelled() => Err(HistoricalDataError::Cancelled),
+            result = self.fetch_historical_ticks(symbol, date) => result,
+        }
+    }
+
+    /// Like `fetch_historical_ticks_cancellable`, but records from the
+    /// source that fail [`Tick`] validation (e.g. a zero-price bad print)
+    /// come back in [`FetchedTicks::invalid`] instead of failing the fetch.
+    /// The default reports none; gateways that build ticks from raw records
+    /// should override it.
+    async fn fetch_checked_ticks_cancellable(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        token: &CancellationToken,
+    ) -> Result<FetchedTicks, HistoricalDataError> {
+        self.fetch_historical_ticks_cancellable(symbol, date, token)
+            .await
+            .map(FetchedTicks::from)
+    }
+
+    /// Like `fetch_checked_ticks_cancellable`, for a dated `contract` a
+    /// roll schedule resolved a continuous symbol to (e.g. `NQH5`). The
+    /// default fetches `contract` as if it were a symbol; gateways that
+    /// address dated contracts apart from their root should override it.
+    async fn fetch_contract_ticks_cancellable(
+        &self,
+        contract: &str,
+        date: NaiveDate,
+        token: &CancellationToken,
+    ) -> Result<FetchedTicks, HistoricalDataError> {
+        self.fetch_checked_ticks_cancellable(contract, date, token)
+            .await
+    }
+
+    /// Streams a day's ticks instead of returning them all at once, so
+    /// callers such as [`TickRepository::save_stream`] can persist them in
+    /// bounded chunks. The default fetches the whole day with
+    /// `fetch_historical_ticks` and replays it; gateways that page through
+    /// their source should override it to yield as pages arrive.
+    ///
+    /// [`TickRepository::save_stream`]: crate::ports::TickRepository::save_stream
+    fn fetch_historical_stream<'a>(
+        &'a self,
+        symbol: &'a str,
+        date: NaiveDate,
+    ) -> HistoricalTickStream<'a> {
+        stream::once(self.fetch_historical_ticks(symbol, date))
+            .flat_map(|result| match result {
+                Ok(ticks) => stream::iter(ticks).map(Ok).left_stream(),
+                Err(err) => stream::iter([Err(err)]).right_stream(),
+            })
+            .boxed()
+    }
+
     fn max_history_days(&self) -> u32;
+
+    /// Identifies the data provider, recorded with stored data for audits.
+    fn source_id(&self) -> &str {
+        "unknown"
+    }
 }
 
+/// A day's fetch from [`HistoricalDataGateway::fetch_checked_ticks_cancellable`].
+#[derive(Debug, Default)]
+pub struct FetchedTicks {
+    pub ticks: Vec<Tick>,
+    /// Why each record that could not become a [`Tick`] was rejected.
+    pub invalid: Vec<TickValidationError>,
+}
+
+impl From<Vec<Tick>> for FetchedTicks {
+    fn from(ticks: Vec<Tick>) -> Self {
+        Self {
+            ticks,
+            invalid: Vec::new(),
+        }
+    }
+}
+
+/// Ticks of one day from [`HistoricalDataGateway::fetch_historical_stream`].
+pub type HistoricalTickStream<'a> = BoxStream<'a, Result<Tick, HistoricalDataError>>;
+
 #[async_trait]
 pub trait GapDetector: Interface {
     async fn detect_gaps(
@@ -23,10 +117,26 @@ pub trait GapDetector: Interface {
     ) -> Result<Vec<DateRange>, GapDetectionError>;
 }
 
+/// Maps a continuous symbol to the underlying contracts a gateway should be
+/// asked for, e.g. the quarterly futures a continuous "NQ" rolls through.
+pub trait ContractResolver: Interface {
+    /// `range` split into consecutive spans covering it, in date order, each
+    /// with the contract to fetch for its days.
+    fn resolve(&self, symbol: &str, range: &DateRange) -> Vec<ContractSpan>;
+}
+
+impl ContractResolver for RollSchedule {
+    fn resolve(&self, symbol: &str, range: &DateRange) -> Vec<ContractSpan> {
+        self.split(symbol, range)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum HistoricalDataError {
+    /// The gateway refused the request because of pacing rules. `retry_after`
+    /// carries the server's hint when one was given.
     #[error("API rate limit exceeded")]
-    RateLimitExceeded,
+    RateLimitExceeded { retry_after: Option<Duration> },
 
     #[error("Historical data not available for date: {0}")]
     DataNotAvailable(NaiveDate),
@@ -36,6 +146,9 @@ pub enum HistoricalDataError {
 
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+
+    #[error("Fetch cancelled")]
+    Cancelled,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -46,3 +159,24 @@ pub enum GapDetectionError {
     #[error("Invalid date range")]
     InvalidDateRange,
 }
+
+/// Parses a retry-after hint as sent by gateways: bare seconds (`"15"`,
+/// `"1.5"`) or a value suffixed with `ms`/`s` (`"1500ms"`, `"2s"`).
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, millis) = if let Some(ms) = value.strip_suffix("ms") {
+        (ms.trim(), true)
+    } else if let Some(secs) = value.strip_suffix('s') {
+        (secs.trim(), false)
+    } else {
+        (value, false)
+    };
+
+    let amount = number.parse::<f64>().ok()?;
+    if !amount.is_finite() || amount < 0.0 {
+        return None;
+    }
+
+    let secs = if millis { amount / 1000.0 } else { amount };
+    Some(Duration::from_secs_f64(secs))
+}
@@ -1,10 +1,87 @@
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use shaku::Interface;
+use std::collections::BTreeMap;
+use std::fmt;
+use tracing::warn;
 
 pub type JobInstanceId = String;
 
+pub const JOB_KEY_PREFIX: &str = "ingest:job:";
+
+/// Namespace of `backfill_dates` jobs, kept apart from range jobs so a date
+/// list never picks up a range job's cursor or overwrites its state.
+pub const DATES_JOB_KEY_PREFIX: &str = "ingest:dates-job:";
+
+/// Identifies a backfill job in the state store: `ingest:job:{symbol}:{start}`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JobKey {
+    pub symbol: String,
+    pub start: NaiveDate,
+}
+
+impl JobKey {
+    pub fn new(symbol: &str, start: NaiveDate) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            start,
+        }
+    }
+
+    /// Splits the start date off the right, so symbols containing `:` survive.
+    pub fn parse(key: &str) -> Result<Self, JobKeyError> {
+        let rest = key
+            .strip_prefix(JOB_KEY_PREFIX)
+            .ok_or_else(|| JobKeyError::MissingPrefix(key.to_string()))?;
+        let (symbol, start) = rest
+            .rsplit_once(':')
+            .ok_or_else(|| JobKeyError::MissingStartDate(key.to_string()))?;
+        if symbol.is_empty() {
+            return Err(JobKeyError::EmptySymbol(key.to_string()));
+        }
+        let start = NaiveDate::parse_from_str(start, "%Y-%m-%d")
+            .map_err(|_| JobKeyError::InvalidStartDate(key.to_string()))?;
+        Ok(Self::new(symbol, start))
+    }
+
+    /// Key of a `backfill_dates` job whose earliest date is `start`:
+    /// `ingest:dates-job:{symbol}:{start}`.
+    pub fn dates_key(&self) -> String {
+        format!("{}{}:{}", DATES_JOB_KEY_PREFIX, self.symbol, self.start)
+    }
+
+    /// Parses a key found while listing the store, logging and skipping
+    /// (`None`) anything that is not a well-formed job key.
+    pub fn parse_listed(key: &str) -> Option<Self> {
+        match Self::parse(key) {
+            Ok(job_key) => Some(job_key),
+            Err(err) => {
+                warn!("Skipping malformed job key: {}", err);
+                None
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum JobKeyError {
+    #[error("Job key '{0}' does not start with '{JOB_KEY_PREFIX}'")]
+    MissingPrefix(String),
+    #[error("Job key '{0}' has no start date")]
+    MissingStartDate(String),
+    #[error("Job key '{0}' has an empty symbol")]
+    EmptySymbol(String),
+    #[error("Job key '{0}' has an invalid start date")]
+    InvalidStartDate(String),
+}
+
+impl fmt::Display for JobKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}:{}", JOB_KEY_PREFIX, self.symbol, self.start)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum JobStatus {
@@ -12,24 +89,45 @@ pub enum JobStatus {
     Running,
     Completed,
     Failed,
+    /// Stopped on request; the next run resumes from the cursor.
+    Cancelled,
 }
 
 impl JobStatus {
+    pub const ALL: [JobStatus; 5] = [
+        JobStatus::Pending,
+        JobStatus::Running,
+        JobStatus::Completed,
+        JobStatus::Failed,
+        JobStatus::Cancelled,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             JobStatus::Pending => "PENDING",
             JobStatus::Running => "RUNNING",
             JobStatus::Completed => "COMPLETED",
             JobStatus::Failed => "FAILED",
+            JobStatus::Cancelled => "CANCELLED",
         }
     }
 
+    /// Whether the job has finished and will not change again on its own.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(value: &str) -> Option<Self> {
         match value {
             "PENDING" => Some(JobStatus::Pending),
             "RUNNING" => Some(JobStatus::Running),
             "COMPLETED" => Some(JobStatus::Completed),
             "FAILED" => Some(JobStatus::Failed),
+            "CANCELLED" => Some(JobStatus::Cancelled),
             _ => None,
         }
     }
@@ -47,6 +145,16 @@ pub struct JobState {
     #[serde(default)]
     #[serde(alias = "last_error")]
     pub last_error_type: Option<String>,
+    /// Checksum of the ticks fetched for each recently completed day, used to
+    /// notice when a re-fetch returns different data.
+    #[serde(default)]
+    pub day_checksums: BTreeMap<NaiveDate, u64>,
+    /// Ticks written for the job across every run, not just the latest.
+    #[serde(default)]
+    pub total_ticks: u64,
+    /// Latest day any run of the job has processed successfully.
+    #[serde(default)]
+    pub last_processed_date: Option<NaiveDate>,
 }
 
 impl JobState {
@@ -65,10 +173,27 @@ impl JobState {
             heartbeat_at,
             critical_ranges: Vec::new(),
             last_error_type: None,
+            day_checksums: BTreeMap::new(),
+            total_ticks: 0,
+            last_processed_date: None,
         }
     }
 }
 
+/// One status change or error in a job's audit trail.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobAuditEntry {
+    pub at: DateTime<Utc>,
+    pub event: JobAuditEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum JobAuditEvent {
+    Status(JobStatus),
+    Error(String),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CriticalRange {
     pub start: String,
@@ -83,12 +208,18 @@ pub enum JobStateError {
     StaleInstance(String),
     #[error("Backend error: {0}")]
     Backend(String),
+    /// The Redis endpoint is a cluster node that redirected the request.
+    #[error("Redis Cluster is not supported: {0}")]
+    ClusterNotSupported(String),
 }
 
 #[async_trait]
 pub trait JobStateRepository: Interface {
     async fn get(&self, job_key: &str) -> Result<Option<JobState>, JobStateError>;
     async fn upsert(&self, job_key: &str, state: &JobState) -> Result<(), JobStateError>;
+    /// Removes the job's state; deleting a job that does not exist is not
+    /// an error.
+    async fn delete(&self, job_key: &str) -> Result<(), JobStateError>;
     async fn update_cursor(
         &self,
         job_key: &str,
@@ -113,4 +244,140 @@ pub trait JobStateRepository: Interface {
         job_instance_id: &JobInstanceId,
         message: &str,
     ) -> Result<(), JobStateError>;
+    async fn update_day_checksums(
+        &self,
+        job_key: &str,
+        job_instance_id: &JobInstanceId,
+        day_checksums: &BTreeMap<NaiveDate, u64>,
+    ) -> Result<(), JobStateError>;
+    /// Stores the job's lifetime tick count and latest processed day.
+    async fn update_progress(
+        &self,
+        job_key: &str,
+        job_instance_id: &JobInstanceId,
+        total_ticks: u64,
+        last_processed_date: NaiveDate,
+    ) -> Result<(), JobStateError>;
+    /// Status changes and errors recorded for the job, oldest first. Stores
+    /// keep only a bounded number of recent entries; stores without an
+    /// audit trail return none.
+    async fn audit_trail(&self, _job_key: &str) -> Result<Vec<JobAuditEntry>, JobStateError> {
+        Ok(Vec::new())
+    }
+    /// Returns every job (key and state) currently in `status`.
+    async fn find_by_status(
+        &self,
+        status: JobStatus,
+    ) -> Result<Vec<(String, JobState)>, JobStateError>;
+    /// Returns every job whose key matches the glob `pattern` (`*` and `?`,
+    /// as in [`key_matches`]), e.g. `ingest:job:*` for all of them or
+    /// `ingest:job:NQ:*` for one symbol's.
+    async fn list_jobs(&self, pattern: &str) -> Result<Vec<(String, JobState)>, JobStateError>;
+}
+
+/// Whether `key` matches the glob `pattern`, where `*` matches any run of
+/// characters and `?` any single one. The subset of Redis `MATCH` syntax
+/// that job key patterns use.
+pub fn key_matches(pattern: &str, key: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let key: Vec<char> = key.chars().collect();
+    let (mut p, mut k) = (0, 0);
+    // Position of the last `*` and the key index it was tried at.
+    let mut star: Option<(usize, usize)> = None;
+    while k < key.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, k));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == key[k] => {
+                p += 1;
+                k += 1;
+            }
+            _ => match star {
+                // Let the last `*` swallow one more character.
+                Some((star_p, star_k)) => {
+                    p = star_p + 1;
+                    k = star_k + 1;
+                    star = Some((star_p, star_k + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Highest date reached by a completed job for `symbol`, across every start
+/// date the symbol has been backfilled from.
+pub async fn highest_completed_date(
+    repo: &dyn JobStateRepository,
+    symbol: &str,
+) -> Result<Option<NaiveDate>, JobStateError> {
+    Ok(repo
+        .find_by_status(JobStatus::Completed)
+        .await?
+        .into_iter()
+        .filter(|(key, _)| JobKey::parse_listed(key).is_some_and(|key| key.symbol == symbol))
+        .filter_map(|(_, state)| DateTime::<Utc>::from_timestamp_millis(state.cursor))
+        .map(|cursor| cursor.date_naive())
+        .max())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_patterns_match_like_redis_globs() {
+        let key = "ingest:job:NQ:2025-01-02";
+        for pattern in ["ingest:job:*", "ingest:job:NQ:*", "*", "*:2025-01-0?", key] {
+            assert!(key_matches(pattern, key), "{pattern}");
+        }
+        for pattern in ["ingest:job:ES:*", "ingest:job:", "?", "*:2025-01-0"] {
+            assert!(!key_matches(pattern, key), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn status_strings_round_trip() {
+        for status in JobStatus::ALL {
+            assert_eq!(JobStatus::from_str(status.as_str()), Some(status.clone()));
+            let json = serde_json::to_string(&status).unwrap();
+            assert_eq!(json, format!("\"{}\"", status.as_str()));
+            assert_eq!(serde_json::from_str::<JobStatus>(&json).unwrap(), status);
+        }
+        assert_eq!(JobStatus::from_str("CANCELED"), None);
+    }
+
+    #[test]
+    fn job_key_round_trips() {
+        let date = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        for symbol in ["NQ", "NQ:H5", "CL:NYMEX:F6"] {
+            let key = JobKey::new(symbol, date);
+            let raw = key.to_string();
+            assert_eq!(raw, format!("ingest:job:{}:2025-01-02", symbol));
+            assert_eq!(JobKey::parse(&raw), Ok(key));
+        }
+    }
+
+    #[test]
+    fn malformed_job_keys_are_typed_errors() {
+        let cases = [
+            (
+                "other:NQ:2025-01-02",
+                JobKeyError::MissingPrefix as fn(_) -> _,
+            ),
+            ("", JobKeyError::MissingPrefix),
+            ("ingest:job:NQ", JobKeyError::MissingStartDate),
+            ("ingest:job::2025-01-02", JobKeyError::EmptySymbol),
+            ("ingest:job:NQ:2025-13-40", JobKeyError::InvalidStartDate),
+            ("ingest:job:NQ:H5", JobKeyError::InvalidStartDate),
+            ("ingest:job:garbage:", JobKeyError::InvalidStartDate),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(JobKey::parse(raw), Err(expected(raw.to_string())), "{raw}");
+            assert_eq!(JobKey::parse_listed(raw), None);
+        }
+    }
 }
@@ -1,17 +1,31 @@
+pub mod backfill_events;
 pub mod backfill_service;
+pub mod gap_queue;
 pub mod historical_data;
 pub mod job_state;
 pub mod ports;
+pub mod progress;
 pub mod rate_limiter;
 pub mod services;
 
-pub use backfill_service::{BackfillError, BackfillReport, BackfillService, BackfillServiceImpl};
+pub use backfill_events::{BackfillEvent, BackfillProgress};
+pub use backfill_service::{
+    BackfillConfig, BackfillError, BackfillOptions, BackfillPlan, BackfillReport, BackfillService,
+    BackfillServiceImpl, DayOutcome, InvalidTickPolicy, NoDataPolicy, PlanReason, PlannedDay,
+    RateBudget,
+};
+pub use gap_queue::{GapQueue, GapQueueError};
 pub use historical_data::{
-    GapDetectionError, GapDetector, HistoricalDataError, HistoricalDataGateway,
+    parse_retry_after, ContractResolver, FetchedTicks, GapDetectionError, GapDetector,
+    HistoricalDataError, HistoricalDataGateway, HistoricalTickStream,
 };
 pub use job_state::{
-    CriticalRange, JobInstanceId, JobState, JobStateError, JobStateRepository, JobStatus,
+    highest_completed_date, key_matches, CriticalRange, JobAuditEntry, JobAuditEvent,
+    JobInstanceId, JobKey, JobKeyError, JobState, JobStateError, JobStateRepository, JobStatus,
+};
+pub use ports::{
+    DeadLetterSink, DepthRepository, MarketDataGateway, SaveStreamError, TickRepository,
 };
-pub use ports::{MarketDataGateway, TickRepository};
+pub use progress::{basket_progress, BasketProgress, SymbolProgress};
 pub use rate_limiter::RateLimiter;
-pub use services::IngestionServiceImpl;
+pub use services::{BusyFlushPolicy, IngestionServiceImpl};
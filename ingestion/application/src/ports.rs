@@ -1,7 +1,11 @@
 use async_trait::async_trait;
-use ingestion_domain::Tick;
+use chrono::NaiveDate;
+use futures::StreamExt;
+use ingestion_domain::{MarketDepth, Tick};
 use shaku::Interface;
 
+use crate::historical_data::{HistoricalDataError, HistoricalTickStream};
+
 #[async_trait]
 pub trait MarketDataGateway: Interface {
     async fn subscribe(&self, symbol: &str) -> Result<TickStream, GatewayError>;
@@ -10,6 +14,105 @@ pub trait MarketDataGateway: Interface {
 #[async_trait]
 pub trait TickRepository: Interface {
     async fn save_batch(&self, ticks: Vec<Tick>) -> Result<(), RepositoryError>;
+    /// Periodic flush of buffered ticks. Stores may keep small buffers back
+    /// until there is enough to write efficiently; `shutdown` writes all.
+    async fn flush(&self) -> Result<(), RepositoryError>;
+    async fn shutdown(&self) -> Result<(), RepositoryError>;
+
+    /// Restores ticks a previous run logged but never durably stored, and
+    /// returns how many. Call at startup, before the first `save_batch`.
+    /// Stores without a write-ahead log recover nothing.
+    async fn recover(&self) -> Result<usize, RepositoryError> {
+        Ok(0)
+    }
+
+    /// Like `flush`, but returns `Ok(false)` instead of waiting when a write
+    /// is in progress. For periodic flushes: the write under way makes
+    /// waiting pointless, and waiting would let flushes queue up behind it.
+    async fn try_flush(&self) -> Result<bool, RepositoryError> {
+        self.flush().await.map(|()| true)
+    }
+
+    /// Saves `ticks` as they arrive, `chunk_size` at a time, so at most one
+    /// chunk is held in memory. Returns how many ticks were saved. On a
+    /// source error the chunks already saved stay saved.
+    async fn save_stream(
+        &self,
+        mut ticks: HistoricalTickStream<'_>,
+        chunk_size: usize,
+    ) -> Result<usize, SaveStreamError> {
+        let chunk_size = chunk_size.max(1);
+        let mut chunk = Vec::with_capacity(chunk_size);
+        let mut saved = 0;
+        while let Some(tick) = ticks.next().await {
+            chunk.push(tick?);
+            if chunk.len() == chunk_size {
+                saved += chunk.len();
+                self.save_batch(std::mem::replace(
+                    &mut chunk,
+                    Vec::with_capacity(chunk_size),
+                ))
+                .await?;
+            }
+        }
+        if !chunk.is_empty() {
+            saved += chunk.len();
+            self.save_batch(chunk).await?;
+        }
+        Ok(saved)
+    }
+
+    /// Replaces everything stored for `symbol` on `date` with `ticks`.
+    /// Each stored unit (e.g. a file) is swapped whole, so readers never see
+    /// one half written; a day stored in several units may briefly read as
+    /// part old, part new. Stores without files to replace just save the
+    /// batch.
+    async fn replace_day(
+        &self,
+        _symbol: &str,
+        _date: NaiveDate,
+        ticks: Vec<Tick>,
+    ) -> Result<(), RepositoryError> {
+        self.save_batch(ticks).await
+    }
+
+    /// Names the provider of the ticks saved from now on, for stores that
+    /// keep provenance.
+    async fn set_source(&self, _source: &str) {}
+
+    /// Records that `symbol` has no data on `date`, so gap detection treats
+    /// the day as present. Stores without such a marker do nothing.
+    async fn mark_no_data(&self, _symbol: &str, _date: NaiveDate) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+
+    /// Deletes `symbol`'s stored files that hold no ticks, such as those
+    /// left by aborted writes, and returns how many were removed. Stores
+    /// without files remove nothing.
+    async fn prune_empty(&self, _symbol: &str) -> Result<usize, RepositoryError> {
+        Ok(0)
+    }
+
+    /// Resolves data written for `symbol` on `date` by both a backfill and
+    /// live ingestion into a single authoritative copy. Stores that cannot
+    /// hold conflicting copies do nothing.
+    async fn reconcile_day(&self, _symbol: &str, _date: NaiveDate) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+}
+
+/// Keeps tick batches the repository failed to write, so a transient write
+/// failure loses no data and the batch can be re-ingested later.
+#[async_trait]
+pub trait DeadLetterSink: Interface {
+    async fn write_batch(&self, symbol: &str, ticks: &[Tick]) -> Result<(), RepositoryError>;
+}
+
+/// Persists order-book depth snapshots. Opt-in: the top-of-book path only
+/// needs [`TickRepository`].
+#[async_trait]
+pub trait DepthRepository: Interface {
+    async fn save_batch(&self, snapshots: Vec<MarketDepth>) -> Result<(), RepositoryError>;
     async fn flush(&self) -> Result<(), RepositoryError>;
     async fn shutdown(&self) -> Result<(), RepositoryError>;
 }
@@ -38,4 +141,18 @@ pub enum RepositoryError {
 
     #[error("File rotation error: {0}")]
     FileRotationError(String),
+
+    #[error("File is locked by another writer: {0}")]
+    FileLocked(String),
+}
+
+/// Failure of [`TickRepository::save_stream`]: either the stream or the
+/// store gave out.
+#[derive(Debug, thiserror::Error)]
+pub enum SaveStreamError {
+    #[error("Source error: {0}")]
+    Source(#[from] HistoricalDataError),
+
+    #[error("Repository error: {0}")]
+    Repository(#[from] RepositoryError),
 }
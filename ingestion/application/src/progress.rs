@@ -0,0 +1,100 @@
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use ingestion_domain::{DateRange, TradingCalendar};
+use std::collections::HashMap;
+
+use crate::job_state::{
+    JobKey, JobState, JobStateError, JobStateRepository, JobStatus, JOB_KEY_PREFIX,
+};
+
+/// One symbol's share of a basket backfill, in trading days.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolProgress {
+    pub symbol: String,
+    /// `None` when the symbol has no job state for the range yet.
+    pub status: Option<JobStatus>,
+    pub done_days: u32,
+    pub total_days: u32,
+}
+
+impl SymbolProgress {
+    pub fn percent(&self) -> f64 {
+        percent(self.done_days, self.total_days)
+    }
+}
+
+/// Progress across several symbols backfilled over the same range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasketProgress {
+    pub range: DateRange,
+    pub symbols: Vec<SymbolProgress>,
+}
+
+impl BasketProgress {
+    /// Completion weighted by each symbol's trading days.
+    pub fn percent(&self) -> f64 {
+        let done = self.symbols.iter().map(|s| s.done_days).sum();
+        let total = self.symbols.iter().map(|s| s.total_days).sum();
+        percent(done, total)
+    }
+}
+
+fn percent(done: u32, total: u32) -> f64 {
+    if total == 0 {
+        return 100.0;
+    }
+    f64::from(done) * 100.0 / f64::from(total)
+}
+
+/// Combines the job states of `symbols` for backfills starting at
+/// `range.start()`. A job's completion comes from its cursor: every trading
+/// day up to the cursor's date is done. Completed jobs count in full and
+/// symbols without a job count as 0%.
+pub async fn basket_progress(
+    repo: &dyn JobStateRepository,
+    symbols: &[String],
+    range: &DateRange,
+    calendar: &TradingCalendar,
+) -> Result<BasketProgress, JobStateError> {
+    let mut states: HashMap<String, JobState> = HashMap::new();
+    for (key, state) in repo.list_jobs(&format!("{}*", JOB_KEY_PREFIX)).await? {
+        match JobKey::parse_listed(&key) {
+            Some(key) if key.start == range.start() => {
+                states.insert(key.symbol, state);
+            }
+            _ => {}
+        }
+    }
+
+    let before_start = range
+        .start()
+        .checked_sub_days(Days::new(1))
+        .unwrap_or(NaiveDate::MIN);
+    let total_days = calendar.trading_days_between(before_start, range.end());
+
+    let symbols = symbols
+        .iter()
+        .map(|symbol| {
+            let state = states.remove(symbol);
+            let done_days = match &state {
+                None => 0,
+                Some(state) if state.status == JobStatus::Completed => total_days,
+                Some(state) => DateTime::<Utc>::from_timestamp_millis(state.cursor)
+                    .map(|cursor| cursor.date_naive().min(range.end()))
+                    .map_or(0, |cursor| {
+                        calendar.trading_days_between(before_start, cursor)
+                    }),
+            };
+            SymbolProgress {
+                symbol: symbol.clone(),
+                status: state.map(|state| state.status),
+                done_days,
+                total_days,
+            }
+        })
+        .collect();
+
+    Ok(BasketProgress {
+        range: range.clone(),
+        symbols,
+    })
+}
@@ -1,9 +1,100 @@
 use async_trait::async_trait;
 use shaku::Interface;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// The contract a request is for. IB paces requests per contract, exchange
+/// and tick type on top of the account-wide limit, so limiters key their
+/// per-contract windows by [`Self::contract_key`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RateLimitScope {
+    pub symbol: String,
+    pub exchange: Option<String>,
+    pub tick_type: Option<String>,
+}
+
+impl RateLimitScope {
+    pub fn contract(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            exchange: None,
+            tick_type: None,
+        }
+    }
+
+    pub fn with_exchange(mut self, exchange: impl Into<String>) -> Self {
+        self.exchange = Some(exchange.into());
+        self
+    }
+
+    pub fn with_tick_type(mut self, tick_type: impl Into<String>) -> Self {
+        self.tick_type = Some(tick_type.into());
+        self
+    }
+
+    /// `symbol:exchange:tick_type`, with unset parts left empty.
+    pub fn contract_key(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.symbol,
+            self.exchange.as_deref().unwrap_or_default(),
+            self.tick_type.as_deref().unwrap_or_default()
+        )
+    }
+}
 
 #[async_trait]
 pub trait RateLimiter: Interface {
-    async fn acquire(&self) -> Result<(), RateLimiterError>;
+    async fn acquire(&self, scope: &RateLimitScope) -> Result<(), RateLimiterError>;
+
+    /// Attempts a single admission without waiting; `Ok(false)` means the
+    /// limit is currently exhausted. Limiters that cannot refuse admit.
+    async fn try_acquire(&self, scope: &RateLimitScope) -> Result<bool, RateLimiterError> {
+        self.acquire(scope).await.map(|()| true)
+    }
+
+    /// Like `acquire`, but gives up with [`RateLimiterError::Cancelled`] as
+    /// soon as `token` is cancelled instead of waiting out the window.
+    async fn acquire_cancellable(
+        &self,
+        scope: &RateLimitScope,
+        token: &CancellationToken,
+    ) -> Result<(), RateLimiterError> {
+        tokio::select! {
+            biased;
+            _ = token.cancelled() => Err(RateLimiterError::Cancelled),
+            result = self.acquire(scope) => result,
+        }
+    }
+
+    /// Like `acquire`, but gives up with [`RateLimiterError::Timeout`] if no
+    /// slot frees up within `timeout`, so a saturated limiter can't stall the
+    /// caller indefinitely.
+    async fn acquire_with_timeout(
+        &self,
+        scope: &RateLimitScope,
+        timeout: Duration,
+    ) -> Result<(), RateLimiterError> {
+        tokio::time::timeout(timeout, self.acquire(scope))
+            .await
+            .map_err(|_| RateLimiterError::Timeout(timeout))?
+    }
+
+    /// Acquires a slot like `acquire` and returns how long the caller was blocked.
+    async fn acquire_with_estimate(
+        &self,
+        scope: &RateLimitScope,
+    ) -> Result<Duration, RateLimiterError> {
+        let started = Instant::now();
+        self.acquire(scope).await?;
+        Ok(started.elapsed())
+    }
+
+    /// Estimates how long an `acquire` for `scope` issued now would block,
+    /// from the current window occupancy. Does not reserve a slot.
+    async fn estimated_wait(&self, _scope: &RateLimitScope) -> Result<Duration, RateLimiterError> {
+        Ok(Duration::ZERO)
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -18,6 +109,19 @@ pub enum RateLimiterError {
     #[error("Failed to execute rate limiting script: {0}")]
     ScriptError(String),
 
+    /// The Redis endpoint is part of a cluster and redirected the request.
+    /// Point `REDIS_URL` at a standalone (non-cluster) instance.
+    #[error("Redis Cluster is not supported: {0}")]
+    ClusterNotSupported(String),
+
+    /// The caller's cancellation token fired while waiting for a slot.
+    #[error("Rate limiter wait was cancelled")]
+    Cancelled,
+
+    /// No slot freed up within the caller's timeout.
+    #[error("Timed out after {0:?} waiting for a rate limiter slot")]
+    Timeout(Duration),
+
     /// An unexpected internal error occurred while enforcing rate limits.
     /// Should not happen under normal conditions.
     #[error("An unexpected error occurred: {0}")]
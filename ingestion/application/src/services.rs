@@ -1,10 +1,13 @@
 use crate::ports::{MarketDataGateway, TickRepository};
 use async_trait::async_trait;
+use chrono::Utc;
 use futures::StreamExt;
+use ingestion_domain::{SymbolAlias, Tick};
 use shaku::{Component, Interface};
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
-use tracing::{error, info, warn};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, error, info, warn};
 
 #[async_trait]
 pub trait IngestionService: Interface {
@@ -18,8 +21,110 @@ pub struct IngestionServiceImpl {
     gateway: Arc<dyn MarketDataGateway>,
     #[shaku(inject)]
     repository: Arc<dyn TickRepository>,
+    /// Ticks buffered before a count-triggered flush. `0` disables count
+    /// flushing, so ticks are only written when `flush_interval` elapses.
     batch_size: usize,
+    /// Interval between timer flushes. Must be non-zero, as it is the only
+    /// flush trigger when `batch_size` is 0; `run` rejects a zero interval
+    /// with [`IngestionError::InvalidConfig`].
     flush_interval: Duration,
+    /// When set, the first timer flush waits for the next wall-clock multiple
+    /// of this duration (e.g. one minute) instead of one `flush_interval`
+    /// from start.
+    #[shaku(default)]
+    flush_alignment: Option<Duration>,
+    /// Stamp each received tick with its receive latency.
+    #[shaku(default)]
+    record_recv_latency: bool,
+    /// What a timer flush does when the repository is busy writing.
+    #[shaku(default)]
+    busy_flush: BusyFlushPolicy,
+    /// Rewrites each received tick's symbol to its canonical form, so the
+    /// same instrument lands in the same files whatever the source calls it.
+    #[shaku(default)]
+    symbol_alias: SymbolAlias,
+}
+
+/// Behaviour of the periodic repository flush when another write (e.g. a
+/// file rotation) holds the repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BusyFlushPolicy {
+    /// Skip this flush; the next timer tick flushes what is still buffered.
+    #[default]
+    Skip,
+    /// Wait for the write to finish, then flush.
+    Wait,
+}
+
+impl IngestionServiceImpl {
+    pub fn new(
+        gateway: Arc<dyn MarketDataGateway>,
+        repository: Arc<dyn TickRepository>,
+        batch_size: usize,
+        flush_interval: Duration,
+    ) -> Self {
+        Self {
+            gateway,
+            repository,
+            batch_size,
+            flush_interval,
+            flush_alignment: None,
+            record_recv_latency: false,
+            busy_flush: BusyFlushPolicy::default(),
+            symbol_alias: SymbolAlias::default(),
+        }
+    }
+
+    pub fn with_flush_alignment(mut self, alignment: Duration) -> Self {
+        self.flush_alignment = Some(alignment);
+        self
+    }
+
+    pub fn with_recv_latency(mut self, enabled: bool) -> Self {
+        self.record_recv_latency = enabled;
+        self
+    }
+
+    pub fn with_busy_flush_policy(mut self, policy: BusyFlushPolicy) -> Self {
+        self.busy_flush = policy;
+        self
+    }
+
+    pub fn with_symbol_alias(mut self, symbol_alias: SymbolAlias) -> Self {
+        self.symbol_alias = symbol_alias;
+        self
+    }
+
+    fn first_flush_delay(&self) -> Duration {
+        match self.flush_alignment {
+            Some(alignment) => {
+                let since_epoch = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                let alignment_nanos = alignment.as_nanos();
+                let into_period = since_epoch.as_nanos() % alignment_nanos;
+                Duration::from_nanos(((alignment_nanos - into_period) % alignment_nanos) as u64)
+            }
+            None => self.flush_interval,
+        }
+    }
+
+    fn should_count_flush(&self, buffered: usize) -> bool {
+        self.batch_size > 0 && buffered >= self.batch_size
+    }
+}
+
+/// Numbers `tick` in arrival order among its symbol's ticks this run, so
+/// ticks sharing a timestamp keep their order in storage. A sequence the
+/// gateway already assigned is kept.
+fn sequenced(next_sequence: &mut HashMap<String, u64>, tick: Tick) -> Tick {
+    if tick.sequence().is_some() {
+        return tick;
+    }
+    let next = next_sequence.entry(tick.symbol().to_string()).or_default();
+    let sequence = *next;
+    *next += 1;
+    tick.with_sequence(sequence)
 }
 
 #[async_trait]
@@ -27,6 +132,20 @@ impl IngestionService for IngestionServiceImpl {
     async fn run(&self, symbol: &str) -> Result<(), IngestionError> {
         info!("Starting ingestion service for symbol: {}", symbol);
 
+        if self.flush_interval.is_zero() {
+            return Err(IngestionError::InvalidConfig(
+                "flush_interval must be greater than zero".to_string(),
+            ));
+        }
+        if self
+            .flush_alignment
+            .is_some_and(|alignment| alignment.is_zero())
+        {
+            return Err(IngestionError::InvalidConfig(
+                "flush_alignment must be greater than zero".to_string(),
+            ));
+        }
+
         let mut stream = self
             .gateway
             .subscribe(symbol)
@@ -34,32 +153,56 @@ impl IngestionService for IngestionServiceImpl {
             .map_err(IngestionError::GatewayError)?;
 
         let mut batch = Vec::with_capacity(self.batch_size);
-        let mut flush_timer = tokio::time::interval(self.flush_interval);
+        let mut next_sequence = HashMap::new();
+        if self.batch_size == 0 {
+            info!(
+                "batch_size is 0: flushing every {:?} only",
+                self.flush_interval
+            );
+        }
+        // Start one interval (or alignment boundary) out: the immediate first
+        // tick of a plain `interval` would flush whatever arrived during
+        // subscription.
+        let mut flush_timer = tokio::time::interval_at(
+            tokio::time::Instant::now() + self.first_flush_delay(),
+            self.flush_interval,
+        );
 
         loop {
             tokio::select! {
-                Some(tick_result) = stream.next() => {
+                tick_result = stream.next() => {
                     match tick_result {
-                        Ok(tick) => {
-                            batch.push(tick);
-                            if batch.len() >= self.batch_size {
+                        Some(Ok(tick)) => {
+                            let tick = self.symbol_alias.apply(tick);
+                            let tick = if self.record_recv_latency {
+                                let latency = Utc::now().signed_duration_since(tick.timestamp());
+                                tick.with_recv_latency_ms(latency.num_milliseconds())
+                            } else {
+                                tick
+                            };
+                            batch.push(sequenced(&mut next_sequence, tick));
+                            if self.should_count_flush(batch.len()) {
                                 self.flush_batch(&mut batch).await?;
                             }
                         }
-                        Err(e) => {
+                        Some(Err(e)) => {
                             error!("Stream error: {}", e);
                             return Err(IngestionError::GatewayError(e));
                         }
+                        // The timer branch never disables, so a `select!`
+                        // `else` would not fire; stop here instead of polling
+                        // a finished stream again.
+                        None => {
+                            warn!("Market data stream ended");
+                            break;
+                        }
                     }
                 }
                 _ = flush_timer.tick() => {
                     if !batch.is_empty() {
                         self.flush_batch(&mut batch).await?;
                     }
-                }
-                else => {
-                    warn!("Market data stream ended");
-                    break;
+                    self.flush_repository().await?;
                 }
             }
         }
@@ -91,6 +234,19 @@ impl IngestionServiceImpl {
         batch.clear();
         Ok(())
     }
+
+    /// Periodic flush of what the repository has buffered.
+    async fn flush_repository(&self) -> Result<(), IngestionError> {
+        match self.busy_flush {
+            BusyFlushPolicy::Wait => self.repository.flush().await?,
+            BusyFlushPolicy::Skip => {
+                if !self.repository.try_flush().await? {
+                    debug!("Skipped timer flush: repository is busy writing");
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -100,4 +256,7 @@ pub enum IngestionError {
 
     #[error("Repository error: {0}")]
     RepositoryError(#[from] crate::ports::RepositoryError),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
 }
@@ -0,0 +1,140 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use common::*;
+use ingestion_application::{
+    BackfillOptions, BackfillService, BackfillServiceImpl, HistoricalDataError,
+    HistoricalDataGateway, JobStatus,
+};
+use ingestion_domain::{DateRange, Tick};
+use tokio_util::sync::CancellationToken;
+
+/// Every fetch blocks as if stuck behind an exhausted rate-limit window.
+struct SaturatedGateway;
+
+#[async_trait]
+impl HistoricalDataGateway for SaturatedGateway {
+    async fn fetch_historical_ticks(
+        &self,
+        _symbol: &str,
+        _date: NaiveDate,
+    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        tokio::time::sleep(Duration::from_secs(600)).await;
+        Ok(Vec::new())
+    }
+
+    fn max_history_days(&self) -> u32 {
+        u32::MAX
+    }
+}
+
+#[tokio::test]
+async fn cancelling_during_a_limiter_wait_stops_promptly() {
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let range = DateRange::new(day(1), day(3)).unwrap();
+    let service = BackfillServiceImpl::new(
+        Arc::new(SaturatedGateway),
+        Arc::new(StubGapDetector::new(vec![range.clone()])),
+        Arc::new(RecordingTickRepository::default()),
+        job_repo.clone(),
+    );
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        canceller.cancel();
+    });
+
+    let started = Instant::now();
+    let result = service
+        .backfill_range_with_options(
+            "ES",
+            range,
+            BackfillOptions {
+                cancel: Some(token),
+                ..BackfillOptions::default()
+            },
+        )
+        .await;
+
+    let report = result.unwrap();
+    assert!(report.cancelled);
+    assert_eq!(report.days_processed, 0);
+    assert!(
+        started.elapsed() < Duration::from_secs(2),
+        "cancellation took {:?}",
+        started.elapsed()
+    );
+    let state = job_repo.snapshot(&job_key("ES", day(1))).await.unwrap();
+    assert_eq!(state.status, JobStatus::Cancelled);
+}
+
+/// Cancels the run's token once the first day has been fetched.
+struct CancelAfterFirstDay {
+    inner: ScriptedHistoricalGateway,
+    token: CancellationToken,
+}
+
+#[async_trait]
+impl HistoricalDataGateway for CancelAfterFirstDay {
+    async fn fetch_historical_ticks(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        let ticks = self.inner.fetch_historical_ticks(symbol, date).await;
+        self.token.cancel();
+        ticks
+    }
+
+    fn max_history_days(&self) -> u32 {
+        u32::MAX
+    }
+}
+
+#[tokio::test]
+async fn cancelling_after_the_first_day_returns_a_partial_report() {
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let repository = Arc::new(RecordingTickRepository::default());
+    let range = DateRange::new(day(1), day(3)).unwrap();
+    let token = CancellationToken::new();
+    let gateway = Arc::new(CancelAfterFirstDay {
+        inner: ScriptedHistoricalGateway::with_ticks(
+            (1..=3)
+                .map(|d| (day(d), sample_ticks("NQ", day(d), 2)))
+                .collect(),
+        ),
+        token: token.clone(),
+    });
+    let service = BackfillServiceImpl::new(
+        gateway,
+        Arc::new(StubGapDetector::new(vec![range.clone()])),
+        repository.clone(),
+        job_repo.clone(),
+    );
+
+    let report = service
+        .backfill_range_with_options(
+            "NQ",
+            range,
+            BackfillOptions {
+                cancel: Some(token),
+                ..BackfillOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert!(report.cancelled);
+    assert_eq!(report.days_processed, 1);
+    assert!(report.failed_days.is_empty());
+    assert_eq!(repository.saved_days().await, vec![day(1)]);
+    assert!(repository.shutdown_called());
+    let state = job_repo.snapshot(&job_key("NQ", day(1))).await.unwrap();
+    assert_eq!(state.status, JobStatus::Cancelled);
+    assert_eq!(state.cursor, timestamp_for(day(1), 11, 0));
+}
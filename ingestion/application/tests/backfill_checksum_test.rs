@@ -0,0 +1,78 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::*;
+use ingestion_application::{BackfillConfig, BackfillEvent, BackfillOptions, BackfillService};
+use ingestion_domain::DateRange;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+async fn refetched_day_with_different_data_is_reported() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![
+        (day(2), sample_ticks("NQ", day(2), 3)),
+        (day(2), sample_ticks("NQ", day(2), 3)),
+        (day(2), sample_ticks("NQ", day(2), 4)),
+    ]));
+    let service = build_service(
+        gateway,
+        vec![DateRange::single_day(day(2))],
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig::default(),
+    );
+
+    let mut changed_per_run = Vec::new();
+    for _ in 0..3 {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        service
+            .backfill_range_with_options(
+                "NQ",
+                DateRange::single_day(day(2)),
+                BackfillOptions {
+                    events: Some(tx),
+                    ..BackfillOptions::default()
+                },
+            )
+            .await
+            .unwrap();
+        let mut changed = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let BackfillEvent::SourceDataChanged { date } = event {
+                changed.push(date);
+            }
+        }
+        changed_per_run.push(changed);
+    }
+
+    assert_eq!(changed_per_run, vec![vec![], vec![], vec![day(2)]]);
+}
+
+#[tokio::test]
+async fn stored_checksums_are_bounded() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(
+        (2..=5)
+            .map(|d| (day(d), sample_ticks("NQ", day(d), 1)))
+            .collect(),
+    ));
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let service = build_service(
+        gateway,
+        vec![DateRange::new(day(2), day(5)).unwrap()],
+        Arc::new(RecordingTickRepository::default()),
+        job_repo.clone(),
+        BackfillConfig {
+            max_day_checksums: 2,
+            ..BackfillConfig::default()
+        },
+    );
+
+    service
+        .backfill_range("NQ", DateRange::new(day(2), day(5)).unwrap())
+        .await
+        .unwrap();
+
+    let state = job_repo.snapshot(&job_key("NQ", day(2))).await.unwrap();
+    let days: Vec<_> = state.day_checksums.keys().copied().collect();
+    assert_eq!(days, vec![day(4), day(5)]);
+}
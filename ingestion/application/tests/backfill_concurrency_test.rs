@@ -0,0 +1,269 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use common::*;
+use ingestion_application::ports::RepositoryError;
+use ingestion_application::{
+    BackfillConfig, BackfillService, BackfillServiceImpl, HistoricalDataError,
+    HistoricalDataGateway, TickRepository,
+};
+use ingestion_domain::{DateRange, Tick};
+
+/// Gateway that takes a set time per day and tracks how many fetches run
+/// at once.
+#[derive(Default)]
+struct SlowGateway {
+    delays: HashMap<NaiveDate, Duration>,
+    failing: Option<NaiveDate>,
+    in_flight: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl SlowGateway {
+    fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl HistoricalDataGateway for SlowGateway {
+    async fn fetch_historical_ticks(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(now, Ordering::SeqCst);
+        if let Some(delay) = self.delays.get(&date) {
+            tokio::time::sleep(*delay).await;
+        }
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        if self.failing == Some(date) {
+            return Err(HistoricalDataError::GatewayError(
+                "connection reset".to_string(),
+            ));
+        }
+        Ok(sample_ticks(symbol, date, 1))
+    }
+
+    fn max_history_days(&self) -> u32 {
+        u32::MAX
+    }
+}
+
+/// Repository that takes a set time per batch and tracks how many writes
+/// run at once.
+#[derive(Default)]
+struct SlowRepository {
+    inner: RecordingTickRepository,
+    delay: Duration,
+    in_flight: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+#[async_trait]
+impl TickRepository for SlowRepository {
+    async fn save_batch(&self, ticks: Vec<Tick>) -> Result<(), RepositoryError> {
+        let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(now, Ordering::SeqCst);
+        tokio::time::sleep(self.delay).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.inner.save_batch(ticks).await
+    }
+
+    async fn flush(&self) -> Result<(), RepositoryError> {
+        self.inner.flush().await
+    }
+
+    async fn shutdown(&self) -> Result<(), RepositoryError> {
+        self.inner.shutdown().await
+    }
+}
+
+fn service(
+    gateway: Arc<SlowGateway>,
+    range: &DateRange,
+    job_repo: Arc<InMemoryJobStateRepository>,
+    max_concurrent_days: usize,
+) -> BackfillServiceImpl {
+    BackfillServiceImpl::new(
+        gateway,
+        Arc::new(StubGapDetector::new(vec![range.clone()])),
+        Arc::new(RecordingTickRepository::default()),
+        job_repo,
+    )
+    .with_config(BackfillConfig {
+        max_concurrent_days,
+        ..BackfillConfig::default()
+    })
+}
+
+#[tokio::test]
+async fn days_are_fetched_concurrently_up_to_the_limit() {
+    let range = DateRange::new(day(1), day(6)).unwrap();
+    let delays = (1..=6)
+        .map(|d| (day(d), Duration::from_millis(50)))
+        .collect::<HashMap<_, _>>();
+
+    for (limit, expected_peak) in [(1, 1), (3, 3)] {
+        let gateway = Arc::new(SlowGateway {
+            delays: delays.clone(),
+            ..SlowGateway::default()
+        });
+        let report = service(
+            gateway.clone(),
+            &range,
+            Arc::new(InMemoryJobStateRepository::new()),
+            limit,
+        )
+        .backfill_range("NQ", range.clone())
+        .await
+        .unwrap();
+
+        assert_eq!(report.days_processed, 6, "limit {}", limit);
+        assert_eq!(gateway.peak(), expected_peak, "limit {}", limit);
+    }
+}
+
+#[tokio::test]
+async fn days_fetched_early_wait_for_a_slow_earlier_day() {
+    let range = DateRange::new(day(1), day(3)).unwrap();
+    let gateway = Arc::new(SlowGateway {
+        delays: HashMap::from([(day(1), Duration::from_millis(100))]),
+        ..SlowGateway::default()
+    });
+    let repository = Arc::new(RecordingTickRepository::default());
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+
+    BackfillServiceImpl::new(
+        gateway,
+        Arc::new(StubGapDetector::new(vec![range.clone()])),
+        repository.clone(),
+        job_repo.clone(),
+    )
+    .with_config(BackfillConfig {
+        max_concurrent_days: 3,
+        ..BackfillConfig::default()
+    })
+    .backfill_range("NQ", range.clone())
+    .await
+    .unwrap();
+
+    // Days 2 and 3 are fetched first, but written, and the cursor moved,
+    // only after day 1.
+    assert_eq!(repository.saved_days().await, vec![day(1), day(2), day(3)]);
+    assert_eq!(
+        job_repo.cursor_updates().await,
+        (1..=3)
+            .map(|d| timestamp_for(day(d), 10, 0))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn cursor_does_not_pass_a_failed_day() {
+    let range = DateRange::new(day(1), day(3)).unwrap();
+    let gateway = Arc::new(SlowGateway {
+        delays: HashMap::from([(day(1), Duration::from_millis(50))]),
+        failing: Some(day(2)),
+        ..SlowGateway::default()
+    });
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+
+    let report = service(gateway, &range, job_repo.clone(), 3)
+        .backfill_range("NQ", range.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(report.days_processed, 2);
+    assert_eq!(report.failed_days.len(), 1);
+    assert_eq!(report.failed_days[0].0, day(2));
+    assert_eq!(
+        job_repo.cursor_updates().await,
+        vec![timestamp_for(day(1), 10, 0)]
+    );
+    let state = job_repo.snapshot(&job_key("NQ", day(1))).await.unwrap();
+    assert_eq!(state.cursor, timestamp_for(day(1), 10, 0));
+}
+
+#[tokio::test]
+async fn cursor_stops_before_a_hole_left_by_a_failed_middle_day() {
+    let range = DateRange::new(day(1), day(4)).unwrap();
+    // Day 4 finishes first and day 3 fails last, with day 4 already done.
+    let gateway = Arc::new(SlowGateway {
+        delays: HashMap::from([
+            (day(1), Duration::from_millis(30)),
+            (day(2), Duration::from_millis(60)),
+            (day(3), Duration::from_millis(90)),
+        ]),
+        failing: Some(day(3)),
+        ..SlowGateway::default()
+    });
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+
+    let report = service(gateway, &range, job_repo.clone(), 4)
+        .backfill_range("NQ", range.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(report.days_processed, 3);
+    assert_eq!(report.failed_days.len(), 1);
+    assert_eq!(report.failed_days[0].0, day(3));
+    assert_eq!(
+        job_repo.cursor_updates().await,
+        vec![timestamp_for(day(1), 10, 0), timestamp_for(day(2), 10, 0)]
+    );
+    let state = job_repo.snapshot(&job_key("NQ", day(1))).await.unwrap();
+    assert_eq!(state.cursor, timestamp_for(day(2), 10, 0));
+}
+
+#[tokio::test]
+async fn fetch_and_write_concurrency_are_limited_separately() {
+    let range = DateRange::new(day(1), day(6)).unwrap();
+    let gateway = Arc::new(SlowGateway {
+        delays: (1..=6)
+            .map(|d| (day(d), Duration::from_millis(30)))
+            .collect(),
+        ..SlowGateway::default()
+    });
+    let repository = Arc::new(SlowRepository {
+        delay: Duration::from_millis(60),
+        ..SlowRepository::default()
+    });
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+
+    let report = BackfillServiceImpl::new(
+        gateway.clone(),
+        Arc::new(StubGapDetector::new(vec![range.clone()])),
+        repository.clone(),
+        job_repo.clone(),
+    )
+    .with_config(BackfillConfig {
+        max_concurrent_days: 3,
+        max_concurrent_writes: 2,
+        ..BackfillConfig::default()
+    })
+    .backfill_range("NQ", range.clone())
+    .await
+    .unwrap();
+
+    assert_eq!(report.days_processed, 6);
+    assert_eq!(report.total_ticks, 6);
+    assert_eq!(gateway.peak(), 3);
+    assert_eq!(repository.peak.load(Ordering::SeqCst), 2);
+    let mut saved = repository.inner.saved_days().await;
+    saved.sort();
+    assert_eq!(saved, (1..=6).map(day).collect::<Vec<_>>());
+    assert_eq!(
+        job_repo.cursor_updates().await.last(),
+        Some(&timestamp_for(day(6), 10, 0))
+    );
+    let state = job_repo.snapshot(&job_key("NQ", day(1))).await.unwrap();
+    assert_eq!(state.cursor, timestamp_for(day(6), 10, 0));
+}
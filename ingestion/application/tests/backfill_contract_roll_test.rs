@@ -0,0 +1,69 @@
+mod common;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use common::*;
+use ingestion_application::{
+    BackfillService, BackfillServiceImpl, HistoricalDataError, HistoricalDataGateway,
+};
+use ingestion_domain::{DateRange, RollSchedule, Tick};
+use tokio::sync::Mutex;
+
+/// Gateway that records the symbol each day was fetched as, and returns
+/// ticks carrying that symbol.
+#[derive(Default)]
+struct ContractGateway {
+    fetches: Mutex<Vec<(String, NaiveDate)>>,
+}
+
+#[async_trait]
+impl HistoricalDataGateway for ContractGateway {
+    async fn fetch_historical_ticks(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        self.fetches.lock().await.push((symbol.to_string(), date));
+        Ok(sample_ticks(symbol, date, 2))
+    }
+
+    fn max_history_days(&self) -> u32 {
+        u32::MAX
+    }
+}
+
+#[tokio::test]
+async fn days_either_side_of_a_roll_fetch_their_own_contract() {
+    let range = DateRange::new(day(1), day(6)).unwrap();
+    let gateway = Arc::new(ContractGateway::default());
+    let repository = Arc::new(RecordingTickRepository::default());
+    let schedule = RollSchedule::new()
+        .with_roll("NQ", NaiveDate::from_ymd_opt(2024, 12, 16).unwrap(), "NQH5")
+        .with_roll("NQ", day(4), "NQM5");
+    let service = BackfillServiceImpl::new(
+        gateway.clone(),
+        Arc::new(StubGapDetector::new(vec![range.clone()])),
+        repository.clone(),
+        Arc::new(InMemoryJobStateRepository::new()),
+    )
+    .with_contract_resolver(Arc::new(schedule));
+
+    let report = service.backfill_range("NQ", range).await.unwrap();
+
+    assert_eq!(report.days_processed, 6);
+    let mut fetches = gateway.fetches.lock().await.clone();
+    fetches.sort_by_key(|(_, date)| *date);
+    let expected: Vec<(String, NaiveDate)> = (1..=6)
+        .map(|d| {
+            let contract = if d < 4 { "NQH5" } else { "NQM5" };
+            (contract.to_string(), day(d))
+        })
+        .collect();
+    assert_eq!(fetches, expected);
+    // Both contracts are stored under the continuous symbol.
+    let batches = repository.batches().await;
+    assert_eq!(batches.iter().map(Vec::len).sum::<usize>(), 12);
+    assert!(batches.iter().flatten().all(|tick| tick.symbol() == "NQ"));
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use common::*;
+use ingestion_application::{BackfillConfig, BackfillService, JobState, JobStatus};
+use ingestion_domain::DateRange;
+
+async fn stale_job_with_cursor(job_repo: &InMemoryJobStateRepository, cursor: i64) {
+    job_repo
+        .insert_state(
+            job_key("NQ", day(1)),
+            JobState::new(
+                "job-1".to_string(),
+                JobStatus::Running,
+                cursor,
+                end_of_day(day(2)),
+                Utc::now() - chrono::Duration::seconds(600),
+            ),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn cursor_past_range_end_completes_without_fetching() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::new());
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    stale_job_with_cursor(&job_repo, timestamp_for(day(20), 0, 0)).await;
+    let service = build_service(
+        gateway.clone(),
+        vec![],
+        Arc::new(RecordingTickRepository::default()),
+        job_repo.clone(),
+        BackfillConfig::default(),
+    );
+
+    let report = service
+        .backfill_range("NQ", DateRange::new(day(1), day(2)).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(report.days_processed, 0);
+    assert!(gateway.fetches().await.is_empty());
+    let state = job_repo.snapshot(&job_key("NQ", day(1))).await.unwrap();
+    assert_eq!(state.status, JobStatus::Completed);
+}
+
+#[tokio::test]
+async fn out_of_range_cursor_restarts_from_range_start() {
+    for cursor in [i64::MAX, i64::MIN] {
+        let gateway = Arc::new(ScriptedHistoricalGateway::new());
+        let job_repo = Arc::new(InMemoryJobStateRepository::new());
+        stale_job_with_cursor(&job_repo, cursor).await;
+        let service = build_service(
+            gateway.clone(),
+            vec![DateRange::new(day(1), day(2)).unwrap()],
+            Arc::new(RecordingTickRepository::default()),
+            job_repo.clone(),
+            BackfillConfig::default(),
+        );
+
+        let result = service
+            .backfill_range("NQ", DateRange::new(day(1), day(2)).unwrap())
+            .await;
+
+        assert!(result.is_ok(), "cursor {} errored: {:?}", cursor, result);
+    }
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use common::*;
+use ingestion_application::{
+    BackfillConfig, BackfillError, BackfillService, JobKey, JobState, JobStatus,
+};
+use ingestion_domain::DateRange;
+
+#[tokio::test]
+async fn fetches_exactly_the_requested_dates() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![
+        (day(3), sample_ticks("NQ", day(3), 2)),
+        (day(7), sample_ticks("NQ", day(7), 1)),
+        (day(5), sample_ticks("NQ", day(5), 3)),
+    ]));
+    let repository = Arc::new(RecordingTickRepository::default());
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    // Gap detection is bypassed, so this gap must not add day 4.
+    let service = build_service(
+        gateway.clone(),
+        vec![DateRange::single_day(day(4))],
+        repository.clone(),
+        job_repo.clone(),
+        BackfillConfig::default(),
+    );
+
+    let report = service
+        .backfill_dates("NQ", vec![day(7), day(3), day(5), day(3)])
+        .await
+        .unwrap();
+
+    assert_eq!(gateway.fetches().await, vec![day(3), day(5), day(7)]);
+    assert_eq!(repository.saved_days().await, vec![day(3), day(5), day(7)]);
+    assert_eq!(report.days_processed, 3);
+    assert_eq!(report.total_ticks, 6);
+    assert_eq!(report.range, DateRange::new(day(3), day(7)).unwrap());
+    assert!(repository.shutdown_called());
+
+    let state = job_repo
+        .snapshot(&JobKey::new("NQ", day(3)).dates_key())
+        .await
+        .unwrap();
+    assert_eq!(state.status, JobStatus::Completed);
+    assert!(job_repo.snapshot(&job_key("NQ", day(3))).await.is_none());
+}
+
+#[tokio::test]
+async fn a_cancelled_range_job_does_not_skip_requested_dates() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![
+        (day(3), sample_ticks("NQ", day(3), 2)),
+        (day(5), sample_ticks("NQ", day(5), 1)),
+    ]));
+    let repository = Arc::new(RecordingTickRepository::default());
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    // A range job from the same start, cancelled with its cursor past both dates.
+    let range_cursor = timestamp_for(day(6), 12, 0);
+    job_repo
+        .insert_state(
+            job_key("NQ", day(3)),
+            JobState::new(
+                "range-job".to_string(),
+                JobStatus::Cancelled,
+                range_cursor,
+                end_of_day(day(9)),
+                Utc::now(),
+            ),
+        )
+        .await;
+    let service = build_service(
+        gateway.clone(),
+        vec![],
+        repository.clone(),
+        job_repo.clone(),
+        BackfillConfig::default(),
+    );
+
+    let report = service
+        .backfill_dates("NQ", vec![day(3), day(5)])
+        .await
+        .unwrap();
+
+    assert_eq!(gateway.fetches().await, vec![day(3), day(5)]);
+    assert_eq!(repository.saved_days().await, vec![day(3), day(5)]);
+    assert_eq!(report.days_processed, 2);
+    let range_job = job_repo.snapshot(&job_key("NQ", day(3))).await.unwrap();
+    assert_eq!(range_job.status, JobStatus::Cancelled);
+    assert_eq!(range_job.cursor, range_cursor);
+}
+
+#[tokio::test]
+async fn rejects_dates_outside_history_window() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::new().with_max_history_days(30));
+    let service = build_service(
+        gateway.clone(),
+        vec![],
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig::default(),
+    );
+
+    let result = service.backfill_dates("NQ", vec![day(2)]).await;
+
+    assert!(matches!(
+        result,
+        Err(BackfillError::OutsideHistoryWindow { date, max_history_days: 30 }) if date == day(2)
+    ));
+    assert!(gateway.fetches().await.is_empty());
+}
+
+#[tokio::test]
+async fn rejects_empty_date_list() {
+    let service = build_service(
+        Arc::new(ScriptedHistoricalGateway::new()),
+        vec![],
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig::default(),
+    );
+
+    let result = service.backfill_dates("NQ", Vec::new()).await;
+
+    assert!(matches!(result, Err(BackfillError::NoDatesRequested)));
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use common::*;
+use ingestion_application::ports::RepositoryError;
+use ingestion_application::{BackfillConfig, BackfillService, DeadLetterSink, JobStatus};
+use ingestion_domain::{DateRange, Tick};
+use tokio::sync::Mutex;
+
+#[derive(Default)]
+struct RecordingDeadLetterSink {
+    batches: Mutex<Vec<(String, Vec<Tick>)>>,
+}
+
+#[async_trait]
+impl DeadLetterSink for RecordingDeadLetterSink {
+    async fn write_batch(&self, symbol: &str, ticks: &[Tick]) -> Result<(), RepositoryError> {
+        self.batches
+            .lock()
+            .await
+            .push((symbol.to_string(), ticks.to_vec()));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn failed_write_lands_in_the_dead_letter_sink() {
+    let ticks = sample_ticks("ES", day(2), 4);
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![(
+        day(2),
+        ticks.clone(),
+    )]));
+    let repository = Arc::new(RecordingTickRepository::default());
+    repository.fail_writes();
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let sink = Arc::new(RecordingDeadLetterSink::default());
+    let range = DateRange::single_day(day(2));
+    let service = build_service(
+        gateway,
+        vec![range.clone()],
+        repository.clone(),
+        job_repo.clone(),
+        BackfillConfig::default(),
+    )
+    .with_dead_letter_sink(sink.clone());
+
+    let report = service.backfill_range("ES", range).await.unwrap();
+
+    // The failure is still reported...
+    assert_eq!(report.failed_days.len(), 1);
+    assert_eq!(report.failed_days[0].0, day(2));
+    let state = job_repo.snapshot(&job_key("ES", day(2))).await.unwrap();
+    assert_eq!(state.status, JobStatus::Failed);
+    // ...but the ticks survive.
+    assert!(repository.batches().await.is_empty());
+    assert_eq!(*sink.batches.lock().await, vec![("ES".to_string(), ticks)]);
+}
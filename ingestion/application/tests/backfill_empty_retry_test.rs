@@ -0,0 +1,80 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::*;
+use ingestion_application::{BackfillConfig, BackfillService, HistoricalDataError};
+use ingestion_domain::DateRange;
+
+fn config(empty_day_retries: u32) -> BackfillConfig {
+    BackfillConfig {
+        empty_day_retries,
+        empty_day_retry_delay: Duration::ZERO,
+        ..BackfillConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn empty_trading_day_is_refetched_until_it_has_data() {
+    // 2025-01-02 is a Thursday.
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![
+        (day(2), Vec::new()),
+        (day(2), sample_ticks("NQ", day(2), 3)),
+    ]));
+    let repository = Arc::new(RecordingTickRepository::default());
+    let range = DateRange::single_day(day(2));
+    let service = build_service(
+        gateway.clone(),
+        vec![range.clone()],
+        repository.clone(),
+        Arc::new(InMemoryJobStateRepository::new()),
+        config(2),
+    );
+
+    let report = service.backfill_range("NQ", range).await.unwrap();
+
+    assert_eq!(gateway.fetches().await, vec![day(2), day(2)]);
+    assert_eq!(report.total_ticks, 3);
+    assert_eq!(repository.saved_days().await, vec![day(2)]);
+}
+
+#[tokio::test]
+async fn empty_day_is_accepted_once_retries_run_out() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::new());
+    let range = DateRange::single_day(day(2));
+    let service = build_service(
+        gateway.clone(),
+        vec![range.clone()],
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        config(2),
+    );
+
+    let report = service.backfill_range("NQ", range).await.unwrap();
+
+    assert_eq!(gateway.fetches().await, vec![day(2); 3]);
+    assert_eq!(report.days_processed, 1);
+    assert_eq!(report.total_ticks, 0);
+}
+
+#[tokio::test]
+async fn weekends_and_unavailable_days_are_not_refetched() {
+    // 2025-01-04 is a Saturday.
+    let gateway = Arc::new(ScriptedHistoricalGateway::new());
+    gateway
+        .push(day(3), Err(HistoricalDataError::DataNotAvailable(day(3))))
+        .await;
+    let range = DateRange::new(day(3), day(4)).unwrap();
+    let service = build_service(
+        gateway.clone(),
+        vec![range.clone()],
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        config(2),
+    );
+
+    service.backfill_range("NQ", range).await.unwrap();
+
+    assert_eq!(gateway.fetches().await, vec![day(3), day(4)]);
+}
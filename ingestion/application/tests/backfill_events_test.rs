@@ -0,0 +1,114 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::*;
+use ingestion_application::{
+    BackfillConfig, BackfillEvent, BackfillOptions, BackfillProgress, BackfillService,
+    HistoricalDataError, JobStatus,
+};
+use ingestion_domain::DateRange;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+async fn emits_events_in_order_for_two_day_backfill() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![
+        (day(1), sample_ticks("NQ", day(1), 2)),
+        (day(2), sample_ticks("NQ", day(2), 3)),
+    ]));
+    let service = build_service(
+        gateway,
+        vec![DateRange::new(day(1), day(2)).unwrap()],
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig::default(),
+    );
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    service
+        .backfill_range_with_options(
+            "NQ",
+            DateRange::new(day(1), day(2)).unwrap(),
+            BackfillOptions {
+                events: Some(tx),
+                ..BackfillOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let mut events = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        events.push(event);
+    }
+
+    assert_eq!(
+        events,
+        vec![
+            BackfillEvent::JobInitialized {
+                job_key: job_key("NQ", day(1)),
+                resume_from: day(1),
+            },
+            BackfillEvent::GapsDetected(2),
+            BackfillEvent::DayStarted(day(1)),
+            // Day 2 is fetched while day 1 waits for the writer.
+            BackfillEvent::DayStarted(day(2)),
+            BackfillEvent::DayCompleted {
+                date: day(1),
+                ticks: 2,
+            },
+            BackfillEvent::DayCompleted {
+                date: day(2),
+                ticks: 3,
+            },
+            BackfillEvent::Finalized {
+                status: JobStatus::Completed,
+                days_processed: 2,
+                total_ticks: 5,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reports_progress_once_per_processed_day() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![
+        (day(1), sample_ticks("NQ", day(1), 2)),
+        (day(3), sample_ticks("NQ", day(3), 4)),
+    ]));
+    gateway
+        .push(
+            day(2),
+            Err(HistoricalDataError::GatewayError("reset".to_string())),
+        )
+        .await;
+    let range = DateRange::new(day(1), day(3)).unwrap();
+    let service = build_service(
+        gateway,
+        vec![range.clone()],
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig::default(),
+    );
+    let (tx, mut rx) = mpsc::channel(8);
+
+    service
+        .backfill_range_with_progress("NQ", range, tx)
+        .await
+        .unwrap();
+
+    let mut progress = Vec::new();
+    while let Ok(day) = rx.try_recv() {
+        progress.push(day);
+    }
+    let at = |date, ticks, days_done| BackfillProgress {
+        date,
+        ticks,
+        days_done,
+        days_total: 3,
+    };
+    assert_eq!(
+        progress,
+        vec![at(day(1), 2, 1), at(day(2), 0, 2), at(day(3), 4, 3)]
+    );
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use common::*;
+use ingestion_application::{BackfillConfig, BackfillError, BackfillOptions, BackfillService};
+use ingestion_domain::DateRange;
+
+fn force_overwrite() -> BackfillOptions {
+    BackfillOptions {
+        force_overwrite: true,
+        ..BackfillOptions::default()
+    }
+}
+
+#[tokio::test]
+async fn force_overwrite_replaces_days_without_gaps() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![
+        (day(2), sample_ticks("ES", day(2), 2)),
+        (day(3), sample_ticks("ES", day(3), 1)),
+    ]));
+    let repository = Arc::new(RecordingTickRepository::default());
+    // No gaps: a normal run would only fetch the first day.
+    let service = build_service(
+        gateway.clone(),
+        Vec::new(),
+        repository.clone(),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig::default(),
+    );
+
+    let report = service
+        .backfill_range_with_options(
+            "ES",
+            DateRange::new(day(2), day(4)).unwrap(),
+            force_overwrite(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(gateway.fetches().await, vec![day(2), day(3), day(4)]);
+    // Day 4 came back empty, so its existing data is left alone.
+    assert_eq!(repository.replaced_days().await, vec![day(2), day(3)]);
+    assert_eq!(report.days_processed, 3);
+    assert_eq!(report.total_ticks, 3);
+}
+
+#[tokio::test]
+async fn force_overwrite_refuses_the_live_day() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::new());
+    let service = build_service(
+        gateway.clone(),
+        Vec::new(),
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig::default(),
+    );
+    let today = Utc::now().date_naive();
+
+    let result = service
+        .backfill_range_with_options(
+            "ES",
+            DateRange::new(today - chrono::Duration::days(2), today).unwrap(),
+            force_overwrite(),
+        )
+        .await;
+
+    assert!(matches!(result, Err(BackfillError::OverlapsLiveWindow(date)) if date == today));
+    assert!(gateway.fetches().await.is_empty());
+}
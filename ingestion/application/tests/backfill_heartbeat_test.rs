@@ -0,0 +1,45 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::*;
+use ingestion_application::{BackfillConfig, BackfillService};
+use ingestion_domain::DateRange;
+
+/// Backfills five gap days back to back and returns the heartbeats sent.
+async fn heartbeats_for(min_heartbeat_interval: Duration) -> usize {
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(
+        (1..=5)
+            .map(|d| (day(d), sample_ticks("NQ", day(d), 1)))
+            .collect(),
+    ));
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let range = DateRange::new(day(1), day(5)).unwrap();
+    let service = build_service(
+        gateway,
+        vec![range.clone()],
+        Arc::new(RecordingTickRepository::default()),
+        job_repo.clone(),
+        BackfillConfig {
+            min_heartbeat_interval,
+            ..BackfillConfig::default()
+        },
+    );
+
+    let report = service.backfill_range("NQ", range).await.unwrap();
+    assert_eq!(report.days_processed, 5);
+    job_repo.heartbeat_count()
+}
+
+#[tokio::test]
+async fn back_to_back_days_share_one_heartbeat_interval() {
+    // Starting the job writes a fresh heartbeat, and the whole run fits
+    // inside one interval.
+    assert_eq!(heartbeats_for(Duration::from_secs(30)).await, 0);
+}
+
+#[tokio::test]
+async fn zero_interval_heartbeats_every_day_and_on_finish() {
+    assert_eq!(heartbeats_for(Duration::ZERO).await, 6);
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use common::*;
+use ingestion_application::{
+    BackfillConfig, BackfillService, BackfillServiceImpl, DayOutcome, FetchedTicks,
+    HistoricalDataError, HistoricalDataGateway, InvalidTickPolicy,
+};
+use ingestion_domain::{DateRange, Tick, TickValidationError};
+use tokio_util::sync::CancellationToken;
+
+/// Gateway whose source mixes good ticks with records that fail validation.
+struct BadPrintGateway {
+    ticks: Vec<Tick>,
+    bad_prints: usize,
+}
+
+#[async_trait]
+impl HistoricalDataGateway for BadPrintGateway {
+    async fn fetch_historical_ticks(
+        &self,
+        _symbol: &str,
+        _date: NaiveDate,
+    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        Ok(self.ticks.clone())
+    }
+
+    async fn fetch_checked_ticks_cancellable(
+        &self,
+        _symbol: &str,
+        _date: NaiveDate,
+        _token: &CancellationToken,
+    ) -> Result<FetchedTicks, HistoricalDataError> {
+        Ok(FetchedTicks {
+            ticks: self.ticks.clone(),
+            invalid: (0..self.bad_prints)
+                .map(|_| TickValidationError::InvalidPrice("last_price must be positive"))
+                .collect(),
+        })
+    }
+
+    fn max_history_days(&self) -> u32 {
+        u32::MAX
+    }
+}
+
+fn service(
+    ticks: Vec<Tick>,
+    repository: Arc<RecordingTickRepository>,
+    policy: InvalidTickPolicy,
+) -> BackfillServiceImpl {
+    BackfillServiceImpl::new(
+        Arc::new(BadPrintGateway {
+            ticks,
+            bad_prints: 3,
+        }),
+        Arc::new(StubGapDetector::new(vec![DateRange::single_day(day(2))])),
+        repository,
+        Arc::new(InMemoryJobStateRepository::new()),
+    )
+    .with_config(BackfillConfig {
+        invalid_tick_policy: policy,
+        ..BackfillConfig::default()
+    })
+}
+
+#[tokio::test]
+async fn invalid_ticks_are_skipped_and_counted() {
+    let ticks = sample_ticks("ES", day(2), 4);
+    let repository = Arc::new(RecordingTickRepository::default());
+    let service = service(ticks.clone(), repository.clone(), InvalidTickPolicy::Skip);
+
+    let report = service
+        .backfill_range("ES", DateRange::single_day(day(2)))
+        .await
+        .unwrap();
+
+    assert!(report.failed_days.is_empty());
+    assert_eq!(report.total_ticks, 4);
+    assert_eq!(report.invalid_ticks.get(&day(2)), Some(&3));
+    assert_eq!(report.day_outcomes.get(&day(2)), Some(&DayOutcome::Full));
+    assert_eq!(repository.batches().await, vec![ticks]);
+}
+
+#[tokio::test]
+async fn fail_policy_fails_the_day_without_writing() {
+    let repository = Arc::new(RecordingTickRepository::default());
+    let service = service(
+        sample_ticks("ES", day(2), 4),
+        repository.clone(),
+        InvalidTickPolicy::Fail,
+    );
+
+    let report = service
+        .backfill_range("ES", DateRange::single_day(day(2)))
+        .await
+        .unwrap();
+
+    assert_eq!(report.failed_days.len(), 1);
+    assert!(report.failed_days[0].1.contains("3 invalid ticks"));
+    assert!(report.invalid_ticks.is_empty());
+    assert!(repository.batches().await.is_empty());
+}
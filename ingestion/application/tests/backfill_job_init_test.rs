@@ -1,12 +1,14 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Duration as StdDuration;
 
 use async_trait::async_trait;
 use chrono::{Duration, NaiveDate, Utc};
 use ingestion_application::ports::RepositoryError;
 use ingestion_application::{
-    BackfillError, BackfillService, BackfillServiceImpl, GapDetectionError, GapDetector,
-    HistoricalDataError, HistoricalDataGateway, JobState, JobStateError, JobStateRepository,
-    JobStatus, TickRepository,
+    key_matches, BackfillConfig, BackfillError, BackfillOptions, BackfillService,
+    BackfillServiceImpl, GapDetectionError, GapDetector, HistoricalDataError,
+    HistoricalDataGateway, JobState, JobStateError, JobStateRepository, JobStatus, TickRepository,
 };
 use ingestion_domain::{DateRange, Tick};
 use tokio::sync::Mutex;
@@ -23,6 +25,9 @@ async fn stale_job_takeover_preserves_cursor() {
         heartbeat_at: Utc::now() - Duration::seconds(600),
         critical_ranges: Vec::new(),
         last_error_type: None,
+        day_checksums: BTreeMap::new(),
+        total_ticks: 0,
+        last_processed_date: None,
     };
     let repo = Arc::new(StubJobStateRepository::new(
         job_key.clone(),
@@ -53,6 +58,9 @@ async fn active_job_returns_error() {
         heartbeat_at: Utc::now(),
         critical_ranges: Vec::new(),
         last_error_type: None,
+        day_checksums: BTreeMap::new(),
+        total_ticks: 0,
+        last_processed_date: None,
     };
     let repo = Arc::new(StubJobStateRepository::new(
         job_key.clone(),
@@ -74,6 +82,93 @@ async fn active_job_returns_error() {
     assert_eq!(final_state.job_instance_id, "running");
 }
 
+#[tokio::test]
+async fn waiting_caller_proceeds_once_lock_is_released() {
+    let job_key = job_key("CL", day(1));
+    let repo = Arc::new(StubJobStateRepository::new(
+        job_key.clone(),
+        Some(held_state("holder")),
+    ));
+    let service = build_polling_service(repo.clone());
+
+    let holder = repo.clone();
+    let release_key = job_key.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(StdDuration::from_millis(50)).await;
+        holder
+            .update_status(&release_key, &"holder".to_string(), JobStatus::Completed)
+            .await
+            .unwrap();
+    });
+
+    let range = DateRange::new(day(1), day(1)).unwrap();
+    let options = BackfillOptions {
+        wait_for_lock: Some(StdDuration::from_secs(5)),
+        ..BackfillOptions::default()
+    };
+    service
+        .backfill_range_with_options("CL", range, options)
+        .await
+        .expect("should proceed once the holder finishes");
+
+    let final_state = repo.snapshot().await.expect("state present");
+    assert_ne!(final_state.job_instance_id, "holder");
+    assert_eq!(final_state.status, JobStatus::Completed);
+}
+
+#[tokio::test]
+async fn waiting_caller_gives_up_after_timeout() {
+    let job_key = job_key("CL", day(1));
+    let repo = Arc::new(StubJobStateRepository::new(
+        job_key.clone(),
+        Some(held_state("holder")),
+    ));
+    let service = build_polling_service(repo.clone());
+
+    let range = DateRange::new(day(1), day(1)).unwrap();
+    let options = BackfillOptions {
+        wait_for_lock: Some(StdDuration::from_millis(50)),
+        ..BackfillOptions::default()
+    };
+    let err = service
+        .backfill_range_with_options("CL", range, options)
+        .await
+        .expect_err("lock is never released");
+    assert!(matches!(err, BackfillError::JobAlreadyRunning(key) if key == job_key));
+    assert_eq!(repo.snapshot().await.unwrap().job_instance_id, "holder");
+}
+
+/// A running job with a fresh heartbeat, as left by a live instance.
+fn held_state(instance: &str) -> JobState {
+    JobState {
+        status: JobStatus::Running,
+        job_instance_id: instance.to_string(),
+        cursor: timestamp_for(day(1), 0, 0) - 1,
+        end_time: timestamp_for(day(1), 23, 59),
+        heartbeat_at: Utc::now(),
+        critical_ranges: Vec::new(),
+        last_error_type: None,
+        day_checksums: BTreeMap::new(),
+        total_ticks: 0,
+        last_processed_date: None,
+    }
+}
+
+fn build_polling_service(repo: Arc<StubJobStateRepository>) -> Arc<dyn BackfillService> {
+    Arc::new(
+        BackfillServiceImpl::new(
+            Arc::new(NoopHistoricalGateway),
+            Arc::new(NoopGapDetector),
+            Arc::new(NoopTickRepository),
+            repo,
+        )
+        .with_config(BackfillConfig {
+            lock_poll_interval: StdDuration::from_millis(10),
+            ..BackfillConfig::default()
+        }),
+    )
+}
+
 fn build_service(repo: Arc<StubJobStateRepository>) -> Arc<dyn BackfillService> {
     let gateway = Arc::new(NoopHistoricalGateway);
     let gap_detector = Arc::new(NoopGapDetector);
@@ -196,6 +291,13 @@ impl JobStateRepository for StubJobStateRepository {
         Ok(())
     }
 
+    async fn delete(&self, job_key: &str) -> Result<(), JobStateError> {
+        if job_key == self.key {
+            *self.state.lock().await = None;
+        }
+        Ok(())
+    }
+
     async fn update_cursor(
         &self,
         _job_key: &str,
@@ -237,4 +339,55 @@ impl JobStateRepository for StubJobStateRepository {
         })
         .await
     }
+
+    async fn update_day_checksums(
+        &self,
+        _job_key: &str,
+        job_instance_id: &String,
+        day_checksums: &BTreeMap<NaiveDate, u64>,
+    ) -> Result<(), JobStateError> {
+        self.with_mut(job_instance_id, |state| {
+            state.day_checksums = day_checksums.clone()
+        })
+        .await
+    }
+
+    async fn update_progress(
+        &self,
+        _job_key: &str,
+        job_instance_id: &String,
+        total_ticks: u64,
+        last_processed_date: NaiveDate,
+    ) -> Result<(), JobStateError> {
+        self.with_mut(job_instance_id, |state| {
+            state.total_ticks = total_ticks;
+            state.last_processed_date = Some(last_processed_date);
+        })
+        .await
+    }
+
+    async fn find_by_status(
+        &self,
+        status: JobStatus,
+    ) -> Result<Vec<(String, JobState)>, JobStateError> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .iter()
+            .filter(|state| state.status == status)
+            .map(|state| (self.key.clone(), state.clone()))
+            .collect())
+    }
+
+    async fn list_jobs(&self, pattern: &str) -> Result<Vec<(String, JobState)>, JobStateError> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .iter()
+            .filter(|_| key_matches(pattern, &self.key))
+            .map(|state| (self.key.clone(), state.clone()))
+            .collect())
+    }
 }
@@ -0,0 +1,47 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::*;
+use ingestion_application::{BackfillConfig, BackfillService};
+use ingestion_domain::DateRange;
+
+#[tokio::test]
+async fn lifetime_totals_accumulate_across_runs() {
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![
+        (day(2), sample_ticks("NQ", day(2), 2)),
+        (day(3), sample_ticks("NQ", day(3), 3)),
+        (day(6), sample_ticks("NQ", day(6), 4)),
+    ]));
+    let run = |gaps: Vec<DateRange>, end| {
+        let service = build_service(
+            gateway.clone(),
+            gaps,
+            Arc::new(RecordingTickRepository::default()),
+            job_repo.clone(),
+            BackfillConfig::default(),
+        );
+        async move {
+            service
+                .backfill_range("NQ", DateRange::new(day(2), end).unwrap())
+                .await
+                .unwrap()
+        }
+    };
+
+    let first = run(vec![DateRange::new(day(2), day(3)).unwrap()], day(3)).await;
+    assert_eq!(first.total_ticks, 5);
+    assert_eq!(first.lifetime_ticks, 5);
+    assert_eq!(first.last_processed_date, Some(day(3)));
+
+    // Day 6 is the only gap left; the start day comes back empty this time.
+    let second = run(vec![DateRange::single_day(day(6))], day(6)).await;
+    assert_eq!(second.total_ticks, 4);
+    assert_eq!(second.lifetime_ticks, 9);
+    assert_eq!(second.last_processed_date, Some(day(6)));
+
+    let state = job_repo.snapshot(&job_key("NQ", day(2))).await.unwrap();
+    assert_eq!(state.total_ticks, 9);
+    assert_eq!(state.last_processed_date, Some(day(6)));
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::*;
+use ingestion_application::{BackfillConfig, BackfillError, BackfillOptions, BackfillService};
+use ingestion_domain::DateRange;
+
+/// Days 1-10 are all gaps.
+fn setup() -> (
+    impl BackfillService,
+    Arc<ScriptedHistoricalGateway>,
+    Arc<InMemoryJobStateRepository>,
+    DateRange,
+) {
+    let gateway = Arc::new(ScriptedHistoricalGateway::new());
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let range = DateRange::new(day(1), day(10)).unwrap();
+    let service = build_service(
+        gateway.clone(),
+        vec![range.clone()],
+        Arc::new(RecordingTickRepository::default()),
+        job_repo.clone(),
+        BackfillConfig::default(),
+    );
+    (service, gateway, job_repo, range)
+}
+
+#[tokio::test]
+async fn range_over_the_cap_is_refused_before_fetching() {
+    let (service, gateway, job_repo, range) = setup();
+    let options = BackfillOptions {
+        max_days: Some(5),
+        ..BackfillOptions::default()
+    };
+
+    let err = service
+        .backfill_range_with_options("ES", range, options)
+        .await
+        .expect_err("ten days exceeds the cap");
+
+    assert!(matches!(
+        err,
+        BackfillError::TooManyDays {
+            days: 10,
+            max_days: 5
+        }
+    ));
+    assert!(gateway.fetches().await.is_empty());
+    // No job was started, so nothing holds the lock.
+    assert!(job_repo.snapshot(&job_key("ES", day(1))).await.is_none());
+}
+
+#[tokio::test]
+async fn range_within_the_cap_proceeds() {
+    let (service, gateway, _, range) = setup();
+    let options = BackfillOptions {
+        max_days: Some(10),
+        ..BackfillOptions::default()
+    };
+
+    let report = service
+        .backfill_range_with_options("ES", range, options)
+        .await
+        .unwrap();
+
+    assert_eq!(report.days_processed, 10);
+    assert_eq!(gateway.fetches().await.len(), 10);
+}
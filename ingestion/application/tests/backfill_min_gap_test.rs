@@ -0,0 +1,88 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::*;
+use ingestion_application::{BackfillConfig, BackfillService};
+use ingestion_domain::DateRange;
+
+async fn fetched_days_with_threshold(min_gap_days: u32) -> Vec<chrono::NaiveDate> {
+    let gateway = Arc::new(ScriptedHistoricalGateway::new());
+    let service = build_service(
+        gateway.clone(),
+        vec![
+            DateRange::single_day(day(3)),
+            DateRange::new(day(6), day(7)).unwrap(),
+        ],
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig {
+            min_gap_days,
+            ..BackfillConfig::default()
+        },
+    );
+
+    service
+        .backfill_range("NQ", DateRange::new(day(1), day(8)).unwrap())
+        .await
+        .unwrap();
+    gateway.fetches().await
+}
+
+#[tokio::test]
+async fn one_day_gap_is_retained_at_threshold_one() {
+    assert_eq!(
+        fetched_days_with_threshold(1).await,
+        vec![day(1), day(3), day(6), day(7)]
+    );
+}
+
+#[tokio::test]
+async fn one_day_gap_is_dropped_at_threshold_two() {
+    assert_eq!(
+        fetched_days_with_threshold(2).await,
+        vec![day(1), day(6), day(7)]
+    );
+}
+
+async fn fetched_days_with_coalescing(coalesce_gap_days: u32) -> Vec<chrono::NaiveDate> {
+    let gateway = Arc::new(ScriptedHistoricalGateway::new());
+    // Present on 3, 5 and 8-9.
+    let service = build_service(
+        gateway.clone(),
+        vec![
+            DateRange::single_day(day(2)),
+            DateRange::single_day(day(4)),
+            DateRange::new(day(6), day(7)).unwrap(),
+            DateRange::single_day(day(10)),
+        ],
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig {
+            coalesce_gap_days,
+            ..BackfillConfig::default()
+        },
+    );
+
+    service
+        .backfill_range("NQ", DateRange::new(day(1), day(10)).unwrap())
+        .await
+        .unwrap();
+    gateway.fetches().await
+}
+
+#[tokio::test]
+async fn fragmented_gaps_are_fetched_separately_by_default() {
+    assert_eq!(
+        fetched_days_with_coalescing(0).await,
+        vec![day(1), day(2), day(4), day(6), day(7), day(10)]
+    );
+}
+
+#[tokio::test]
+async fn fragmented_gaps_are_coalesced_at_threshold_two() {
+    assert_eq!(
+        fetched_days_with_coalescing(2).await,
+        (1..=7).map(day).chain([day(10)]).collect::<Vec<_>>()
+    );
+}
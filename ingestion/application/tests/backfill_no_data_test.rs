@@ -0,0 +1,132 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::*;
+use ingestion_application::{
+    BackfillConfig, BackfillReport, BackfillService, HistoricalDataError, JobStatus, NoDataPolicy,
+};
+use ingestion_domain::DateRange;
+
+struct Run {
+    report: BackfillReport,
+    repository: Arc<RecordingTickRepository>,
+    status: JobStatus,
+}
+
+/// Days 1-3 are all gaps; the gateway has no data for day 2.
+async fn run_with_policy(policy: NoDataPolicy) -> Run {
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![
+        (day(1), sample_ticks("ES", day(1), 2)),
+        (day(3), sample_ticks("ES", day(3), 1)),
+    ]));
+    gateway
+        .push(day(2), Err(HistoricalDataError::DataNotAvailable(day(2))))
+        .await;
+    let repository = Arc::new(RecordingTickRepository::default());
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let range = DateRange::new(day(1), day(3)).unwrap();
+    let service = build_service(
+        gateway,
+        vec![range.clone()],
+        repository.clone(),
+        job_repo.clone(),
+        BackfillConfig {
+            no_data_policy: policy,
+            ..BackfillConfig::default()
+        },
+    );
+
+    let report = service.backfill_range("ES", range).await.unwrap();
+    let status = job_repo
+        .snapshot(&job_key("ES", day(1)))
+        .await
+        .unwrap()
+        .status;
+    Run {
+        report,
+        repository,
+        status,
+    }
+}
+
+#[tokio::test]
+async fn skip_is_the_default_and_lists_the_day_separately() {
+    assert_eq!(BackfillConfig::default().no_data_policy, NoDataPolicy::Skip);
+
+    let run = run_with_policy(NoDataPolicy::Skip).await;
+
+    assert!(run.report.failed_days.is_empty());
+    assert_eq!(run.report.days_no_data, vec![day(2)]);
+    assert_eq!(run.report.days_processed, 2);
+    assert!(run.repository.no_data_days().await.is_empty());
+    assert_eq!(run.status, JobStatus::Completed);
+}
+
+#[tokio::test]
+async fn fail_policy_records_a_failed_day() {
+    let run = run_with_policy(NoDataPolicy::Fail).await;
+
+    let failed: Vec<_> = run.report.failed_days.iter().map(|(d, _)| *d).collect();
+    assert_eq!(failed, vec![day(2)]);
+    assert!(run.report.days_no_data.is_empty());
+    assert_eq!(run.status, JobStatus::Failed);
+}
+
+#[tokio::test]
+async fn fill_empty_policy_marks_the_day_as_present() {
+    let run = run_with_policy(NoDataPolicy::FillEmpty).await;
+
+    assert!(run.report.failed_days.is_empty());
+    assert_eq!(run.report.days_no_data, vec![day(2)]);
+    assert_eq!(run.report.days_processed, 3);
+    assert_eq!(run.repository.no_data_days().await, vec![day(2)]);
+    assert_eq!(run.status, JobStatus::Completed);
+}
+
+#[tokio::test]
+async fn a_resumed_run_refetches_a_skipped_day() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![(
+        day(1),
+        sample_ticks("ES", day(1), 2),
+    )]));
+    gateway
+        .push(day(2), Err(HistoricalDataError::DataNotAvailable(day(2))))
+        .await;
+    gateway
+        .push(day(3), Err(HistoricalDataError::Cancelled))
+        .await;
+    let repository = Arc::new(RecordingTickRepository::default());
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let range = DateRange::new(day(1), day(3)).unwrap();
+    let service = build_service(
+        gateway.clone(),
+        vec![range.clone()],
+        repository.clone(),
+        job_repo.clone(),
+        BackfillConfig::default(),
+    );
+
+    let first = service.backfill_range("ES", range.clone()).await.unwrap();
+    assert!(first.cancelled);
+    assert_eq!(first.days_no_data, vec![day(2)]);
+    let state = job_repo.snapshot(&job_key("ES", day(1))).await.unwrap();
+    assert_eq!(state.status, JobStatus::Cancelled);
+    assert!(state.cursor < timestamp_for(day(2), 0, 0));
+
+    // The data has since arrived.
+    gateway
+        .push(day(2), Ok(sample_ticks("ES", day(2), 3)))
+        .await;
+    gateway
+        .push(day(3), Ok(sample_ticks("ES", day(3), 1)))
+        .await;
+    let second = service.backfill_range("ES", range).await.unwrap();
+
+    assert!(second.days_no_data.is_empty());
+    assert!(repository.saved_days().await.contains(&day(2)));
+    let fetches = gateway.fetches().await;
+    assert_eq!(fetches.iter().filter(|date| **date == day(2)).count(), 2);
+    let state = job_repo.snapshot(&job_key("ES", day(1))).await.unwrap();
+    assert_eq!(state.status, JobStatus::Completed);
+}
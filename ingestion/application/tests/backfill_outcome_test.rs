@@ -0,0 +1,77 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::*;
+use ingestion_application::{
+    BackfillConfig, BackfillReport, BackfillService, DayOutcome, HistoricalDataError,
+};
+use ingestion_domain::DateRange;
+
+/// Days 1-5 are all gaps: three hours of ticks, one hour, an empty day,
+/// no data and a gateway failure.
+async fn run(expected_hours_per_day: Option<u32>) -> BackfillReport {
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![
+        (
+            day(1),
+            [9, 10, 11]
+                .into_iter()
+                .map(|hour| make_tick("ES", day(1), hour))
+                .collect(),
+        ),
+        (day(2), vec![make_tick("ES", day(2), 9)]),
+    ]));
+    gateway
+        .push(day(4), Err(HistoricalDataError::DataNotAvailable(day(4))))
+        .await;
+    gateway
+        .push(
+            day(5),
+            Err(HistoricalDataError::GatewayError("boom".into())),
+        )
+        .await;
+    let range = DateRange::new(day(1), day(5)).unwrap();
+    let service = build_service(
+        gateway,
+        vec![range.clone()],
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig {
+            expected_hours_per_day,
+            ..BackfillConfig::default()
+        },
+    );
+
+    service.backfill_range("ES", range).await.unwrap()
+}
+
+#[tokio::test]
+async fn reports_an_outcome_for_each_day() {
+    let report = run(Some(3)).await;
+
+    let failure = report.failed_days[0].1.clone();
+    let outcomes: Vec<_> = report.day_outcomes.into_iter().collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            (day(1), DayOutcome::Full),
+            (
+                day(2),
+                DayOutcome::Partial {
+                    present: 1,
+                    expected: 3
+                }
+            ),
+            (day(3), DayOutcome::Empty),
+            (day(4), DayOutcome::Skipped),
+            (day(5), DayOutcome::Failed(failure)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn without_an_expectation_any_ticks_are_full() {
+    let report = run(None).await;
+
+    assert_eq!(report.day_outcomes[&day(2)], DayOutcome::Full);
+}
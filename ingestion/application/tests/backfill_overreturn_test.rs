@@ -0,0 +1,62 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::*;
+use ingestion_application::{BackfillConfig, BackfillService};
+use ingestion_domain::DateRange;
+
+#[tokio::test]
+async fn next_day_tick_does_not_advance_cursor_past_requested_day() {
+    let mut day_two = sample_ticks("NQ", day(2), 3);
+    day_two.push(make_tick("NQ", day(3), 0));
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![(
+        day(2),
+        day_two,
+    )]));
+    let repository = Arc::new(RecordingTickRepository::default());
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let service = build_service(
+        gateway,
+        vec![DateRange::single_day(day(2))],
+        repository.clone(),
+        job_repo.clone(),
+        BackfillConfig::default(),
+    );
+
+    let report = service
+        .backfill_range("NQ", DateRange::single_day(day(2)))
+        .await
+        .unwrap();
+
+    // The spilled tick is still saved alongside the day's ticks.
+    assert_eq!(report.total_ticks, 4);
+    assert_eq!(repository.batches().await[0].len(), 4);
+    let state = job_repo.snapshot(&job_key("NQ", day(2))).await.unwrap();
+    assert_eq!(state.cursor, timestamp_for(day(2), 12, 0));
+}
+
+#[tokio::test]
+async fn over_returned_day_does_not_skip_following_days() {
+    let mut day_two = sample_ticks("NQ", day(2), 2);
+    day_two.push(make_tick("NQ", day(4), 1));
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(vec![
+        (day(2), day_two),
+        (day(3), sample_ticks("NQ", day(3), 2)),
+    ]));
+    let service = build_service(
+        gateway.clone(),
+        vec![DateRange::new(day(2), day(3)).unwrap()],
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig::default(),
+    );
+
+    let report = service
+        .backfill_range("NQ", DateRange::new(day(2), day(3)).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(gateway.fetches().await, vec![day(2), day(3)]);
+    assert_eq!(report.days_processed, 2);
+}
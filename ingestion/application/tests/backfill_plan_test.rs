@@ -0,0 +1,107 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use common::*;
+use ingestion_application::{
+    BackfillConfig, BackfillService, JobState, JobStatus, PlanReason, PlannedDay, RateBudget,
+};
+use ingestion_domain::DateRange;
+
+#[tokio::test]
+async fn plan_estimates_duration_without_fetching() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::new());
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let range = DateRange::new(day(1), day(20)).unwrap();
+    let service = build_service(
+        gateway.clone(),
+        vec![
+            DateRange::new(day(1), day(5)).unwrap(),
+            DateRange::new(day(11), day(17)).unwrap(),
+        ],
+        Arc::new(RecordingTickRepository::default()),
+        job_repo.clone(),
+        BackfillConfig {
+            requests_per_day: 10,
+            rate_budgets: vec![
+                RateBudget::new(6, Duration::from_secs(2)),
+                RateBudget::new(60, Duration::from_secs(600)),
+            ],
+            ..BackfillConfig::default()
+        },
+    );
+
+    let plan = service.plan("NQ", range.clone()).await.unwrap();
+    assert_eq!(plan.resume_from, day(1));
+    assert_eq!(plan.days.len(), 12);
+
+    // 120 requests: the 10-minute window dominates, so one full wait after
+    // the first 60 go out (the 6-per-2s window alone would take ~38s).
+    let estimate = service.estimate_duration("NQ", range).await.unwrap();
+    assert_eq!(estimate, plan.estimated_duration);
+    assert!(
+        estimate >= Duration::from_secs(600) && estimate < Duration::from_secs(1200),
+        "estimate {:?} out of range",
+        estimate
+    );
+
+    assert!(gateway.fetches().await.is_empty());
+    assert!(job_repo.snapshot(&job_key("NQ", day(1))).await.is_none());
+}
+
+#[tokio::test]
+async fn plan_labels_each_day_with_why_it_is_fetched() {
+    let planned = |date, reason| PlannedDay { date, reason };
+    // An interrupted job resumes on day 3: once inside a gap, once outside.
+    for (gaps, expected) in [
+        (
+            vec![
+                DateRange::new(day(3), day(4)).unwrap(),
+                DateRange::single_day(day(7)),
+            ],
+            vec![
+                planned(day(3), PlanReason::Gap),
+                planned(day(4), PlanReason::Gap),
+                planned(day(7), PlanReason::Gap),
+            ],
+        ),
+        (
+            vec![DateRange::single_day(day(7))],
+            vec![
+                planned(day(3), PlanReason::ResumeStart),
+                planned(day(7), PlanReason::Gap),
+            ],
+        ),
+    ] {
+        let job_repo = Arc::new(InMemoryJobStateRepository::new());
+        job_repo
+            .insert_state(
+                job_key("NQ", day(1)),
+                JobState::new(
+                    "job-1".to_string(),
+                    JobStatus::Running,
+                    timestamp_for(day(3), 12, 0),
+                    end_of_day(day(10)),
+                    Utc::now() - chrono::Duration::seconds(600),
+                ),
+            )
+            .await;
+        let service = build_service(
+            Arc::new(ScriptedHistoricalGateway::new()),
+            gaps,
+            Arc::new(RecordingTickRepository::default()),
+            job_repo,
+            BackfillConfig::default(),
+        );
+
+        let plan = service
+            .plan("NQ", DateRange::new(day(1), day(10)).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(plan.resume_from, day(3));
+        assert_eq!(plan.days, expected);
+    }
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use common::*;
+use ingestion_application::{
+    parse_retry_after, BackfillConfig, BackfillService, HistoricalDataError, JobStatus,
+};
+use ingestion_domain::DateRange;
+
+#[tokio::test]
+async fn retry_waits_for_gateway_retry_after_hint() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::new());
+    gateway
+        .push(
+            day(1),
+            Err(HistoricalDataError::RateLimitExceeded {
+                retry_after: Some(Duration::from_millis(300)),
+            }),
+        )
+        .await;
+    gateway
+        .push(day(1), Ok(sample_ticks("NQ", day(1), 2)))
+        .await;
+
+    let repository = Arc::new(RecordingTickRepository::default());
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let service = build_service(
+        gateway.clone(),
+        vec![],
+        repository.clone(),
+        job_repo.clone(),
+        BackfillConfig {
+            rate_limit_backoff: Duration::from_secs(10),
+            ..BackfillConfig::default()
+        },
+    );
+
+    let start = Instant::now();
+    let report = service
+        .backfill_range("NQ", DateRange::single_day(day(1)))
+        .await
+        .unwrap();
+    let elapsed = start.elapsed();
+
+    assert!(
+        elapsed >= Duration::from_millis(300),
+        "retry should honour the hint, waited {:?}",
+        elapsed
+    );
+    assert!(
+        elapsed < Duration::from_secs(2),
+        "retry should not fall back to the generic backoff, waited {:?}",
+        elapsed
+    );
+    assert_eq!(gateway.fetches().await, vec![day(1), day(1)]);
+    assert_eq!(report.total_ticks, 2);
+    assert!(report.failed_days.is_empty());
+
+    let state = job_repo.snapshot(&job_key("NQ", day(1))).await.unwrap();
+    assert_eq!(state.status, JobStatus::Completed);
+}
+
+#[tokio::test]
+async fn exhausted_retries_fail_the_day() {
+    let gateway = Arc::new(ScriptedHistoricalGateway::new());
+    for _ in 0..2 {
+        gateway
+            .push(
+                day(1),
+                Err(HistoricalDataError::RateLimitExceeded {
+                    retry_after: Some(Duration::from_millis(10)),
+                }),
+            )
+            .await;
+    }
+
+    let service = build_service(
+        gateway.clone(),
+        vec![],
+        Arc::new(RecordingTickRepository::default()),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig {
+            max_rate_limit_retries: 1,
+            ..BackfillConfig::default()
+        },
+    );
+
+    let report = service
+        .backfill_range("NQ", DateRange::single_day(day(1)))
+        .await
+        .unwrap();
+
+    assert_eq!(gateway.fetches().await.len(), 2);
+    assert_eq!(report.failed_days.len(), 1);
+    assert_eq!(report.failed_days[0].0, day(1));
+}
+
+#[test]
+fn parses_retry_after_formats() {
+    assert_eq!(parse_retry_after("15"), Some(Duration::from_secs(15)));
+    assert_eq!(parse_retry_after("1.5"), Some(Duration::from_millis(1500)));
+    assert_eq!(parse_retry_after("2s"), Some(Duration::from_secs(2)));
+    assert_eq!(
+        parse_retry_after(" 250ms "),
+        Some(Duration::from_millis(250))
+    );
+    assert_eq!(parse_retry_after("soon"), None);
+    assert_eq!(parse_retry_after("-1"), None);
+}
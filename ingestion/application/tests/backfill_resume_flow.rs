@@ -1,16 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono::{NaiveDate, NaiveTime, Utc};
 use ingestion_application::ports::RepositoryError;
 use ingestion_application::{
-    BackfillService, BackfillServiceImpl, GapDetectionError, GapDetector, HistoricalDataError,
-    HistoricalDataGateway, JobState, JobStateError, JobStateRepository, JobStatus, TickRepository,
+    key_matches, BackfillService, BackfillServiceImpl, GapDetectionError, GapDetector,
+    HistoricalDataError, HistoricalDataGateway, JobState, JobStateError, JobStateRepository,
+    JobStatus, TickRepository,
 };
+use ingestion_domain::test_support::sample_ticks;
 use ingestion_domain::{DateRange, Tick};
-use rust_decimal::Decimal;
 use tokio::sync::{Mutex, MutexGuard};
 
 #[tokio::test]
@@ -119,27 +120,6 @@ fn end_of_day(date: NaiveDate) -> i64 {
         .timestamp_millis()
 }
 
-fn sample_ticks(symbol: &str, date: NaiveDate, count: usize) -> Vec<Tick> {
-    (0..count)
-        .map(|idx| make_tick(symbol, date, 10 + idx as u32))
-        .collect()
-}
-
-fn make_tick(symbol: &str, date: NaiveDate, hour: u32) -> Tick {
-    let timestamp = date.and_hms_opt(hour, 0, 0).unwrap();
-    Tick::new(
-        Utc.from_utc_datetime(&timestamp),
-        symbol.to_string(),
-        Decimal::new(100_000, 2),
-        1,
-        Decimal::new(100_500, 2),
-        1,
-        Decimal::new(100_250, 2),
-        1,
-    )
-    .unwrap()
-}
-
 struct StubHistoricalGateway {
     ticks: HashMap<NaiveDate, Vec<Tick>>,
 }
@@ -271,6 +251,11 @@ impl JobStateRepository for InMemoryJobStateRepository {
         Ok(())
     }
 
+    async fn delete(&self, job_key: &str) -> Result<(), JobStateError> {
+        self.states.lock().await.remove(job_key);
+        Ok(())
+    }
+
     async fn update_cursor(
         &self,
         job_key: &str,
@@ -330,4 +315,61 @@ impl JobStateRepository for InMemoryJobStateRepository {
         entry.last_error_type = Some(message.to_string());
         Ok(())
     }
+
+    async fn update_day_checksums(
+        &self,
+        job_key: &str,
+        job_instance_id: &String,
+        day_checksums: &BTreeMap<NaiveDate, u64>,
+    ) -> Result<(), JobStateError> {
+        let mut states = self.require_state(job_key).await?;
+        let entry = states.get_mut(job_key).unwrap();
+        if &entry.job_instance_id != job_instance_id {
+            return Err(JobStateError::StaleInstance(job_key.to_string()));
+        }
+        entry.day_checksums = day_checksums.clone();
+        Ok(())
+    }
+
+    async fn update_progress(
+        &self,
+        job_key: &str,
+        job_instance_id: &String,
+        total_ticks: u64,
+        last_processed_date: NaiveDate,
+    ) -> Result<(), JobStateError> {
+        let mut states = self.require_state(job_key).await?;
+        let entry = states.get_mut(job_key).unwrap();
+        if &entry.job_instance_id != job_instance_id {
+            return Err(JobStateError::StaleInstance(job_key.to_string()));
+        }
+        entry.total_ticks = total_ticks;
+        entry.last_processed_date = Some(last_processed_date);
+        Ok(())
+    }
+
+    async fn find_by_status(
+        &self,
+        status: JobStatus,
+    ) -> Result<Vec<(String, JobState)>, JobStateError> {
+        Ok(self
+            .states
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, state)| state.status == status)
+            .map(|(key, state)| (key.clone(), state.clone()))
+            .collect())
+    }
+
+    async fn list_jobs(&self, pattern: &str) -> Result<Vec<(String, JobState)>, JobStateError> {
+        Ok(self
+            .states
+            .lock()
+            .await
+            .iter()
+            .filter(|(key, _)| key_matches(pattern, key))
+            .map(|(key, state)| (key.clone(), state.clone()))
+            .collect())
+    }
 }
@@ -0,0 +1,30 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::*;
+use ingestion_application::{BackfillConfig, BackfillService};
+use ingestion_domain::DateRange;
+
+#[tokio::test]
+async fn gateway_source_id_is_passed_to_the_repository() {
+    let gateway = Arc::new(
+        ScriptedHistoricalGateway::with_ticks(vec![(day(1), sample_ticks("ES", day(1), 1))])
+            .with_source_id("vendor-x"),
+    );
+    let repository = Arc::new(RecordingTickRepository::default());
+    let service = build_service(
+        gateway,
+        Vec::new(),
+        repository.clone(),
+        Arc::new(InMemoryJobStateRepository::new()),
+        BackfillConfig::default(),
+    );
+
+    service
+        .backfill_range("ES", DateRange::single_day(day(1)))
+        .await
+        .unwrap();
+
+    assert_eq!(repository.source().await.as_deref(), Some("vendor-x"));
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use common::*;
+use ingestion_application::ports::RepositoryError;
+use ingestion_application::{
+    BackfillConfig, BackfillService, BackfillServiceImpl, GapDetectionError, GapDetector,
+    JobStatus, TickRepository,
+};
+use ingestion_domain::{DateRange, Tick};
+use tokio::sync::Mutex;
+
+/// Repository that silently drops one day's ticks, and reports gaps from
+/// what it actually kept.
+struct LossyRepository {
+    lost: NaiveDate,
+    saved: Mutex<BTreeSet<NaiveDate>>,
+}
+
+#[async_trait]
+impl TickRepository for LossyRepository {
+    async fn save_batch(&self, ticks: Vec<Tick>) -> Result<(), RepositoryError> {
+        let mut saved = self.saved.lock().await;
+        for tick in ticks {
+            let date = tick.timestamp().date_naive();
+            if date != self.lost {
+                saved.insert(date);
+            }
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GapDetector for LossyRepository {
+    async fn detect_gaps(
+        &self,
+        _symbol: &str,
+        range: DateRange,
+    ) -> Result<Vec<DateRange>, GapDetectionError> {
+        let saved = self.saved.lock().await;
+        Ok(range
+            .split_by_days()
+            .into_iter()
+            .filter(|day| !saved.contains(&day.start()))
+            .collect())
+    }
+}
+
+async fn run(verify_after_run: bool) -> (Vec<NaiveDate>, JobStatus) {
+    let gateway = Arc::new(ScriptedHistoricalGateway::with_ticks(
+        (2..=4)
+            .map(|d| (day(d), sample_ticks("NQ", day(d), 2)))
+            .collect(),
+    ));
+    let repository = Arc::new(LossyRepository {
+        lost: day(3),
+        saved: Mutex::new(BTreeSet::new()),
+    });
+    let job_repo = Arc::new(InMemoryJobStateRepository::new());
+    let service =
+        BackfillServiceImpl::new(gateway, repository.clone(), repository, job_repo.clone())
+            .with_config(BackfillConfig {
+                verify_after_run,
+                ..BackfillConfig::default()
+            });
+
+    let report = service
+        .backfill_range("NQ", DateRange::new(day(2), day(4)).unwrap())
+        .await
+        .unwrap();
+    let state = job_repo.snapshot(&job_key("NQ", day(2))).await.unwrap();
+    let failed = report.failed_days.iter().map(|(date, _)| *date).collect();
+    (failed, state.status)
+}
+
+#[tokio::test]
+async fn post_run_check_catches_lost_day() {
+    assert_eq!(run(true).await, (vec![day(3)], JobStatus::Failed));
+}
+
+#[tokio::test]
+async fn lost_day_goes_unnoticed_without_post_run_check() {
+    assert_eq!(run(false).await, (vec![], JobStatus::Completed));
+}
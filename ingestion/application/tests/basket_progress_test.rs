@@ -0,0 +1,90 @@
+mod common;
+
+use chrono::Utc;
+use common::*;
+use ingestion_application::{basket_progress, JobState, JobStatus};
+use ingestion_domain::{DateRange, TradingCalendar};
+
+fn state(status: JobStatus, cursor: i64) -> JobState {
+    JobState::new(
+        "instance".to_string(),
+        status,
+        cursor,
+        end_of_day(day(10)),
+        Utc::now(),
+    )
+}
+
+#[tokio::test]
+async fn aggregates_mixed_progress_weighted_by_trading_days() {
+    // Mon 6 .. Fri 10: five trading days per symbol.
+    let range = DateRange::new(day(6), day(10)).unwrap();
+    let repo = InMemoryJobStateRepository::new();
+    repo.insert_state(
+        job_key("ES", day(6)),
+        state(JobStatus::Completed, timestamp_for(day(10), 20, 0)),
+    )
+    .await;
+    repo.insert_state(
+        job_key("NQ", day(6)),
+        state(JobStatus::Running, timestamp_for(day(7), 20, 0)),
+    )
+    .await;
+    repo.insert_state(
+        job_key("CL", day(6)),
+        state(JobStatus::Failed, timestamp_for(day(6), 20, 0)),
+    )
+    .await;
+    // A different start date is a different job and must be ignored.
+    repo.insert_state(
+        job_key("YM", day(2)),
+        state(JobStatus::Completed, timestamp_for(day(10), 20, 0)),
+    )
+    .await;
+    // Keys that are not job keys are skipped rather than misread.
+    repo.insert_state(
+        "ingest:job:ES".to_string(),
+        state(JobStatus::Failed, timestamp_for(day(6), 20, 0)),
+    )
+    .await;
+    let symbols: Vec<String> = ["ES", "NQ", "CL", "YM"].map(String::from).to_vec();
+
+    let progress = basket_progress(&repo, &symbols, &range, &TradingCalendar::new())
+        .await
+        .unwrap();
+
+    let done: Vec<(&str, u32, Option<JobStatus>)> = progress
+        .symbols
+        .iter()
+        .map(|s| (s.symbol.as_str(), s.done_days, s.status.clone()))
+        .collect();
+    assert_eq!(
+        done,
+        vec![
+            ("ES", 5, Some(JobStatus::Completed)),
+            ("NQ", 2, Some(JobStatus::Running)),
+            ("CL", 1, Some(JobStatus::Failed)),
+            ("YM", 0, None),
+        ]
+    );
+    assert!(progress.symbols.iter().all(|s| s.total_days == 5));
+    assert_eq!(progress.percent(), 40.0);
+}
+
+#[tokio::test]
+async fn freshly_started_job_has_no_progress() {
+    let range = DateRange::new(day(6), day(10)).unwrap();
+    let repo = InMemoryJobStateRepository::new();
+    // New jobs start with the cursor just before the range.
+    repo.insert_state(
+        job_key("ES", day(6)),
+        state(JobStatus::Running, timestamp_for(day(6), 0, 0) - 1),
+    )
+    .await;
+
+    let progress = basket_progress(&repo, &["ES".to_string()], &range, &TradingCalendar::new())
+        .await
+        .unwrap();
+
+    assert_eq!(progress.percent(), 0.0);
+}
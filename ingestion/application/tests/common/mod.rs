@@ -0,0 +1,432 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+use ingestion_application::ports::RepositoryError;
+use ingestion_application::{
+    key_matches, BackfillConfig, BackfillServiceImpl, GapDetectionError, GapDetector,
+    HistoricalDataError, HistoricalDataGateway, JobState, JobStateError, JobStateRepository,
+    JobStatus, TickRepository,
+};
+#[allow(unused_imports)]
+pub use ingestion_domain::test_support::{sample_tick, sample_ticks};
+use ingestion_domain::{DateRange, Tick};
+use tokio::sync::Mutex;
+
+#[allow(dead_code)]
+pub fn build_service(
+    gateway: Arc<ScriptedHistoricalGateway>,
+    gaps: Vec<DateRange>,
+    repository: Arc<RecordingTickRepository>,
+    job_repo: Arc<InMemoryJobStateRepository>,
+    config: BackfillConfig,
+) -> BackfillServiceImpl {
+    BackfillServiceImpl::new(
+        gateway,
+        Arc::new(StubGapDetector::new(gaps)),
+        repository,
+        job_repo,
+    )
+    .with_config(config)
+}
+
+pub fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 1, d).unwrap()
+}
+
+#[allow(dead_code)]
+pub fn job_key(symbol: &str, start: NaiveDate) -> String {
+    format!("ingest:job:{}:{}", symbol, start)
+}
+
+#[allow(dead_code)]
+pub fn timestamp_for(date: NaiveDate, hour: u32, minute: u32) -> i64 {
+    date.and_hms_opt(hour, minute, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp_millis()
+}
+
+#[allow(dead_code)]
+pub fn end_of_day(date: NaiveDate) -> i64 {
+    date.and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap())
+        .and_utc()
+        .timestamp_millis()
+}
+
+#[allow(dead_code)]
+pub fn make_tick(symbol: &str, date: NaiveDate, hour: u32) -> Tick {
+    let timestamp = date.and_hms_opt(hour, 0, 0).unwrap();
+    sample_tick(symbol, Utc.from_utc_datetime(&timestamp))
+}
+
+#[allow(dead_code)]
+type ScriptedResponses = HashMap<NaiveDate, VecDeque<Result<Vec<Tick>, HistoricalDataError>>>;
+
+/// Gateway whose responses are queued per date. Once a date's queue is
+/// drained, further fetches for it return an empty day.
+#[allow(dead_code)]
+#[derive(Default)]
+pub struct ScriptedHistoricalGateway {
+    responses: Mutex<ScriptedResponses>,
+    fetches: Mutex<Vec<NaiveDate>>,
+    max_history_days: Option<u32>,
+    source_id: Option<String>,
+}
+
+#[allow(dead_code)]
+impl ScriptedHistoricalGateway {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_history_days(mut self, days: u32) -> Self {
+        self.max_history_days = Some(days);
+        self
+    }
+
+    pub fn with_source_id(mut self, source_id: &str) -> Self {
+        self.source_id = Some(source_id.to_string());
+        self
+    }
+
+    pub fn with_ticks(entries: Vec<(NaiveDate, Vec<Tick>)>) -> Self {
+        let gateway = Self::default();
+        {
+            let mut responses = gateway.responses.try_lock().unwrap();
+            for (date, ticks) in entries {
+                responses.entry(date).or_default().push_back(Ok(ticks));
+            }
+        }
+        gateway
+    }
+
+    pub async fn push(&self, date: NaiveDate, response: Result<Vec<Tick>, HistoricalDataError>) {
+        self.responses
+            .lock()
+            .await
+            .entry(date)
+            .or_default()
+            .push_back(response);
+    }
+
+    pub async fn fetches(&self) -> Vec<NaiveDate> {
+        self.fetches.lock().await.clone()
+    }
+}
+
+#[async_trait]
+impl HistoricalDataGateway for ScriptedHistoricalGateway {
+    async fn fetch_historical_ticks(
+        &self,
+        _symbol: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        self.fetches.lock().await.push(date);
+        self.responses
+            .lock()
+            .await
+            .get_mut(&date)
+            .and_then(|queue| queue.pop_front())
+            .unwrap_or_else(|| Ok(Vec::new()))
+    }
+
+    fn max_history_days(&self) -> u32 {
+        self.max_history_days.unwrap_or(u32::MAX)
+    }
+
+    fn source_id(&self) -> &str {
+        self.source_id.as_deref().unwrap_or("scripted")
+    }
+}
+
+#[allow(dead_code)]
+pub struct StubGapDetector {
+    gaps: Vec<DateRange>,
+}
+
+#[allow(dead_code)]
+impl StubGapDetector {
+    pub fn new(gaps: Vec<DateRange>) -> Self {
+        Self { gaps }
+    }
+}
+
+#[async_trait]
+impl GapDetector for StubGapDetector {
+    async fn detect_gaps(
+        &self,
+        _symbol: &str,
+        _range: DateRange,
+    ) -> Result<Vec<DateRange>, GapDetectionError> {
+        Ok(self.gaps.clone())
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Default)]
+pub struct RecordingTickRepository {
+    batches: Mutex<Vec<Vec<Tick>>>,
+    replaced_days: Mutex<Vec<NaiveDate>>,
+    no_data_days: Mutex<Vec<NaiveDate>>,
+    source: Mutex<Option<String>>,
+    shutdown_called: AtomicBool,
+    fail_writes: AtomicBool,
+}
+
+#[allow(dead_code)]
+impl RecordingTickRepository {
+    /// Makes every later `save_batch` and `replace_day` fail with an I/O error.
+    pub fn fail_writes(&self) {
+        self.fail_writes.store(true, Ordering::Relaxed);
+    }
+
+    fn check_writable(&self) -> Result<(), RepositoryError> {
+        if self.fail_writes.load(Ordering::Relaxed) {
+            return Err(RepositoryError::IoError(std::io::Error::other("disk full")));
+        }
+        Ok(())
+    }
+
+    pub async fn batches(&self) -> Vec<Vec<Tick>> {
+        self.batches.lock().await.clone()
+    }
+
+    pub async fn saved_days(&self) -> Vec<NaiveDate> {
+        self.batches
+            .lock()
+            .await
+            .iter()
+            .filter_map(|batch| batch.first())
+            .map(|tick| tick.timestamp().date_naive())
+            .collect()
+    }
+
+    /// Days written through `replace_day` rather than `save_batch`.
+    pub async fn replaced_days(&self) -> Vec<NaiveDate> {
+        self.replaced_days.lock().await.clone()
+    }
+
+    /// Days passed to `mark_no_data`.
+    pub async fn no_data_days(&self) -> Vec<NaiveDate> {
+        self.no_data_days.lock().await.clone()
+    }
+
+    pub async fn source(&self) -> Option<String> {
+        self.source.lock().await.clone()
+    }
+
+    pub fn shutdown_called(&self) -> bool {
+        self.shutdown_called.load(Ordering::Relaxed)
+    }
+}
+
+#[async_trait]
+impl TickRepository for RecordingTickRepository {
+    async fn save_batch(&self, ticks: Vec<Tick>) -> Result<(), RepositoryError> {
+        self.check_writable()?;
+        self.batches.lock().await.push(ticks);
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> Result<(), RepositoryError> {
+        self.shutdown_called.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn replace_day(
+        &self,
+        _symbol: &str,
+        date: NaiveDate,
+        ticks: Vec<Tick>,
+    ) -> Result<(), RepositoryError> {
+        self.check_writable()?;
+        self.replaced_days.lock().await.push(date);
+        self.batches.lock().await.push(ticks);
+        Ok(())
+    }
+
+    async fn set_source(&self, source: &str) {
+        *self.source.lock().await = Some(source.to_string());
+    }
+
+    async fn mark_no_data(&self, _symbol: &str, date: NaiveDate) -> Result<(), RepositoryError> {
+        self.no_data_days.lock().await.push(date);
+        Ok(())
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Default)]
+pub struct InMemoryJobStateRepository {
+    states: Mutex<HashMap<String, JobState>>,
+    heartbeats: AtomicUsize,
+    cursor_updates: Mutex<Vec<i64>>,
+}
+
+#[allow(dead_code)]
+impl InMemoryJobStateRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn insert_state(&self, key: String, state: JobState) {
+        self.states.lock().await.insert(key, state);
+    }
+
+    pub async fn snapshot(&self, key: &str) -> Option<JobState> {
+        self.states.lock().await.get(key).cloned()
+    }
+
+    pub fn heartbeat_count(&self) -> usize {
+        self.heartbeats.load(Ordering::Relaxed)
+    }
+
+    /// Every cursor written through `update_cursor`, in order.
+    pub async fn cursor_updates(&self) -> Vec<i64> {
+        self.cursor_updates.lock().await.clone()
+    }
+
+    async fn with_state<F>(
+        &self,
+        job_key: &str,
+        job_instance_id: &String,
+        f: F,
+    ) -> Result<(), JobStateError>
+    where
+        F: FnOnce(&mut JobState),
+    {
+        let mut states = self.states.lock().await;
+        let entry = states
+            .get_mut(job_key)
+            .ok_or_else(|| JobStateError::NotFound(job_key.to_string()))?;
+        if &entry.job_instance_id != job_instance_id {
+            return Err(JobStateError::StaleInstance(job_key.to_string()));
+        }
+        f(entry);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl JobStateRepository for InMemoryJobStateRepository {
+    async fn get(&self, job_key: &str) -> Result<Option<JobState>, JobStateError> {
+        Ok(self.states.lock().await.get(job_key).cloned())
+    }
+
+    async fn upsert(&self, job_key: &str, state: &JobState) -> Result<(), JobStateError> {
+        self.states
+            .lock()
+            .await
+            .insert(job_key.to_string(), state.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, job_key: &str) -> Result<(), JobStateError> {
+        self.states.lock().await.remove(job_key);
+        Ok(())
+    }
+
+    async fn update_cursor(
+        &self,
+        job_key: &str,
+        job_instance_id: &String,
+        cursor: i64,
+    ) -> Result<(), JobStateError> {
+        self.cursor_updates.lock().await.push(cursor);
+        self.with_state(job_key, job_instance_id, |state| state.cursor = cursor)
+            .await
+    }
+
+    async fn update_status(
+        &self,
+        job_key: &str,
+        job_instance_id: &String,
+        status: JobStatus,
+    ) -> Result<(), JobStateError> {
+        self.with_state(job_key, job_instance_id, |state| state.status = status)
+            .await
+    }
+
+    async fn heartbeat(
+        &self,
+        job_key: &str,
+        job_instance_id: &String,
+        heartbeat_at: chrono::DateTime<Utc>,
+    ) -> Result<(), JobStateError> {
+        self.heartbeats.fetch_add(1, Ordering::Relaxed);
+        self.with_state(job_key, job_instance_id, |state| {
+            state.heartbeat_at = heartbeat_at
+        })
+        .await
+    }
+
+    async fn save_error(
+        &self,
+        job_key: &str,
+        job_instance_id: &String,
+        message: &str,
+    ) -> Result<(), JobStateError> {
+        self.with_state(job_key, job_instance_id, |state| {
+            state.last_error_type = Some(message.to_string())
+        })
+        .await
+    }
+
+    async fn update_day_checksums(
+        &self,
+        job_key: &str,
+        job_instance_id: &String,
+        day_checksums: &BTreeMap<NaiveDate, u64>,
+    ) -> Result<(), JobStateError> {
+        self.with_state(job_key, job_instance_id, |state| {
+            state.day_checksums = day_checksums.clone()
+        })
+        .await
+    }
+
+    async fn update_progress(
+        &self,
+        job_key: &str,
+        job_instance_id: &String,
+        total_ticks: u64,
+        last_processed_date: NaiveDate,
+    ) -> Result<(), JobStateError> {
+        self.with_state(job_key, job_instance_id, |state| {
+            state.total_ticks = total_ticks;
+            state.last_processed_date = Some(last_processed_date);
+        })
+        .await
+    }
+
+    async fn find_by_status(
+        &self,
+        status: JobStatus,
+    ) -> Result<Vec<(String, JobState)>, JobStateError> {
+        Ok(self
+            .states
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, state)| state.status == status)
+            .map(|(key, state)| (key.clone(), state.clone()))
+            .collect())
+    }
+
+    async fn list_jobs(&self, pattern: &str) -> Result<Vec<(String, JobState)>, JobStateError> {
+        Ok(self
+            .states
+            .lock()
+            .await
+            .iter()
+            .filter(|(key, _)| key_matches(pattern, key))
+            .map(|(key, state)| (key.clone(), state.clone()))
+            .collect())
+    }
+}
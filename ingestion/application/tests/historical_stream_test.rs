@@ -0,0 +1,188 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate};
+use common::*;
+use futures::{stream, StreamExt};
+use ingestion_application::backfill_service::BackfillService;
+use ingestion_application::ports::RepositoryError;
+use ingestion_application::{
+    BackfillConfig, BackfillServiceImpl, HistoricalDataError, HistoricalDataGateway,
+    HistoricalTickStream, SaveStreamError, TickRepository,
+};
+use ingestion_domain::{DateRange, Tick};
+
+const DAY_TICKS: usize = 100_000;
+const CHUNK: usize = 1_000;
+
+/// Yields `DAY_TICKS` evenly spaced ticks for the day, generated lazily,
+/// and counts how many it has handed out.
+#[derive(Default)]
+struct SyntheticDayGateway {
+    produced: Arc<AtomicUsize>,
+    fail_after: Option<usize>,
+}
+
+#[async_trait]
+impl HistoricalDataGateway for SyntheticDayGateway {
+    async fn fetch_historical_ticks(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        self.fetch_historical_stream(symbol, date)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    fn fetch_historical_stream<'a>(
+        &'a self,
+        symbol: &'a str,
+        date: NaiveDate,
+    ) -> HistoricalTickStream<'a> {
+        let start = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let step = Duration::milliseconds(86_400_000 / DAY_TICKS as i64);
+        stream::iter(0..DAY_TICKS)
+            .map(move |i| {
+                if self.fail_after == Some(i) {
+                    return Err(HistoricalDataError::GatewayError("dropped".into()));
+                }
+                self.produced.fetch_add(1, Ordering::SeqCst);
+                Ok(sample_tick(symbol, start + step * i as i32))
+            })
+            .boxed()
+    }
+
+    fn max_history_days(&self) -> u32 {
+        365
+    }
+}
+
+/// Records the largest batch and the most ticks ever pulled from the
+/// gateway but not yet saved.
+struct BufferWatchingRepository {
+    produced: Arc<AtomicUsize>,
+    saved: AtomicUsize,
+    largest_batch: AtomicUsize,
+    peak_unsaved: AtomicUsize,
+}
+
+impl BufferWatchingRepository {
+    fn new(produced: Arc<AtomicUsize>) -> Self {
+        Self {
+            produced,
+            saved: AtomicUsize::new(0),
+            largest_batch: AtomicUsize::new(0),
+            peak_unsaved: AtomicUsize::new(0),
+        }
+    }
+}
+
+#[async_trait]
+impl TickRepository for BufferWatchingRepository {
+    async fn save_batch(&self, ticks: Vec<Tick>) -> Result<(), RepositoryError> {
+        let unsaved = self.produced.load(Ordering::SeqCst) - self.saved.load(Ordering::SeqCst);
+        self.peak_unsaved.fetch_max(unsaved, Ordering::SeqCst);
+        self.largest_batch.fetch_max(ticks.len(), Ordering::SeqCst);
+        self.saved.fetch_add(ticks.len(), Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn large_day_is_saved_in_bounded_chunks() {
+    let gateway = SyntheticDayGateway::default();
+    let repository = BufferWatchingRepository::new(gateway.produced.clone());
+
+    let saved = repository
+        .save_stream(gateway.fetch_historical_stream("NQ", day(2)), CHUNK)
+        .await
+        .unwrap();
+
+    assert_eq!(saved, DAY_TICKS);
+    assert_eq!(repository.saved.load(Ordering::SeqCst), DAY_TICKS);
+    assert_eq!(repository.largest_batch.load(Ordering::SeqCst), CHUNK);
+    assert_eq!(repository.peak_unsaved.load(Ordering::SeqCst), CHUNK);
+}
+
+#[tokio::test]
+async fn backfill_streams_large_days_in_bounded_chunks() {
+    let gateway = Arc::new(SyntheticDayGateway::default());
+    let repository = Arc::new(BufferWatchingRepository::new(gateway.produced.clone()));
+    let range = DateRange::new(day(2), day(3)).unwrap();
+
+    let report = BackfillServiceImpl::new(
+        gateway.clone(),
+        Arc::new(StubGapDetector::new(vec![range.clone()])),
+        repository.clone(),
+        Arc::new(InMemoryJobStateRepository::new()),
+    )
+    .with_config(BackfillConfig {
+        stream_chunk_ticks: Some(CHUNK),
+        ..BackfillConfig::default()
+    })
+    .backfill_range("NQ", range)
+    .await
+    .unwrap();
+
+    assert_eq!(report.days_processed, 2);
+    assert_eq!(report.total_ticks, 2 * DAY_TICKS);
+    assert_eq!(repository.saved.load(Ordering::SeqCst), 2 * DAY_TICKS);
+    assert_eq!(repository.largest_batch.load(Ordering::SeqCst), CHUNK);
+    assert_eq!(repository.peak_unsaved.load(Ordering::SeqCst), CHUNK);
+}
+
+#[tokio::test]
+async fn source_error_keeps_earlier_chunks() {
+    let gateway = SyntheticDayGateway {
+        fail_after: Some(2_500),
+        ..SyntheticDayGateway::default()
+    };
+    let repository = BufferWatchingRepository::new(gateway.produced.clone());
+
+    let err = repository
+        .save_stream(gateway.fetch_historical_stream("NQ", day(2)), CHUNK)
+        .await
+        .expect_err("stream fails part way");
+
+    assert!(matches!(err, SaveStreamError::Source(_)));
+    assert_eq!(repository.saved.load(Ordering::SeqCst), 2 * CHUNK);
+}
+
+#[tokio::test]
+async fn default_stream_replays_the_fetched_day() {
+    let ticks = sample_ticks("ES", day(3), 5);
+    let gateway = ScriptedHistoricalGateway::with_ticks(vec![(day(3), ticks.clone())]);
+    gateway
+        .push(day(4), Err(HistoricalDataError::DataNotAvailable(day(4))))
+        .await;
+
+    let streamed: Vec<Tick> = gateway
+        .fetch_historical_stream("ES", day(3))
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    let missing: Vec<_> = gateway
+        .fetch_historical_stream("ES", day(4))
+        .collect()
+        .await;
+
+    assert_eq!(streamed, ticks);
+    assert!(matches!(
+        missing.as_slice(),
+        [Err(HistoricalDataError::DataNotAvailable(_))]
+    ));
+}
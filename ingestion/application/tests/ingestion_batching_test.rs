@@ -0,0 +1,259 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use common::*;
+use ingestion_application::ports::{GatewayError, RepositoryError, TickStream};
+use ingestion_application::services::{IngestionError, IngestionService};
+use ingestion_application::{
+    BusyFlushPolicy, IngestionServiceImpl, MarketDataGateway, TickRepository,
+};
+use ingestion_domain::{SymbolAlias, Tick};
+use tokio::sync::{mpsc, Mutex};
+
+/// Gateway whose stream yields whatever the test sends on the channel and
+/// ends when the sender is dropped.
+struct ChannelMarketDataGateway {
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<Tick>>>,
+}
+
+impl ChannelMarketDataGateway {
+    fn new() -> (Self, mpsc::UnboundedSender<Tick>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let gateway = Self {
+            receiver: Mutex::new(Some(rx)),
+        };
+        (gateway, tx)
+    }
+}
+
+#[async_trait]
+impl MarketDataGateway for ChannelMarketDataGateway {
+    async fn subscribe(&self, _symbol: &str) -> Result<TickStream, GatewayError> {
+        let receiver = self
+            .receiver
+            .lock()
+            .await
+            .take()
+            .ok_or_else(|| GatewayError::ConnectionFailed("already subscribed".to_string()))?;
+        let stream = futures::stream::unfold(receiver, |mut rx| async move {
+            rx.recv().await.map(|tick| (Ok(tick), rx))
+        });
+        Ok(Box::new(Box::pin(stream)))
+    }
+}
+
+#[tokio::test]
+async fn zero_batch_size_flushes_only_on_timer() {
+    let (gateway, sender) = ChannelMarketDataGateway::new();
+    let repository = Arc::new(RecordingTickRepository::default());
+    let service = IngestionServiceImpl::new(
+        Arc::new(gateway),
+        repository.clone(),
+        0,
+        Duration::from_millis(300),
+    );
+    let handle = tokio::spawn(async move { service.run("NQ").await });
+
+    for hour in 0..5 {
+        sender.send(make_tick("NQ", day(1), hour)).unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(
+        repository.batches().await.is_empty(),
+        "ticks must not be flushed per tick"
+    );
+
+    tokio::time::sleep(Duration::from_millis(350)).await;
+    let batches = repository.batches().await;
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].len(), 5);
+
+    drop(sender);
+    handle.await.unwrap().unwrap();
+    assert!(repository.shutdown_called());
+}
+
+#[tokio::test]
+async fn first_flush_is_aligned_to_clock_boundary() {
+    let alignment = Duration::from_millis(400);
+    let (gateway, sender) = ChannelMarketDataGateway::new();
+    let repository = Arc::new(RecordingTickRepository::default());
+    let service = IngestionServiceImpl::new(
+        Arc::new(gateway),
+        repository.clone(),
+        0,
+        Duration::from_secs(60),
+    )
+    .with_flush_alignment(alignment);
+    let handle = tokio::spawn(async move { service.run("NQ").await });
+
+    sender.send(make_tick("NQ", day(1), 0)).unwrap();
+    let flushed_at = loop {
+        if !repository.batches().await.is_empty() {
+            break SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    };
+
+    // Without alignment nothing would flush for a minute; with it the flush
+    // lands just after a 400ms wall-clock boundary.
+    let past_boundary = flushed_at.as_millis() % alignment.as_millis();
+    assert!(
+        past_boundary < 100,
+        "flush landed {}ms past the boundary",
+        past_boundary
+    );
+
+    drop(sender);
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn zero_flush_interval_is_rejected() {
+    let (gateway, _sender) = ChannelMarketDataGateway::new();
+    let service = IngestionServiceImpl::new(
+        Arc::new(gateway),
+        Arc::new(RecordingTickRepository::default()),
+        0,
+        Duration::ZERO,
+    );
+
+    let result = service.run("NQ").await;
+
+    assert!(matches!(result, Err(IngestionError::InvalidConfig(_))));
+}
+
+/// Repository stuck mid-rotation: a blocking flush never returns and every
+/// non-blocking one finds the writer busy.
+#[derive(Default)]
+struct BusyTickRepository {
+    saved: AtomicUsize,
+    flushes: AtomicUsize,
+    skipped_flushes: AtomicUsize,
+}
+
+#[async_trait]
+impl TickRepository for BusyTickRepository {
+    async fn save_batch(&self, ticks: Vec<Tick>) -> Result<(), RepositoryError> {
+        self.saved.fetch_add(ticks.len(), Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), RepositoryError> {
+        self.flushes.fetch_add(1, Ordering::SeqCst);
+        std::future::pending().await
+    }
+
+    async fn try_flush(&self) -> Result<bool, RepositoryError> {
+        self.skipped_flushes.fetch_add(1, Ordering::SeqCst);
+        Ok(false)
+    }
+
+    async fn shutdown(&self) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn timer_flush_skips_while_repository_is_busy() {
+    let (gateway, sender) = ChannelMarketDataGateway::new();
+    let repository = Arc::new(BusyTickRepository::default());
+    let service = IngestionServiceImpl::new(
+        Arc::new(gateway),
+        repository.clone(),
+        0,
+        Duration::from_millis(50),
+    )
+    .with_busy_flush_policy(BusyFlushPolicy::Skip);
+    let handle = tokio::spawn(async move { service.run("NQ").await });
+
+    sender.send(make_tick("NQ", day(1), 0)).unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    sender.send(make_tick("NQ", day(1), 1)).unwrap();
+    drop(sender);
+
+    tokio::time::timeout(Duration::from_secs(1), handle)
+        .await
+        .expect("a busy repository must not stall ingestion")
+        .unwrap()
+        .unwrap();
+    assert_eq!(repository.flushes.load(Ordering::SeqCst), 0);
+    assert!(repository.skipped_flushes.load(Ordering::SeqCst) >= 2);
+    assert_eq!(repository.saved.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn aliased_symbols_are_saved_under_the_canonical_one() {
+    let (gateway, sender) = ChannelMarketDataGateway::new();
+    let repository = Arc::new(RecordingTickRepository::default());
+    let service = IngestionServiceImpl::new(
+        Arc::new(gateway),
+        repository.clone(),
+        4,
+        Duration::from_secs(60),
+    )
+    .with_symbol_alias(SymbolAlias::parse("NQ1!=NQ,/NQ=NQ").unwrap());
+    let handle = tokio::spawn(async move { service.run("NQ").await });
+
+    for (hour, symbol) in ["NQ1!", "/NQ", "NQ", "ES"].into_iter().enumerate() {
+        sender.send(make_tick(symbol, day(1), hour as u32)).unwrap();
+    }
+    drop(sender);
+    handle.await.unwrap().unwrap();
+
+    let symbols: Vec<String> = repository
+        .batches()
+        .await
+        .concat()
+        .iter()
+        .map(|tick| tick.symbol().to_string())
+        .collect();
+    assert_eq!(symbols, vec!["NQ", "NQ", "NQ", "ES"]);
+}
+
+#[tokio::test]
+async fn ticks_are_numbered_per_symbol_in_arrival_order() {
+    let (gateway, sender) = ChannelMarketDataGateway::new();
+    let repository = Arc::new(RecordingTickRepository::default());
+    let service = IngestionServiceImpl::new(
+        Arc::new(gateway),
+        repository.clone(),
+        10,
+        Duration::from_secs(60),
+    )
+    .with_symbol_alias(SymbolAlias::parse("NQ1!=NQ").unwrap());
+    let handle = tokio::spawn(async move { service.run("NQ").await });
+
+    // The same timestamp twice, an alias, and a gateway-assigned sequence.
+    sender.send(make_tick("NQ", day(1), 0)).unwrap();
+    sender.send(make_tick("ES", day(1), 0)).unwrap();
+    sender.send(make_tick("NQ1!", day(1), 0)).unwrap();
+    sender
+        .send(make_tick("ES", day(1), 1).with_sequence(42))
+        .unwrap();
+    sender.send(make_tick("NQ", day(1), 1)).unwrap();
+    drop(sender);
+    handle.await.unwrap().unwrap();
+
+    let sequences: Vec<(String, Option<u64>)> = repository
+        .batches()
+        .await
+        .concat()
+        .iter()
+        .map(|tick| (tick.symbol().to_string(), tick.sequence()))
+        .collect();
+    assert_eq!(
+        sequences,
+        vec![
+            ("NQ".to_string(), Some(0)),
+            ("ES".to_string(), Some(0)),
+            ("NQ".to_string(), Some(1)),
+            ("ES".to_string(), Some(42)),
+            ("NQ".to_string(), Some(2)),
+        ]
+    );
+}
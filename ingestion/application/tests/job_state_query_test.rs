@@ -0,0 +1,66 @@
+mod common;
+
+use chrono::Utc;
+use common::*;
+use ingestion_application::{highest_completed_date, JobState, JobStatus};
+
+fn state(status: JobStatus, cursor: i64) -> JobState {
+    JobState {
+        status,
+        job_instance_id: "instance".to_string(),
+        cursor,
+        end_time: cursor,
+        heartbeat_at: Utc::now(),
+        critical_ranges: Vec::new(),
+        last_error_type: None,
+        day_checksums: Default::default(),
+        total_ticks: 0,
+        last_processed_date: None,
+    }
+}
+
+#[tokio::test]
+async fn highest_completed_date_spans_jobs_with_different_starts() {
+    let repo = InMemoryJobStateRepository::new();
+    repo.insert_state(
+        job_key("NQ", day(1)),
+        state(JobStatus::Completed, end_of_day(day(5))),
+    )
+    .await;
+    repo.insert_state(
+        job_key("NQ", day(10)),
+        state(JobStatus::Completed, end_of_day(day(14))),
+    )
+    .await;
+    repo.insert_state(
+        job_key("NQ", day(3)),
+        state(JobStatus::Completed, timestamp_for(day(8), 12, 0)),
+    )
+    .await;
+    // Not completed, and other symbols, must not count.
+    repo.insert_state(
+        job_key("NQ", day(15)),
+        state(JobStatus::Running, end_of_day(day(20))),
+    )
+    .await;
+    repo.insert_state(
+        job_key("NQ:H5", day(1)),
+        state(JobStatus::Completed, end_of_day(day(25))),
+    )
+    .await;
+    repo.insert_state(
+        job_key("ES", day(1)),
+        state(JobStatus::Completed, end_of_day(day(28))),
+    )
+    .await;
+
+    assert_eq!(
+        highest_completed_date(&repo, "NQ").await.unwrap(),
+        Some(day(14))
+    );
+    assert_eq!(
+        highest_completed_date(&repo, "NQ:H5").await.unwrap(),
+        Some(day(25))
+    );
+    assert_eq!(highest_completed_date(&repo, "CL").await.unwrap(), None);
+}
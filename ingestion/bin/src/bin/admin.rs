@@ -0,0 +1,62 @@
+use clap::Parser;
+use ingestion_application::backfill_service::BackfillService;
+use ingestion_application::JobStateRepository;
+use ingestion_infrastructure::admin;
+use shaku::HasComponent;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+mod di {
+    include!("../di.rs");
+}
+
+/// Bearer token clients must send. The server refuses to start without one.
+const ADMIN_TOKEN_ENV: &str = "INGEST_ADMIN_TOKEN";
+
+#[derive(Parser)]
+#[command(name = "admin")]
+#[command(about = "Serve the admin HTTP API for backfill jobs", long_about = None)]
+struct Cli {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8081")]
+    addr: SocketAddr,
+
+    /// Refuse backfills that would fetch more than this many days, unless
+    /// the request sets allow_large_range
+    #[arg(long, default_value_t = admin::DEFAULT_MAX_DAYS)]
+    max_days: usize,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Before anything reads the environment.
+    di::load_env_file();
+    tracing_subscriber::fmt::init();
+
+    let cli = Cli::parse();
+    let token = di::or_exit(
+        std::env::var(ADMIN_TOKEN_ENV)
+            .ok()
+            .filter(|token| !token.trim().is_empty())
+            .ok_or_else(|| format!("{} must be set to the API's bearer token", ADMIN_TOKEN_ENV)),
+    );
+    let module = di::or_exit(di::create_backfill_module());
+    let backfill: Arc<dyn BackfillService> = module.resolve();
+    let jobs: Arc<dyn JobStateRepository> = module.resolve();
+
+    let listener = di::or_exit(TcpListener::bind(cli.addr).await);
+    admin::serve(
+        listener,
+        admin::router(
+            backfill,
+            jobs,
+            admin::AdminConfig {
+                max_days: Some(cli.max_days),
+                ..admin::AdminConfig::new(token)
+            },
+        ),
+    )
+    .await?;
+    Ok(())
+}
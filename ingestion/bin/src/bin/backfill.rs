@@ -1,59 +1,591 @@
 use chrono::NaiveDate;
-use clap::Parser;
-use ingestion_application::backfill_service::BackfillService;
+use clap::{Args, Parser, Subcommand};
+use failed_days::FailedDaysFile;
+use ingestion_application::backfill_service::{
+    BackfillError, BackfillOptions, BackfillReport, BackfillService,
+};
+use ingestion_application::{
+    basket_progress, BackfillConfig, BackfillEvent, BackfillProgress, JobStateRepository,
+    JobStatus, TickRepository,
+};
+use ingestion_domain::{DateRange, TradingCalendar};
+use ingestion_infrastructure::repositories::diff_directories;
+use ingestion_infrastructure::scripts::preload_scripts;
+use ingestion_infrastructure::RedisConnection;
 use shaku::HasComponent;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 mod di {
     include!("../di.rs");
 }
 
+#[path = "../failed_days.rs"]
+mod failed_days;
+#[path = "../selftest.rs"]
+mod selftest;
+
 #[derive(Parser)]
 #[command(name = "backfill")]
 #[command(about = "Backfill historical tick data", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: Option<RunArgs>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write, read back and verify a day of mock ticks in a temp directory
+    Selftest,
+    /// Show combined progress of a basket backfill from stored job states
+    Progress {
+        /// Symbols in the basket, e.g. ES,NQ,CL
+        #[arg(long, value_delimiter = ',', required = true)]
+        symbols: Vec<String>,
+
+        #[arg(short, long)]
+        start_date: NaiveDate,
+
+        #[arg(short, long)]
+        end_date: NaiveDate,
+    },
+    /// Delete a symbol's data files that hold no ticks, e.g. from aborted writes
+    Prune {
+        #[arg(long)]
+        symbol: String,
+    },
+    /// Merge each date's backfilled daily file with the live hourly files
+    /// written for it, keeping the live ticks where both cover an hour
+    Reconcile {
+        #[arg(long)]
+        symbol: String,
+
+        /// Dates to reconcile, e.g. 2025-01-03,2025-01-07
+        #[arg(long, value_delimiter = ',', required = true)]
+        dates: Vec<NaiveDate>,
+    },
+    /// Compare a symbol's per-day row counts between two data directories
+    Diff {
+        /// First data directory ("A")
+        a: PathBuf,
+
+        /// Second data directory ("B")
+        b: PathBuf,
+
+        #[arg(long)]
+        symbol: String,
+
+        #[arg(short, long)]
+        start_date: NaiveDate,
+
+        #[arg(short, long)]
+        end_date: NaiveDate,
+
+        /// Also compare tick content on days whose row counts match
+        #[arg(long)]
+        checksums: bool,
+    },
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// Defaults to the symbol recorded in the retry file when one is given
+    #[arg(long, required_unless_present = "retry_file")]
+    symbol: Option<String>,
+
+    #[arg(short, long, required_unless_present_any = ["retry_file", "dates"])]
+    start_date: Option<String>,
+
+    /// Last day to backfill. Inclusive unless --end-exclusive is given
+    #[arg(short, long, required_unless_present_any = ["retry_file", "dates"])]
+    end_date: Option<String>,
+
+    /// Treat --end-date as the first day not to backfill
+    #[arg(long, conflicts_with_all = ["dates", "retry_file"])]
+    end_exclusive: bool,
+
+    /// Refetch only these dates, e.g. 2025-01-03,2025-01-07 (skips gap detection)
+    #[arg(
+        long,
+        value_delimiter = ',',
+        conflicts_with_all = ["start_date", "end_date", "retry_file"]
+    )]
+    dates: Vec<NaiveDate>,
+
+    /// Write failed days to this JSON file when any day fails
+    #[arg(long)]
+    failure_file: Option<PathBuf>,
+
+    /// Backfill only the dates listed in a failure file from a previous run
+    #[arg(long, conflicts_with_all = ["start_date", "end_date"])]
+    retry_file: Option<PathBuf>,
+
+    /// Print a line per day as the backfill progresses
+    #[arg(long)]
+    progress: bool,
+
+    /// Print the days that would be fetched and an ETA, then exit
+    #[arg(long, conflicts_with_all = ["dates", "retry_file", "failure_file"])]
+    dry_run: bool,
+
+    /// Refetch every day in the range and replace existing files
+    #[arg(long, conflicts_with_all = ["dates", "retry_file", "dry_run"])]
+    force_overwrite: bool,
+
+    /// If another instance holds the job's lock, wait up to this long for
+    /// it to free up, e.g. 90s, 10m, 1h
+    #[arg(long, value_parser = parse_duration, conflicts_with = "dry_run")]
+    wait_for_lock: Option<Duration>,
+
+    /// Refuse to start if more than this many days would be fetched
+    #[arg(long, default_value_t = 366)]
+    max_days: usize,
+
+    /// Proceed even when the range needs more than --max-days days
+    #[arg(long)]
+    allow_large_range: bool,
+
+    /// Days fetched at the same time [env:
+    /// INGEST_BACKFILL_MAX_CONCURRENT_DAYS, default 1]
+    #[arg(long)]
+    max_concurrent_days: Option<usize>,
+
+    /// Fetched days written at the same time [env:
+    /// INGEST_BACKFILL_MAX_CONCURRENT_WRITES, default 1]
     #[arg(long)]
-    symbol: String,
+    max_concurrent_writes: Option<usize>,
 
-    #[arg(short, long)]
-    start_date: String,
+    /// Merge gaps separated by fewer present days than this, refetching the
+    /// days between [env: INGEST_BACKFILL_COALESCE_GAP_DAYS, default 0]
+    #[arg(long)]
+    coalesce_gap_days: Option<u32>,
 
-    #[arg(short, long)]
-    end_date: String,
+    /// Refetch an empty trading day this many times before accepting it
+    /// [env: INGEST_BACKFILL_EMPTY_DAY_RETRIES, default 0]
+    #[arg(long)]
+    empty_day_retries: Option<u32>,
+}
+
+impl RunArgs {
+    /// Backfill settings from the environment, overridden by any given flags.
+    fn backfill_config(&self) -> BackfillConfig {
+        let mut config = di::backfill_config();
+        if let Some(days) = self.max_concurrent_days {
+            config.max_concurrent_days = days;
+        }
+        if let Some(writes) = self.max_concurrent_writes {
+            config.max_concurrent_writes = writes;
+        }
+        if let Some(days) = self.coalesce_gap_days {
+            config.coalesce_gap_days = days;
+        }
+        if let Some(retries) = self.empty_day_retries {
+            config.empty_day_retries = retries;
+        }
+        config
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Before anything reads the environment.
+    di::load_env_file();
     tracing_subscriber::fmt::init();
 
     let cli = Cli::parse();
 
-    let start_date = NaiveDate::parse_from_str(&cli.start_date, "%Y-%m-%d")?;
-    let end_date = NaiveDate::parse_from_str(&cli.end_date, "%Y-%m-%d")?;
+    match (cli.command, cli.run) {
+        (Some(Command::Selftest), _) => run_self_test().await,
+        (
+            Some(Command::Progress {
+                symbols,
+                start_date,
+                end_date,
+            }),
+            _,
+        ) => run_progress(&symbols, DateRange::new(start_date, end_date)?).await,
+        (Some(Command::Prune { symbol }), _) => run_prune(&symbol).await,
+        (Some(Command::Reconcile { symbol, dates }), _) => run_reconcile(&symbol, &dates).await,
+        (
+            Some(Command::Diff {
+                a,
+                b,
+                symbol,
+                start_date,
+                end_date,
+                checksums,
+            }),
+            _,
+        ) => {
+            let range = DateRange::new(start_date, end_date)?;
+            run_diff(&a, &b, &symbol, &range, checksums)
+        }
+        (None, Some(run)) => run_backfill(run).await,
+        (None, None) => {
+            use clap::CommandFactory;
+            Cli::command().print_help()?;
+            std::process::exit(2);
+        }
+    }
+}
+
+async fn run_self_test() -> Result<(), Box<dyn std::error::Error>> {
+    let report = selftest::run_in_temp_dir().await?;
+
+    println!("Self-test for {}:", report.date);
+    println!("  Ticks written: {}", report.ticks_written);
+    println!("  Ticks read: {}", report.ticks_read);
+    println!(
+        "  Checksum: {:016x} / {:016x}",
+        report.checksum_written, report.checksum_read
+    );
+    println!("  Gaps found: {}", report.gaps_found);
+
+    if report.passed() {
+        println!("PASS");
+        Ok(())
+    } else {
+        println!("FAIL");
+        std::process::exit(1);
+    }
+}
 
-    let range = ingestion_domain::DateRange::new(start_date, end_date)?;
+async fn run_progress(
+    symbols: &[String],
+    range: DateRange,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let module = di::or_exit(di::create_app_module());
+    let job_states: Arc<dyn JobStateRepository> = module.resolve();
+    let progress = basket_progress(
+        job_states.as_ref(),
+        symbols,
+        &range,
+        &TradingCalendar::new(),
+    )
+    .await?;
 
     println!(
-        "Starting backfill for {} from {} to {}",
-        cli.symbol, start_date, end_date
+        "Backfill progress from {} to {}:",
+        progress.range.start(),
+        progress.range.end()
     );
+    for symbol in &progress.symbols {
+        let status = symbol
+            .status
+            .as_ref()
+            .map_or("NOT STARTED", JobStatus::as_str);
+        println!(
+            "  {:<10} {:>5.1}%  {}/{} days  {}",
+            symbol.symbol,
+            symbol.percent(),
+            symbol.done_days,
+            symbol.total_days,
+            status
+        );
+    }
+    println!("  Overall: {:.1}%", progress.percent());
+    Ok(())
+}
+
+async fn run_prune(symbol: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let module = di::or_exit(di::create_backfill_module());
+    let repository: Arc<dyn TickRepository> = module.resolve();
+    let removed = repository.prune_empty(symbol).await?;
+    println!("Removed {} empty file(s) for {}", removed, symbol);
+    Ok(())
+}
 
-    let module = di::create_app_module();
+async fn run_reconcile(
+    symbol: &str,
+    dates: &[NaiveDate],
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Live ingestion's module: reconciling maps ticks onto its hourly files.
+    let module = di::or_exit(di::create_app_module());
+    let repository: Arc<dyn TickRepository> = module.resolve();
+    for date in dates {
+        repository.reconcile_day(symbol, *date).await?;
+        println!("Reconciled {} on {}", symbol, date);
+    }
+    Ok(())
+}
+
+fn run_diff(
+    a: &Path,
+    b: &Path,
+    symbol: &str,
+    range: &DateRange,
+    checksums: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let differences = diff_directories(a, b, symbol, range, checksums)?;
+    if differences.is_empty() {
+        println!("No differences for {}", symbol);
+        return Ok(());
+    }
+    for difference in &differences {
+        println!("{}", difference);
+    }
+    println!("{} day(s) differ for {}", differences.len(), symbol);
+    std::process::exit(1);
+}
+
+async fn run_backfill(cli: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let module = di::or_exit(di::create_backfill_module_with(cli.backfill_config()));
+    // Load the Redis scripts up front so a broken one stops us here, not
+    // partway through the range.
+    let redis: Arc<dyn RedisConnection> = module.resolve();
+    di::or_exit(preload_scripts(redis.as_ref()).await);
     let service: Arc<dyn BackfillService> = module.resolve();
 
-    let report = service.backfill_range(&cli.symbol, range).await?;
+    let report = match &cli.retry_file {
+        Some(path) => retry_failed_days(service.as_ref(), path, cli.symbol.clone()).await?,
+        None if !cli.dates.is_empty() => {
+            let symbol = cli.symbol.clone().expect("clap requires --symbol");
+            println!(
+                "Starting backfill for {} on {} date(s)",
+                symbol,
+                cli.dates.len()
+            );
+            service.backfill_dates(&symbol, cli.dates.clone()).await?
+        }
+        None => {
+            let symbol = cli.symbol.clone().expect("clap requires --symbol");
+            let range = cli_range(
+                parse_date(cli.start_date.as_deref())?,
+                parse_date(cli.end_date.as_deref())?,
+                cli.end_exclusive,
+            )?;
+            let (start_date, end_date) = (range.start(), range.end());
+
+            if cli.dry_run {
+                return print_plan(service.as_ref(), &symbol, range).await;
+            }
 
-    println!("\nBackfill completed:");
+            println!(
+                "Starting backfill for {} from {} to {}",
+                symbol, start_date, end_date
+            );
+            // Ctrl-C stops the run promptly, even mid rate-limit wait.
+            let cancel = CancellationToken::new();
+            let on_interrupt = cancel.clone();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    on_interrupt.cancel();
+                }
+            });
+            let mut options = BackfillOptions {
+                force_overwrite: cli.force_overwrite,
+                cancel: Some(cancel),
+                wait_for_lock: cli.wait_for_lock,
+                max_days: (!cli.allow_large_range).then_some(cli.max_days),
+                ..BackfillOptions::default()
+            };
+            let printer = cli.progress.then(|| {
+                let (events_tx, events_rx) = mpsc::unbounded_channel();
+                let (progress_tx, progress_rx) = mpsc::channel(64);
+                options.events = Some(events_tx);
+                options.progress = Some(progress_tx);
+                tokio::spawn(print_progress(events_rx, progress_rx))
+            });
+
+            let report = match service
+                .backfill_range_with_options(&symbol, range, options)
+                .await
+            {
+                Err(err @ BackfillError::TooManyDays { .. }) => {
+                    eprintln!("{} (pass --allow-large-range to proceed)", err);
+                    std::process::exit(1);
+                }
+                result => result?,
+            };
+            if let Some(printer) = printer {
+                printer.await?;
+            }
+            report
+        }
+    };
+
+    if report.cancelled {
+        println!("\nBackfill cancelled (run again to resume):");
+    } else {
+        println!("\nBackfill completed:");
+    }
     println!("  Symbol: {}", report.symbol);
     println!("  Days processed: {}", report.days_processed);
     println!("  Total ticks: {}", report.total_ticks);
+    if report.lifetime_ticks as usize != report.total_ticks {
+        println!("  Ticks across all runs: {}", report.lifetime_ticks);
+    }
+    if !report.days_no_data.is_empty() {
+        println!("  Days without data: {}", report.days_no_data.len());
+    }
+    if !report.invalid_ticks.is_empty() {
+        println!(
+            "  Invalid ticks dropped: {} over {} day(s)",
+            report.invalid_ticks.values().sum::<usize>(),
+            report.invalid_ticks.len()
+        );
+    }
 
     if !report.failed_days.is_empty() {
         println!("\n  Failed days:");
         for (date, error) in &report.failed_days {
             println!("    {} - {}", date, error);
         }
+
+        if let Some(path) = &cli.failure_file {
+            FailedDaysFile::from_report(&report).write_to(path)?;
+            println!("\n  Failed days written to {}", path.display());
+        }
     }
 
     Ok(())
 }
+
+async fn print_plan(
+    service: &dyn BackfillService,
+    symbol: &str,
+    range: DateRange,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let plan = service.plan(symbol, range).await?;
+
+    println!(
+        "Backfill plan for {} from {} to {}:",
+        plan.symbol,
+        plan.range.start(),
+        plan.range.end()
+    );
+    if plan.resume_from != plan.range.start() {
+        println!("  Resuming from: {}", plan.resume_from);
+    }
+    println!("  Days to fetch: {}", plan.days.len());
+    for day in &plan.days {
+        println!("    {}  ({})", day.date, day.reason);
+    }
+    println!(
+        "  Estimated duration: ~{}s",
+        plan.estimated_duration.as_secs()
+    );
+    Ok(())
+}
+
+/// Prints a running line per finished day, plus details of days that
+/// failed, had no data or changed at the source.
+async fn print_progress(
+    mut events: mpsc::UnboundedReceiver<BackfillEvent>,
+    mut progress: mpsc::Receiver<BackfillProgress>,
+) {
+    loop {
+        tokio::select! {
+            Some(event) = events.recv() => match event {
+                BackfillEvent::GapsDetected(days) => println!("  {} day(s) to process", days),
+                BackfillEvent::DayFailed { date, error } => {
+                    println!("  {} - failed: {}", date, error)
+                }
+                BackfillEvent::DayNoData(date) => println!("  {} - no data", date),
+                BackfillEvent::SourceDataChanged { date } => {
+                    println!("  {} - source data changed since the last fetch", date)
+                }
+                _ => {}
+            },
+            Some(day) = progress.recv() => println!(
+                "  [{}/{}] {} - {} ticks",
+                day.days_done, day.days_total, day.date, day.ticks
+            ),
+            else => break,
+        }
+    }
+}
+
+fn parse_date(value: Option<&str>) -> Result<NaiveDate, Box<dyn std::error::Error>> {
+    let value = value.ok_or("missing date argument")?;
+    Ok(NaiveDate::parse_from_str(value, "%Y-%m-%d")?)
+}
+
+/// Range from the command line's dates. `end` is the last day included
+/// unless `end_exclusive`, in which case the range stops the day before.
+fn cli_range(
+    start: NaiveDate,
+    end: NaiveDate,
+    end_exclusive: bool,
+) -> Result<DateRange, Box<dyn std::error::Error>> {
+    if !end_exclusive {
+        return Ok(DateRange::new(start, end)?);
+    }
+    match end.pred_opt() {
+        Some(last) if last >= start => Ok(DateRange::new(start, last)?),
+        _ => Err(format!(
+            "empty range: {} to {} (exclusive) contains no days",
+            start, end
+        )
+        .into()),
+    }
+}
+
+/// Parses a duration such as `90s`, `10m` or `1h`; bare numbers are seconds.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let (digits, unit_secs) = match value.char_indices().last() {
+        Some((i, 's')) => (&value[..i], 1),
+        Some((i, 'm')) => (&value[..i], 60),
+        Some((i, 'h')) => (&value[..i], 3600),
+        _ => (value, 1),
+    };
+    digits
+        .parse::<u64>()
+        .map(|n| Duration::from_secs(n.saturating_mul(unit_secs)))
+        .map_err(|_| format!("invalid duration '{}': expected e.g. 90s, 10m or 1h", value))
+}
+
+/// Re-runs the dates listed in a failure file as a single targeted backfill.
+async fn retry_failed_days(
+    service: &dyn BackfillService,
+    path: &std::path::Path,
+    symbol: Option<String>,
+) -> Result<BackfillReport, Box<dyn std::error::Error>> {
+    let file = FailedDaysFile::read_from(path)?;
+    let symbol = symbol.unwrap_or_else(|| file.symbol.clone());
+    let dates = file.dates();
+    if dates.is_empty() {
+        return Err(format!("No failed days listed in {}", path.display()).into());
+    }
+
+    println!(
+        "Retrying {} failed day(s) for {} from {}",
+        dates.len(),
+        symbol,
+        path.display()
+    );
+
+    Ok(service.backfill_dates(&symbol, dates).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, day).unwrap()
+    }
+
+    #[test]
+    fn end_date_is_inclusive_unless_flagged() {
+        let inclusive = cli_range(date(2), date(5), false).unwrap();
+        let exclusive = cli_range(date(2), date(5), true).unwrap();
+
+        assert_eq!(inclusive, DateRange::new(date(2), date(5)).unwrap());
+        assert_eq!(exclusive, DateRange::new(date(2), date(4)).unwrap());
+    }
+
+    #[test]
+    fn exclusive_end_on_the_start_date_is_rejected() {
+        assert!(cli_range(date(2), date(2), false).is_ok());
+        assert!(cli_range(date(2), date(2), true).is_err());
+        assert!(cli_range(date(3), date(2), true).is_err());
+    }
+}
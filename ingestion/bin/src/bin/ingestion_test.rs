@@ -8,7 +8,7 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilte
 #[path = "../di.rs"]
 mod di;
 
-use crate::di::create_app_module;
+use crate::di::{create_app_module, or_exit};
 use ingestion_application::services::IngestionService;
 
 #[tokio::main]
@@ -20,9 +20,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("Starting Ingestion Test (will stop after 15 seconds)");
 
-    let module = create_app_module();
+    let module = or_exit(create_app_module());
     let service: Arc<dyn IngestionService> = module.resolve();
     let repository: Arc<dyn TickRepository> = module.resolve();
+    let recovered = or_exit(repository.recover().await);
+    if recovered > 0 {
+        info!("Recovered {} ticks from write-ahead logs", recovered);
+    }
 
     tokio::select! {
         result = service.run("NQ") => {
@@ -1,3 +1,4 @@
+use ingestion_infrastructure::repositories::parquet::SOURCE_KEY;
 use parquet::file::reader::{FileReader, SerializedFileReader};
 use std::fs::File;
 use std::path::Path;
@@ -16,6 +17,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  - Version: {}", metadata.file_metadata().version());
     println!("  - Num rows: {}", metadata.file_metadata().num_rows());
     println!("  - Num row groups: {}", metadata.num_row_groups());
+    let source = metadata
+        .file_metadata()
+        .key_value_metadata()
+        .and_then(|entries| entries.iter().find(|kv| kv.key == SOURCE_KEY))
+        .and_then(|kv| kv.value.as_deref());
+    println!("  - Source: {}", source.unwrap_or("unrecorded"));
 
     println!("\n📋 Schema:");
     println!("{:?}", metadata.file_metadata().schema());
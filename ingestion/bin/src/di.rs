@@ -1,20 +1,55 @@
 use ingestion_application::backfill_service::BackfillServiceImplParameters;
 use ingestion_application::services::IngestionServiceImplParameters;
-use ingestion_application::{BackfillServiceImpl, IngestionServiceImpl};
+use ingestion_application::{
+    BackfillConfig, BackfillServiceImpl, BusyFlushPolicy, ContractResolver, IngestionServiceImpl,
+};
+use ingestion_domain::{RollSchedule, SymbolAlias, TradingCalendar};
 use ingestion_infrastructure::detectors::gap::ParquetGapDetectorParameters;
+use ingestion_infrastructure::filesystem::{ensure_writable_dir, OutputDirError};
+#[cfg(not(feature = "ib-gateway"))]
 use ingestion_infrastructure::gateways::historical::MockHistoricalDataGatewayParameters;
+use ingestion_infrastructure::gateways::historical::DEFAULT_ACQUIRE_TIMEOUT;
+#[cfg(feature = "ib-gateway")]
+use ingestion_infrastructure::gateways::ib::historical::IbHistoricalDataGatewayParameters;
+#[cfg(feature = "ib-gateway")]
+use ingestion_infrastructure::gateways::ib::IbHistoricalConfig;
 use ingestion_infrastructure::gateways::market_data::MockMarketDataGatewayParameters;
-use ingestion_infrastructure::rate_limiting::redis::RedisConnectionManager;
-use ingestion_infrastructure::repositories::parquet::ParquetTickRepositoryParameters;
+use ingestion_infrastructure::rate_limiting::limiter::{
+    IbRateLimiterConfig, IbRateLimiterParameters,
+};
+use ingestion_infrastructure::rate_limiting::redis::{redis_db_from_env, RedisConnectionManager};
+use ingestion_infrastructure::repositories::parquet::{
+    FileDateMode, FileRotation, ParquetTickRepositoryParameters, PriceFormat,
+    DEFAULT_MAX_BATCH_TICKS, DEFAULT_MIN_FLUSH_ROWS,
+};
+use ingestion_infrastructure::repositories::JsonlDeadLetterSink;
+use ingestion_infrastructure::state::redis::{
+    RedisJobStateRepositoryParameters, DEFAULT_AUDIT_TRAIL_LEN, JOB_STATE_REDIS_DB_ENV,
+};
+#[cfg(feature = "ib-gateway")]
+use ingestion_infrastructure::IbHistoricalDataGateway;
+#[cfg(not(feature = "ib-gateway"))]
+use ingestion_infrastructure::MockHistoricalDataGateway;
 use ingestion_infrastructure::{
-    IbRateLimiter, MockHistoricalDataGateway, MockMarketDataGateway, ParquetGapDetector,
-    ParquetTickRepository, RedisJobStateRepository,
+    IbRateLimiter, MockMarketDataGateway, ParquetGapDetector, ParquetTickRepository, RedisGapQueue,
+    RedisJobStateRepository, StdFileSystem,
 };
+use parquet::basic::Compression;
 use shaku::module;
+use std::collections::HashMap;
+use std::fmt::Display;
 use std::path::Path;
-use std::sync::Arc;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
-use tokio::sync::Mutex;
+use tracing::info;
+
+/// Source of historical data: generated by the mock unless built with the
+/// `ib-gateway` feature.
+#[cfg(not(feature = "ib-gateway"))]
+type HistoricalGateway = MockHistoricalDataGateway;
+#[cfg(feature = "ib-gateway")]
+type HistoricalGateway = IbHistoricalDataGateway;
 
 module! {
     pub AppModule {
@@ -23,42 +58,237 @@ module! {
             MockMarketDataGateway,
             ParquetTickRepository,
             IbRateLimiter,
-            MockHistoricalDataGateway,
+            HistoricalGateway,
             ParquetGapDetector,
             BackfillServiceImpl,
             RedisConnectionManager,
-            RedisJobStateRepository
+            RedisJobStateRepository,
+            RedisGapQueue,
+            StdFileSystem
         ],
         providers = []
     }
 }
 
-pub fn create_app_module() -> AppModule {
+/// Unwraps a startup step, or prints its error and exits. Returning the
+/// error from `main` would print its `Debug` form instead of the message.
+pub fn or_exit<T, E: std::fmt::Display>(result: Result<T, E>) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("Startup failed: {}", err);
+        std::process::exit(1);
+    })
+}
+
+/// Loads `.env` from the working directory or a parent, if there is one.
+/// Variables already set win. A file that exists but can't be read or
+/// parsed stops startup rather than leaving its settings silently unset.
+#[allow(dead_code)]
+pub fn load_env_file() {
+    if let Err(err) = dotenvy::dotenv() {
+        if !err.not_found() {
+            or_exit(Err::<(), _>(format!("Failed to load .env: {}", err)));
+        }
+    }
+}
+
+/// Module for live ingestion: tick files rotate hourly.
+#[allow(dead_code)]
+pub fn create_app_module() -> Result<AppModule, OutputDirError> {
+    build_app_module(FileRotation::Hourly, backfill_config())
+}
+
+/// Module for backfills, which save whole days at a time and so write one
+/// file per day.
+#[allow(dead_code)]
+pub fn create_backfill_module() -> Result<AppModule, OutputDirError> {
+    create_backfill_module_with(backfill_config())
+}
+
+/// [`create_backfill_module`] with `config` in place of the one from the
+/// environment, e.g. after applying command-line overrides.
+#[allow(dead_code)]
+pub fn create_backfill_module_with(config: BackfillConfig) -> Result<AppModule, OutputDirError> {
+    build_app_module(FileRotation::Daily, config)
+}
+
+/// `key` parsed from the environment, or `default` when unset. A value that
+/// doesn't parse stops startup.
+fn env_or<T: FromStr>(key: &str, default: T) -> T
+where
+    T::Err: Display,
+{
+    match std::env::var(key) {
+        Ok(value) => or_exit(
+            value
+                .trim()
+                .parse()
+                .map_err(|err| format!("Invalid {} '{}': {}", key, value, err)),
+        ),
+        Err(_) => default,
+    }
+}
+
+/// Backfill settings, with the ones operators tune read from `INGEST_BACKFILL_*`
+/// variables; unset ones keep [`BackfillConfig::default`].
+pub fn backfill_config() -> BackfillConfig {
+    let defaults = BackfillConfig::default();
+    BackfillConfig {
+        max_concurrent_days: env_or(
+            "INGEST_BACKFILL_MAX_CONCURRENT_DAYS",
+            defaults.max_concurrent_days,
+        ),
+        max_concurrent_writes: env_or(
+            "INGEST_BACKFILL_MAX_CONCURRENT_WRITES",
+            defaults.max_concurrent_writes,
+        ),
+        coalesce_gap_days: env_or(
+            "INGEST_BACKFILL_COALESCE_GAP_DAYS",
+            defaults.coalesce_gap_days,
+        ),
+        empty_day_retries: env_or(
+            "INGEST_BACKFILL_EMPTY_DAY_RETRIES",
+            defaults.empty_day_retries,
+        ),
+        // 0, like unset, buffers whole days.
+        stream_chunk_ticks: Some(env_or("INGEST_BACKFILL_STREAM_CHUNK_TICKS", 0))
+            .filter(|ticks| *ticks > 0)
+            .or(defaults.stream_chunk_ticks),
+        ..defaults
+    }
+}
+
+/// Seconds finished jobs are kept in Redis, from
+/// `INGEST_JOB_TERMINAL_TTL_SECS`; unset or 0 keeps them until deleted.
+fn job_terminal_ttl_secs() -> Option<u64> {
+    Some(env_or("INGEST_JOB_TERMINAL_TTL_SECS", 0)).filter(|secs| *secs > 0)
+}
+
+/// Rate limiter settings from the environment, logged so the effective
+/// windows show up in the startup output.
+fn rate_limiter_config() -> IbRateLimiterConfig {
+    let config = IbRateLimiterConfig::from_env();
+    info!("Rate limiter algorithm: {}", config.algorithm);
+    for (name, window) in config.windows() {
+        info!(
+            "Rate limit window {}: {} requests per {}s",
+            name, window.limit, window.duration_secs
+        );
+    }
+    config
+}
+
+/// Symbol aliases from `INGEST_SYMBOL_ALIASES` (`alias=canonical` pairs,
+/// comma-separated); unset means symbols are stored as received.
+fn symbol_alias() -> SymbolAlias {
+    let spec = std::env::var("INGEST_SYMBOL_ALIASES").unwrap_or_default();
+    let mapping = or_exit(SymbolAlias::parse(&spec));
+    if !mapping.is_identity() {
+        info!("Symbol aliases: {}", spec);
+    }
+    mapping
+}
+
+/// Futures rolls from `INGEST_ROLL_SCHEDULE` (`symbol@YYYY-MM-DD=contract`
+/// entries, comma-separated); unset fetches every symbol as itself.
+fn contract_resolver() -> Option<Arc<dyn ContractResolver>> {
+    let spec = std::env::var("INGEST_ROLL_SCHEDULE").unwrap_or_default();
+    let schedule = or_exit(RollSchedule::parse(&spec));
+    if schedule.is_empty() {
+        return None;
+    }
+    info!("Roll schedule: {}", spec);
+    Some(Arc::new(schedule))
+}
+
+#[cfg(not(feature = "ib-gateway"))]
+fn historical_gateway_parameters() -> MockHistoricalDataGatewayParameters {
+    MockHistoricalDataGatewayParameters {
+        base_price: 16000.0,
+        max_history_days: 365,
+        price_profiles: HashMap::new(),
+        acquire_timeout: Some(DEFAULT_ACQUIRE_TIMEOUT),
+    }
+}
+
+/// IB connection and contract settings from the environment; see
+/// [`IbHistoricalConfig::from_env`].
+#[cfg(feature = "ib-gateway")]
+fn historical_gateway_parameters() -> IbHistoricalDataGatewayParameters {
+    let config = IbHistoricalConfig::from_env();
+    info!(
+        "Historical data from IB at {}:{} ({} {} {})",
+        config.host, config.port, config.sec_type, config.exchange, config.currency
+    );
+    IbHistoricalDataGatewayParameters {
+        config,
+        acquire_timeout: Some(DEFAULT_ACQUIRE_TIMEOUT),
+        connection: Default::default(),
+        next_req_id: Default::default(),
+    }
+}
+
+fn build_app_module(
+    rotation: FileRotation,
+    backfill: BackfillConfig,
+) -> Result<AppModule, OutputDirError> {
     let output_dir = Path::new("./data/").to_path_buf();
-    std::fs::create_dir_all(&output_dir).expect("Failed to create output directory");
-    AppModule::builder()
+    ensure_writable_dir(&output_dir)?;
+    let dead_letter_dir = output_dir.join("dead_letter");
+    ensure_writable_dir(&dead_letter_dir)?;
+    Ok(AppModule::builder()
         .with_component_parameters::<IngestionServiceImpl>(IngestionServiceImplParameters {
             batch_size: 1000,
             flush_interval: Duration::from_secs(5),
+            flush_alignment: None,
+            record_recv_latency: false,
+            busy_flush: BusyFlushPolicy::default(),
+            symbol_alias: symbol_alias(),
         })
         .with_component_parameters::<MockMarketDataGateway>(MockMarketDataGatewayParameters {
             tick_interval: Duration::from_millis(100),
             base_price: 16000.0,
+            price_profiles: HashMap::new(),
         })
         .with_component_parameters::<ParquetTickRepository>(ParquetTickRepositoryParameters {
             output_dir: output_dir.clone(),
-            writer: Arc::new(Mutex::new(None)),
-            current_hour: Arc::new(Mutex::new(None)),
+            lock_files: true,
+            symbol_dictionary: true,
+            mark_incomplete_on_shutdown: false,
+            max_batch_ticks: DEFAULT_MAX_BATCH_TICKS,
+            min_flush_rows: DEFAULT_MIN_FLUSH_ROWS,
+            price_format: PriceFormat::default(),
+            file_dates: FileDateMode::default(),
+            rotation,
+            recv_latency_column: false,
+            compression: Compression::SNAPPY,
+            // Live ingestion keeps hour files open for up to an hour, so log
+            // their ticks; a crash then loses none. Backfills can refetch.
+            write_ahead_log: rotation == FileRotation::Hourly,
+            source: Arc::new(RwLock::new(None)),
+            writers: Default::default(),
+            closed_files: Default::default(),
+        })
+        .with_component_parameters::<HistoricalGateway>(historical_gateway_parameters())
+        .with_component_parameters::<IbRateLimiter>(IbRateLimiterParameters {
+            config: rate_limiter_config(),
+        })
+        .with_component_parameters::<RedisJobStateRepository>(RedisJobStateRepositoryParameters {
+            audit_trail_len: DEFAULT_AUDIT_TRAIL_LEN,
+            terminal_ttl_secs: job_terminal_ttl_secs(),
+            redis_db: redis_db_from_env(JOB_STATE_REDIS_DB_ENV),
         })
-        .with_component_parameters::<MockHistoricalDataGateway>(
-            MockHistoricalDataGatewayParameters {
-                base_price: 16000.0,
-                max_history_days: 365,
-            },
-        )
         .with_component_parameters::<ParquetGapDetector>(ParquetGapDetectorParameters {
             data_dir: output_dir,
+            calendar: TradingCalendar::default(),
+            intra_day: None,
+        })
+        .with_component_parameters::<BackfillServiceImpl>(BackfillServiceImplParameters {
+            config: backfill,
+            dead_letters: Some(Arc::new(JsonlDeadLetterSink::new(
+                dead_letter_dir,
+                Arc::new(StdFileSystem),
+            ))),
+            contract_resolver: contract_resolver(),
         })
-        .with_component_parameters::<BackfillServiceImpl>(BackfillServiceImplParameters {})
-        .build()
+        .build())
 }
@@ -0,0 +1,106 @@
+use chrono::NaiveDate;
+use ingestion_application::BackfillReport;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FailedDaysFileError {
+    #[error("Failed to access failed-days file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Malformed failed-days file: {0}")]
+    Format(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedDay {
+    pub date: NaiveDate,
+    pub error: String,
+}
+
+/// JSON record of the days a backfill run could not complete, written so a
+/// later `--retry-file` run can target exactly those dates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedDaysFile {
+    pub symbol: String,
+    pub failed_days: Vec<FailedDay>,
+}
+
+impl FailedDaysFile {
+    pub fn from_report(report: &BackfillReport) -> Self {
+        Self {
+            symbol: report.symbol.clone(),
+            failed_days: report
+                .failed_days
+                .iter()
+                .map(|(date, error)| FailedDay {
+                    date: *date,
+                    error: error.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn write_to(&self, path: &Path) -> Result<(), FailedDaysFileError> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn read_from(path: &Path) -> Result<Self, FailedDaysFileError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Distinct failed dates in ascending order.
+    pub fn dates(&self) -> Vec<NaiveDate> {
+        let mut dates: Vec<NaiveDate> = self.failed_days.iter().map(|day| day.date).collect();
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ingestion_domain::DateRange;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, d).unwrap()
+    }
+
+    #[test]
+    fn failed_days_round_trip_through_file() {
+        let report = BackfillReport {
+            symbol: "NQ".to_string(),
+            range: DateRange::new(date(1), date(5)).unwrap(),
+            days_processed: 5,
+            total_ticks: 100,
+            failed_days: vec![
+                (date(4), "Rate limit exceeded".to_string()),
+                (date(2), "Network error: timeout".to_string()),
+            ],
+            days_no_data: Vec::new(),
+            day_outcomes: Default::default(),
+            invalid_ticks: Default::default(),
+            cancelled: false,
+            lifetime_ticks: 100,
+            last_processed_date: Some(date(5)),
+        };
+        let path = std::env::temp_dir()
+            .join(format!("failed-days-{}", uuid::Uuid::new_v4()))
+            .join("failed.json");
+
+        let written = FailedDaysFile::from_report(&report);
+        written.write_to(&path).unwrap();
+        let read = FailedDaysFile::read_from(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(read, written);
+        assert_eq!(read.symbol, "NQ");
+        assert_eq!(read.dates(), vec![date(2), date(4)]);
+    }
+}
@@ -1,6 +1,6 @@
 mod di;
 
-use crate::di::create_app_module;
+use crate::di::{create_app_module, load_env_file, or_exit};
 use ingestion_application::services::IngestionService;
 use ingestion_application::TickRepository;
 use shaku::HasComponent;
@@ -10,6 +10,8 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilte
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Before anything reads the environment.
+    load_env_file();
     tracing_subscriber::registry()
         .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
         .with(tracing_subscriber::fmt::layer())
@@ -17,9 +19,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("Starting Aetherium Trader - Ingestion Service");
 
-    let module = create_app_module();
+    let module = or_exit(create_app_module());
     let service: Arc<dyn IngestionService> = module.resolve();
     let repository: Arc<dyn TickRepository> = module.resolve();
+    // Restore what a crashed run logged before new ticks reopen its files.
+    let recovered = or_exit(repository.recover().await);
+    if recovered > 0 {
+        info!("Recovered {} ticks from write-ahead logs", recovered);
+    }
 
     info!("Starting data ingestion for NQ futures (Press Ctrl+C to stop)");
 
@@ -0,0 +1,105 @@
+use chrono::{Days, NaiveDate, Utc};
+use ingestion_application::{GapDetector, HistoricalDataGateway, TickRepository};
+use ingestion_domain::{DateRange, Tick};
+use ingestion_infrastructure::{
+    MockHistoricalDataGateway, NoopRateLimiter, ParquetGapDetector, ParquetTickReader,
+    ParquetTickRepository, StdFileSystem,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+
+const SELF_TEST_SYMBOL: &str = "SELFTEST";
+
+#[derive(Debug)]
+pub struct SelfTestReport {
+    pub date: NaiveDate,
+    pub ticks_written: usize,
+    pub ticks_read: usize,
+    pub checksum_written: u64,
+    pub checksum_read: u64,
+    pub gaps_found: usize,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.ticks_written > 0
+            && self.ticks_written == self.ticks_read
+            && self.checksum_written == self.checksum_read
+            && self.gaps_found == 0
+    }
+}
+
+/// Runs the self-test in a scratch directory under the system temp dir and
+/// removes it afterwards, whatever the outcome.
+pub async fn run_in_temp_dir() -> Result<SelfTestReport, Box<dyn Error>> {
+    let work_dir =
+        std::env::temp_dir().join(format!("aetherium-selftest-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&work_dir)?;
+    let result = run_self_test(&work_dir).await;
+    std::fs::remove_dir_all(&work_dir)?;
+    result
+}
+
+/// Generates a day of mock ticks, writes them through the Parquet repository,
+/// reads them back and checks the gap detector sees the day as present.
+pub async fn run_self_test(work_dir: &Path) -> Result<SelfTestReport, Box<dyn Error>> {
+    let date = Utc::now()
+        .date_naive()
+        .checked_sub_days(Days::new(1))
+        .ok_or("date underflow")?;
+
+    let gateway = MockHistoricalDataGateway::new(16000.0, 365, Arc::new(NoopRateLimiter));
+    let ticks = gateway
+        .fetch_historical_ticks(SELF_TEST_SYMBOL, date)
+        .await?;
+    let checksum_written = checksum(&ticks);
+    let ticks_written = ticks.len();
+
+    let repository = ParquetTickRepository::new(work_dir.to_path_buf(), Arc::new(StdFileSystem));
+    repository.save_batch(ticks).await?;
+    repository.shutdown().await?;
+
+    let read_back = ParquetTickReader::new(work_dir.to_path_buf()).read_symbol(SELF_TEST_SYMBOL)?;
+
+    let gaps = ParquetGapDetector::new(work_dir.to_path_buf(), Arc::new(StdFileSystem))
+        .detect_gaps(SELF_TEST_SYMBOL, DateRange::single_day(date))
+        .await?;
+
+    Ok(SelfTestReport {
+        date,
+        ticks_written,
+        ticks_read: read_back.len(),
+        checksum_written,
+        checksum_read: checksum(&read_back),
+        gaps_found: gaps.len(),
+    })
+}
+
+fn checksum(ticks: &[Tick]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for tick in ticks {
+        tick.timestamp().timestamp_micros().hash(&mut hasher);
+        tick.symbol().hash(&mut hasher);
+        for price in [tick.bid_price(), tick.ask_price(), tick.last_price()] {
+            price.normalize().to_string().hash(&mut hasher);
+        }
+        (tick.bid_size(), tick.ask_size(), tick.last_size()).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn self_test_round_trip_passes() {
+        let report = run_in_temp_dir().await.expect("self-test should run");
+
+        assert!(report.passed(), "self-test failed: {:?}", report);
+        assert_eq!(report.ticks_written, 24 * 60);
+    }
+}
@@ -0,0 +1,106 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc, Weekday};
+use std::collections::BTreeSet;
+
+/// Which dates the market trades on: weekdays, minus any listed holidays.
+///
+/// With a session open time set, a session that opens in the evening (UTC)
+/// belongs to the next trading day's trade date, as for futures overnight
+/// sessions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TradingCalendar {
+    holidays: BTreeSet<NaiveDate>,
+    session_open: Option<NaiveTime>,
+}
+
+impl TradingCalendar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_holidays(mut self, holidays: impl IntoIterator<Item = NaiveDate>) -> Self {
+        self.holidays.extend(holidays);
+        self
+    }
+
+    /// UTC time the session for the next trade date opens, e.g. 23:00 for
+    /// CME Globex (18:00 New York in winter).
+    pub fn with_session_open(mut self, session_open: NaiveTime) -> Self {
+        self.session_open = Some(session_open);
+        self
+    }
+
+    /// Trade date a tick at `timestamp` belongs to. Without a session open
+    /// time this is the UTC calendar date; with one, ticks at or after the
+    /// open roll to the next trading day.
+    pub fn trade_date(&self, timestamp: DateTime<Utc>) -> NaiveDate {
+        let date = timestamp.date_naive();
+        match self.session_open {
+            Some(open) if timestamp.time() >= open => date
+                .iter_days()
+                .skip(1)
+                .find(|date| self.is_trading_day(*date))
+                .unwrap_or(date),
+            _ => date,
+        }
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+
+    /// Trading days after `after` up to and including `up_to`.
+    pub fn trading_days_between(&self, after: NaiveDate, up_to: NaiveDate) -> u32 {
+        after
+            .iter_days()
+            .skip(1)
+            .take_while(|date| *date <= up_to)
+            .filter(|date| self.is_trading_day(*date))
+            .count() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(d: u32) -> NaiveDate {
+        // 2025-01-06 is a Monday.
+        NaiveDate::from_ymd_opt(2025, 1, d).unwrap()
+    }
+
+    #[test]
+    fn weekends_and_holidays_are_not_trading_days() {
+        let calendar = TradingCalendar::new().with_holidays([date(1)]);
+
+        assert!(!calendar.is_trading_day(date(1)));
+        assert!(calendar.is_trading_day(date(3)));
+        assert!(!calendar.is_trading_day(date(4)));
+        assert!(!calendar.is_trading_day(date(5)));
+        assert!(calendar.is_trading_day(date(6)));
+    }
+
+    #[test]
+    fn evening_session_rolls_to_next_trading_day() {
+        use chrono::TimeZone;
+        let at = |d, h| Utc.with_ymd_and_hms(2025, 1, d, h, 0, 0).unwrap();
+        let calendar =
+            TradingCalendar::new().with_session_open(NaiveTime::from_hms_opt(23, 0, 0).unwrap());
+
+        assert_eq!(calendar.trade_date(at(6, 22)), date(6));
+        assert_eq!(calendar.trade_date(at(6, 23)), date(7));
+        // Sunday evening opens Monday's session.
+        assert_eq!(calendar.trade_date(at(5, 23)), date(6));
+        // Without an open time the UTC date is used.
+        assert_eq!(TradingCalendar::new().trade_date(at(6, 23)), date(6));
+    }
+
+    #[test]
+    fn counts_trading_days_in_half_open_interval() {
+        let calendar = TradingCalendar::new();
+
+        assert_eq!(calendar.trading_days_between(date(3), date(3)), 0);
+        assert_eq!(calendar.trading_days_between(date(3), date(6)), 1);
+        assert_eq!(calendar.trading_days_between(date(2), date(10)), 6);
+        assert_eq!(calendar.trading_days_between(date(6), date(3)), 0);
+    }
+}
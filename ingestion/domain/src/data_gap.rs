@@ -62,6 +62,37 @@ pub fn detect_gaps(
     gaps
 }
 
+/// Drops gaps spanning fewer than `min_gap_days` days. A threshold of 0 or 1
+/// keeps every gap.
+pub fn filter_min_gap_days(gaps: Vec<DateRange>, min_gap_days: u32) -> Vec<DateRange> {
+    gaps.into_iter()
+        .filter(|gap| gap.days() >= min_gap_days)
+        .collect()
+}
+
+/// Merges gaps separated by fewer than `max_present_days` present days into
+/// one range that also covers the days between them, in date order. A
+/// threshold of 0 leaves the gaps as they are.
+pub fn coalesce_gaps(mut gaps: Vec<DateRange>, max_present_days: u32) -> Vec<DateRange> {
+    if max_present_days == 0 {
+        return gaps;
+    }
+    gaps.sort_by_key(DateRange::start);
+    let mut merged: Vec<DateRange> = Vec::with_capacity(gaps.len());
+    for gap in gaps {
+        if let Some(last) = merged.last_mut() {
+            let present_between = (gap.start() - last.end()).num_days() - 1;
+            if present_between < i64::from(max_present_days) {
+                let end = last.end().max(gap.end());
+                *last = DateRange::new(last.start(), end).expect("Merged range should be valid");
+                continue;
+            }
+        }
+        merged.push(gap);
+    }
+    merged
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,4 +157,47 @@ mod tests {
         let gaps = detect_gaps("NQ", expected, &existing);
         assert_eq!(gaps.len(), 2);
     }
+
+    #[test]
+    fn test_min_gap_days_filter() {
+        let one_day = DateRange::single_day(NaiveDate::from_ymd_opt(2025, 1, 3).unwrap());
+        let three_days = DateRange::new(
+            NaiveDate::from_ymd_opt(2025, 1, 6).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 8).unwrap(),
+        )
+        .unwrap();
+        let gaps = vec![one_day.clone(), three_days.clone()];
+
+        assert_eq!(
+            filter_min_gap_days(gaps.clone(), 1),
+            vec![one_day, three_days.clone()]
+        );
+        assert_eq!(filter_min_gap_days(gaps, 2), vec![three_days]);
+    }
+
+    #[test]
+    fn test_coalesce_gaps() {
+        let date = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        // Present on 2, 4 and 7-8.
+        let gaps = vec![
+            DateRange::single_day(date(1)),
+            DateRange::single_day(date(3)),
+            DateRange::new(date(5), date(6)).unwrap(),
+            DateRange::single_day(date(9)),
+        ];
+
+        assert_eq!(coalesce_gaps(gaps.clone(), 0), gaps);
+        assert_eq!(coalesce_gaps(gaps.clone(), 1), gaps);
+        assert_eq!(
+            coalesce_gaps(gaps.clone(), 2),
+            vec![
+                DateRange::new(date(1), date(6)).unwrap(),
+                DateRange::single_day(date(9))
+            ]
+        );
+        assert_eq!(
+            coalesce_gaps(gaps, 3),
+            vec![DateRange::new(date(1), date(9)).unwrap()]
+        );
+    }
 }
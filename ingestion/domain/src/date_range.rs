@@ -1,6 +1,9 @@
-use chrono::{Days, NaiveDate};
+use chrono::{DateTime, Days, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Inclusive range of calendar dates. The dates carry no timezone; use
+/// [`DateRange::utc`] or [`DateRange::in_timezone`] to turn them into
+/// instants.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DateRange {
     start: NaiveDate,
@@ -42,6 +45,19 @@ impl DateRange {
         self.start <= other.end && self.end >= other.start
     }
 
+    /// The range as UTC days, the convention storage and job cursors use.
+    pub fn utc(&self) -> ZonedDateRange<Utc> {
+        self.in_timezone(Utc)
+    }
+
+    /// The range as local days in `tz`, e.g. an exchange's own calendar.
+    pub fn in_timezone<Tz: TimeZone>(&self, tz: Tz) -> ZonedDateRange<Tz> {
+        ZonedDateRange {
+            range: self.clone(),
+            tz,
+        }
+    }
+
     pub fn split_by_days(&self) -> Vec<DateRange> {
         let mut result = Vec::new();
         let mut current = self.start;
@@ -57,6 +73,53 @@ impl DateRange {
     }
 }
 
+/// A [`DateRange`] whose dates are local days in `tz`, for converting to
+/// UTC bounds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZonedDateRange<Tz: TimeZone> {
+    range: DateRange,
+    tz: Tz,
+}
+
+impl<Tz: TimeZone> ZonedDateRange<Tz> {
+    pub fn range(&self) -> &DateRange {
+        &self.range
+    }
+
+    pub fn timezone(&self) -> &Tz {
+        &self.tz
+    }
+
+    /// Local midnight at the start of the first day.
+    pub fn start_utc(&self) -> DateTime<Utc> {
+        self.local_to_utc(self.range.start.and_time(NaiveTime::MIN), false)
+    }
+
+    /// Local 23:59:59 on the last day.
+    pub fn end_utc(&self) -> DateTime<Utc> {
+        let last_second = NaiveTime::from_hms_opt(23, 59, 59).expect("valid time");
+        self.local_to_utc(self.range.end.and_time(last_second), true)
+    }
+
+    /// Resolves a DST overlap to the earlier or later instant, and a DST
+    /// gap to the first instant after it.
+    fn local_to_utc(&self, local: NaiveDateTime, latest: bool) -> DateTime<Utc> {
+        match self.tz.from_local_datetime(&local) {
+            LocalResult::Single(at) => at.with_timezone(&Utc),
+            LocalResult::Ambiguous(earlier, later) => {
+                if latest { later } else { earlier }.with_timezone(&Utc)
+            }
+            LocalResult::None => (1..=24)
+                .find_map(|hours| {
+                    let shifted = local + chrono::Duration::hours(hours);
+                    self.tz.from_local_datetime(&shifted).earliest()
+                })
+                .map(|at| at.with_timezone(&Utc))
+                .unwrap_or_else(|| local.and_utc()),
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum DateRangeError {
     #[error("Start date must be before or equal to end date")]
@@ -66,7 +129,7 @@ pub enum DateRangeError {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::NaiveDate;
+    use chrono::{FixedOffset, NaiveDate};
 
     #[test]
     fn test_valid_date_range() {
@@ -88,6 +151,35 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn utc_bounds_cover_whole_utc_days() {
+        let range = DateRange::new(
+            NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 3).unwrap(),
+        )
+        .unwrap();
+
+        let zoned = range.utc();
+        assert_eq!(zoned.start_utc().to_rfc3339(), "2025-01-01T00:00:00+00:00");
+        assert_eq!(zoned.end_utc().to_rfc3339(), "2025-01-03T23:59:59+00:00");
+    }
+
+    #[test]
+    fn local_days_convert_to_shifted_utc_bounds() {
+        // New York in winter.
+        let new_york = FixedOffset::west_opt(5 * 3600).unwrap();
+        let range = DateRange::new(
+            NaiveDate::from_ymd_opt(2025, 1, 6).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 7).unwrap(),
+        )
+        .unwrap();
+
+        let zoned = range.in_timezone(new_york);
+        assert_eq!(zoned.start_utc().to_rfc3339(), "2025-01-06T05:00:00+00:00");
+        assert_eq!(zoned.end_utc().to_rfc3339(), "2025-01-08T04:59:59+00:00");
+        assert_eq!(zoned.range(), &range);
+    }
+
     #[test]
     fn test_split_by_days() {
         let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
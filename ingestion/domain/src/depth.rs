@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepthLevel {
+    pub price: Decimal,
+    pub size: u32,
+}
+
+impl DepthLevel {
+    pub fn new(price: Decimal, size: u32) -> Self {
+        Self { price, size }
+    }
+}
+
+/// Several levels of the order book at one instant. Level 0 is the best
+/// price on each side; bids descend and asks ascend from there.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarketDepth {
+    timestamp: DateTime<Utc>,
+    symbol: String,
+    bids: Vec<DepthLevel>,
+    asks: Vec<DepthLevel>,
+}
+
+impl MarketDepth {
+    pub fn new(
+        timestamp: DateTime<Utc>,
+        symbol: String,
+        bids: Vec<DepthLevel>,
+        asks: Vec<DepthLevel>,
+    ) -> Result<Self, DepthValidationError> {
+        if symbol.is_empty() {
+            return Err(DepthValidationError::EmptySymbol);
+        }
+
+        if bids
+            .iter()
+            .chain(asks.iter())
+            .any(|level| level.price <= Decimal::ZERO)
+        {
+            return Err(DepthValidationError::InvalidPrice(
+                "level prices must be positive",
+            ));
+        }
+
+        if bids.windows(2).any(|pair| pair[1].price >= pair[0].price) {
+            return Err(DepthValidationError::UnorderedLevels("bids must descend"));
+        }
+
+        if asks.windows(2).any(|pair| pair[1].price <= pair[0].price) {
+            return Err(DepthValidationError::UnorderedLevels("asks must ascend"));
+        }
+
+        Ok(Self {
+            timestamp,
+            symbol,
+            bids,
+            asks,
+        })
+    }
+
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn bids(&self) -> &[DepthLevel] {
+        &self.bids
+    }
+
+    pub fn asks(&self) -> &[DepthLevel] {
+        &self.asks
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DepthValidationError {
+    #[error("Symbol cannot be empty")]
+    EmptySymbol,
+    #[error("Invalid price: {0}")]
+    InvalidPrice(&'static str),
+    #[error("Unordered levels: {0}")]
+    UnorderedLevels(&'static str),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn levels(prices: &[Decimal]) -> Vec<DepthLevel> {
+        prices
+            .iter()
+            .map(|price| DepthLevel::new(*price, 1))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_depth_creation() {
+        let depth = MarketDepth::new(
+            Utc::now(),
+            "NQ".to_string(),
+            levels(&[dec!(16000.00), dec!(15999.75)]),
+            levels(&[dec!(16000.25), dec!(16000.50)]),
+        );
+
+        assert!(depth.is_ok());
+    }
+
+    #[test]
+    fn test_unordered_levels_rejected() {
+        let result = MarketDepth::new(
+            Utc::now(),
+            "NQ".to_string(),
+            levels(&[dec!(15999.75), dec!(16000.00)]),
+            Vec::new(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(DepthValidationError::UnorderedLevels(_))
+        ));
+    }
+
+    #[test]
+    fn test_non_positive_level_price_rejected() {
+        let result = MarketDepth::new(Utc::now(), "NQ".to_string(), Vec::new(), levels(&[dec!(0)]));
+
+        assert!(matches!(result, Err(DepthValidationError::InvalidPrice(_))));
+    }
+}
@@ -1,7 +1,21 @@
+pub mod calendar;
 pub mod data_gap;
 pub mod date_range;
+pub mod depth;
+pub mod roll_schedule;
+pub mod symbol_alias;
 pub mod tick;
 
-pub use data_gap::{detect_gaps, DataGap};
-pub use date_range::{DateRange, DateRangeError};
-pub use tick::Tick;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+
+pub use calendar::TradingCalendar;
+pub use data_gap::{coalesce_gaps, detect_gaps, filter_min_gap_days, DataGap};
+pub use date_range::{DateRange, DateRangeError, ZonedDateRange};
+pub use depth::{DepthLevel, DepthValidationError, MarketDepth};
+pub use roll_schedule::{ContractSpan, RollSchedule, RollScheduleError};
+pub use symbol_alias::{SymbolAlias, SymbolAliasError};
+pub use tick::{
+    first_out_of_order, is_time_ordered, ticks_checksum, vwap, DefaultTickValidator, Tick,
+    TickFields, TickValidationError, TickValidator, TicksChecksum,
+};
@@ -0,0 +1,173 @@
+use crate::date_range::DateRange;
+use chrono::{Days, NaiveDate};
+use std::collections::{BTreeMap, HashMap};
+
+/// The underlying contract to fetch for part of a continuous symbol's range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractSpan {
+    pub range: DateRange,
+    pub contract: String,
+}
+
+/// Which underlying contract a continuous symbol (e.g. "NQ") trades as over
+/// time. Each roll names the first day a contract is used; days before a
+/// symbol's first roll, and symbols without rolls, use the symbol itself,
+/// so the default changes nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RollSchedule {
+    rolls: HashMap<String, BTreeMap<NaiveDate, String>>,
+}
+
+impl RollSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `symbol` trades as `contract` from `from` until its next roll.
+    pub fn with_roll(
+        mut self,
+        symbol: impl Into<String>,
+        from: NaiveDate,
+        contract: impl Into<String>,
+    ) -> Self {
+        self.rolls
+            .entry(symbol.into())
+            .or_default()
+            .insert(from, contract.into());
+        self
+    }
+
+    /// Parses comma-separated `symbol@first-day=contract` rolls, e.g.
+    /// `NQ@2024-12-16=NQH5,NQ@2025-03-17=NQM5`. An empty spec has no rolls.
+    pub fn parse(spec: &str) -> Result<Self, RollScheduleError> {
+        let mut schedule = Self::new();
+        for roll in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|roll| !roll.is_empty())
+        {
+            let invalid = || RollScheduleError::InvalidRoll(roll.to_string());
+            let (key, contract) = roll.split_once('=').ok_or_else(invalid)?;
+            let (symbol, from) = key.split_once('@').ok_or_else(invalid)?;
+            let (symbol, contract) = (symbol.trim(), contract.trim());
+            let from = NaiveDate::parse_from_str(from.trim(), "%Y-%m-%d").map_err(|_| invalid())?;
+            if symbol.is_empty() || contract.is_empty() {
+                return Err(invalid());
+            }
+            schedule = schedule.with_roll(symbol, from, contract);
+        }
+        Ok(schedule)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rolls.is_empty()
+    }
+
+    /// Contract `symbol` trades as on `date`.
+    pub fn contract_for<'a>(&'a self, symbol: &'a str, date: NaiveDate) -> &'a str {
+        self.rolls
+            .get(symbol)
+            .and_then(|rolls| rolls.range(..=date).next_back())
+            .map_or(symbol, |(_, contract)| contract.as_str())
+    }
+
+    /// `range` split at `symbol`'s rolls into consecutive spans covering it,
+    /// in date order, each with its contract.
+    pub fn split(&self, symbol: &str, range: &DateRange) -> Vec<ContractSpan> {
+        let roll_days = self
+            .rolls
+            .get(symbol)
+            .into_iter()
+            .flat_map(|rolls| rolls.range(range.start()..=range.end()))
+            .map(|(from, _)| *from)
+            .filter(|from| *from > range.start());
+        let mut spans = Vec::new();
+        let mut start = range.start();
+        for roll_day in roll_days {
+            let end = roll_day
+                .checked_sub_days(Days::new(1))
+                .expect("a roll after the range start has a day before it");
+            spans.push(self.span(symbol, start, end));
+            start = roll_day;
+        }
+        spans.push(self.span(symbol, start, range.end()));
+        spans
+    }
+
+    fn span(&self, symbol: &str, start: NaiveDate, end: NaiveDate) -> ContractSpan {
+        ContractSpan {
+            range: DateRange::new(start, end).expect("spans are split in date order"),
+            contract: self.contract_for(symbol, start).to_string(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum RollScheduleError {
+    #[error("Invalid roll '{0}': expected symbol@YYYY-MM-DD=contract")]
+    InvalidRoll(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap()
+    }
+
+    #[test]
+    fn parse_and_contract_for() {
+        let schedule = RollSchedule::parse(" NQ@2025-03-17=NQM5, NQ@2024-12-16 = NQH5 ,").unwrap();
+
+        assert_eq!(schedule.contract_for("NQ", date(1, 2)), "NQH5");
+        assert_eq!(schedule.contract_for("NQ", date(3, 16)), "NQH5");
+        assert_eq!(schedule.contract_for("NQ", date(3, 17)), "NQM5");
+        assert_eq!(
+            schedule.contract_for("NQ", NaiveDate::from_ymd_opt(2024, 12, 1).unwrap()),
+            "NQ"
+        );
+        assert_eq!(schedule.contract_for("ES", date(1, 2)), "ES");
+
+        assert!(RollSchedule::parse("").unwrap().is_empty());
+        assert_eq!(
+            RollSchedule::parse("NQ=NQH5"),
+            Err(RollScheduleError::InvalidRoll("NQ=NQH5".to_string()))
+        );
+        assert_eq!(
+            RollSchedule::parse("NQ@2025-02-30=NQH5"),
+            Err(RollScheduleError::InvalidRoll(
+                "NQ@2025-02-30=NQH5".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn split_breaks_the_range_at_rolls() {
+        let schedule = RollSchedule::new()
+            .with_roll("NQ", date(1, 1), "NQH5")
+            .with_roll("NQ", date(3, 17), "NQM5")
+            .with_roll("NQ", date(6, 16), "NQU5");
+        let span = |start, end, contract: &str| ContractSpan {
+            range: DateRange::new(start, end).unwrap(),
+            contract: contract.to_string(),
+        };
+
+        assert_eq!(
+            schedule.split("NQ", &DateRange::new(date(3, 10), date(3, 20)).unwrap()),
+            vec![
+                span(date(3, 10), date(3, 16), "NQH5"),
+                span(date(3, 17), date(3, 20), "NQM5"),
+            ]
+        );
+        // A roll on the first day starts the only span.
+        assert_eq!(
+            schedule.split("NQ", &DateRange::new(date(3, 17), date(3, 20)).unwrap()),
+            vec![span(date(3, 17), date(3, 20), "NQM5")]
+        );
+        assert_eq!(
+            schedule.split("ES", &DateRange::new(date(3, 10), date(3, 20)).unwrap()),
+            vec![span(date(3, 10), date(3, 20), "ES")]
+        );
+    }
+}
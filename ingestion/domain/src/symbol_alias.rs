@@ -0,0 +1,90 @@
+use crate::tick::Tick;
+use std::collections::HashMap;
+
+/// Maps the names different sources use for an instrument (e.g. "NQ1!",
+/// "/NQ") to the one canonical symbol its ticks are stored under. Symbols
+/// without an alias map to themselves, so the default changes nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolAlias {
+    aliases: HashMap<String, String>,
+}
+
+impl SymbolAlias {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_alias(mut self, alias: impl Into<String>, canonical: impl Into<String>) -> Self {
+        self.aliases.insert(alias.into(), canonical.into());
+        self
+    }
+
+    /// Parses comma-separated `alias=canonical` pairs, e.g.
+    /// `NQ1!=NQ,/NQ=NQ`. An empty spec is the identity mapping.
+    pub fn parse(spec: &str) -> Result<Self, SymbolAliasError> {
+        let mut mapping = Self::new();
+        for pair in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+        {
+            let (alias, canonical) = pair
+                .split_once('=')
+                .map(|(alias, canonical)| (alias.trim(), canonical.trim()))
+                .filter(|(alias, canonical)| !alias.is_empty() && !canonical.is_empty())
+                .ok_or_else(|| SymbolAliasError::InvalidPair(pair.to_string()))?;
+            mapping = mapping.with_alias(alias, canonical);
+        }
+        Ok(mapping)
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.aliases
+            .iter()
+            .all(|(alias, canonical)| alias == canonical)
+    }
+
+    /// Canonical form of `symbol`.
+    pub fn canonical<'a>(&'a self, symbol: &'a str) -> &'a str {
+        self.aliases.get(symbol).map_or(symbol, String::as_str)
+    }
+
+    /// `tick` filed under its canonical symbol.
+    pub fn apply(&self, tick: Tick) -> Tick {
+        match self.aliases.get(tick.symbol()) {
+            Some(canonical) => tick.with_symbol(canonical.clone()),
+            None => tick,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum SymbolAliasError {
+    #[error("Invalid symbol alias '{0}': expected alias=canonical")]
+    InvalidPair(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_canonical() {
+        let mapping = SymbolAlias::parse(" NQ1!=NQ, /NQ = NQ ,").unwrap();
+        assert_eq!(mapping.canonical("NQ1!"), "NQ");
+        assert_eq!(mapping.canonical("/NQ"), "NQ");
+        assert_eq!(mapping.canonical("NQ"), "NQ");
+        assert_eq!(mapping.canonical("ES"), "ES");
+        assert!(!mapping.is_identity());
+
+        assert!(SymbolAlias::parse("").unwrap().is_identity());
+        assert_eq!(
+            SymbolAlias::parse("NQ1!=NQ,ES"),
+            Err(SymbolAliasError::InvalidPair("ES".to_string()))
+        );
+        assert_eq!(
+            SymbolAlias::parse("NQ1!="),
+            Err(SymbolAliasError::InvalidPair("NQ1!=".to_string()))
+        );
+    }
+}
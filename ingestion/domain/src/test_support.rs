@@ -0,0 +1,37 @@
+//! Tick builders shared by tests across the workspace, so a change to
+//! `Tick`'s fields only needs fixing here. Built for this crate's own tests
+//! and, via the `test-support` feature, for other crates' dev-dependencies.
+
+use crate::Tick;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use rust_decimal::Decimal;
+
+/// A valid tick at `timestamp`: bid 1000.00, ask 1005.00, last 1002.50,
+/// all sizes 1.
+pub fn sample_tick(symbol: &str, timestamp: DateTime<Utc>) -> Tick {
+    Tick::new(
+        timestamp,
+        symbol.to_string(),
+        Decimal::new(100_000, 2),
+        1,
+        Decimal::new(100_500, 2),
+        1,
+        Decimal::new(100_250, 2),
+        1,
+    )
+    .expect("sample tick should be valid")
+}
+
+/// `count` ticks on `date`, one per hour from 10:00 UTC. At most 14.
+pub fn sample_ticks(symbol: &str, date: NaiveDate, count: usize) -> Vec<Tick> {
+    assert!(
+        count <= 14,
+        "sample_ticks places one tick per hour from 10:00"
+    );
+    (0..count)
+        .map(|idx| {
+            let timestamp = date.and_hms_opt(10 + idx as u32, 0, 0).unwrap();
+            sample_tick(symbol, Utc.from_utc_datetime(&timestamp))
+        })
+        .collect()
+}
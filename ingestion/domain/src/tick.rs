@@ -2,6 +2,10 @@ use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
+/// Minimum scale of [`Tick::mid_price`], matching the Parquet `Decimal128(_, 4)`
+/// price columns.
+const MID_PRICE_MIN_SCALE: u32 = 4;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Tick {
     timestamp: DateTime<Utc>,
@@ -12,42 +16,111 @@ pub struct Tick {
     ask_size: u32,
     last_price: Decimal,
     last_size: u32,
+    /// Wall-clock receive time minus `timestamp`, in milliseconds. Only
+    /// live ingestion sets it; historical ticks have no receive time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recv_latency_ms: Option<i64>,
+    /// Monotonically increasing number from the gateway or ingestion,
+    /// ordering ticks that share a timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sequence: Option<u64>,
 }
 
-impl Tick {
-    pub fn new(
-        timestamp: DateTime<Utc>,
-        symbol: String,
-        bid_price: Decimal,
-        bid_size: u32,
-        ask_price: Decimal,
-        ask_size: u32,
-        last_price: Decimal,
-        last_size: u32,
-    ) -> Result<Self, TickValidationError> {
-        if symbol.is_empty() {
+/// The raw values a [`Tick`] is built from, as seen by a [`TickValidator`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickFields {
+    pub timestamp: DateTime<Utc>,
+    pub symbol: String,
+    pub bid_price: Decimal,
+    pub bid_size: u32,
+    pub ask_price: Decimal,
+    pub ask_size: u32,
+    pub last_price: Decimal,
+    pub last_size: u32,
+}
+
+/// A sanity rule a tick must pass to be constructed. Venues add their own
+/// (maximum spread, tick size, size bounds) on top of
+/// [`DefaultTickValidator`], combining several with [`TickValidator::and`].
+pub trait TickValidator {
+    fn validate(&self, fields: &TickFields) -> Result<(), TickValidationError>;
+
+    /// Checks `self`, then `other`.
+    fn and<V: TickValidator>(self, other: V) -> And<Self, V>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+}
+
+/// Two validators applied in order; see [`TickValidator::and`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct And<A, B>(A, B);
+
+impl<A: TickValidator, B: TickValidator> TickValidator for And<A, B> {
+    fn validate(&self, fields: &TickFields) -> Result<(), TickValidationError> {
+        self.0.validate(fields)?;
+        self.1.validate(fields)
+    }
+}
+
+/// The checks every tick passes: a symbol, positive prices and an uncrossed
+/// market.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultTickValidator;
+
+impl TickValidator for DefaultTickValidator {
+    fn validate(&self, fields: &TickFields) -> Result<(), TickValidationError> {
+        if fields.symbol.is_empty() {
             return Err(TickValidationError::EmptySymbol);
         }
 
-        if bid_price <= Decimal::ZERO {
+        if fields.bid_price <= Decimal::ZERO {
             return Err(TickValidationError::InvalidPrice(
                 "bid_price must be positive",
             ));
         }
 
-        if ask_price <= Decimal::ZERO {
+        if fields.ask_price <= Decimal::ZERO {
             return Err(TickValidationError::InvalidPrice(
                 "ask_price must be positive",
             ));
         }
 
-        if last_price <= Decimal::ZERO {
+        if fields.last_price <= Decimal::ZERO {
             return Err(TickValidationError::InvalidPrice(
                 "last_price must be positive",
             ));
         }
 
-        Ok(Self {
+        // A locked market (bid == ask) is fine; a crossed one is not. Sizes
+        // are not checked: zero-size quotes accompany trade-only prints.
+        if fields.bid_price > fields.ask_price {
+            return Err(TickValidationError::CrossedMarket {
+                bid: fields.bid_price,
+                ask: fields.ask_price,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Tick {
+    /// Builds a tick that passes [`DefaultTickValidator`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        timestamp: DateTime<Utc>,
+        symbol: String,
+        bid_price: Decimal,
+        bid_size: u32,
+        ask_price: Decimal,
+        ask_size: u32,
+        last_price: Decimal,
+        last_size: u32,
+    ) -> Result<Self, TickValidationError> {
+        let fields = TickFields {
             timestamp,
             symbol,
             bid_price,
@@ -56,7 +129,54 @@ impl Tick {
             ask_size,
             last_price,
             last_size,
-        })
+        };
+        DefaultTickValidator.validate(&fields)?;
+        Ok(Self::from_fields(fields))
+    }
+
+    /// Builds a tick that passes [`DefaultTickValidator`] and then
+    /// `validator`.
+    pub fn new_validated(
+        fields: TickFields,
+        validator: &impl TickValidator,
+    ) -> Result<Self, TickValidationError> {
+        DefaultTickValidator.validate(&fields)?;
+        validator.validate(&fields)?;
+        Ok(Self::from_fields(fields))
+    }
+
+    fn from_fields(fields: TickFields) -> Self {
+        Self {
+            timestamp: fields.timestamp,
+            symbol: fields.symbol,
+            bid_price: fields.bid_price,
+            bid_size: fields.bid_size,
+            ask_price: fields.ask_price,
+            ask_size: fields.ask_size,
+            last_price: fields.last_price,
+            last_size: fields.last_size,
+            recv_latency_ms: None,
+            sequence: None,
+        }
+    }
+
+    pub fn with_recv_latency_ms(mut self, recv_latency_ms: i64) -> Self {
+        self.recv_latency_ms = Some(recv_latency_ms);
+        self
+    }
+
+    /// Refiles the tick under another symbol, e.g. its canonical one.
+    /// Ignored if `symbol` is empty.
+    pub fn with_symbol(mut self, symbol: String) -> Self {
+        if !symbol.is_empty() {
+            self.symbol = symbol;
+        }
+        self
+    }
+
+    pub fn with_sequence(mut self, sequence: u64) -> Self {
+        self.sequence = Some(sequence);
+        self
     }
 
     pub fn timestamp(&self) -> DateTime<Utc> {
@@ -90,6 +210,151 @@ impl Tick {
     pub fn last_size(&self) -> u32 {
         self.last_size
     }
+
+    pub fn recv_latency_ms(&self) -> Option<i64> {
+        self.recv_latency_ms
+    }
+
+    pub fn sequence(&self) -> Option<u64> {
+        self.sequence
+    }
+
+    /// Key for sorting ticks: timestamp, then sequence. Ticks without a
+    /// sequence come before those with one at the same timestamp; sort
+    /// stably to keep their arrival order.
+    pub fn sort_key(&self) -> (DateTime<Utc>, Option<u64>) {
+        (self.timestamp, self.sequence)
+    }
+
+    /// Identity of the tick for dedup and merging: its timestamp in
+    /// microseconds (the Parquet resolution) and its symbol. Two updates of
+    /// the same quote share a key even if their prices differ.
+    pub fn content_key(&self) -> (i64, &str) {
+        (self.timestamp.timestamp_micros(), &self.symbol)
+    }
+
+    /// FNV-1a over the key, prices and sizes, so ticks with the same key but
+    /// different content hash apart. Prices are normalized first, so equal
+    /// prices at different scales hash the same; arrival metadata (sequence,
+    /// receive latency) is left out. Stable across runs and builds.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = Fnv1a::new();
+        hash.feed(self.symbol.as_bytes());
+        self.feed_content(&mut hash);
+        hash.finish()
+    }
+
+    fn feed_content(&self, hash: &mut Fnv1a) {
+        hash.feed(&self.timestamp.timestamp_micros().to_le_bytes());
+        for price in [self.bid_price, self.ask_price, self.last_price] {
+            hash.feed(&price.normalize().serialize());
+        }
+        for size in [self.bid_size, self.ask_size, self.last_size] {
+            hash.feed(&size.to_le_bytes());
+        }
+    }
+
+    /// `ask_price - bid_price`; never negative, as crossed quotes are rejected.
+    pub fn spread(&self) -> Decimal {
+        self.ask_price - self.bid_price
+    }
+
+    /// Exact midpoint of bid and ask, carried to at least 4 decimal places
+    /// to match the Parquet price columns.
+    pub fn mid_price(&self) -> Decimal {
+        let mut mid = (self.bid_price + self.ask_price) / Decimal::TWO;
+        if mid.scale() < MID_PRICE_MIN_SCALE {
+            mid.rescale(MID_PRICE_MIN_SCALE);
+        }
+        mid
+    }
+}
+
+/// Index of the first tick whose timestamp is earlier than its predecessor's.
+/// Equal timestamps are allowed.
+pub fn first_out_of_order(ticks: &[Tick]) -> Option<usize> {
+    ticks
+        .windows(2)
+        .position(|pair| pair[1].timestamp() < pair[0].timestamp())
+        .map(|idx| idx + 1)
+}
+
+pub fn is_time_ordered(ticks: &[Tick]) -> bool {
+    first_out_of_order(ticks).is_none()
+}
+
+/// FNV-1a over each tick's timestamp, prices and sizes, in order. Cheap and
+/// stable across runs and builds; meant for spotting changed source data,
+/// not for integrity against tampering.
+pub fn ticks_checksum(ticks: &[Tick]) -> u64 {
+    let mut checksum = TicksChecksum::new();
+    for tick in ticks {
+        checksum.add(tick);
+    }
+    checksum.finish()
+}
+
+/// [`ticks_checksum`] fed one tick at a time, for ticks that are never all
+/// in memory at once.
+pub struct TicksChecksum(Fnv1a);
+
+impl TicksChecksum {
+    pub fn new() -> Self {
+        Self(Fnv1a::new())
+    }
+
+    pub fn add(&mut self, tick: &Tick) {
+        tick.feed_content(&mut self.0);
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+}
+
+impl Default for TicksChecksum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 64-bit FNV-1a, fed byte slices in order.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn feed(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Volume-weighted average of `last_price` over `ticks`:
+/// `sum(last_price * last_size) / sum(last_size)`. `None` when the total
+/// size is zero, including for an empty slice.
+pub fn vwap(ticks: &[Tick]) -> Option<Decimal> {
+    let (notional, volume) =
+        ticks
+            .iter()
+            .fold((Decimal::ZERO, 0u64), |(notional, volume), tick| {
+                (
+                    notional + tick.last_price * Decimal::from(tick.last_size),
+                    volume + u64::from(tick.last_size),
+                )
+            });
+    (volume > 0).then(|| notional / Decimal::from(volume))
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -98,11 +363,17 @@ pub enum TickValidationError {
     EmptySymbol,
     #[error("Invalid price: {0}")]
     InvalidPrice(&'static str),
+    #[error("Crossed market: bid {bid} is above ask {ask}")]
+    CrossedMarket { bid: Decimal, ask: Decimal },
+    /// A venue-specific [`TickValidator`] rule failed.
+    #[error("Rejected by {rule}: {reason}")]
+    RuleViolation { rule: &'static str, reason: String },
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::{TimeZone, Timelike};
     use rust_decimal_macros::dec;
 
     #[test]
@@ -121,6 +392,80 @@ mod tests {
         assert!(tick.is_ok());
     }
 
+    struct MaxSpread(Decimal);
+
+    impl TickValidator for MaxSpread {
+        fn validate(&self, fields: &TickFields) -> Result<(), TickValidationError> {
+            let spread = fields.ask_price - fields.bid_price;
+            if spread > self.0 {
+                return Err(TickValidationError::RuleViolation {
+                    rule: "max_spread",
+                    reason: format!("spread {} exceeds {}", spread, self.0),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    struct MinLastSize(u32);
+
+    impl TickValidator for MinLastSize {
+        fn validate(&self, fields: &TickFields) -> Result<(), TickValidationError> {
+            if fields.last_size < self.0 {
+                return Err(TickValidationError::RuleViolation {
+                    rule: "min_last_size",
+                    reason: format!("last_size {} is below {}", fields.last_size, self.0),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    fn fields(bid_price: Decimal, ask_price: Decimal) -> TickFields {
+        TickFields {
+            timestamp: Utc::now(),
+            symbol: "NQ".to_string(),
+            bid_price,
+            bid_size: 10,
+            ask_price,
+            ask_size: 15,
+            last_price: bid_price,
+            last_size: 5,
+        }
+    }
+
+    #[test]
+    fn test_custom_validator_rejects_wide_spread() {
+        let validator = MaxSpread(dec!(1.00));
+
+        assert!(Tick::new_validated(fields(dec!(16000.25), dec!(16000.50)), &validator).is_ok());
+        let result = Tick::new_validated(fields(dec!(16000.00), dec!(16005.00)), &validator);
+        assert!(matches!(
+            result,
+            Err(TickValidationError::RuleViolation {
+                rule: "max_spread",
+                ..
+            })
+        ));
+
+        // The default checks still run first.
+        let crossed = Tick::new_validated(fields(dec!(16001.00), dec!(16000.50)), &validator);
+        assert!(matches!(
+            crossed,
+            Err(TickValidationError::CrossedMarket { .. })
+        ));
+
+        let composed = MaxSpread(dec!(1.00)).and(MinLastSize(10));
+        let result = Tick::new_validated(fields(dec!(16000.25), dec!(16000.50)), &composed);
+        assert!(matches!(
+            result,
+            Err(TickValidationError::RuleViolation {
+                rule: "min_last_size",
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn test_empty_symbol_rejected() {
         let result = Tick::new(
@@ -168,4 +513,170 @@ mod tests {
 
         assert!(matches!(result, Err(TickValidationError::InvalidPrice(_))));
     }
+
+    fn quote(
+        bid: Decimal,
+        bid_size: u32,
+        ask: Decimal,
+        ask_size: u32,
+    ) -> Result<Tick, TickValidationError> {
+        Tick::new(
+            Utc::now(),
+            "NQ".to_string(),
+            bid,
+            bid_size,
+            ask,
+            ask_size,
+            dec!(16000.25),
+            5,
+        )
+    }
+
+    #[test]
+    fn test_locked_market_allowed() {
+        assert!(quote(dec!(16000.25), 10, dec!(16000.25), 15).is_ok());
+    }
+
+    #[test]
+    fn test_crossed_market_rejected() {
+        let result = quote(dec!(16000.50), 10, dec!(16000.25), 15);
+
+        match result {
+            Err(TickValidationError::CrossedMarket { bid, ask }) => {
+                assert_eq!(bid, dec!(16000.50));
+                assert_eq!(ask, dec!(16000.25));
+            }
+            other => panic!("expected a crossed market, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_zero_size_quotes_allowed() {
+        assert!(quote(dec!(16000.25), 0, dec!(16000.50), 0).is_ok());
+    }
+
+    #[test]
+    fn test_spread_and_mid_price() {
+        let tick = quote(dec!(16000.25), 10, dec!(16000.50), 15).unwrap();
+
+        assert_eq!(tick.spread(), dec!(0.25));
+        assert_eq!(tick.mid_price(), dec!(16000.375));
+        assert_eq!(tick.mid_price().scale(), 4);
+
+        let whole = quote(dec!(16000), 10, dec!(16001), 15).unwrap();
+        assert_eq!(whole.mid_price().to_string(), "16000.5000");
+    }
+
+    fn tick_at(second: u32) -> Tick {
+        Tick::new(
+            Utc.with_ymd_and_hms(2025, 1, 2, 10, 0, second).unwrap(),
+            "NQ".to_string(),
+            dec!(16000.25),
+            10,
+            dec!(16000.50),
+            15,
+            dec!(16000.25),
+            5,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_time_ordering() {
+        let ordered: Vec<Tick> = [0, 1, 5].into_iter().map(tick_at).collect();
+        assert!(is_time_ordered(&ordered));
+        assert!(is_time_ordered(&[]));
+
+        let equal: Vec<Tick> = [3, 3, 4].into_iter().map(tick_at).collect();
+        assert!(is_time_ordered(&equal));
+
+        let unordered: Vec<Tick> = [0, 2, 2, 1, 0].into_iter().map(tick_at).collect();
+        assert!(!is_time_ordered(&unordered));
+        assert_eq!(first_out_of_order(&unordered), Some(3));
+    }
+
+    #[test]
+    fn test_checksum_tracks_content() {
+        let ticks: Vec<Tick> = [0, 1, 2].into_iter().map(tick_at).collect();
+        assert_eq!(ticks_checksum(&ticks), ticks_checksum(&ticks.clone()));
+
+        let mut changed = ticks.clone();
+        changed[1].last_size += 1;
+        assert_ne!(ticks_checksum(&ticks), ticks_checksum(&changed));
+
+        // Equal prices at different scales hash the same.
+        let mut rescaled = ticks.clone();
+        rescaled[0].bid_price = dec!(16000.2500);
+        assert_eq!(ticks_checksum(&ticks), ticks_checksum(&rescaled));
+    }
+
+    #[test]
+    fn test_content_key_and_hash() {
+        let tick = tick_at(0);
+        let mut repriced = tick.clone();
+        repriced.last_price += dec!(0.25);
+
+        assert_eq!(tick.content_key(), repriced.content_key());
+        assert_ne!(tick.content_hash(), repriced.content_hash());
+
+        // Arrival metadata is not content.
+        let resent = tick.clone().with_sequence(7).with_recv_latency_ms(3);
+        assert_eq!(tick.content_hash(), resent.content_hash());
+
+        let other_symbol = tick.clone().with_symbol("ES".to_string());
+        assert_ne!(tick.content_key(), other_symbol.content_key());
+        assert_ne!(tick.content_hash(), other_symbol.content_hash());
+    }
+
+    fn trade(last_price: Decimal, last_size: u32) -> Tick {
+        let mut tick = tick_at(0);
+        tick.last_price = last_price;
+        tick.last_size = last_size;
+        tick
+    }
+
+    #[test]
+    fn test_vwap_of_known_series() {
+        let ticks = [
+            trade(dec!(100.00), 10),
+            trade(dec!(101.00), 30),
+            trade(dec!(99.50), 0),
+            trade(dec!(102.00), 60),
+        ];
+        // (1000 + 3030 + 6120) / 100
+        assert_eq!(vwap(&ticks), Some(dec!(101.50)));
+    }
+
+    #[test]
+    fn test_vwap_of_single_tick_is_its_price() {
+        assert_eq!(vwap(&[trade(dec!(16000.25), 7)]), Some(dec!(16000.25)));
+    }
+
+    #[test]
+    fn test_vwap_without_volume_is_none() {
+        assert_eq!(vwap(&[trade(dec!(100), 0), trade(dec!(101), 0)]), None);
+        assert_eq!(vwap(&[]), None);
+    }
+
+    #[test]
+    fn test_sequence_breaks_timestamp_ties() {
+        let mut ticks = [
+            tick_at(1).with_sequence(7),
+            tick_at(0).with_sequence(9),
+            tick_at(1).with_sequence(3),
+            tick_at(1).with_sequence(5),
+        ];
+
+        ticks.sort_by_key(Tick::sort_key);
+
+        let order: Vec<(u32, Option<u64>)> = ticks
+            .iter()
+            .map(|tick| (tick.timestamp().second(), tick.sequence()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![(0, Some(9)), (1, Some(3)), (1, Some(5)), (1, Some(7))]
+        );
+        assert_eq!(tick_at(0).sequence(), None);
+    }
 }
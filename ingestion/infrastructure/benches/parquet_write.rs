@@ -0,0 +1,110 @@
+//! Throughput of the Parquet write path, as a baseline for optimising
+//! record batch conversion and the writer.
+//!
+//! Run with `cargo bench -p ingestion-infrastructure --bench parquet_write`.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use ingestion_application::ports::TickRepository;
+use ingestion_domain::Tick;
+use ingestion_infrastructure::repositories::FileRotation;
+use ingestion_infrastructure::{InMemoryFileSystem, ParquetTickRepository};
+use rust_decimal::Decimal;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+const BATCH_SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+/// `count` NQ-like ticks `step` apart from `start`, walking the price a
+/// quarter point at a time so the decimal columns don't compress to nothing.
+fn ticks(start: DateTime<Utc>, step: Duration, count: usize) -> Vec<Tick> {
+    let mut last = Decimal::new(1_600_000, 2);
+    (0..count)
+        .map(|i| {
+            let drift = match i % 7 {
+                0 | 3 => Decimal::new(25, 2),
+                1 | 5 => Decimal::new(-25, 2),
+                _ => Decimal::ZERO,
+            };
+            last += drift;
+            Tick::new(
+                start + step * i as i32,
+                "NQ".to_string(),
+                last - Decimal::new(25, 2),
+                1 + (i % 13) as u32,
+                last,
+                1 + (i % 11) as u32,
+                last,
+                1 + (i % 5) as u32,
+            )
+            .unwrap()
+            .with_sequence(i as u64)
+        })
+        .collect()
+}
+
+fn repository() -> ParquetTickRepository {
+    ParquetTickRepository::new(PathBuf::from("/bench"), Arc::new(InMemoryFileSystem::new()))
+        .with_file_locking(false)
+        .with_rotation(FileRotation::Hourly)
+}
+
+/// One batch within a single hour: conversion plus encoding into one file.
+fn write_batch(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let start = Utc.with_ymd_and_hms(2025, 1, 2, 14, 0, 0).unwrap();
+    let mut group = c.benchmark_group("write_batch");
+    for size in BATCH_SIZES {
+        let batch = ticks(start, Duration::milliseconds(100), size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &batch, |b, batch| {
+            b.iter_batched(
+                || (repository(), batch.clone()),
+                |(repo, batch)| {
+                    runtime.block_on(async {
+                        repo.save_batch(batch).await.unwrap();
+                        repo.shutdown().await.unwrap();
+                    })
+                },
+                BatchSize::SmallInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+/// `save_batch` calls whose ticks span several hours, so every call closes
+/// one hourly file and opens the next.
+fn save_batch_with_rotation(c: &mut Criterion) {
+    const HOURS: i32 = 4;
+    let runtime = Runtime::new().unwrap();
+    let start = Utc.with_ymd_and_hms(2025, 1, 2, 14, 0, 0).unwrap();
+    let mut group = c.benchmark_group("save_batch_with_rotation");
+    for size in BATCH_SIZES {
+        let step = Duration::hours(HOURS.into()) / size as i32;
+        let batches: Vec<Vec<Tick>> = ticks(start, step, size)
+            .chunks(size / HOURS as usize)
+            .map(<[Tick]>::to_vec)
+            .collect();
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &batches, |b, batches| {
+            b.iter_batched(
+                || (repository(), batches.clone()),
+                |(repo, batches)| {
+                    runtime.block_on(async {
+                        for batch in batches {
+                            repo.save_batch(batch).await.unwrap();
+                        }
+                        repo.shutdown().await.unwrap();
+                    })
+                },
+                BatchSize::SmallInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, write_batch, save_batch_with_rotation);
+criterion_main!(benches);
@@ -0,0 +1,328 @@
+//! Optional HTTP API for starting, inspecting and cancelling backfills
+//! remotely. Built only with the `admin-api` feature.
+//!
+//! | Method | Path                 | Does                                   |
+//! |--------|----------------------|----------------------------------------|
+//! | POST   | `/backfill`          | Starts a backfill in the background    |
+//! | GET    | `/jobs`              | Lists jobs, optionally `?status=...`   |
+//! | GET    | `/jobs/{key}`        | A job's state and, once done, report   |
+//! | POST   | `/jobs/{key}/cancel` | Cancels a backfill this server started |
+//!
+//! Every request must carry `Authorization: Bearer <token>` with the
+//! configured token; others get 401.
+
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::NaiveDate;
+use ingestion_application::backfill_service::{
+    BackfillError, BackfillOptions, BackfillReport, BackfillService,
+};
+use ingestion_application::job_state::JOB_KEY_PREFIX;
+use ingestion_application::{JobKey, JobState, JobStateError, JobStateRepository, JobStatus};
+use ingestion_domain::DateRange;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdminApiError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("Missing or wrong bearer token")]
+    Unauthorized,
+    #[error("Unknown job: {0}")]
+    NotFound(String),
+    #[error("Job already running: {0}")]
+    Conflict(String),
+    #[error("Job state error: {0}")]
+    JobState(#[from] JobStateError),
+    #[error("Backfill error: {0}")]
+    Backfill(#[from] BackfillError),
+}
+
+impl IntoResponse for AdminApiError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::JobState(_) | Self::Backfill(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = ErrorBody {
+            error: self.to_string(),
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StartBackfillRequest {
+    pub symbol: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    #[serde(default)]
+    pub force_overwrite: bool,
+    /// Start even when the range needs more than the server's `max_days`.
+    #[serde(default)]
+    pub allow_large_range: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StartBackfillResponse {
+    pub job_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListJobsQuery {
+    status: Option<JobStatus>,
+}
+
+#[derive(Debug, Serialize)]
+struct JobSummary {
+    job_key: String,
+    state: JobState,
+}
+
+/// What this server knows about a job beyond its stored state.
+#[derive(Default)]
+struct RunRecord {
+    /// Present while the backfill is running.
+    cancel: Option<CancellationToken>,
+    /// Set once it has finished.
+    outcome: Option<Result<BackfillReport, String>>,
+}
+
+#[derive(Debug, Serialize)]
+struct JobDetail<'a> {
+    job_key: &'a str,
+    state: Option<JobState>,
+    running: bool,
+    report: Option<&'a BackfillReport>,
+    error: Option<&'a str>,
+}
+
+/// Same default as the backfill CLI's `--max-days`.
+pub const DEFAULT_MAX_DAYS: usize = 366;
+
+/// Settings of the admin API.
+#[derive(Clone)]
+pub struct AdminConfig {
+    /// Bearer token every request must present.
+    pub token: String,
+    /// Backfills that would fetch more days than this are refused with 400
+    /// unless the request sets `allow_large_range`. `None` turns the check
+    /// off.
+    pub max_days: Option<usize>,
+}
+
+impl AdminConfig {
+    pub fn new(token: String) -> Self {
+        Self {
+            token,
+            max_days: Some(DEFAULT_MAX_DAYS),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AdminState {
+    backfill: Arc<dyn BackfillService>,
+    jobs: Arc<dyn JobStateRepository>,
+    config: AdminConfig,
+    runs: Arc<Mutex<HashMap<String, RunRecord>>>,
+}
+
+/// Routes of the admin API over the given services.
+pub fn router(
+    backfill: Arc<dyn BackfillService>,
+    jobs: Arc<dyn JobStateRepository>,
+    config: AdminConfig,
+) -> Router {
+    let state = AdminState {
+        backfill,
+        jobs,
+        config,
+        runs: Arc::new(Mutex::new(HashMap::new())),
+    };
+    Router::new()
+        .route("/backfill", post(start_backfill))
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/{key}", get(get_job))
+        .route("/jobs/{key}/cancel", post(cancel_job))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+/// Serves `router` on `listener` until the process stops.
+pub async fn serve(listener: TcpListener, router: Router) -> std::io::Result<()> {
+    info!("Admin API listening on {}", listener.local_addr()?);
+    axum::serve(listener, router).await
+}
+
+async fn require_token(
+    State(state): State<AdminState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AdminApiError> {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match presented {
+        Some(token) if tokens_match(token, &state.config.token) => Ok(next.run(request).await),
+        _ => Err(AdminApiError::Unauthorized),
+    }
+}
+
+/// Compares without returning early on the first differing byte, so
+/// response times do not leak how much of a guess was right.
+fn tokens_match(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn start_backfill(
+    State(state): State<AdminState>,
+    Json(request): Json<StartBackfillRequest>,
+) -> Result<(StatusCode, Json<StartBackfillResponse>), AdminApiError> {
+    let range = DateRange::new(request.start_date, request.end_date)
+        .map_err(|e| AdminApiError::BadRequest(e.to_string()))?;
+    if request.symbol.is_empty() {
+        return Err(AdminApiError::BadRequest("symbol is empty".to_string()));
+    }
+    if let Some(max_days) = state.config.max_days.filter(|_| !request.allow_large_range) {
+        // Checked here rather than through `BackfillOptions::max_days` so
+        // the caller gets the refusal instead of a job that fails at once.
+        let days = state
+            .backfill
+            .plan(&request.symbol, range.clone())
+            .await?
+            .days_to_fetch(request.force_overwrite);
+        if days > max_days {
+            return Err(AdminApiError::BadRequest(
+                BackfillError::TooManyDays { days, max_days }.to_string(),
+            ));
+        }
+    }
+    let job_key = JobKey::new(&request.symbol, range.start()).to_string();
+    let cancel = CancellationToken::new();
+    {
+        let mut runs = state.runs.lock().unwrap();
+        if runs.get(&job_key).is_some_and(|run| run.cancel.is_some()) {
+            return Err(AdminApiError::Conflict(job_key));
+        }
+        runs.insert(
+            job_key.clone(),
+            RunRecord {
+                cancel: Some(cancel.clone()),
+                outcome: None,
+            },
+        );
+    }
+
+    let options = BackfillOptions {
+        force_overwrite: request.force_overwrite,
+        cancel: Some(cancel),
+        ..BackfillOptions::default()
+    };
+    let key = job_key.clone();
+    tokio::spawn(async move {
+        let outcome = state
+            .backfill
+            .backfill_range_with_options(&request.symbol, range, options)
+            .await
+            .map_err(|e| e.to_string());
+        if let Err(err) = &outcome {
+            warn!("Backfill {} failed: {}", key, err);
+        }
+        state.runs.lock().unwrap().insert(
+            key,
+            RunRecord {
+                cancel: None,
+                outcome: Some(outcome),
+            },
+        );
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(StartBackfillResponse { job_key }),
+    ))
+}
+
+async fn list_jobs(
+    State(state): State<AdminState>,
+    Query(query): Query<ListJobsQuery>,
+) -> Result<Response, AdminApiError> {
+    let listed = match query.status {
+        Some(status) => state.jobs.find_by_status(status).await?,
+        None => {
+            state
+                .jobs
+                .list_jobs(&format!("{}*", JOB_KEY_PREFIX))
+                .await?
+        }
+    };
+    let mut jobs: Vec<JobSummary> = listed
+        .into_iter()
+        .map(|(job_key, state)| JobSummary { job_key, state })
+        .collect();
+    jobs.sort_by(|a, b| a.job_key.cmp(&b.job_key));
+    Ok(Json(jobs).into_response())
+}
+
+async fn get_job(
+    State(state): State<AdminState>,
+    Path(key): Path<String>,
+) -> Result<Response, AdminApiError> {
+    let stored = state.jobs.get(&key).await?;
+    let runs = state.runs.lock().unwrap();
+    let run = runs.get(&key);
+    if stored.is_none() && run.is_none() {
+        return Err(AdminApiError::NotFound(key));
+    }
+    let outcome = run.and_then(|run| run.outcome.as_ref());
+    let detail = JobDetail {
+        job_key: &key,
+        state: stored,
+        running: run.is_some_and(|run| run.cancel.is_some()),
+        report: outcome.and_then(|outcome| outcome.as_ref().ok()),
+        error: outcome.and_then(|outcome| outcome.as_ref().err().map(String::as_str)),
+    };
+    Ok(Json(detail).into_response())
+}
+
+/// Only backfills this server started can be cancelled; the job is left
+/// cancelled and resumes on its next run.
+async fn cancel_job(
+    State(state): State<AdminState>,
+    Path(key): Path<String>,
+) -> Result<StatusCode, AdminApiError> {
+    let runs = state.runs.lock().unwrap();
+    match runs.get(&key).and_then(|run| run.cancel.as_ref()) {
+        Some(cancel) => {
+            cancel.cancel();
+            info!("Cancellation requested for {}", key);
+            Ok(StatusCode::ACCEPTED)
+        }
+        None => Err(AdminApiError::NotFound(key)),
+    }
+}
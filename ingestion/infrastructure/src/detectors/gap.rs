@@ -1,88 +1,284 @@
+use crate::filesystem::FileSystem;
+use crate::repositories::parquet::{INCOMPLETE_HOUR_KEY, NO_DATA_KEY};
+use crate::repositories::{ParquetFileName, ParquetTickReader};
 use async_trait::async_trait;
-use chrono::NaiveDate;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use ingestion_application::{GapDetectionError, GapDetector};
-use ingestion_domain::DateRange;
-use parquet::file::reader::{FileReader, SerializedFileReader};
+use ingestion_domain::{DateRange, TradingCalendar};
+use parquet::file::metadata::{FooterTail, ParquetMetaDataReader};
+use parquet::file::FOOTER_SIZE;
 use shaku::Component;
 use std::collections::HashSet;
-use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
+
+/// How densely a stored day must be covered to count as complete; see
+/// [`ParquetGapDetector::detect_intraday_gaps`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntraDayCheck {
+    /// Longest stretch without ticks tolerated inside the session.
+    pub max_gap: Duration,
+    /// Time the session opens on the file's date, in `timezone`. Silence
+    /// outside the session is not a gap.
+    pub session_start: NaiveTime,
+    /// Time the session closes, in `timezone`.
+    pub session_end: NaiveTime,
+    /// Zone the session times are in, so the session keeps its local hours
+    /// across daylight saving changes.
+    pub timezone: Tz,
+}
+
+impl IntraDayCheck {
+    /// UTC instant of the local `time` on `date`. A time skipped by a
+    /// daylight saving change is taken an hour later.
+    fn session_time(&self, date: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+        let local = date.and_time(time);
+        self.timezone
+            .from_local_datetime(&local)
+            .earliest()
+            .or_else(|| {
+                self.timezone
+                    .from_local_datetime(&(local + Duration::hours(1)))
+                    .earliest()
+            })
+            .map_or_else(|| local.and_utc(), |at| at.with_timezone(&Utc))
+    }
+}
+
+impl Default for IntraDayCheck {
+    /// Five minutes within CME equity index regular trading hours, 08:30 to
+    /// 15:00 Chicago time.
+    fn default() -> Self {
+        Self {
+            max_gap: Duration::minutes(5),
+            session_start: NaiveTime::from_hms_opt(8, 30, 0).unwrap(),
+            session_end: NaiveTime::from_hms_opt(15, 0, 0).unwrap(),
+            timezone: chrono_tz::America::Chicago,
+        }
+    }
+}
+
+/// Start and end of a stretch without ticks.
+pub type IntraDayGap = (DateTime<Utc>, DateTime<Utc>);
 
 #[derive(Component)]
 #[shaku(interface = GapDetector)]
 pub struct ParquetGapDetector {
+    #[shaku(inject)]
+    fs: Arc<dyn FileSystem>,
+
     data_dir: PathBuf,
+    #[shaku(default)]
+    calendar: TradingCalendar,
+    /// When set, `detect_gaps` also reads the timestamps of stored trading
+    /// days and reports days with holes as missing.
+    #[shaku(default)]
+    intra_day: Option<IntraDayCheck>,
 }
 
 impl ParquetGapDetector {
-    fn get_existing_dates(&self, symbol: &str) -> Result<HashSet<NaiveDate>, GapDetectionError> {
-        let mut dates = HashSet::new();
+    pub fn new(data_dir: PathBuf, fs: Arc<dyn FileSystem>) -> Self {
+        Self {
+            fs,
+            data_dir,
+            calendar: TradingCalendar::default(),
+            intra_day: None,
+        }
+    }
 
-        let entries = fs::read_dir(&self.data_dir)?;
+    pub fn with_calendar(mut self, calendar: TradingCalendar) -> Self {
+        self.calendar = calendar;
+        self
+    }
 
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
+    pub fn with_intra_day_check(mut self, check: IntraDayCheck) -> Self {
+        self.intra_day = Some(check);
+        self
+    }
 
-            if !path.is_file() {
-                continue;
-            }
+    /// Stretches of `symbol`'s session on `date` longer than the configured
+    /// [`IntraDayCheck::max_gap`] (the default check if none is configured)
+    /// without a stored tick, including from the open to the first tick and
+    /// from the last tick to the close. A day with no files is one gap
+    /// spanning the session; a day stored as having no data has none.
+    pub fn detect_intraday_gaps(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<IntraDayGap>, GapDetectionError> {
+        let check = self.intra_day.clone().unwrap_or_default();
+        let open = check.session_time(date, check.session_start);
+        let close = check.session_time(date, check.session_end);
 
-            let filename = match path.file_name().and_then(|n| n.to_str()) {
-                Some(name) => name,
-                None => continue,
-            };
+        let files = self.day_files(symbol, date)?;
+        if files.is_empty() {
+            return Ok(vec![(open, close)]);
+        }
+        let mut timestamps = Vec::new();
+        for path in &files {
+            timestamps.extend(
+                ParquetTickReader::read_timestamps_from(self.fs.as_ref(), path).map_err(|e| {
+                    GapDetectionError::IoError(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        e.to_string(),
+                    ))
+                })?,
+            );
+        }
+        if timestamps.is_empty() {
+            return Ok(Vec::new());
+        }
+        timestamps.retain(|timestamp| (open..=close).contains(timestamp));
+        timestamps.sort();
 
-            if !filename.ends_with(".parquet") {
-                continue;
+        let mut gaps = Vec::new();
+        let mut previous = open;
+        for timestamp in timestamps.into_iter().chain([close]) {
+            if timestamp - previous > check.max_gap {
+                gaps.push((previous, timestamp));
             }
+            previous = timestamp;
+        }
+        Ok(gaps)
+    }
 
-            if !filename.starts_with(&format!("{}_", symbol)) {
-                continue;
-            }
+    /// `symbol`'s files for `date`, whatever their rotation.
+    fn day_files(&self, symbol: &str, date: NaiveDate) -> Result<Vec<PathBuf>, GapDetectionError> {
+        Ok(self
+            .fs
+            .read_dir(&self.data_dir)?
+            .into_iter()
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(ParquetFileName::parse)
+                    .is_some_and(|name| name.symbol == symbol && name.date == date)
+            })
+            .collect())
+    }
 
-            let parts: Vec<&str> = filename.trim_end_matches(".parquet").split('_').collect();
-            if parts.len() != 3 {
-                continue;
-            }
+    /// Trading days between the latest complete trading day on disk (up to
+    /// `as_of`) and `as_of`; 0 when current. A symbol with no data at all
+    /// reports `u32::MAX`, so any alert threshold fires.
+    pub fn days_behind(&self, symbol: &str, as_of: NaiveDate) -> Result<u32, GapDetectionError> {
+        let latest = self
+            .get_existing_dates(symbol)?
+            .into_iter()
+            .filter(|date| *date <= as_of && self.calendar.is_trading_day(*date))
+            .max();
 
-            let date_str = parts[1];
-            if date_str.len() != 8 {
-                continue;
-            }
+        Ok(match latest {
+            Some(latest) => self.calendar.trading_days_between(latest, as_of),
+            None => u32::MAX,
+        })
+    }
+
+    fn get_existing_dates(&self, symbol: &str) -> Result<HashSet<NaiveDate>, GapDetectionError> {
+        let mut dates = HashSet::new();
+        let mut incomplete_dates = HashSet::new();
 
-            let year = date_str[0..4].parse::<i32>().ok();
-            let month = date_str[4..6].parse::<u32>().ok();
-            let day = date_str[6..8].parse::<u32>().ok();
+        let entries = self.fs.read_dir(&self.data_dir)?;
+        let mut non_utf8_names = Vec::new();
 
-            if let (Some(y), Some(m), Some(d)) = (year, month, day) {
-                if let Some(date) = NaiveDate::from_ymd_opt(y, m, d) {
-                    if Self::file_has_data(&path)? {
-                        dates.insert(date);
+        for path in entries {
+            let filename = match path.file_name() {
+                Some(name) => match name.to_str() {
+                    Some(name) => name,
+                    None => {
+                        non_utf8_names.push(name.to_os_string());
+                        continue;
                     }
-                }
+                },
+                None => continue,
+            };
+
+            match ParquetFileName::parse(filename) {
+                Some(name) if name.symbol == symbol => match self.file_status(&path)? {
+                    FileStatus::Empty => {}
+                    FileStatus::Complete => {
+                        dates.insert(name.date);
+                    }
+                    FileStatus::Incomplete => {
+                        incomplete_dates.insert(name.date);
+                    }
+                },
+                _ => continue,
             }
         }
 
-        Ok(dates)
+        if let Some(first) = non_utf8_names.first() {
+            warn!(
+                "Skipped {} non-UTF-8 file name(s) in {} (first: {:?})",
+                non_utf8_names.len(),
+                self.data_dir.display(),
+                first
+            );
+        }
+
+        // One partial hour makes the whole day refillable.
+        Ok(dates.difference(&incomplete_dates).copied().collect())
     }
 
-    fn file_has_data(path: &PathBuf) -> Result<bool, GapDetectionError> {
-        let file = fs::File::open(path)?;
-        let reader = SerializedFileReader::new(file).map_err(|e| {
-            GapDetectionError::IoError(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                e.to_string(),
-            ))
-        })?;
+    /// Reads only the Parquet footer to get the row count and marker flags.
+    fn file_status(&self, path: &Path) -> Result<FileStatus, GapDetectionError> {
+        let invalid = |e: parquet::errors::ParquetError| {
+            GapDetectionError::IoError(io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+        };
+
+        let mut file = self.fs.open(path)?;
+        let len = file.seek(SeekFrom::End(0))?;
+        if len < FOOTER_SIZE as u64 {
+            return Err(GapDetectionError::IoError(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is too short to be a Parquet file", path.display()),
+            )));
+        }
+
+        let mut footer = [0u8; FOOTER_SIZE];
+        file.seek(SeekFrom::End(-(FOOTER_SIZE as i64)))?;
+        file.read_exact(&mut footer)?;
+        let tail = FooterTail::try_from(footer).map_err(invalid)?;
+
+        let metadata_len = tail.metadata_length() as u64;
+        if metadata_len + FOOTER_SIZE as u64 > len {
+            return Err(GapDetectionError::IoError(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} has a truncated Parquet footer", path.display()),
+            )));
+        }
+
+        let mut metadata = vec![0u8; metadata_len as usize];
+        file.seek(SeekFrom::End(-((metadata_len + FOOTER_SIZE as u64) as i64)))?;
+        file.read_exact(&mut metadata)?;
+        let metadata = ParquetMetaDataReader::decode_metadata(&metadata).map_err(invalid)?;
 
-        let metadata = reader.metadata();
-        let num_rows: i64 = metadata.file_metadata().num_rows();
+        let file_metadata = metadata.file_metadata();
+        let has_key = |key: &str| {
+            file_metadata
+                .key_value_metadata()
+                .is_some_and(|entries| entries.iter().any(|kv| kv.key == key))
+        };
 
-        Ok(num_rows > 0)
+        Ok(if has_key(INCOMPLETE_HOUR_KEY) {
+            FileStatus::Incomplete
+        } else if file_metadata.num_rows() > 0 || has_key(NO_DATA_KEY) {
+            FileStatus::Complete
+        } else {
+            FileStatus::Empty
+        })
     }
 }
 
+enum FileStatus {
+    Empty,
+    Complete,
+    /// Closed mid-hour by a shutdown; see [`INCOMPLETE_HOUR_KEY`].
+    Incomplete,
+}
+
 #[async_trait]
 impl GapDetector for ParquetGapDetector {
     async fn detect_gaps(
@@ -94,7 +290,21 @@ impl GapDetector for ParquetGapDetector {
             return Err(GapDetectionError::InvalidDateRange);
         }
 
-        let existing_dates = self.get_existing_dates(symbol)?;
+        let mut existing_dates = self.get_existing_dates(symbol)?;
+        if self.intra_day.is_some() {
+            let mut holed = Vec::new();
+            for date in existing_dates.iter().copied() {
+                if range.contains(date)
+                    && self.calendar.is_trading_day(date)
+                    && !self.detect_intraday_gaps(symbol, date)?.is_empty()
+                {
+                    holed.push(date);
+                }
+            }
+            for date in holed {
+                existing_dates.remove(&date);
+            }
+        }
         let existing_vec: Vec<NaiveDate> = existing_dates.into_iter().collect();
 
         let gaps = ingestion_domain::detect_gaps(symbol, range, &existing_vec);
@@ -102,3 +312,326 @@ impl GapDetector for ParquetGapDetector {
         Ok(gaps.into_iter().map(|g| g.range().clone()).collect())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{InMemoryFileSystem, StdFileSystem};
+    use crate::repositories::{FileRotation, ParquetTickRepository};
+    use chrono::Timelike;
+    use chrono::{TimeZone, Utc};
+    use ingestion_application::TickRepository;
+    use ingestion_domain::test_support::sample_tick;
+    use ingestion_domain::Tick;
+    use rust_decimal::Decimal;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, d).unwrap()
+    }
+
+    async fn write_day(fs: Arc<dyn FileSystem>, dir: &Path, symbol: &str, day: NaiveDate) {
+        let tick = Tick::new(
+            Utc.from_utc_datetime(&day.and_hms_opt(10, 0, 0).unwrap()),
+            symbol.to_string(),
+            Decimal::new(16000, 0),
+            1,
+            Decimal::new(16001, 0),
+            1,
+            Decimal::new(16000, 0),
+            1,
+        )
+        .unwrap();
+        let repository = ParquetTickRepository::new(dir.to_path_buf(), fs);
+        repository.save_batch(vec![tick]).await.unwrap();
+        repository.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn underscore_symbol_files_are_detected_and_not_claimed_by_prefix() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        let dir = Path::new("/data");
+        write_day(fs.clone(), dir, "MES_Z5", date(2)).await;
+        write_day(fs.clone(), dir, "MES", date(3)).await;
+        let detector = ParquetGapDetector::new(dir.to_path_buf(), fs);
+        let range = DateRange::new(date(1), date(3)).unwrap();
+
+        let underscore_gaps = detector.detect_gaps("MES_Z5", range.clone()).await.unwrap();
+        let plain_gaps = detector.detect_gaps("MES", range).await.unwrap();
+
+        assert_eq!(
+            underscore_gaps,
+            vec![
+                DateRange::single_day(date(1)),
+                DateRange::single_day(date(3))
+            ]
+        );
+        assert_eq!(plain_gaps, vec![DateRange::new(date(1), date(2)).unwrap()]);
+    }
+
+    #[tokio::test]
+    async fn size_rotated_parts_count_as_their_day() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        let dir = Path::new("/data");
+        let repository = ParquetTickRepository::new(dir.to_path_buf(), fs.clone())
+            .with_rotation(FileRotation::BySize { max_bytes: 1 });
+        for minute in 0..2 {
+            let tick = Tick::new(
+                Utc.with_ymd_and_hms(2025, 1, 2, 10, minute, 0).unwrap(),
+                "NQ".to_string(),
+                Decimal::new(16000, 0),
+                1,
+                Decimal::new(16001, 0),
+                1,
+                Decimal::new(16000, 0),
+                1,
+            )
+            .unwrap();
+            repository.save_batch(vec![tick]).await.unwrap();
+        }
+        repository.shutdown().await.unwrap();
+        let detector = ParquetGapDetector::new(dir.to_path_buf(), fs.clone());
+
+        let gaps = detector
+            .detect_gaps("NQ", DateRange::new(date(1), date(3)).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(fs.paths().len(), 2);
+        assert_eq!(
+            gaps,
+            vec![
+                DateRange::single_day(date(1)),
+                DateRange::single_day(date(3))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn minute_segments_count_as_their_day() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        let dir = Path::new("/data");
+        let repository = ParquetTickRepository::new(dir.to_path_buf(), fs.clone())
+            .with_rotation(FileRotation::Interval { minutes: 15 });
+        let ticks = [0, 20, 40]
+            .into_iter()
+            .map(|minute| {
+                Tick::new(
+                    Utc.with_ymd_and_hms(2025, 1, 2, 10, minute, 0).unwrap(),
+                    "NQ".to_string(),
+                    Decimal::new(16000, 0),
+                    1,
+                    Decimal::new(16001, 0),
+                    1,
+                    Decimal::new(16000, 0),
+                    1,
+                )
+                .unwrap()
+            })
+            .collect();
+        repository.save_batch(ticks).await.unwrap();
+        repository.shutdown().await.unwrap();
+        let detector = ParquetGapDetector::new(dir.to_path_buf(), fs.clone());
+
+        let gaps = detector
+            .detect_gaps("NQ", DateRange::new(date(1), date(3)).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(fs.paths().len(), 3);
+        assert_eq!(
+            gaps,
+            vec![
+                DateRange::single_day(date(1)),
+                DateRange::single_day(date(3))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_files_outside_data_dir_and_unrelated_names() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        let dir = Path::new("/data");
+        write_day(fs.clone(), dir, "NQ", date(1)).await;
+        write_day(fs.clone(), &dir.join("archive"), "NQ", date(2)).await;
+        fs.insert("/data/NQ_20250103_10.parquet.tmp", vec![0; 16]);
+        fs.insert("/data/notes.txt", b"hello".to_vec());
+
+        let gaps = ParquetGapDetector::new(dir.to_path_buf(), fs)
+            .detect_gaps("NQ", DateRange::new(date(1), date(3)).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(gaps, vec![DateRange::new(date(2), date(3)).unwrap()]);
+    }
+
+    #[tokio::test]
+    async fn shutdown_truncated_hour_is_treated_as_missing() {
+        let dir = Path::new("/data");
+        let at = |day: u32, hour: u32| {
+            Tick::new(
+                Utc.with_ymd_and_hms(2025, 1, day, hour, 0, 0).unwrap(),
+                "NQ".to_string(),
+                Decimal::new(16000, 0),
+                1,
+                Decimal::new(16001, 0),
+                1,
+                Decimal::new(16000, 0),
+                1,
+            )
+            .unwrap()
+        };
+
+        for marking in [false, true] {
+            let fs = Arc::new(InMemoryFileSystem::new());
+            let repository = ParquetTickRepository::new(dir.to_path_buf(), fs.clone())
+                .with_incomplete_marking(marking);
+            // Hour 9 rotates out complete; hour 10 is cut short by shutdown.
+            repository
+                .save_batch(vec![at(1, 9), at(1, 10)])
+                .await
+                .unwrap();
+            repository.shutdown().await.unwrap();
+
+            let gaps = ParquetGapDetector::new(dir.to_path_buf(), fs)
+                .detect_gaps("NQ", DateRange::single_day(date(1)))
+                .await
+                .unwrap();
+
+            let expected = if marking {
+                vec![DateRange::single_day(date(1))]
+            } else {
+                Vec::new()
+            };
+            assert_eq!(gaps, expected, "marking = {}", marking);
+        }
+    }
+
+    #[tokio::test]
+    async fn days_behind_counts_missing_trading_days() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        let dir = Path::new("/data");
+        // Thu 2, Fri 3; Mon 6 is missing.
+        write_day(fs.clone(), dir, "NQ", date(2)).await;
+        write_day(fs.clone(), dir, "NQ", date(3)).await;
+        let detector = ParquetGapDetector::new(dir.to_path_buf(), fs);
+
+        assert_eq!(detector.days_behind("NQ", date(3)).unwrap(), 0);
+        // The weekend does not count against the symbol.
+        assert_eq!(detector.days_behind("NQ", date(5)).unwrap(), 0);
+        assert_eq!(detector.days_behind("NQ", date(6)).unwrap(), 1);
+        assert_eq!(detector.days_behind("ES", date(6)).unwrap(), u32::MAX);
+
+        let with_holiday = ParquetGapDetector::new(dir.to_path_buf(), detector.fs.clone())
+            .with_calendar(TradingCalendar::new().with_holidays([date(6)]));
+        assert_eq!(with_holiday.days_behind("NQ", date(6)).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn no_data_marker_fills_the_day() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        let dir = Path::new("/data");
+        let repository = ParquetTickRepository::new(dir.to_path_buf(), fs.clone());
+        repository.mark_no_data("NQ", date(1)).await.unwrap();
+
+        let gaps = ParquetGapDetector::new(dir.to_path_buf(), fs)
+            .detect_gaps("NQ", DateRange::new(date(1), date(2)).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(gaps, vec![DateRange::single_day(date(2))]);
+    }
+
+    #[tokio::test]
+    async fn hour_long_hole_inside_the_session_is_reported() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        let dir = Path::new("/data");
+        let open = Utc.with_ymd_and_hms(2025, 1, 2, 14, 30, 0).unwrap();
+        let ticks = (0..=390)
+            .map(|minute| open + Duration::minutes(minute))
+            .filter(|at| at.hour() != 16)
+            .map(|at| sample_tick("NQ", at))
+            .collect();
+        let repository = ParquetTickRepository::new(dir.to_path_buf(), fs.clone());
+        repository.save_batch(ticks).await.unwrap();
+        repository.shutdown().await.unwrap();
+        write_day(fs.clone(), dir, "NQ", date(3)).await;
+        let detector = ParquetGapDetector::new(dir.to_path_buf(), fs);
+        let range = DateRange::new(date(2), date(3)).unwrap();
+
+        let holes = detector.detect_intraday_gaps("NQ", date(2)).unwrap();
+        let by_presence = detector.detect_gaps("NQ", range.clone()).await.unwrap();
+        let by_coverage = detector
+            .with_intra_day_check(IntraDayCheck::default())
+            .detect_gaps("NQ", range.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            holes,
+            vec![(
+                Utc.with_ymd_and_hms(2025, 1, 2, 15, 59, 0).unwrap(),
+                Utc.with_ymd_and_hms(2025, 1, 2, 17, 0, 0).unwrap()
+            )]
+        );
+        assert!(by_presence.is_empty());
+        // Day 3 holds a single tick, so it is holed too.
+        assert_eq!(by_coverage, vec![range]);
+    }
+
+    #[tokio::test]
+    async fn session_follows_chicago_time_across_daylight_saving() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        let dir = Path::new("/data");
+        // 08:30 to 15:00 CDT, an hour earlier in UTC than in January.
+        let open = Utc.with_ymd_and_hms(2025, 7, 1, 13, 30, 0).unwrap();
+        let ticks = (0..=390)
+            .map(|minute| sample_tick("NQ", open + Duration::minutes(minute)))
+            .collect();
+        let repository = ParquetTickRepository::new(dir.to_path_buf(), fs.clone());
+        repository.save_batch(ticks).await.unwrap();
+        repository.shutdown().await.unwrap();
+
+        let holes = ParquetGapDetector::new(dir.to_path_buf(), fs)
+            .detect_intraday_gaps("NQ", NaiveDate::from_ymd_opt(2025, 7, 1).unwrap())
+            .unwrap();
+
+        assert!(holes.is_empty(), "{:?}", holes);
+    }
+
+    #[tokio::test]
+    async fn corrupt_parquet_file_is_reported() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        fs.insert("/data/NQ_20250101_10.parquet", b"not parquet".to_vec());
+
+        let result = ParquetGapDetector::new(PathBuf::from("/data"), fs)
+            .detect_gaps("NQ", DateRange::single_day(date(1)))
+            .await;
+
+        assert!(matches!(result, Err(GapDetectionError::IoError(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn non_utf8_file_names_are_skipped() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = std::env::temp_dir().join(format!("gap-detector-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_day(Arc::new(StdFileSystem), &dir, "NQ", date(1)).await;
+        let odd_name = OsStr::from_bytes(b"NQ_\xff_20250102_00.parquet");
+        if std::fs::write(dir.join(odd_name), b"").is_err() {
+            // Some filesystems refuse non-UTF-8 names outright.
+            std::fs::remove_dir_all(&dir).unwrap();
+            return;
+        }
+
+        let gaps = ParquetGapDetector::new(dir.clone(), Arc::new(StdFileSystem))
+            .detect_gaps("NQ", DateRange::single_day(date(1)))
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(gaps.is_empty());
+    }
+}
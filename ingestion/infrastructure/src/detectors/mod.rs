@@ -1,3 +1,3 @@
 pub mod gap;
 
-pub use gap::ParquetGapDetector;
+pub use gap::{IntraDayCheck, IntraDayGap, ParquetGapDetector};
@@ -0,0 +1,173 @@
+use super::{FileSystem, ReadSeek};
+use shaku::Component;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// [`FileSystem`] backed by `std::fs`.
+#[derive(Component, Default)]
+#[shaku(interface = FileSystem)]
+pub struct StdFileSystem;
+
+impl FileSystem for StdFileSystem {
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        Ok(Box::new(File::create(path)?))
+    }
+
+    fn create_locked(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    format!("{} is locked", path.display()),
+                ))
+            }
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+        file.set_len(0)?;
+        Ok(Box::new(file))
+    }
+
+    fn is_locked(&self, path: &Path) -> io::Result<bool> {
+        // Dropping the probe handle releases the lock if it was taken.
+        match File::open(path)?.try_lock() {
+            Ok(()) => Ok(false),
+            Err(TryLockError::WouldBlock) => Ok(true),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek>> {
+        Ok(Box::new(File::open(path)?))
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+}
+
+/// The output directory could not be created or written to.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Output directory {} is not writable: {source}. \
+     Check that it exists with write permission and is not on a read-only mount.",
+    path.display()
+)]
+pub struct OutputDirError {
+    pub path: PathBuf,
+    #[source]
+    pub source: io::Error,
+}
+
+/// Creates `dir` if needed, then creates and deletes a probe file in it, so
+/// an unwritable output directory is reported at startup rather than on the
+/// first write.
+pub fn ensure_writable_dir(dir: &Path) -> Result<(), OutputDirError> {
+    let error = |source| OutputDirError {
+        path: dir.to_path_buf(),
+        source,
+    };
+    fs::create_dir_all(dir).map_err(error)?;
+    let probe = dir.join(format!(".write-probe-{}", std::process::id()));
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .and_then(|mut file| file.write_all(b"probe"))
+        .map_err(error)?;
+    fs::remove_file(&probe).map_err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", label, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn writable_dir_passes_and_leaves_no_probe() {
+        let dir = temp_dir("probe-ok");
+        let nested = dir.join("data");
+
+        ensure_writable_dir(&nested).unwrap();
+
+        assert!(nested.is_dir());
+        assert_eq!(fs::read_dir(&nested).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_dir_is_reported_with_its_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("probe-ro");
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+        // Permission bits do not bind root; nothing to assert there.
+        let bypassed = File::create(dir.join("root-check")).is_ok();
+
+        let result = ensure_writable_dir(&dir);
+
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        if !bypassed {
+            let err = result.unwrap_err();
+            assert_eq!(err.path, dir);
+            assert_eq!(err.source.kind(), io::ErrorKind::PermissionDenied);
+            assert!(err.to_string().contains(&dir.display().to_string()));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn is_locked_reports_a_lock_held_by_another_handle() {
+        let dir = temp_dir("lock-probe");
+        let path = dir.join("NQ_20250102_10.parquet");
+        let fs = StdFileSystem;
+
+        let writer = fs.create_locked(&path).unwrap();
+        assert!(fs.is_locked(&path).unwrap());
+        drop(writer);
+
+        assert!(!fs.is_locked(&path).unwrap());
+        // The probe released its own lock.
+        fs.create_locked(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn path_under_a_file_is_reported() {
+        let dir = temp_dir("probe-file");
+        let file = dir.join("not-a-dir");
+        fs::write(&file, b"").unwrap();
+
+        let err = ensure_writable_dir(&file.join("data")).unwrap_err();
+
+        assert_eq!(err.path, file.join("data"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
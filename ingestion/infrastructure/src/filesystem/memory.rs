@@ -0,0 +1,188 @@
+use super::{FileSystem, ReadSeek};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+type Files = Arc<Mutex<BTreeMap<PathBuf, Vec<u8>>>>;
+
+/// [`FileSystem`] that keeps every file in memory. Directories are implicit:
+/// a file lives in whatever directory its path's parent names.
+#[derive(Clone, Default)]
+pub struct InMemoryFileSystem {
+    files: Files,
+    locks: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl InMemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, path: impl Into<PathBuf>, contents: Vec<u8>) {
+        self.files.lock().unwrap().insert(path.into(), contents);
+    }
+
+    pub fn contents(&self, path: &Path) -> Option<Vec<u8>> {
+        self.files.lock().unwrap().get(path).cloned()
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.files.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} does not exist", path.display()),
+    )
+}
+
+/// Writer that appends straight into the shared map, so data is visible to
+/// readers as soon as it is written.
+struct MemoryFile {
+    path: PathBuf,
+    files: Files,
+    locks: Option<Arc<Mutex<HashSet<PathBuf>>>>,
+}
+
+impl Drop for MemoryFile {
+    fn drop(&mut self) {
+        if let Some(locks) = &self.locks {
+            locks.lock().unwrap().remove(&self.path);
+        }
+    }
+}
+
+impl Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut files = self.files.lock().unwrap();
+        let contents = files
+            .get_mut(&self.path)
+            .ok_or_else(|| not_found(&self.path))?;
+        contents.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl FileSystem for InMemoryFileSystem {
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), Vec::new());
+        Ok(Box::new(MemoryFile {
+            path: path.to_path_buf(),
+            files: self.files.clone(),
+            locks: None,
+        }))
+    }
+
+    fn create_locked(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        if !self.locks.lock().unwrap().insert(path.to_path_buf()) {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                format!("{} is locked", path.display()),
+            ));
+        }
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), Vec::new());
+        Ok(Box::new(MemoryFile {
+            path: path.to_path_buf(),
+            files: self.files.clone(),
+            locks: Some(self.locks.clone()),
+        }))
+    }
+
+    fn is_locked(&self, path: &Path) -> io::Result<bool> {
+        if self.contents(path).is_none() {
+            return Err(not_found(path));
+        }
+        Ok(self.locks.lock().unwrap().contains(path))
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek>> {
+        let contents = self.contents(path).ok_or_else(|| not_found(path))?;
+        Ok(Box::new(Cursor::new(contents)))
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        Ok(self
+            .files
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|path| path.parent() == Some(dir))
+            .cloned()
+            .collect())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut files = self.files.lock().unwrap();
+        let contents = files.remove(from).ok_or_else(|| not_found(from))?;
+        files.insert(to.to_path_buf(), contents);
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        self.files
+            .lock()
+            .unwrap()
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn written_files_can_be_listed_renamed_and_removed() {
+        let fs = InMemoryFileSystem::new();
+        let dir = Path::new("/data");
+
+        fs.create(&dir.join("a.tmp"))
+            .unwrap()
+            .write_all(b"hello")
+            .unwrap();
+        fs.insert("/data/nested/b.parquet", vec![1]);
+        fs.rename(&dir.join("a.tmp"), &dir.join("a.parquet"))
+            .unwrap();
+
+        assert_eq!(fs.read_dir(dir).unwrap(), vec![dir.join("a.parquet")]);
+        let mut contents = String::new();
+        fs.open(&dir.join("a.parquet"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "hello");
+
+        fs.remove(&dir.join("a.parquet")).unwrap();
+        assert_eq!(
+            fs.open(&dir.join("a.parquet")).err().map(|e| e.kind()),
+            Some(io::ErrorKind::NotFound)
+        );
+    }
+
+    #[test]
+    fn is_locked_lasts_as_long_as_the_locked_writer() {
+        let fs = InMemoryFileSystem::new();
+        let path = Path::new("/data/a.parquet");
+
+        let writer = fs.create_locked(path).unwrap();
+        assert!(fs.is_locked(path).unwrap());
+        drop(writer);
+
+        assert!(!fs.is_locked(path).unwrap());
+    }
+}
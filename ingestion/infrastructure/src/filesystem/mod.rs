@@ -0,0 +1,40 @@
+pub mod local;
+pub mod memory;
+
+pub use local::{ensure_writable_dir, OutputDirError, StdFileSystem};
+pub use memory::InMemoryFileSystem;
+
+use shaku::Interface;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// Readable, seekable handle returned by [`FileSystem::open`].
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// Minimal filesystem surface used by the Parquet repository and gap
+/// detector, so both can run against an in-memory store in tests.
+pub trait FileSystem: Interface {
+    /// Creates (or truncates) the file at `path` for writing.
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + Send>>;
+
+    /// Like [`FileSystem::create`], but takes an exclusive advisory lock first
+    /// and fails with `ErrorKind::WouldBlock` if another handle holds it. The
+    /// file is only truncated once the lock is held; dropping the returned
+    /// writer releases the lock.
+    fn create_locked(&self, path: &Path) -> io::Result<Box<dyn Write + Send>>;
+
+    /// Whether another handle holds the lock [`FileSystem::create_locked`]
+    /// takes on `path`. Probes without blocking and without truncating.
+    fn is_locked(&self, path: &Path) -> io::Result<bool>;
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek>>;
+
+    /// Lists the regular files directly inside `dir`.
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    fn remove(&self, path: &Path) -> io::Result<()>;
+}
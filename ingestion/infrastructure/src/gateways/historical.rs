@@ -1,24 +1,79 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
-use ingestion_application::{HistoricalDataError, HistoricalDataGateway, RateLimiter};
-use ingestion_domain::Tick;
+use ingestion_application::rate_limiter::{RateLimitScope, RateLimiterError};
+use ingestion_application::{
+    FetchedTicks, HistoricalDataError, HistoricalDataGateway, RateLimiter,
+};
+use ingestion_domain::{Tick, TickValidationError};
 use rust_decimal::Decimal;
 use shaku::Component;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration as StdDuration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use super::price_profile::{profile_for, PriceProfile};
+
+/// Prices climb from the base in a sawtooth over each 100 seconds, reaching
+/// just under `volatility` above it.
+const DEFAULT_VOLATILITY: f64 = 100.0;
+
+/// Longest a fetch waits for a rate limiter slot before failing. Above the
+/// IB ten-minute window, so only a stuck limiter trips it.
+pub const DEFAULT_ACQUIRE_TIMEOUT: StdDuration = StdDuration::from_secs(15 * 60);
 
 #[derive(Component)]
 #[shaku(interface = HistoricalDataGateway)]
 pub struct MockHistoricalDataGateway {
+    /// Base price for symbols without a profile.
     base_price: f64,
     max_history_days: u32,
+    #[shaku(default)]
+    price_profiles: HashMap<String, PriceProfile>,
+    /// Ceiling on the wait for a rate limiter slot; `None` waits forever.
+    #[shaku(default = Some(DEFAULT_ACQUIRE_TIMEOUT))]
+    acquire_timeout: Option<StdDuration>,
     #[shaku(inject)]
     rate_limiter: Arc<dyn RateLimiter>,
 }
 
 impl MockHistoricalDataGateway {
-    fn generate_tick(&self, symbol: &str, timestamp: DateTime<Utc>) -> Tick {
-        let price_offset = (timestamp.timestamp() % 100) as f64;
-        let base = Decimal::try_from(self.base_price).unwrap();
+    pub fn new(base_price: f64, max_history_days: u32, rate_limiter: Arc<dyn RateLimiter>) -> Self {
+        Self {
+            base_price,
+            max_history_days,
+            price_profiles: HashMap::new(),
+            acquire_timeout: Some(DEFAULT_ACQUIRE_TIMEOUT),
+            rate_limiter,
+        }
+    }
+
+    pub fn with_acquire_timeout(mut self, acquire_timeout: Option<StdDuration>) -> Self {
+        self.acquire_timeout = acquire_timeout;
+        self
+    }
+
+    /// Per-symbol prices, so several symbols produce distinct series.
+    pub fn with_price_profiles(mut self, price_profiles: HashMap<String, PriceProfile>) -> Self {
+        self.price_profiles = price_profiles;
+        self
+    }
+
+    /// Fails for profiles whose prices reach zero, like a bad print from a
+    /// real source.
+    fn generate_tick(
+        &self,
+        symbol: &str,
+        timestamp: DateTime<Utc>,
+    ) -> Result<Tick, TickValidationError> {
+        let profile = profile_for(
+            &self.price_profiles,
+            symbol,
+            PriceProfile::new(self.base_price, DEFAULT_VOLATILITY),
+        );
+        let price_offset = (timestamp.timestamp() % 100) as f64 * profile.volatility / 100.0;
+        let base = Decimal::try_from(profile.base_price).unwrap();
         let offset = Decimal::try_from(price_offset).unwrap();
 
         let last_price = base + offset;
@@ -40,41 +95,216 @@ impl MockHistoricalDataGateway {
             last_price,
             last_size,
         )
-        .expect("Generated tick should be valid")
     }
 }
 
-#[async_trait]
-impl HistoricalDataGateway for MockHistoricalDataGateway {
-    async fn fetch_historical_ticks(
+impl MockHistoricalDataGateway {
+    async fn fetch(
         &self,
         symbol: &str,
         date: NaiveDate,
-    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        cancel: Option<&CancellationToken>,
+    ) -> Result<FetchedTicks, HistoricalDataError> {
         let days_ago = (Utc::now().date_naive() - date).num_days();
         if days_ago > self.max_history_days as i64 {
             return Err(HistoricalDataError::DataNotAvailable(date));
         }
 
-        self.rate_limiter
-            .acquire()
-            .await
-            .expect("Failed to acquire rate limiter token");
+        let scope = RateLimitScope::contract(symbol);
+        if let Ok(estimate) = self.rate_limiter.estimated_wait(&scope).await {
+            if !estimate.is_zero() {
+                info!(
+                    "Rate limiter saturated, fetch for {} {} expected to wait {:?}",
+                    symbol, date, estimate
+                );
+            }
+        }
+
+        let started = Instant::now();
+        let acquire = async {
+            match self.acquire_timeout {
+                Some(timeout) => {
+                    self.rate_limiter
+                        .acquire_with_timeout(&scope, timeout)
+                        .await
+                }
+                None => self.rate_limiter.acquire(&scope).await,
+            }
+        };
+        let acquired = match cancel {
+            Some(token) => tokio::select! {
+                biased;
+                _ = token.cancelled() => Err(RateLimiterError::Cancelled),
+                result = acquire => result,
+            },
+            None => acquire.await,
+        };
+        match acquired {
+            Ok(()) => debug!("Acquired rate limiter slot after {:?}", started.elapsed()),
+            Err(RateLimiterError::Cancelled) => return Err(HistoricalDataError::Cancelled),
+            Err(err) => return Err(HistoricalDataError::GatewayError(err.to_string())),
+        }
 
         let start_time = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
         let start_datetime = date.and_time(start_time);
         let start_utc = Utc.from_utc_datetime(&start_datetime);
 
-        let mut ticks = Vec::new();
+        let mut fetched = FetchedTicks::default();
         for minute in 0..(24 * 60) {
             let timestamp = start_utc + Duration::minutes(minute);
-            ticks.push(self.generate_tick(symbol, timestamp));
+            match self.generate_tick(symbol, timestamp) {
+                Ok(tick) => fetched.ticks.push(tick),
+                Err(err) => fetched.invalid.push(err),
+            }
         }
 
-        Ok(ticks)
+        Ok(fetched)
+    }
+
+    /// The valid ticks of `fetched`, for callers that cannot report the rest.
+    fn valid_ticks(fetched: FetchedTicks, symbol: &str, date: NaiveDate) -> Vec<Tick> {
+        if !fetched.invalid.is_empty() {
+            warn!(
+                "Dropped {} invalid ticks for {} {}",
+                fetched.invalid.len(),
+                symbol,
+                date
+            );
+        }
+        fetched.ticks
+    }
+}
+
+#[async_trait]
+impl HistoricalDataGateway for MockHistoricalDataGateway {
+    async fn fetch_historical_ticks(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        let fetched = self.fetch(symbol, date, None).await?;
+        Ok(Self::valid_ticks(fetched, symbol, date))
+    }
+
+    async fn fetch_historical_ticks_cancellable(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        token: &CancellationToken,
+    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        let fetched = self.fetch(symbol, date, Some(token)).await?;
+        Ok(Self::valid_ticks(fetched, symbol, date))
+    }
+
+    async fn fetch_checked_ticks_cancellable(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        token: &CancellationToken,
+    ) -> Result<FetchedTicks, HistoricalDataError> {
+        self.fetch(symbol, date, Some(token)).await
     }
 
     fn max_history_days(&self) -> u32 {
         self.max_history_days
     }
+
+    fn source_id(&self) -> &str {
+        "mock"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limiting::{
+        InProcessRateLimiter, NoopRateLimiter, RateLimitAlgorithm, RateLimitWindow,
+    };
+
+    #[tokio::test]
+    async fn symbols_follow_their_price_profiles() {
+        let gateway = MockHistoricalDataGateway::new(16000.0, 365, Arc::new(NoopRateLimiter))
+            .with_price_profiles(HashMap::from([(
+                "ES".to_string(),
+                PriceProfile::new(5000.0, 10.0),
+            )]));
+        let date = Utc::now().date_naive() - Duration::days(1);
+
+        let nq = gateway.fetch_historical_ticks("NQ", date).await.unwrap();
+        let es = gateway.fetch_historical_ticks("ES", date).await.unwrap();
+
+        let range = |ticks: &[Tick]| {
+            let prices = ticks.iter().map(Tick::last_price);
+            (prices.clone().min().unwrap(), prices.max().unwrap())
+        };
+        // Unmapped NQ keeps the gateway-wide base price and default swing.
+        assert_eq!(range(&nq), (Decimal::from(16000), Decimal::from(16080)));
+        assert_eq!(range(&es), (Decimal::from(5000), Decimal::from(5008)));
+    }
+
+    #[tokio::test]
+    async fn a_saturated_limiter_fails_the_fetch_after_the_timeout() {
+        let limiter = Arc::new(InProcessRateLimiter::new(
+            RateLimitAlgorithm::SlidingWindowLog,
+            &[RateLimitWindow::new(1, 60)],
+        ));
+        let gateway = MockHistoricalDataGateway::new(16000.0, 365, limiter)
+            .with_acquire_timeout(Some(StdDuration::from_millis(50)));
+        let date = Utc::now().date_naive() - Duration::days(1);
+
+        gateway.fetch_historical_ticks("NQ", date).await.unwrap();
+        let started = Instant::now();
+        let result = gateway.fetch_historical_ticks("NQ", date).await;
+
+        assert!(
+            matches!(&result, Err(HistoricalDataError::GatewayError(msg)) if msg.contains("Timed out")),
+            "{:?}",
+            result.map(|ticks| ticks.len())
+        );
+        assert!(started.elapsed() < StdDuration::from_secs(1));
+    }
+
+    /// A limiter whose backend is down.
+    struct UnreachableRateLimiter;
+
+    #[async_trait]
+    impl RateLimiter for UnreachableRateLimiter {
+        async fn acquire(&self, _scope: &RateLimitScope) -> Result<(), RateLimiterError> {
+            Err(RateLimiterError::ConnectionError(
+                "connection refused".to_string(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn a_failing_limiter_fails_the_fetch() {
+        let gateway =
+            MockHistoricalDataGateway::new(16000.0, 365, Arc::new(UnreachableRateLimiter));
+        let date = Utc::now().date_naive() - Duration::days(1);
+
+        let result = gateway.fetch_historical_ticks("NQ", date).await;
+
+        assert!(
+            matches!(&result, Err(HistoricalDataError::GatewayError(msg)) if msg.contains("connection refused")),
+            "{:?}",
+            result.map(|ticks| ticks.len())
+        );
+    }
+
+    #[tokio::test]
+    async fn zero_price_ticks_are_reported_not_fatal() {
+        // From a base of zero, every fifth minute prices at zero.
+        let gateway = MockHistoricalDataGateway::new(0.0, 365, Arc::new(NoopRateLimiter));
+        let date = Utc::now().date_naive() - Duration::days(1);
+
+        let fetched = gateway
+            .fetch_checked_ticks_cancellable("NQ", date, &CancellationToken::new())
+            .await
+            .unwrap();
+        let valid = gateway.fetch_historical_ticks("NQ", date).await.unwrap();
+
+        assert_eq!(fetched.invalid.len(), 288);
+        assert_eq!(fetched.ticks.len(), 24 * 60 - 288);
+        assert_eq!(valid, fetched.ticks);
+    }
 }
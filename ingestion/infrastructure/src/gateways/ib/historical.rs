@@ -0,0 +1,446 @@
+use super::wire::{Bar, Incoming, TwsConnection, WireError, REQ_HISTORICAL_DATA};
+use crate::gateways::historical::DEFAULT_ACQUIRE_TIMEOUT;
+use crate::rate_limiting::limiter::read_env_or_default;
+use async_trait::async_trait;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use ingestion_application::rate_limiter::{RateLimitScope, RateLimiterError};
+use ingestion_application::{
+    FetchedTicks, HistoricalDataError, HistoricalDataGateway, RateLimiter,
+};
+use ingestion_domain::Tick;
+use rust_decimal::prelude::ToPrimitive;
+use shaku::Component;
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// IB error code for historical data service failures: pacing violations
+/// and queries with no data both arrive as 162.
+const HMDS_ERROR: i32 = 162;
+
+/// Wait suggested after a pacing violation. IB counts historical requests
+/// over a rolling ten minutes, so only a full window is sure to clear.
+const PACING_VIOLATION_RETRY_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// Connection-wide TWS errors (sent with request id -1) after which no
+/// request in flight will be answered: TWS lost IB (1100) or its data
+/// servers (2110), or its API socket was reset (1300).
+const CONNECTION_LOST: [i32; 3] = [1100, 1300, 2110];
+
+/// Bar types requested per day. Trades give the last price and size; the
+/// bid/ask bars give the quote at each minute.
+const TRADES: &str = "TRADES";
+const BID_ASK: &str = "BID_ASK";
+
+/// Where to reach TWS or IB Gateway and which contract to ask for.
+#[derive(Debug, Clone)]
+pub struct IbHistoricalConfig {
+    pub host: String,
+    /// 7497 for paper TWS, 7496 live; IB Gateway uses 4002/4001.
+    pub port: u16,
+    /// API client id, unique among the sessions connected to one TWS.
+    pub client_id: i32,
+    /// IB security type, e.g. `FUT` or `STK`.
+    pub sec_type: String,
+    pub exchange: String,
+    pub currency: String,
+    /// `lastTradeDateOrContractMonth`, e.g. `202503`. Futures need it unless
+    /// the symbol alone is unambiguous; expired contracts are included.
+    pub contract_month: String,
+    /// Only bars inside regular trading hours.
+    pub use_rth: bool,
+    /// Longest wait for one reply before the connection is dropped.
+    pub request_timeout: Duration,
+    pub max_history_days: u32,
+}
+
+impl Default for IbHistoricalConfig {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+impl IbHistoricalConfig {
+    pub fn from_env() -> Self {
+        const HOST_ENV: &str = "IB_HOST";
+        const PORT_ENV: &str = "IB_PORT";
+        const CLIENT_ID_ENV: &str = "IB_CLIENT_ID";
+        const SEC_TYPE_ENV: &str = "IB_SEC_TYPE";
+        const EXCHANGE_ENV: &str = "IB_EXCHANGE";
+        const CURRENCY_ENV: &str = "IB_CURRENCY";
+        const CONTRACT_MONTH_ENV: &str = "IB_CONTRACT_MONTH";
+        const USE_RTH_ENV: &str = "IB_USE_RTH";
+        const REQUEST_TIMEOUT_ENV: &str = "IB_REQUEST_TIMEOUT_SECONDS";
+        const MAX_HISTORY_DAYS_ENV: &str = "IB_MAX_HISTORY_DAYS";
+
+        let string_or = |key: &str, default: &str| env::var(key).unwrap_or(default.to_string());
+        Self {
+            host: string_or(HOST_ENV, "127.0.0.1"),
+            port: read_env_or_default(PORT_ENV, 7497),
+            client_id: read_env_or_default(CLIENT_ID_ENV, 17),
+            sec_type: string_or(SEC_TYPE_ENV, "FUT"),
+            exchange: string_or(EXCHANGE_ENV, "CME"),
+            currency: string_or(CURRENCY_ENV, "USD"),
+            contract_month: string_or(CONTRACT_MONTH_ENV, ""),
+            use_rth: read_env_or_default(USE_RTH_ENV, false),
+            request_timeout: Duration::from_secs(read_env_or_default(REQUEST_TIMEOUT_ENV, 60)),
+            max_history_days: read_env_or_default(MAX_HISTORY_DAYS_ENV, 365),
+        }
+    }
+}
+
+/// Historical data from Interactive Brokers over the TWS API socket
+/// protocol. Each day is two `reqHistoricalData` calls for one-minute
+/// `TRADES` and `BID_ASK` bars, joined by minute into one tick each: the
+/// trade bar's close and volume as the last price and size, the bid/ask
+/// bar's open and close (IB's time-averaged bid and ask) as the quote.
+/// Minutes missing from either series are skipped. Quote sizes are not in
+/// the bars and are stored as zero.
+///
+/// One connection is shared and requests on it run one at a time; it is
+/// reopened after any failure.
+#[derive(Component)]
+#[shaku(interface = HistoricalDataGateway)]
+pub struct IbHistoricalDataGateway {
+    config: IbHistoricalConfig,
+    /// Ceiling on the wait for a rate limiter slot; `None` waits forever.
+    #[shaku(default = Some(DEFAULT_ACQUIRE_TIMEOUT))]
+    acquire_timeout: Option<Duration>,
+    #[shaku(default)]
+    connection: Mutex<Option<TwsConnection>>,
+    #[shaku(default)]
+    next_req_id: AtomicI32,
+    #[shaku(inject)]
+    rate_limiter: Arc<dyn RateLimiter>,
+}
+
+impl IbHistoricalDataGateway {
+    pub fn new(config: IbHistoricalConfig, rate_limiter: Arc<dyn RateLimiter>) -> Self {
+        Self {
+            config,
+            acquire_timeout: Some(DEFAULT_ACQUIRE_TIMEOUT),
+            connection: Mutex::new(None),
+            next_req_id: AtomicI32::new(0),
+            rate_limiter,
+        }
+    }
+
+    pub fn with_acquire_timeout(mut self, acquire_timeout: Option<Duration>) -> Self {
+        self.acquire_timeout = acquire_timeout;
+        self
+    }
+
+    async fn fetch(
+        &self,
+        contract: Contract<'_>,
+        date: NaiveDate,
+        cancel: &CancellationToken,
+    ) -> Result<FetchedTicks, HistoricalDataError> {
+        let days_ago = (Utc::now().date_naive() - date).num_days();
+        if days_ago > self.config.max_history_days as i64 {
+            return Err(HistoricalDataError::DataNotAvailable(date));
+        }
+
+        let trades = self.request_bars(contract, date, TRADES, cancel).await?;
+        let quotes = self.request_bars(contract, date, BID_ASK, cancel).await?;
+        Ok(join_bars(contract.name(), date, trades, quotes))
+    }
+
+    /// One `reqHistoricalData` call, after a rate limiter slot for this
+    /// contract and bar type.
+    async fn request_bars(
+        &self,
+        contract: Contract<'_>,
+        date: NaiveDate,
+        what_to_show: &str,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<Bar>, HistoricalDataError> {
+        let symbol = contract.name();
+        let scope = RateLimitScope::contract(symbol)
+            .with_exchange(&self.config.exchange)
+            .with_tick_type(what_to_show);
+        let acquire = async {
+            match self.acquire_timeout {
+                Some(timeout) => {
+                    self.rate_limiter
+                        .acquire_with_timeout(&scope, timeout)
+                        .await
+                }
+                None => self.rate_limiter.acquire(&scope).await,
+            }
+        };
+        let acquired = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => Err(RateLimiterError::Cancelled),
+            result = acquire => result,
+        };
+        match acquired {
+            Ok(()) => {}
+            Err(RateLimiterError::Cancelled) => return Err(HistoricalDataError::Cancelled),
+            Err(err) => return Err(HistoricalDataError::GatewayError(err.to_string())),
+        }
+
+        let req_id = self.next_req_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let request = self.historical_data_request(req_id, contract, date, what_to_show);
+        let mut slot = self.connection.lock().await;
+        // Taken out for the request, so a cancelled or failed call, which
+        // may leave half a message unread, drops it instead of reusing it.
+        let mut connection = match slot.take() {
+            Some(connection) => connection,
+            None => self.connect().await?,
+        };
+        let reply = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => return Err(HistoricalDataError::Cancelled),
+            reply = tokio::time::timeout(
+                self.config.request_timeout,
+                Self::exchange(&mut connection, &request, req_id),
+            ) => reply,
+        };
+        match reply {
+            // Dropped, so the next request starts on a fresh connection.
+            Ok(Ok(Err((code, message)))) if CONNECTION_LOST.contains(&code) => {
+                Err(HistoricalDataError::GatewayError(format!(
+                    "TWS connection lost during {} request for {} {}: IB error {}: {}",
+                    what_to_show, symbol, date, code, message
+                )))
+            }
+            Ok(Ok(reply)) => {
+                *slot = Some(connection);
+                reply.map_err(|(code, message)| classify_error(date, code, message))
+            }
+            Ok(Err(err)) => Err(HistoricalDataError::GatewayError(err.to_string())),
+            Err(_) => Err(HistoricalDataError::GatewayError(format!(
+                "No reply to {} request for {} {} within {:?}",
+                what_to_show, symbol, date, self.config.request_timeout
+            ))),
+        }
+    }
+
+    async fn connect(&self) -> Result<TwsConnection, HistoricalDataError> {
+        let connection =
+            TwsConnection::connect(&self.config.host, self.config.port, self.config.client_id)
+                .await
+                .map_err(|err| {
+                    HistoricalDataError::GatewayError(format!(
+                        "Failed to connect to TWS at {}:{}: {}",
+                        self.config.host, self.config.port, err
+                    ))
+                })?;
+        info!(
+            "Connected to TWS at {}:{} (server version {})",
+            self.config.host,
+            self.config.port,
+            connection.server_version()
+        );
+        Ok(connection)
+    }
+
+    /// Sends `request` and reads until the reply for `req_id`. The inner
+    /// result is IB's error code and message when it refused the request.
+    async fn exchange(
+        connection: &mut TwsConnection,
+        request: &[String],
+        req_id: i32,
+    ) -> Result<Result<Vec<Bar>, (i32, String)>, WireError> {
+        connection.send(request).await?;
+        loop {
+            match connection.recv().await? {
+                Incoming::HistoricalData { req_id: id, bars } if id == req_id => {
+                    return Ok(Ok(bars))
+                }
+                Incoming::Error {
+                    req_id: id,
+                    code,
+                    message,
+                } if id == req_id => return Ok(Err((code, message))),
+                Incoming::Error { code, message, .. } if CONNECTION_LOST.contains(&code) => {
+                    return Ok(Err((code, message)))
+                }
+                Incoming::Error { code, message, .. } => {
+                    debug!("TWS notice {}: {}", code, message)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The fields of a `reqHistoricalData` call for one-minute bars
+    /// covering the UTC day `date`.
+    fn historical_data_request(
+        &self,
+        req_id: i32,
+        contract: Contract<'_>,
+        date: NaiveDate,
+        what_to_show: &str,
+    ) -> Vec<String> {
+        let end = date.checked_add_days(Days::new(1)).unwrap_or(date);
+        let config = &self.config;
+        let (symbol, contract_month, local_symbol) = match contract {
+            Contract::Root(symbol) => (symbol, config.contract_month.as_str(), ""),
+            Contract::Local(local_symbol) => ("", "", local_symbol),
+        };
+        vec![
+            REQ_HISTORICAL_DATA.to_string(),
+            req_id.to_string(),
+            // conId
+            "0".to_string(),
+            symbol.to_string(),
+            config.sec_type.clone(),
+            contract_month.to_string(),
+            // strike, right, multiplier
+            "0".to_string(),
+            String::new(),
+            String::new(),
+            config.exchange.clone(),
+            // primaryExchange
+            String::new(),
+            config.currency.clone(),
+            local_symbol.to_string(),
+            // tradingClass
+            String::new(),
+            // includeExpired
+            "1".to_string(),
+            end.format("%Y%m%d-00:00:00").to_string(),
+            "1 min".to_string(),
+            "86400 S".to_string(),
+            u8::from(config.use_rth).to_string(),
+            what_to_show.to_string(),
+            // formatDate: epoch seconds
+            "2".to_string(),
+            // keepUpToDate, chartOptions
+            "0".to_string(),
+            String::new(),
+        ]
+    }
+}
+
+/// Maps an IB error for a historical data request.
+fn classify_error(date: NaiveDate, code: i32, message: String) -> HistoricalDataError {
+    let lower = message.to_lowercase();
+    if lower.contains("pacing violation") {
+        HistoricalDataError::RateLimitExceeded {
+            retry_after: Some(PACING_VIOLATION_RETRY_AFTER),
+        }
+    } else if code == HMDS_ERROR && lower.contains("no data") {
+        HistoricalDataError::DataNotAvailable(date)
+    } else {
+        HistoricalDataError::GatewayError(format!("IB error {}: {}", code, message))
+    }
+}
+
+/// How a request names its contract: a root symbol completed with
+/// [`IbHistoricalConfig::contract_month`], or a dated contract by its IB
+/// local symbol (e.g. `NQH5`), which already implies the month.
+#[derive(Clone, Copy)]
+enum Contract<'a> {
+    Root(&'a str),
+    Local(&'a str),
+}
+
+impl<'a> Contract<'a> {
+    fn name(self) -> &'a str {
+        match self {
+            Contract::Root(name) | Contract::Local(name) => name,
+        }
+    }
+}
+
+/// One tick per minute of `date` present in both series.
+fn join_bars(symbol: &str, date: NaiveDate, trades: Vec<Bar>, quotes: Vec<Bar>) -> FetchedTicks {
+    let quotes: BTreeMap<DateTime<Utc>, Bar> =
+        quotes.into_iter().map(|bar| (bar.time, bar)).collect();
+    let mut fetched = FetchedTicks::default();
+    let mut unquoted = 0;
+    for trade in trades {
+        if trade.time.date_naive() != date {
+            continue;
+        }
+        let Some(quote) = quotes.get(&trade.time) else {
+            unquoted += 1;
+            continue;
+        };
+        let last_size = trade.volume.to_u32().unwrap_or(0);
+        match Tick::new(
+            trade.time,
+            symbol.to_string(),
+            quote.open,
+            0,
+            quote.close,
+            0,
+            trade.close,
+            last_size,
+        ) {
+            Ok(tick) => fetched.ticks.push(tick),
+            Err(err) => fetched.invalid.push(err),
+        }
+    }
+    if unquoted > 0 {
+        warn!(
+            "Skipped {} trade bars without a quote for {} {}",
+            unquoted, symbol, date
+        );
+    }
+    fetched
+}
+
+#[async_trait]
+impl HistoricalDataGateway for IbHistoricalDataGateway {
+    async fn fetch_historical_ticks(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        self.fetch_historical_ticks_cancellable(symbol, date, &CancellationToken::new())
+            .await
+    }
+
+    async fn fetch_historical_ticks_cancellable(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        token: &CancellationToken,
+    ) -> Result<Vec<Tick>, HistoricalDataError> {
+        let fetched = self.fetch(Contract::Root(symbol), date, token).await?;
+        if !fetched.invalid.is_empty() {
+            warn!(
+                "Dropped {} invalid ticks for {} {}",
+                fetched.invalid.len(),
+                symbol,
+                date
+            );
+        }
+        Ok(fetched.ticks)
+    }
+
+    async fn fetch_checked_ticks_cancellable(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        token: &CancellationToken,
+    ) -> Result<FetchedTicks, HistoricalDataError> {
+        self.fetch(Contract::Root(symbol), date, token).await
+    }
+
+    async fn fetch_contract_ticks_cancellable(
+        &self,
+        contract: &str,
+        date: NaiveDate,
+        token: &CancellationToken,
+    ) -> Result<FetchedTicks, HistoricalDataError> {
+        self.fetch(Contract::Local(contract), date, token).await
+    }
+
+    fn max_history_days(&self) -> u32 {
+        self.config.max_history_days
+    }
+
+    fn source_id(&self) -> &str {
+        "ib"
+    }
+}
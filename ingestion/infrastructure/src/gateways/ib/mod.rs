@@ -0,0 +1,7 @@
+//! Interactive Brokers over the TWS API socket protocol, as served by TWS
+//! and IB Gateway.
+
+pub mod historical;
+mod wire;
+
+pub use historical::{IbHistoricalConfig, IbHistoricalDataGateway};
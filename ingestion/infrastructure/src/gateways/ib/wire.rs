@@ -0,0 +1,289 @@
+//! The TWS API socket protocol: after the `API\0` greeting, every message is
+//! a 4-byte big-endian length followed by NUL-terminated text fields, the
+//! first of which is the message id.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Client versions offered in the handshake.
+const MIN_CLIENT_VERSION: i32 = 100;
+const MAX_CLIENT_VERSION: i32 = 176;
+/// Oldest server that drops the version field from historical data
+/// requests and replies (`MIN_SERVER_VER_SYNT_REALTIME_BARS`).
+pub const MIN_SERVER_VERSION: i32 = 124;
+/// Messages larger than this are treated as a corrupt stream.
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// Outgoing message ids.
+pub const REQ_HISTORICAL_DATA: i32 = 20;
+pub const START_API: i32 = 71;
+
+/// Incoming message ids.
+pub const ERR_MSG: i32 = 4;
+pub const HISTORICAL_DATA: i32 = 17;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WireError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Malformed message: {0}")]
+    Malformed(String),
+
+    #[error("Server version {0} is older than the supported {MIN_SERVER_VERSION}")]
+    ServerTooOld(i32),
+}
+
+/// Frames `fields` as one message.
+pub fn encode(fields: &[String]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for field in fields {
+        payload.extend_from_slice(field.as_bytes());
+        payload.push(0);
+    }
+    frame(&payload)
+}
+
+fn frame(payload: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(4 + payload.len());
+    message.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    message.extend_from_slice(payload);
+    message
+}
+
+/// The greeting and the range of client versions this module speaks. The
+/// version range is framed but, unlike other messages, not NUL-terminated.
+pub fn handshake() -> Vec<u8> {
+    let mut greeting = b"API\0".to_vec();
+    greeting.extend(frame(
+        format!("v{}..{}", MIN_CLIENT_VERSION, MAX_CLIENT_VERSION).as_bytes(),
+    ));
+    greeting
+}
+
+/// Reads one message and splits it into its fields.
+pub async fn read_message<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Vec<String>, WireError> {
+    let len = reader.read_u32().await? as usize;
+    if len > MAX_MESSAGE_LEN {
+        return Err(WireError::Malformed(format!(
+            "length {} exceeds limit",
+            len
+        )));
+    }
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload).await?;
+    let text = String::from_utf8(payload)
+        .map_err(|err| WireError::Malformed(format!("invalid UTF-8: {}", err)))?;
+    // Every field ends in a NUL, so the split leaves an empty tail.
+    let mut fields: Vec<String> = text.split('\0').map(str::to_string).collect();
+    if fields.last().is_some_and(String::is_empty) {
+        fields.pop();
+    }
+    Ok(fields)
+}
+
+/// One bar of a `reqHistoricalData` reply, requested with epoch-second
+/// dates (`formatDate=2`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bar {
+    /// Start of the bar.
+    pub time: DateTime<Utc>,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    /// -1 for bar types without volume, such as `BID_ASK`.
+    pub volume: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incoming {
+    HistoricalData {
+        req_id: i32,
+        bars: Vec<Bar>,
+    },
+    /// Request failures, and notices with `req_id` -1 such as data farm
+    /// status.
+    Error {
+        req_id: i32,
+        code: i32,
+        message: String,
+    },
+    /// Any message this module does not use.
+    Other(i32),
+}
+
+/// Walks the fields of a message after its id.
+struct Fields<'a> {
+    fields: std::slice::Iter<'a, String>,
+}
+
+impl<'a> Fields<'a> {
+    fn next_str(&mut self) -> Result<&'a str, WireError> {
+        self.fields
+            .next()
+            .map(String::as_str)
+            .ok_or_else(|| WireError::Malformed("message ended early".to_string()))
+    }
+
+    fn next<T: FromStr>(&mut self) -> Result<T, WireError> {
+        let field = self.next_str()?;
+        field
+            .parse()
+            .map_err(|_| WireError::Malformed(format!("unexpected field '{}'", field)))
+    }
+}
+
+/// Decodes a message read by [`read_message`] for a server of at least
+/// [`MIN_SERVER_VERSION`].
+pub fn decode(message: &[String]) -> Result<Incoming, WireError> {
+    let mut fields = Fields {
+        fields: message.iter(),
+    };
+    let id: i32 = fields.next()?;
+    match id {
+        HISTORICAL_DATA => {
+            let req_id = fields.next()?;
+            let _start = fields.next_str()?;
+            let _end = fields.next_str()?;
+            let count: usize = fields.next()?;
+            let mut bars = Vec::with_capacity(count.min(message.len()));
+            for _ in 0..count {
+                let secs: i64 = fields.next()?;
+                let time = DateTime::from_timestamp(secs, 0)
+                    .ok_or_else(|| WireError::Malformed(format!("bar time {}", secs)))?;
+                let bar = Bar {
+                    time,
+                    open: fields.next()?,
+                    high: fields.next()?,
+                    low: fields.next()?,
+                    close: fields.next()?,
+                    volume: fields.next()?,
+                };
+                let _wap = fields.next_str()?;
+                let _bar_count = fields.next_str()?;
+                bars.push(bar);
+            }
+            Ok(Incoming::HistoricalData { req_id, bars })
+        }
+        ERR_MSG => {
+            let _version = fields.next_str()?;
+            Ok(Incoming::Error {
+                req_id: fields.next()?,
+                code: fields.next()?,
+                message: fields.next_str()?.to_string(),
+            })
+        }
+        other => Ok(Incoming::Other(other)),
+    }
+}
+
+/// An API session with TWS or IB Gateway.
+pub struct TwsConnection {
+    stream: TcpStream,
+    server_version: i32,
+}
+
+impl TwsConnection {
+    /// Connects, negotiates the protocol version and starts the API session
+    /// as `client_id`.
+    pub async fn connect(host: &str, port: u16, client_id: i32) -> Result<Self, WireError> {
+        let mut stream = TcpStream::connect((host, port)).await?;
+        stream.write_all(&handshake()).await?;
+        let reply = read_message(&mut stream).await?;
+        let server_version: i32 = Fields {
+            fields: reply.iter(),
+        }
+        .next()?;
+        if server_version < MIN_SERVER_VERSION {
+            return Err(WireError::ServerTooOld(server_version));
+        }
+        let mut connection = Self {
+            stream,
+            server_version,
+        };
+        connection
+            .send(&[
+                START_API.to_string(),
+                "2".to_string(),
+                client_id.to_string(),
+                String::new(),
+            ])
+            .await?;
+        Ok(connection)
+    }
+
+    pub fn server_version(&self) -> i32 {
+        self.server_version
+    }
+
+    pub async fn send(&mut self, fields: &[String]) -> Result<(), WireError> {
+        self.stream.write_all(&encode(fields)).await?;
+        Ok(())
+    }
+
+    pub async fn recv(&mut self) -> Result<Incoming, WireError> {
+        decode(&read_message(&mut self.stream).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(raw: &str) -> Vec<String> {
+        raw.split('|').map(str::to_string).collect()
+    }
+
+    #[tokio::test]
+    async fn encoded_messages_read_back_as_their_fields() {
+        let sent = fields("20|7||NQ");
+        let bytes = encode(&sent);
+
+        assert_eq!(&bytes[..4], &[0, 0, 0, 9]);
+        assert_eq!(read_message(&mut bytes.as_slice()).await.unwrap(), sent);
+    }
+
+    #[test]
+    fn historical_data_decodes_to_bars() {
+        let message = fields(
+            "17|3|20250102 00:00:00|20250103 00:00:00|2\
+             |1735776000|21000.25|21001|20999.5|21000.75|12|21000.4|9\
+             |1735776060|21000.75|21002|21000.5|21001.5|-1|-1|0",
+        );
+
+        let Incoming::HistoricalData { req_id, bars } = decode(&message).unwrap() else {
+            panic!("expected historical data");
+        };
+
+        assert_eq!(req_id, 3);
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].time.to_rfc3339(), "2025-01-02T00:00:00+00:00");
+        assert_eq!(bars[0].close, Decimal::new(2100075, 2));
+        assert_eq!(bars[1].volume, Decimal::from(-1));
+    }
+
+    #[test]
+    fn truncated_bars_are_malformed() {
+        let message = fields("17|3|start|end|2|1735776000|1|1|1|1|0|1|1");
+
+        assert!(matches!(decode(&message), Err(WireError::Malformed(_))));
+    }
+
+    #[test]
+    fn errors_carry_request_and_code() {
+        let message = fields("4|2|5|162|HMDS query returned no data|");
+
+        assert_eq!(
+            decode(&message).unwrap(),
+            Incoming::Error {
+                req_id: 5,
+                code: 162,
+                message: "HMDS query returned no data".to_string(),
+            }
+        );
+    }
+}
@@ -6,14 +6,23 @@ use ingestion_domain::Tick;
 use rand::Rng;
 use rust_decimal::Decimal;
 use shaku::Component;
+use std::collections::HashMap;
 use std::time::Duration;
 use tracing::info;
 
+use super::price_profile::{profile_for, PriceProfile};
+
+/// Each tick's last price is drawn uniformly within this of the base price.
+const DEFAULT_VOLATILITY: f64 = 2.0;
+
 #[derive(Component)]
 #[shaku(interface = MarketDataGateway)]
 pub struct MockMarketDataGateway {
     tick_interval: Duration,
+    /// Base price for symbols without a profile.
     base_price: f64,
+    #[shaku(default)]
+    price_profiles: HashMap<String, PriceProfile>,
 }
 
 impl MockMarketDataGateway {
@@ -21,14 +30,33 @@ impl MockMarketDataGateway {
         Self {
             tick_interval,
             base_price,
+            price_profiles: HashMap::new(),
         }
     }
 
-    fn generate_tick(&self, symbol: &str) -> Tick {
+    /// Per-symbol prices, so several symbols produce distinct series.
+    pub fn with_price_profiles(mut self, price_profiles: HashMap<String, PriceProfile>) -> Self {
+        self.price_profiles = price_profiles;
+        self
+    }
+
+    fn profile(&self, symbol: &str) -> PriceProfile {
+        profile_for(
+            &self.price_profiles,
+            symbol,
+            PriceProfile::new(self.base_price, DEFAULT_VOLATILITY),
+        )
+    }
+
+    fn generate_tick(symbol: &str, profile: PriceProfile) -> Tick {
         let mut rng = rand::rng();
 
-        let price_change = rng.random_range(-2.0..2.0);
-        let last_price = self.base_price + price_change;
+        let price_change = if profile.volatility > 0.0 {
+            rng.random_range(-profile.volatility..profile.volatility)
+        } else {
+            0.0
+        };
+        let last_price = profile.base_price + price_change;
 
         let spread = 0.25;
         let bid_price = last_price - spread / 2.0;
@@ -57,18 +85,17 @@ impl MarketDataGateway for MockMarketDataGateway {
     async fn subscribe(&self, symbol: &str) -> Result<TickStream, GatewayError> {
         info!("Mock gateway: Subscribing to symbol {}", symbol);
 
+        let profile = self.profile(symbol);
         let symbol = symbol.to_string();
         let tick_interval = self.tick_interval;
-        let base_price = self.base_price;
 
         // 建立一個無限 stream，定期產生 Tick
         let stream = stream::unfold((), move |_| {
             let symbol = symbol.clone();
-            let gateway = MockMarketDataGateway::new(tick_interval, base_price);
 
             async move {
                 tokio::time::sleep(tick_interval).await;
-                let tick = gateway.generate_tick(&symbol);
+                let tick = Self::generate_tick(&symbol, profile);
                 Some((Ok(tick), ()))
             }
         });
@@ -95,4 +122,38 @@ mod tests {
             assert!(tick.last_price() > Decimal::ZERO);
         }
     }
+
+    #[tokio::test]
+    async fn symbols_follow_their_price_profiles() {
+        let gateway = MockMarketDataGateway::new(Duration::from_millis(1), 100.0)
+            .with_price_profiles(HashMap::from([
+                ("NQ".to_string(), PriceProfile::new(16000.0, 2.0)),
+                ("ES".to_string(), PriceProfile::new(5000.0, 0.5)),
+            ]));
+        let last_prices = |symbol: &'static str| {
+            let gateway = &gateway;
+            async move {
+                gateway
+                    .subscribe(symbol)
+                    .await
+                    .unwrap()
+                    .take(20)
+                    .map(|tick| tick.unwrap().last_price())
+                    .collect::<Vec<_>>()
+                    .await
+            }
+        };
+        let within = |price: &Decimal, base: i64, volatility: Decimal| {
+            (*price - Decimal::from(base)).abs() <= volatility
+        };
+
+        let nq = last_prices("NQ").await;
+        let es = last_prices("ES").await;
+        let unmapped = last_prices("CL").await;
+
+        assert!(nq.iter().all(|p| within(p, 16000, Decimal::from(2))));
+        assert!(es.iter().all(|p| within(p, 5000, Decimal::new(5, 1))));
+        assert!(unmapped.iter().all(|p| within(p, 100, Decimal::from(2))));
+        assert_ne!(nq, es);
+    }
 }
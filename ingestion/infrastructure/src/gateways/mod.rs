@@ -1,5 +1,13 @@
 pub mod historical;
+pub mod ib;
 pub mod market_data;
+pub mod price_profile;
+pub mod replay;
+pub mod scripted;
 
 pub use historical::MockHistoricalDataGateway;
+pub use ib::IbHistoricalDataGateway;
 pub use market_data::MockMarketDataGateway;
+pub use price_profile::PriceProfile;
+pub use replay::ParquetReplayGateway;
+pub use scripted::ScriptedMarketDataGateway;
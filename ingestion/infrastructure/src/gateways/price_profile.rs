@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+
+/// Shape of a mock symbol's price series.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceProfile {
+    pub base_price: f64,
+    /// How far generated prices stray from `base_price`.
+    pub volatility: f64,
+}
+
+impl PriceProfile {
+    pub const fn new(base_price: f64, volatility: f64) -> Self {
+        Self {
+            base_price,
+            volatility,
+        }
+    }
+}
+
+/// The profile configured for `symbol`, or `default` for unmapped symbols.
+pub(crate) fn profile_for(
+    profiles: &HashMap<String, PriceProfile>,
+    symbol: &str,
+    default: PriceProfile,
+) -> PriceProfile {
+    profiles.get(symbol).copied().unwrap_or(default)
+}
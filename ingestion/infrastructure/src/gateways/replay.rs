@@ -0,0 +1,248 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use futures::{stream, StreamExt};
+use ingestion_application::ports::{GatewayError, MarketDataGateway, RepositoryError, TickStream};
+use ingestion_domain::Tick;
+use shaku::Component;
+use std::collections::{btree_map, BTreeMap, VecDeque};
+use std::ops::Range;
+use std::path::PathBuf;
+use tokio::time::Instant;
+use tracing::info;
+
+use crate::repositories::naming::ParquetFileName;
+use crate::repositories::{FileRotation, ParquetTickReader};
+
+/// Replays the ticks stored for a symbol in a data directory as a live
+/// stream, in timestamp order, then ends it. For running the ingestion loop
+/// against captured data without IB.
+#[derive(Component)]
+#[shaku(interface = MarketDataGateway)]
+pub struct ParquetReplayGateway {
+    /// Directory holding `{symbol}_*.parquet` files, as written by
+    /// `ParquetTickRepository`.
+    data_dir: PathBuf,
+    /// Multiple of the recorded pace to replay at: 1.0 keeps the original
+    /// spacing between ticks, 10.0 is ten times faster. `None` emits ticks
+    /// as fast as they are read.
+    replay_speed: Option<f64>,
+    /// Rotation the live files in `data_dir` were written with, which sets
+    /// how much of the day each one covers.
+    #[shaku(default)]
+    live_rotation: FileRotation,
+}
+
+impl ParquetReplayGateway {
+    pub fn new(data_dir: PathBuf, replay_speed: Option<f64>) -> Self {
+        Self {
+            data_dir,
+            replay_speed,
+            live_rotation: FileRotation::default(),
+        }
+    }
+
+    pub fn with_live_rotation(mut self, live_rotation: FileRotation) -> Self {
+        self.live_rotation = live_rotation;
+        self
+    }
+
+    /// Groups `symbol`'s files by file date, opening each one's footer so a
+    /// file that cannot be read fails the subscription rather than cutting
+    /// the replay short.
+    fn plan(&self, symbol: &str) -> Result<BTreeMap<NaiveDate, ReplayDay>, RepositoryError> {
+        let segment_minutes = self.live_rotation.segment_minutes().unwrap_or(60);
+        let mut days: BTreeMap<NaiveDate, ReplayDay> = BTreeMap::new();
+        for path in ParquetTickReader::new(self.data_dir.clone()).symbol_files(symbol)? {
+            let Some(name) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(ParquetFileName::parse)
+            else {
+                continue;
+            };
+            ParquetTickReader::file_source(&path)?;
+            let day = days.entry(name.date).or_default();
+            match live_span(&name, segment_minutes) {
+                Some(span) => day.live.push((span, path)),
+                None => day.backfill.push(path),
+            }
+        }
+        for day in days.values_mut() {
+            day.live.sort_by_key(|(span, _)| span.start);
+        }
+        Ok(days)
+    }
+}
+
+/// UTC span of the segment a live (hour-named) file belongs to.
+fn live_span(name: &ParquetFileName, segment_minutes: u32) -> Option<Range<DateTime<Utc>>> {
+    let hour = name.hour?;
+    let minute_of_day = hour * 60 + name.minute.unwrap_or(0);
+    let segment_start = minute_of_day / segment_minutes * segment_minutes;
+    let midnight = name
+        .utc_date
+        .unwrap_or(name.date)
+        .and_hms_opt(0, 0, 0)?
+        .and_utc();
+    Some(
+        midnight + Duration::minutes(minute_of_day.into())
+            ..midnight + Duration::minutes((segment_start + segment_minutes).into()),
+    )
+}
+
+/// One file date's files for a symbol.
+#[derive(Default)]
+struct ReplayDay {
+    /// Daily and part files, as written by backfill.
+    backfill: Vec<PathBuf>,
+    /// Live files with the span each covers, in time order.
+    live: Vec<(Range<DateTime<Utc>>, PathBuf)>,
+}
+
+/// A symbol's ticks in timestamp order, read one file at a time: a day's
+/// backfill files together, then each live file once replay reaches it.
+/// Live files win the spans they cover, as in
+/// `ParquetTickRepository::reconcile_day`: backfill ticks inside them are
+/// dropped.
+struct ReplayTicks {
+    symbol: String,
+    days: btree_map::IntoValues<NaiveDate, ReplayDay>,
+    backfill: VecDeque<Tick>,
+    live: VecDeque<(Range<DateTime<Utc>>, PathBuf)>,
+    ready: VecDeque<Tick>,
+    failed: bool,
+}
+
+impl ReplayTicks {
+    fn read(&self, paths: &[PathBuf]) -> Result<Vec<Tick>, RepositoryError> {
+        let mut ticks = Vec::new();
+        for path in paths {
+            ticks.extend(
+                ParquetTickReader::read_file(path)?
+                    .into_iter()
+                    .filter(|tick| tick.symbol() == self.symbol),
+            );
+        }
+        ticks.sort_by_key(Tick::sort_key);
+        Ok(ticks)
+    }
+
+    /// Queues the next file's ticks, or returns `Ok(false)` once every day
+    /// has been read.
+    fn load_next(&mut self) -> Result<bool, RepositoryError> {
+        if let Some((span, path)) = self.live.pop_front() {
+            let before = self
+                .backfill
+                .iter()
+                .take_while(|tick| tick.timestamp() < span.start)
+                .count();
+            self.ready.extend(self.backfill.drain(..before));
+            let ticks = self.read(&[path])?;
+            self.ready.extend(ticks);
+        } else if !self.backfill.is_empty() {
+            self.ready.append(&mut self.backfill);
+        } else if let Some(day) = self.days.next() {
+            let backfill = self.read(&day.backfill)?;
+            self.backfill = backfill
+                .into_iter()
+                .filter(|tick| {
+                    !day.live
+                        .iter()
+                        .any(|(span, _)| span.contains(&tick.timestamp()))
+                })
+                .collect();
+            self.live = day.live.into();
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
+
+impl Iterator for ReplayTicks {
+    type Item = Result<Tick, GatewayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(tick) = self.ready.pop_front() {
+                return Some(Ok(tick));
+            }
+            if self.failed {
+                return None;
+            }
+            match self.load_next() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(err) => {
+                    self.failed = true;
+                    return Some(Err(GatewayError::StreamError(format!(
+                        "Failed to read replay file for {}: {}",
+                        self.symbol, err
+                    ))));
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl MarketDataGateway for ParquetReplayGateway {
+    /// Streams the files for `symbol` one at a time instead of loading the
+    /// whole history up front.
+    async fn subscribe(&self, symbol: &str) -> Result<TickStream, GatewayError> {
+        if let Some(speed) = self.replay_speed {
+            if !speed.is_finite() || speed <= 0.0 {
+                return Err(GatewayError::SubscriptionFailed {
+                    symbol: symbol.to_string(),
+                    reason: format!("replay_speed must be positive, got {}", speed),
+                });
+            }
+        }
+        let days = self.plan(symbol).map_err(|err| {
+            GatewayError::StreamError(format!(
+                "Failed to read replay files for {} in {}: {}",
+                symbol,
+                self.data_dir.display(),
+                err
+            ))
+        })?;
+        info!(
+            "Replay gateway: {} days of files for {} from {}",
+            days.len(),
+            symbol,
+            self.data_dir.display()
+        );
+        let ticks = ReplayTicks {
+            symbol: symbol.to_string(),
+            days: days.into_values(),
+            backfill: VecDeque::new(),
+            live: VecDeque::new(),
+            ready: VecDeque::new(),
+            failed: false,
+        };
+
+        let replay_speed = self.replay_speed;
+        let mut first = None;
+        let started = Instant::now();
+        let stream = stream::iter(ticks).then(move |tick| {
+            // Each tick is due at its offset from the first, scaled, so
+            // sleeps do not accumulate drift.
+            let due = match (&tick, replay_speed) {
+                (Ok(tick), Some(speed)) => {
+                    let first = *first.get_or_insert(tick.timestamp());
+                    let offset = (tick.timestamp() - first).to_std().unwrap_or_default();
+                    Some(started + offset.div_f64(speed))
+                }
+                _ => None,
+            };
+            async move {
+                if let Some(due) = due {
+                    tokio::time::sleep_until(due).await;
+                }
+                tick
+            }
+        });
+
+        Ok(Box::new(Box::pin(stream)))
+    }
+}
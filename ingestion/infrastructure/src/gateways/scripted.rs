@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use ingestion_application::ports::{GatewayError, MarketDataGateway, TickStream};
+use ingestion_domain::Tick;
+use shaku::Component;
+use std::time::Duration;
+use tracing::info;
+
+/// Replays a fixed list of ticks, in order, then ends the stream. For
+/// deterministic tests of the ingestion loop; see `MockMarketDataGateway`
+/// for endless random data.
+#[derive(Component)]
+#[shaku(interface = MarketDataGateway)]
+pub struct ScriptedMarketDataGateway {
+    ticks: Vec<Tick>,
+    /// Delay before each tick is emitted.
+    tick_interval: Duration,
+}
+
+impl ScriptedMarketDataGateway {
+    pub fn new(ticks: Vec<Tick>, tick_interval: Duration) -> Self {
+        Self {
+            ticks,
+            tick_interval,
+        }
+    }
+}
+
+#[async_trait]
+impl MarketDataGateway for ScriptedMarketDataGateway {
+    /// Emits the scripted ticks for `symbol` only.
+    async fn subscribe(&self, symbol: &str) -> Result<TickStream, GatewayError> {
+        info!("Scripted gateway: Subscribing to symbol {}", symbol);
+
+        let ticks: Vec<Tick> = self
+            .ticks
+            .iter()
+            .filter(|tick| tick.symbol() == symbol)
+            .cloned()
+            .collect();
+        let tick_interval = self.tick_interval;
+        let stream = stream::iter(ticks).then(move |tick| async move {
+            tokio::time::sleep(tick_interval).await;
+            Ok(tick)
+        });
+
+        Ok(Box::new(Box::pin(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use ingestion_domain::test_support::sample_tick;
+
+    #[tokio::test]
+    async fn replays_script_in_order_then_ends() {
+        let at = |second| Utc.with_ymd_and_hms(2025, 1, 2, 10, 0, second).unwrap();
+        let script = vec![
+            sample_tick("NQ", at(0)),
+            sample_tick("ES", at(1)),
+            sample_tick("NQ", at(2)),
+        ];
+        let gateway = ScriptedMarketDataGateway::new(script.clone(), Duration::ZERO);
+
+        let emitted: Vec<Tick> = gateway
+            .subscribe("NQ")
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(emitted, vec![script[0].clone(), script[2].clone()]);
+    }
+}
@@ -1,11 +1,21 @@
+#[cfg(feature = "admin-api")]
+pub mod admin;
 pub mod detectors;
+pub mod filesystem;
 pub mod gateways;
 pub mod rate_limiting;
 pub mod repositories;
+pub mod scripts;
 pub mod state;
 
 pub use detectors::ParquetGapDetector;
-pub use gateways::{MockHistoricalDataGateway, MockMarketDataGateway};
-pub use rate_limiting::{IbRateLimiter, RedisConnection};
-pub use repositories::ParquetTickRepository;
-pub use state::RedisJobStateRepository;
+pub use filesystem::{FileSystem, InMemoryFileSystem, StdFileSystem};
+pub use gateways::{
+    IbHistoricalDataGateway, MockHistoricalDataGateway, MockMarketDataGateway,
+    ParquetReplayGateway, ScriptedMarketDataGateway,
+};
+pub use rate_limiting::{IbRateLimiter, NoopRateLimiter, RedisConnection};
+pub use repositories::{
+    ParquetDepthReader, ParquetDepthRepository, ParquetTickReader, ParquetTickRepository,
+};
+pub use state::{RedisGapQueue, RedisJobStateRepository};
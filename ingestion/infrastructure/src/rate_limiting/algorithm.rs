@@ -0,0 +1,171 @@
+use super::limiter::RateLimitWindow;
+use lazy_static::lazy_static;
+use redis::Script;
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+lazy_static! {
+    static ref SLIDING_WINDOW_LOG_SCRIPT: Script = Script::new(include_str!("limiter.lua"));
+    static ref FIXED_WINDOW_COUNTER_SCRIPT: Script = Script::new(include_str!("fixed_window.lua"));
+    static ref TOKEN_BUCKET_SCRIPT: Script = Script::new(include_str!("token_bucket.lua"));
+}
+
+/// How a [`RateLimitWindow`] decides whether a request fits.
+///
+/// The sliding-window log is exact but stores one Redis entry per request;
+/// the fixed-window counter stores one integer per period but can admit up to
+/// twice the limit around a period boundary; the token bucket stores two
+/// fields per window and smooths bursts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RateLimitAlgorithm {
+    #[default]
+    SlidingWindowLog,
+    FixedWindowCounter,
+    TokenBucket,
+}
+
+impl RateLimitAlgorithm {
+    /// Redis script implementing the algorithm, with `limiter.lua`'s
+    /// KEYS/ARGV layout and `{allowed, retry_after_ms}` result.
+    pub(crate) fn script(self) -> &'static Script {
+        match self {
+            Self::SlidingWindowLog => &SLIDING_WINDOW_LOG_SCRIPT,
+            Self::FixedWindowCounter => &FIXED_WINDOW_COUNTER_SCRIPT,
+            Self::TokenBucket => &TOKEN_BUCKET_SCRIPT,
+        }
+    }
+
+    /// Appended to window keys so algorithms never read each other's data
+    /// (sorted set, counters and hash respectively).
+    pub(crate) fn key_suffix(self) -> &'static str {
+        match self {
+            Self::SlidingWindowLog => "",
+            Self::FixedWindowCounter => ":fw",
+            Self::TokenBucket => ":tb",
+        }
+    }
+
+    /// In-process state for one window.
+    pub fn window_state(self, window: &RateLimitWindow) -> Box<dyn WindowState> {
+        let limit = window.limit as u64;
+        let duration_millis = window.duration_secs * 1000;
+        match self {
+            Self::SlidingWindowLog => Box::new(SlidingWindowLog {
+                limit,
+                duration_millis,
+                entries: VecDeque::new(),
+            }),
+            Self::FixedWindowCounter => Box::new(FixedWindowCounter {
+                limit,
+                duration_millis,
+                period: 0,
+                count: 0,
+            }),
+            Self::TokenBucket => Box::new(TokenBucket {
+                limit: limit as f64,
+                duration_millis: duration_millis as f64,
+                tokens: limit as f64,
+                updated_at: None,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for RateLimitAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::SlidingWindowLog => "sliding-window-log",
+            Self::FixedWindowCounter => "fixed-window-counter",
+            Self::TokenBucket => "token-bucket",
+        })
+    }
+}
+
+impl FromStr for RateLimitAlgorithm {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "sliding-window-log" => Ok(Self::SlidingWindowLog),
+            "fixed-window-counter" => Ok(Self::FixedWindowCounter),
+            "token-bucket" => Ok(Self::TokenBucket),
+            other => Err(format!("unknown rate limit algorithm '{}'", other)),
+        }
+    }
+}
+
+/// One window's bookkeeping. Admission is two-phase so several windows can
+/// be checked before any of them records the request.
+pub trait WindowState: Send {
+    fn has_capacity(&mut self, now_millis: u64) -> bool;
+    fn record(&mut self, now_millis: u64);
+}
+
+struct SlidingWindowLog {
+    limit: u64,
+    duration_millis: u64,
+    entries: VecDeque<u64>,
+}
+
+impl WindowState for SlidingWindowLog {
+    fn has_capacity(&mut self, now_millis: u64) -> bool {
+        while self
+            .entries
+            .front()
+            .is_some_and(|entry| entry + self.duration_millis <= now_millis)
+        {
+            self.entries.pop_front();
+        }
+        (self.entries.len() as u64) < self.limit
+    }
+
+    fn record(&mut self, now_millis: u64) {
+        self.entries.push_back(now_millis);
+    }
+}
+
+struct FixedWindowCounter {
+    limit: u64,
+    duration_millis: u64,
+    period: u64,
+    count: u64,
+}
+
+impl WindowState for FixedWindowCounter {
+    fn has_capacity(&mut self, now_millis: u64) -> bool {
+        let period = now_millis / self.duration_millis.max(1);
+        if period != self.period {
+            self.period = period;
+            self.count = 0;
+        }
+        self.count < self.limit
+    }
+
+    fn record(&mut self, _now_millis: u64) {
+        self.count += 1;
+    }
+}
+
+struct TokenBucket {
+    limit: f64,
+    duration_millis: f64,
+    tokens: f64,
+    updated_at: Option<u64>,
+}
+
+impl WindowState for TokenBucket {
+    fn has_capacity(&mut self, now_millis: u64) -> bool {
+        if let Some(updated_at) = self.updated_at {
+            let elapsed = now_millis.saturating_sub(updated_at) as f64;
+            self.tokens = (self.tokens + elapsed * self.limit / self.duration_millis.max(1.0))
+                .min(self.limit);
+        }
+        self.updated_at = Some(now_millis);
+        self.tokens >= 1.0
+    }
+
+    fn record(&mut self, _now_millis: u64) {
+        self.tokens -= 1.0;
+    }
+}
@@ -0,0 +1,39 @@
+-- estimate.lua
+--
+-- Read-only companion to limiter.lua: reports how many milliseconds a new
+-- request would have to wait before every window has a free slot.
+--
+-- Keys and ARGV use the same layout as limiter.lua, minus the trailing
+-- request id.
+
+local redis_time = redis.call('TIME')
+local now_micros = (redis_time[1] * 1000000) + redis_time[2]
+local now_millis = math.floor(now_micros / 1000)
+
+local wait_millis = 0
+
+for i = 1, #KEYS do
+    local key = KEYS[i]
+    local limit = tonumber(ARGV[(i - 1) * 2 + 1])
+    local duration_millis = tonumber(ARGV[(i - 1) * 2 + 2]) * 1000
+
+    local min_score = now_millis - duration_millis
+    local current_count = redis.call('ZCOUNT', key, '(' .. min_score, '+inf')
+
+    if current_count >= limit then
+        -- A window with no slots never frees one; report its full length.
+        local window_wait = duration_millis
+        if limit > 0 then
+            -- A slot frees up once enough of the oldest live entries expire.
+            local offset = current_count - limit
+            local entry = redis.call('ZRANGEBYSCORE', key, '(' .. min_score, '+inf',
+                'WITHSCORES', 'LIMIT', offset, 1)
+            window_wait = tonumber(entry[2]) + duration_millis - now_millis
+        end
+        if window_wait > wait_millis then
+            wait_millis = window_wait
+        end
+    end
+end
+
+return wait_millis
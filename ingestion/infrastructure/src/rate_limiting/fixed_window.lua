@@ -0,0 +1,45 @@
+-- fixed_window.lua
+--
+-- Fixed-window counter variant of limiter.lua: one integer counter per
+-- window and period instead of one sorted-set entry per request. Cheaper on
+-- memory, but allows up to twice the limit across a period boundary.
+--
+-- Keys and ARGV use the same layout as limiter.lua. Each KEYS[i] is a prefix;
+-- the live counter is KEYS[i] .. ':' .. period index. The request id is unused.
+-- Returns {allowed, retry_after_ms} like limiter.lua; a full window frees up
+-- when its period ends.
+
+local redis_time = redis.call('TIME')
+local now_millis = math.floor(((redis_time[1] * 1000000) + redis_time[2]) / 1000)
+
+local counters = {}
+local denied = false
+local retry_after_millis = 0
+for i = 1, #KEYS do
+    local limit = tonumber(ARGV[(i - 1) * 2 + 1])
+    local duration_millis = tonumber(ARGV[(i - 1) * 2 + 2]) * 1000
+    local period = math.floor(now_millis / duration_millis)
+    local counter = KEYS[i] .. ':' .. period
+
+    local count = tonumber(redis.call('GET', counter) or '0')
+    if count >= limit then
+        denied = true
+        local window_wait = (period + 1) * duration_millis - now_millis
+        if window_wait > retry_after_millis then
+            retry_after_millis = window_wait
+        end
+    end
+    counters[i] = counter
+end
+
+if denied then
+    return {0, retry_after_millis} -- Denied
+end
+
+for i = 1, #KEYS do
+    local duration_secs = tonumber(ARGV[(i - 1) * 2 + 2])
+    redis.call('INCR', counters[i])
+    redis.call('EXPIRE', counters[i], duration_secs + 5)
+end
+
+return {1, 0} -- Allowed
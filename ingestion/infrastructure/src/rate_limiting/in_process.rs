@@ -0,0 +1,157 @@
+use super::algorithm::{RateLimitAlgorithm, WindowState};
+use super::limiter::RateLimitWindow;
+use async_trait::async_trait;
+use ingestion_application::rate_limiter::{RateLimitScope, RateLimiter, RateLimiterError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Single-process limiter running any [`RateLimitAlgorithm`] without Redis.
+/// Limits are not shared between processes, and every window counts all
+/// requests together whatever their [`RateLimitScope`].
+pub struct InProcessRateLimiter {
+    started: Instant,
+    windows: Mutex<Vec<Box<dyn WindowState>>>,
+}
+
+impl InProcessRateLimiter {
+    pub fn new(algorithm: RateLimitAlgorithm, windows: &[RateLimitWindow]) -> Self {
+        Self {
+            started: Instant::now(),
+            windows: Mutex::new(
+                windows
+                    .iter()
+                    .map(|window| algorithm.window_state(window))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Admits a request at `now_millis` (milliseconds on the limiter's own
+    /// clock) if every window has room.
+    pub fn try_acquire_at(&self, now_millis: u64) -> bool {
+        let mut windows = self.windows.lock().unwrap();
+        if !windows
+            .iter_mut()
+            .all(|window| window.has_capacity(now_millis))
+        {
+            return false;
+        }
+        for window in windows.iter_mut() {
+            window.record(now_millis);
+        }
+        true
+    }
+
+    fn now_millis(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+}
+
+#[async_trait]
+impl RateLimiter for InProcessRateLimiter {
+    async fn acquire(&self, _scope: &RateLimitScope) -> Result<(), RateLimiterError> {
+        while !self.try_acquire_at(self.now_millis()) {
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+        Ok(())
+    }
+
+    async fn try_acquire(&self, _scope: &RateLimitScope) -> Result<bool, RateLimiterError> {
+        Ok(self.try_acquire_at(self.now_millis()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(algorithm: RateLimitAlgorithm) -> InProcessRateLimiter {
+        InProcessRateLimiter::new(algorithm, &[RateLimitWindow::new(2, 1)])
+    }
+
+    /// Two requests late in one period, then attempts just after the
+    /// boundary, half a window later, and once the first pair has aged out.
+    fn boundary_outcomes(algorithm: RateLimitAlgorithm) -> Vec<bool> {
+        let limiter = limiter(algorithm);
+        [900, 950, 1_000, 1_010, 1_450, 1_960]
+            .into_iter()
+            .map(|at| limiter.try_acquire_at(at))
+            .collect()
+    }
+
+    #[test]
+    fn fixed_window_admits_a_burst_across_the_boundary() {
+        assert_eq!(
+            boundary_outcomes(RateLimitAlgorithm::FixedWindowCounter),
+            vec![true, true, true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn sliding_log_holds_the_limit_across_the_boundary() {
+        assert_eq!(
+            boundary_outcomes(RateLimitAlgorithm::SlidingWindowLog),
+            vec![true, true, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn token_bucket_refills_gradually() {
+        // 2 tokens per second: one token back every 500ms after the burst.
+        assert_eq!(
+            boundary_outcomes(RateLimitAlgorithm::TokenBucket),
+            vec![true, true, false, false, true, true]
+        );
+    }
+
+    #[test]
+    fn every_window_must_admit() {
+        let limiter = InProcessRateLimiter::new(
+            RateLimitAlgorithm::SlidingWindowLog,
+            &[RateLimitWindow::new(10, 10), RateLimitWindow::new(1, 1)],
+        );
+        assert!(limiter.try_acquire_at(0));
+        assert!(!limiter.try_acquire_at(500));
+        assert!(limiter.try_acquire_at(1_001));
+    }
+
+    #[test]
+    fn algorithm_names_round_trip() {
+        for algorithm in [
+            RateLimitAlgorithm::SlidingWindowLog,
+            RateLimitAlgorithm::FixedWindowCounter,
+            RateLimitAlgorithm::TokenBucket,
+        ] {
+            assert_eq!(algorithm.to_string().parse(), Ok(algorithm));
+        }
+        assert!("leaky".parse::<RateLimitAlgorithm>().is_err());
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_wait_returns_promptly() {
+        let limiter = InProcessRateLimiter::new(
+            RateLimitAlgorithm::SlidingWindowLog,
+            &[RateLimitWindow::new(1, 10)],
+        );
+        let scope = RateLimitScope::contract("NQ");
+        limiter.acquire(&scope).await.unwrap();
+        let token = tokio_util::sync::CancellationToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            canceller.cancel();
+        });
+
+        let started = Instant::now();
+        let result = limiter.acquire_cancellable(&scope, &token).await;
+
+        assert!(matches!(result, Err(RateLimiterError::Cancelled)));
+        assert!(
+            started.elapsed() < Duration::from_secs(1),
+            "cancellation took {:?}",
+            started.elapsed()
+        );
+    }
+}
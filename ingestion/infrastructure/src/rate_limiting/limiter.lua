@@ -17,6 +17,9 @@
 -- ARGV[6] = duration_secs_window_3
 -- ...
 -- ARGV[N] = unique_request_id
+--
+-- Returns {allowed, retry_after_ms}: {1, 0} when admitted, otherwise {0, ms}
+-- until every saturated window has a free slot.
 
 -- Get Redis server time for a consistent clock source. This is the single source of truth.
 local redis_time = redis.call('TIME')
@@ -27,6 +30,8 @@ local request_id = ARGV[#ARGV]
 local score = now_millis
 
 -- Iterate through each window (key, limit, duration)
+local denied = false
+local retry_after_millis = 0
 for i = 1, #KEYS do
     local key = KEYS[i]
     local limit = tonumber(ARGV[(i - 1) * 2 + 1])
@@ -38,10 +43,25 @@ for i = 1, #KEYS do
 
     local current_count = redis.call('ZCARD', key)
     if current_count >= limit then
-        return 0 -- Denied
+        denied = true
+        -- A window with no slots never frees one; report its full length.
+        local window_wait = duration_millis
+        if limit > 0 then
+            -- A slot frees up once enough of the oldest live entries expire.
+            local entry = redis.call('ZRANGE', key, current_count - limit,
+                current_count - limit, 'WITHSCORES')
+            window_wait = tonumber(entry[2]) + duration_millis - now_millis
+        end
+        if window_wait > retry_after_millis then
+            retry_after_millis = window_wait
+        end
     end
 end
 
+if denied then
+    return {0, retry_after_millis} -- Denied
+end
+
 for i = 1, #KEYS do
     local key = KEYS[i]
     local duration_secs = tonumber(ARGV[(i - 1) * 2 + 2])
@@ -50,4 +70,4 @@ for i = 1, #KEYS do
     redis.call('EXPIRE', key, duration_secs + 5)
 end
 
-return 1 -- Allowed
+return {1, 0} -- Allowed
@@ -1,6 +1,7 @@
-use super::redis::RedisConnection;
+use super::algorithm::RateLimitAlgorithm;
+use super::redis::{cluster_redirection, redis_db_from_env, RedisConnection, ThrottledConnection};
 use async_trait::async_trait;
-use ingestion_application::rate_limiter::{RateLimiter, RateLimiterError};
+use ingestion_application::rate_limiter::{RateLimitScope, RateLimiter, RateLimiterError};
 use lazy_static::lazy_static;
 use redis::Script;
 use shaku::Component;
@@ -13,15 +14,36 @@ use tracing::warn;
 use uuid::Uuid;
 
 lazy_static! {
-    static ref LUA_SCRIPT: Script = {
-        const SCRIPT_SOURCE: &str = include_str!("limiter.lua");
+    static ref ESTIMATE_SCRIPT: Script = {
+        const SCRIPT_SOURCE: &str = include_str!("estimate.lua");
         Script::new(SCRIPT_SOURCE)
     };
 }
 
-const RATE_LIMIT_RETRY_DELAY_MS: u64 = 200;
+/// Floor on the wait between attempts, so a script reporting a near-zero
+/// delay (clock rounding) doesn't spin.
+const MIN_RETRY_DELAY: Duration = Duration::from_millis(10);
 
-#[derive(Clone)]
+/// Scripts the limiter may run, named for [`crate::scripts::preload_scripts`].
+pub(crate) fn lua_scripts() -> Vec<(&'static str, &'static Script)> {
+    let mut scripts = vec![("rate-limit-estimate", &*ESTIMATE_SCRIPT)];
+    for (name, algorithm) in [
+        (
+            "rate-limit-sliding-window-log",
+            RateLimitAlgorithm::SlidingWindowLog,
+        ),
+        (
+            "rate-limit-fixed-window-counter",
+            RateLimitAlgorithm::FixedWindowCounter,
+        ),
+        ("rate-limit-token-bucket", RateLimitAlgorithm::TokenBucket),
+    ] {
+        scripts.push((name, algorithm.script()));
+    }
+    scripts
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RateLimitWindow {
     pub limit: usize,
     pub duration_secs: u64,
@@ -54,10 +76,18 @@ pub struct IbRateLimiterConfig {
     pub account_id: String,
     /// 60 requests per 10-minute rolling window.
     pub ten_minute_window: RateLimitWindow,
-    /// 6 requests per 2-second rolling window for the same contract/exchange/tick type.
+    /// 6 requests per 2-second rolling window for the same contract/exchange/tick
+    /// type, counted per [`RateLimitScope`].
     pub contract_window: RateLimitWindow,
-    /// Prevent identical requests within 15 seconds.
+    /// Prevent identical requests within 15 seconds, counted per
+    /// [`RateLimitScope`].
     pub duplicate_request_window: RateLimitWindow,
+    /// How each window admits requests. Only the sliding-window log can
+    /// estimate waits; the others report zero.
+    pub algorithm: RateLimitAlgorithm,
+    /// Redis database for the limiter's keys; `None` shares the `REDIS_URL`
+    /// database.
+    pub redis_db: Option<i64>,
 }
 
 impl Default for IbRateLimiterConfig {
@@ -74,6 +104,8 @@ impl IbRateLimiterConfig {
         const CONTRACT_DURATION_ENV: &str = "IB_RATE_LIMIT_CONTRACT_SECONDS";
         const DUP_REQ_LIMIT_ENV: &str = "IB_RATE_LIMIT_DUPLICATE_LIMIT";
         const DUP_REQ_DURATION_ENV: &str = "IB_RATE_LIMIT_DUPLICATE_SECONDS";
+        const ALGORITHM_ENV: &str = "IB_RATE_LIMIT_ALGORITHM";
+        const REDIS_DB_ENV: &str = "IB_RATE_LIMIT_REDIS_DB";
 
         Self {
             account_id: env::var("IB_ACCOUNT_ID").unwrap_or_else(|_| "U12345".to_string()),
@@ -95,11 +127,22 @@ impl IbRateLimiterConfig {
                 1,
                 15,
             ),
+            algorithm: read_env_or_default(ALGORITHM_ENV, RateLimitAlgorithm::default()),
+            redis_db: redis_db_from_env(REDIS_DB_ENV),
         }
     }
+
+    /// The configured windows by name, in the order the scripts take them.
+    pub fn windows(&self) -> Vec<(&'static str, RateLimitWindow)> {
+        vec![
+            ("ten-minute", self.ten_minute_window.clone()),
+            ("contract", self.contract_window.clone()),
+            ("duplicate", self.duplicate_request_window.clone()),
+        ]
+    }
 }
 
-fn read_env_or_default<T>(key: &str, default: T) -> T
+pub(crate) fn read_env_or_default<T>(key: &str, default: T) -> T
 where
     T: Copy + FromStr + Display,
     T::Err: Display,
@@ -116,6 +159,17 @@ where
     }
 }
 
+/// Maps a Redis error to `fallback`, unless it is a cluster redirection.
+fn redis_error(
+    err: redis::RedisError,
+    fallback: fn(String) -> RateLimiterError,
+) -> RateLimiterError {
+    match cluster_redirection(&err) {
+        Some(reason) => RateLimiterError::ClusterNotSupported(reason),
+        None => fallback(err.to_string()),
+    }
+}
+
 #[derive(Component)]
 #[shaku(interface = RateLimiter)]
 pub struct IbRateLimiter {
@@ -126,67 +180,171 @@ pub struct IbRateLimiter {
     config: IbRateLimiterConfig,
 }
 
+impl IbRateLimiter {
+    /// The windows this limiter enforces, by name.
+    pub fn windows(&self) -> Vec<(&'static str, RateLimitWindow)> {
+        self.config.windows()
+    }
+
+    /// Redis key per window. The ten-minute window is account-wide; the
+    /// contract and duplicate windows also carry the scope's contract key,
+    /// so requests for different contracts don't share them.
+    fn window_keys(&self, scope: &RateLimitScope) -> Vec<String> {
+        let contract = scope.contract_key();
+        self.windows()
+            .iter()
+            .map(|(name, window)| {
+                let scope_segment = match *name {
+                    "ten-minute" => String::new(),
+                    _ => format!("{}:", contract),
+                };
+                format!(
+                    "rate_limit:ib:historical:{}:{}{}s{}",
+                    self.config.account_id,
+                    scope_segment,
+                    window.duration_secs,
+                    self.config.algorithm.key_suffix()
+                )
+            })
+            .collect()
+    }
+
+    async fn connection(&self) -> Result<ThrottledConnection, RateLimiterError> {
+        self.redis_client
+            .get_connection_to(self.config.redis_db)
+            .await
+            .map_err(|e| redis_error(e, RateLimiterError::ConnectionError))
+    }
+
+    /// Runs the configured algorithm's script once; `None` if admitted,
+    /// otherwise how long until every window has a free slot.
+    async fn try_acquire_on(
+        &self,
+        conn: &mut ThrottledConnection,
+        scope: &RateLimitScope,
+    ) -> Result<Option<Duration>, RateLimiterError> {
+        let request_id = Uuid::new_v4().to_string();
+        let mut script_invocation = self.config.algorithm.script().prepare_invoke();
+
+        for key in &self.window_keys(scope) {
+            script_invocation.key(key);
+        }
+
+        for (_, window) in self.windows() {
+            script_invocation.arg(window.limit);
+            script_invocation.arg(window.duration_secs);
+        }
+
+        script_invocation.arg(&request_id);
+
+        let result: Vec<i64> = script_invocation
+            .invoke_async(conn)
+            .await
+            .map_err(|e| redis_error(e, RateLimiterError::ScriptError))?;
+        match result.as_slice() {
+            [1, _] => Ok(None),
+            [0, retry_after_ms] => Ok(Some(Duration::from_millis((*retry_after_ms).max(0) as u64))),
+            _ => Err(RateLimiterError::Unexpected(format!(
+                "Lua script returned an unexpected value: {:?}",
+                result
+            ))),
+        }
+    }
+}
+
 #[async_trait]
 impl RateLimiter for IbRateLimiter {
-    async fn acquire(&self) -> Result<(), RateLimiterError> {
-        // Get a connection from the provider.
-        let mut conn = self
-            .redis_client
-            .get_connection()
+    async fn acquire(&self, scope: &RateLimitScope) -> Result<(), RateLimiterError> {
+        let mut conn = self.connection().await?;
+
+        while let Some(retry_after) = self.try_acquire_on(&mut conn, scope).await? {
+            let delay = retry_after.max(MIN_RETRY_DELAY);
+            warn!("Rate limit hit. Retrying in {:?}", delay);
+            tokio::time::sleep(delay).await;
+        }
+        Ok(())
+    }
+
+    async fn try_acquire(&self, scope: &RateLimitScope) -> Result<bool, RateLimiterError> {
+        let mut conn = self.connection().await?;
+        Ok(self.try_acquire_on(&mut conn, scope).await?.is_none())
+    }
+
+    async fn estimated_wait(&self, scope: &RateLimitScope) -> Result<Duration, RateLimiterError> {
+        if self.config.algorithm != RateLimitAlgorithm::SlidingWindowLog {
+            return Ok(Duration::ZERO);
+        }
+        let mut conn = self.connection().await?;
+
+        let mut script_invocation = ESTIMATE_SCRIPT.prepare_invoke();
+        for key in &self.window_keys(scope) {
+            script_invocation.key(key);
+        }
+        for (_, window) in self.windows() {
+            script_invocation.arg(window.limit);
+            script_invocation.arg(window.duration_secs);
+        }
+
+        let wait_millis: i64 = script_invocation
+            .invoke_async(&mut conn)
             .await
-            .map_err(|e| RateLimiterError::ConnectionError(e.to_string()))?;
-
-        let account_id = &self.config.account_id;
-        let windows = [
-            &self.config.ten_minute_window,
-            &self.config.contract_window,
-            &self.config.duplicate_request_window,
-        ];
-        let window_keys = windows.map(|window| {
-            format!(
-                "rate_limit:ib:historical:{}:{}s",
-                account_id, window.duration_secs
-            )
-        });
-
-        loop {
-            let request_id = Uuid::new_v4().to_string();
-            let mut script_invocation = LUA_SCRIPT.prepare_invoke();
-
-            for key in &window_keys {
-                script_invocation.key(key);
-            }
-
-            for window in &windows {
-                script_invocation.arg(window.limit);
-                script_invocation.arg(window.duration_secs);
-            }
-
-            script_invocation.arg(&request_id);
-
-            let result: Result<i32, _> = script_invocation.invoke_async(&mut conn).await;
-
-            match result {
-                Ok(1) => {
-                    // Allowed
-                    return Ok(());
-                }
-                Ok(0) => {
-                    // Denied, wait and retry
-                    warn!("Rate limit hit. Retrying shortly...");
-                    tokio::time::sleep(Duration::from_millis(RATE_LIMIT_RETRY_DELAY_MS)).await;
-                    continue;
-                }
-                Ok(_) => {
-                    // Should not happen
-                    return Err(RateLimiterError::Unexpected(
-                        "Lua script returned an unexpected value.".to_string(),
-                    ));
-                }
-                Err(e) => {
-                    return Err(RateLimiterError::ScriptError(e.to_string()));
-                }
-            }
+            .map_err(|e| redis_error(e, RateLimiterError::ScriptError))?;
+
+        Ok(Duration::from_millis(wait_millis.max(0) as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limiting::redis::RedisConnectionManager;
+
+    fn limiter() -> IbRateLimiter {
+        let config = IbRateLimiterConfig {
+            account_id: "DU1".to_string(),
+            ten_minute_window: RateLimitWindow::new(50, 600),
+            contract_window: RateLimitWindow::new(5, 2),
+            duplicate_request_window: RateLimitWindow::new(1, 20),
+            algorithm: RateLimitAlgorithm::default(),
+            redis_db: None,
+        };
+        // Opening a client does not connect, so no server is needed.
+        IbRateLimiter {
+            redis_client: Arc::new(RedisConnectionManager::new(
+                redis::Client::open("redis://127.0.0.1:6379").unwrap(),
+            )),
+            config,
         }
     }
+
+    #[test]
+    fn reports_the_configured_windows_by_name() {
+        let limiter = limiter();
+
+        assert_eq!(
+            limiter.windows(),
+            vec![
+                ("ten-minute", RateLimitWindow::new(50, 600)),
+                ("contract", RateLimitWindow::new(5, 2)),
+                ("duplicate", RateLimitWindow::new(1, 20)),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_the_account_window_is_shared_across_contracts() {
+        let scope = RateLimitScope::contract("NQ")
+            .with_exchange("CME")
+            .with_tick_type("TRADES");
+
+        assert_eq!(
+            limiter().window_keys(&scope),
+            vec![
+                "rate_limit:ib:historical:DU1:600s",
+                "rate_limit:ib:historical:DU1:NQ:CME:TRADES:2s",
+                "rate_limit:ib:historical:DU1:NQ:CME:TRADES:20s",
+            ]
+        );
+        assert_eq!(RateLimitScope::contract("ES").contract_key(), "ES::");
+    }
 }
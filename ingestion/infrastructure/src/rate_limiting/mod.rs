@@ -1,5 +1,11 @@
+pub mod algorithm;
+pub mod in_process;
 pub mod limiter;
+pub mod noop;
 pub mod redis;
 
+pub use algorithm::{RateLimitAlgorithm, WindowState};
+pub use in_process::InProcessRateLimiter;
 pub use limiter::{IbRateLimiter, IbRateLimiterConfig, IbRateLimiterParameters, RateLimitWindow};
-pub use redis::RedisConnection;
+pub use noop::NoopRateLimiter;
+pub use redis::{RedisConnectConfig, RedisConnection, ThrottledConnection};
@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+use ingestion_application::rate_limiter::{RateLimitScope, RateLimiter, RateLimiterError};
+
+/// Limiter that never blocks, for offline tooling that runs without Redis.
+#[derive(Default)]
+pub struct NoopRateLimiter;
+
+#[async_trait]
+impl RateLimiter for NoopRateLimiter {
+    async fn acquire(&self, _scope: &RateLimitScope) -> Result<(), RateLimiterError> {
+        Ok(())
+    }
+}
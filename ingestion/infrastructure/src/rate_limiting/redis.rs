@@ -1,11 +1,121 @@
 use async_trait::async_trait;
-use redis::aio::MultiplexedConnection;
-use redis::{Client as RedisClient, RedisResult};
+use redis::aio::{ConnectionLike, MultiplexedConnection};
+use redis::{
+    Client as RedisClient, Cmd, ErrorKind, Pipeline, RedisError, RedisFuture, RedisResult,
+    ServerErrorKind, Value,
+};
 use shaku::{Component, Interface};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+const DEFAULT_MAX_IN_FLIGHT: usize = 32;
+const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[async_trait]
 pub trait RedisConnection: Interface {
-    async fn get_connection(&self) -> RedisResult<MultiplexedConnection>;
+    async fn get_connection(&self) -> RedisResult<ThrottledConnection>;
+
+    /// Like `get_connection`, switched to database `db` with `SELECT`;
+    /// `None` stays on the database from `REDIS_URL`. Each call opens its own
+    /// connection, so the switch never affects other callers.
+    async fn get_connection_to(&self, db: Option<i64>) -> RedisResult<ThrottledConnection> {
+        let mut conn = self.get_connection().await?;
+        if let Some(db) = db {
+            let _: () = redis::cmd("SELECT").arg(db).query_async(&mut conn).await?;
+        }
+        Ok(conn)
+    }
+}
+
+/// Database index for one component from the environment variable `key`, so
+/// operators can keep e.g. rate-limit and job-state keys apart. Unset means
+/// the shared database from `REDIS_URL`.
+pub fn redis_db_from_env(key: &str) -> Option<i64> {
+    let value = std::env::var(key).ok()?;
+    match value.trim().parse::<i64>() {
+        Ok(db) if db >= 0 => Some(db),
+        _ => {
+            warn!(
+                "Invalid Redis database '{}' for {}. Using the REDIS_URL database",
+                value, key
+            );
+            None
+        }
+    }
+}
+
+/// Connection whose commands each hold a permit from a semaphore shared by
+/// every connection from the same manager, capping in-flight commands
+/// process-wide. Commands beyond the cap wait for a permit. With a command
+/// timeout set, a command that runs longer (a slow script, a blocked server)
+/// fails with a timeout error; time spent waiting for a permit is not counted.
+#[derive(Clone)]
+pub struct ThrottledConnection<C = MultiplexedConnection> {
+    inner: C,
+    permits: Arc<Semaphore>,
+    command_timeout: Option<Duration>,
+}
+
+impl<C> ThrottledConnection<C> {
+    pub fn new(inner: C, permits: Arc<Semaphore>) -> Self {
+        Self {
+            inner,
+            permits,
+            command_timeout: None,
+        }
+    }
+
+    pub fn with_command_timeout(mut self, command_timeout: Duration) -> Self {
+        self.command_timeout = Some(command_timeout);
+        self
+    }
+}
+
+async fn within<T>(
+    limit: Option<Duration>,
+    command: impl std::future::Future<Output = RedisResult<T>>,
+) -> RedisResult<T> {
+    match limit {
+        Some(limit) => tokio::time::timeout(limit, command)
+            .await
+            .unwrap_or_else(|_| Err(command_timeout_error(limit))),
+        None => command.await,
+    }
+}
+
+impl<C: ConnectionLike + Send> ConnectionLike for ThrottledConnection<C> {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        Box::pin(async move {
+            let _permit = self.permits.acquire().await.map_err(closed_error)?;
+            within(self.command_timeout, self.inner.req_packed_command(cmd)).await
+        })
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        Box::pin(async move {
+            let _permit = self.permits.acquire().await.map_err(closed_error)?;
+            within(
+                self.command_timeout,
+                self.inner.req_packed_commands(cmd, offset, count),
+            )
+            .await
+        })
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.get_db()
+    }
+}
+
+fn closed_error(_: tokio::sync::AcquireError) -> RedisError {
+    RedisError::from((ErrorKind::Client, "Redis command limiter is closed"))
 }
 
 fn create_redis_client() -> RedisClient {
@@ -20,20 +130,313 @@ fn create_redis_client() -> RedisClient {
     })
 }
 
+/// Bounds how long acquiring a connection may take, so an unreachable Redis
+/// fails fast instead of waiting out the OS TCP timeout.
+#[derive(Debug, Clone)]
+pub struct RedisConnectConfig {
+    /// Per-attempt limit on establishing the connection.
+    pub connect_timeout: Duration,
+    /// Total attempts, including the first. Only I/O failures and timeouts
+    /// are retried.
+    pub max_attempts: u32,
+    pub retry_delay: Duration,
+    /// Redis commands allowed in flight at once across all connections.
+    pub max_in_flight: usize,
+    /// Limit on each command once sent, separate from `connect_timeout`.
+    pub command_timeout: Duration,
+}
+
+impl Default for RedisConnectConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(2),
+            max_attempts: 3,
+            retry_delay: Duration::from_millis(100),
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            command_timeout: DEFAULT_COMMAND_TIMEOUT,
+        }
+    }
+}
+
 #[derive(Component)]
 #[shaku(interface = RedisConnection)]
 pub struct RedisConnectionManager {
     #[shaku(default = create_redis_client())]
     client: RedisClient,
+    #[shaku(default)]
+    config: RedisConnectConfig,
+    #[shaku(default = Arc::new(Semaphore::new(DEFAULT_MAX_IN_FLIGHT)))]
+    permits: Arc<Semaphore>,
+}
+
+impl RedisConnectionManager {
+    pub fn new(client: RedisClient) -> Self {
+        Self {
+            client,
+            config: RedisConnectConfig::default(),
+            permits: Arc::new(Semaphore::new(DEFAULT_MAX_IN_FLIGHT)),
+        }
+    }
+
+    pub fn with_config(mut self, config: RedisConnectConfig) -> Self {
+        self.permits = Arc::new(Semaphore::new(config.max_in_flight.max(1)));
+        self.config = config;
+        self
+    }
 }
 
 #[async_trait]
 impl RedisConnection for RedisConnectionManager {
-    async fn get_connection(&self) -> RedisResult<MultiplexedConnection> {
-        self.client.get_multiplexed_async_connection().await
+    async fn get_connection(&self) -> RedisResult<ThrottledConnection> {
+        let attempts = self.config.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            let result = tokio::time::timeout(
+                self.config.connect_timeout,
+                self.client.get_multiplexed_async_connection(),
+            )
+            .await
+            .unwrap_or_else(|_| Err(connect_timeout_error(self.config.connect_timeout)));
+
+            match result {
+                Err(err) if err.is_io_error() && attempt < attempts => {
+                    warn!(
+                        "Redis connection attempt {}/{} failed: {}",
+                        attempt, attempts, err
+                    );
+                    tokio::time::sleep(self.config.retry_delay).await;
+                    attempt += 1;
+                }
+                result => {
+                    return result.map(|conn| {
+                        ThrottledConnection::new(conn, self.permits.clone())
+                            .with_command_timeout(self.config.command_timeout)
+                    })
+                }
+            }
+        }
     }
 }
 
+fn connect_timeout_error(limit: Duration) -> RedisError {
+    std::io::Error::new(
+        std::io::ErrorKind::TimedOut,
+        format!("timeout: no connection within {:?}", limit),
+    )
+    .into()
+}
+
+fn command_timeout_error(limit: Duration) -> RedisError {
+    std::io::Error::new(
+        std::io::ErrorKind::TimedOut,
+        format!("command timeout: no reply within {:?}", limit),
+    )
+    .into()
+}
+
 fn sanitize_redis_url(url: &str) -> String {
-    url.split('@').last().unwrap_or(url).to_string()
+    url.rsplit('@').next().unwrap_or(url).to_string()
+}
+
+/// Describes `err` if it is a Redis Cluster redirection (`MOVED`/`ASK`) or a
+/// cross-slot rejection. The single-node client cannot follow these, so
+/// callers surface them as a "cluster not supported" error instead of a
+/// generic backend failure.
+pub fn cluster_redirection(err: &RedisError) -> Option<String> {
+    let reason = match err.kind() {
+        ErrorKind::Server(ServerErrorKind::Moved) | ErrorKind::Server(ServerErrorKind::Ask) => {
+            match err.redirect_node() {
+                Some((addr, slot)) => format!("slot {} is served by {}", slot, addr),
+                None => "request was redirected to another node".to_string(),
+            }
+        }
+        ErrorKind::Server(ServerErrorKind::CrossSlot) => {
+            "keys in one request hash to different slots".to_string()
+        }
+        _ => return None,
+    };
+    Some(format!(
+        "{} (point REDIS_URL at a standalone, non-cluster Redis endpoint)",
+        reason
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moved_and_ask_are_reported_as_cluster_redirections() {
+        for kind in [ServerErrorKind::Moved, ServerErrorKind::Ask] {
+            let err = RedisError::from((
+                ErrorKind::Server(kind),
+                "redirect",
+                "3999 10.0.0.2:6381".to_string(),
+            ));
+            assert_eq!(
+                cluster_redirection(&err).as_deref(),
+                Some(
+                    "slot 3999 is served by 10.0.0.2:6381 \
+                     (point REDIS_URL at a standalone, non-cluster Redis endpoint)"
+                )
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn unresponsive_endpoint_times_out_promptly() {
+        // Accepts TCP connections but never answers the handshake.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("redis://{}", listener.local_addr().unwrap());
+        let manager = RedisConnectionManager::new(RedisClient::open(url).unwrap()).with_config(
+            RedisConnectConfig {
+                connect_timeout: Duration::from_millis(100),
+                max_attempts: 2,
+                retry_delay: Duration::from_millis(10),
+                ..RedisConnectConfig::default()
+            },
+        );
+
+        let started = std::time::Instant::now();
+        let err = match manager.get_connection().await {
+            Ok(_) => panic!("expected a timeout"),
+            Err(err) => err,
+        };
+
+        assert!(err.is_timeout(), "expected timeout, got {}", err);
+        assert!(err.to_string().starts_with("timeout"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        drop(listener);
+    }
+
+    /// Answers every command after a short delay, tracking peak concurrency.
+    #[derive(Clone, Default)]
+    struct CountingConnection {
+        in_flight: Arc<std::sync::atomic::AtomicUsize>,
+        peak: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl ConnectionLike for CountingConnection {
+        fn req_packed_command<'a>(&'a mut self, _cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+            use std::sync::atomic::Ordering;
+            Box::pin(async move {
+                let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(Value::Okay)
+            })
+        }
+
+        fn req_packed_commands<'a>(
+            &'a mut self,
+            _cmd: &'a Pipeline,
+            _offset: usize,
+            _count: usize,
+        ) -> RedisFuture<'a, Vec<Value>> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+
+        fn get_db(&self) -> i64 {
+            0
+        }
+    }
+
+    #[tokio::test]
+    async fn in_flight_commands_never_exceed_limit() {
+        let inner = CountingConnection::default();
+        let permits = Arc::new(Semaphore::new(3));
+        let tasks: Vec<_> = (0..20)
+            .map(|_| {
+                let mut conn = ThrottledConnection::new(inner.clone(), permits.clone());
+                tokio::spawn(async move {
+                    let _: () = redis::cmd("PING").query_async(&mut conn).await.unwrap();
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(inner.peak.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    /// Stands in for a Lua script that keeps the server busy.
+    #[derive(Clone)]
+    struct SlowConnection(Duration);
+
+    impl ConnectionLike for SlowConnection {
+        fn req_packed_command<'a>(&'a mut self, _cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+            Box::pin(async move {
+                tokio::time::sleep(self.0).await;
+                Ok(Value::Okay)
+            })
+        }
+
+        fn req_packed_commands<'a>(
+            &'a mut self,
+            _cmd: &'a Pipeline,
+            _offset: usize,
+            _count: usize,
+        ) -> RedisFuture<'a, Vec<Value>> {
+            Box::pin(async move {
+                tokio::time::sleep(self.0).await;
+                Ok(Vec::new())
+            })
+        }
+
+        fn get_db(&self) -> i64 {
+            0
+        }
+    }
+
+    #[tokio::test]
+    async fn slow_command_hits_command_timeout() {
+        let permits = Arc::new(Semaphore::new(1));
+        let mut conn = ThrottledConnection::new(SlowConnection(Duration::from_secs(5)), permits)
+            .with_command_timeout(Duration::from_millis(50));
+
+        let started = std::time::Instant::now();
+        let err = redis::Script::new("return 1")
+            .invoke_async::<_, ()>(&mut conn)
+            .await
+            .unwrap_err();
+
+        assert!(err.is_timeout(), "expected timeout, got {}", err);
+        assert!(err.to_string().contains("command timeout"));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn fast_command_is_unaffected_by_command_timeout() {
+        let permits = Arc::new(Semaphore::new(1));
+        let mut conn = ThrottledConnection::new(SlowConnection(Duration::from_millis(1)), permits)
+            .with_command_timeout(Duration::from_secs(1));
+
+        let _: () = redis::cmd("PING").query_async(&mut conn).await.unwrap();
+    }
+
+    #[test]
+    fn component_databases_come_from_the_environment() {
+        for (value, expected) in [
+            ("3", Some(3)),
+            (" 0 ", Some(0)),
+            ("-1", None),
+            ("db3", None),
+        ] {
+            std::env::set_var("REDIS_DB_PARSE_TEST", value);
+            assert_eq!(
+                redis_db_from_env("REDIS_DB_PARSE_TEST"),
+                expected,
+                "{value}"
+            );
+        }
+        assert_eq!(redis_db_from_env("REDIS_DB_PARSE_TEST_UNSET"), None);
+    }
+
+    #[test]
+    fn other_errors_are_not_cluster_redirections() {
+        let err = RedisError::from((ErrorKind::Io, "connection refused"));
+        assert_eq!(cluster_redirection(&err), None);
+    }
 }
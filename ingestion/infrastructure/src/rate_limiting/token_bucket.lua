@@ -0,0 +1,52 @@
+-- token_bucket.lua
+--
+-- Token-bucket variant of limiter.lua: each window is a bucket of `limit`
+-- tokens refilled continuously at `limit` per `duration`. Stores two fields
+-- per window regardless of traffic.
+--
+-- Keys and ARGV use the same layout as limiter.lua; each KEYS[i] is a hash
+-- with `tokens` and `updated_at` (ms). The request id is unused.
+-- Returns {allowed, retry_after_ms} like limiter.lua; an empty bucket is
+-- ready again once it has refilled to one token.
+
+local redis_time = redis.call('TIME')
+local now_millis = math.floor(((redis_time[1] * 1000000) + redis_time[2]) / 1000)
+
+local remaining = {}
+local denied = false
+local retry_after_millis = 0
+for i = 1, #KEYS do
+    local limit = tonumber(ARGV[(i - 1) * 2 + 1])
+    local duration_millis = tonumber(ARGV[(i - 1) * 2 + 2]) * 1000
+
+    local state = redis.call('HMGET', KEYS[i], 'tokens', 'updated_at')
+    local tokens = tonumber(state[1]) or limit
+    local updated_at = tonumber(state[2]) or now_millis
+    local elapsed = math.max(0, now_millis - updated_at)
+    tokens = math.min(limit, tokens + elapsed * limit / duration_millis)
+
+    if tokens < 1 then
+        denied = true
+        -- A bucket with no tokens never refills; report the window length.
+        local window_wait = duration_millis
+        if limit > 0 then
+            window_wait = math.ceil((1 - tokens) * duration_millis / limit)
+        end
+        if window_wait > retry_after_millis then
+            retry_after_millis = window_wait
+        end
+    end
+    remaining[i] = tokens - 1
+end
+
+if denied then
+    return {0, retry_after_millis} -- Denied
+end
+
+for i = 1, #KEYS do
+    local duration_millis = tonumber(ARGV[(i - 1) * 2 + 2]) * 1000
+    redis.call('HSET', KEYS[i], 'tokens', tostring(remaining[i]), 'updated_at', now_millis)
+    redis.call('PEXPIRE', KEYS[i], duration_millis + 5000)
+end
+
+return {1, 0} -- Allowed
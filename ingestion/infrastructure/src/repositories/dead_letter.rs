@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use ingestion_application::ports::{DeadLetterSink, RepositoryError};
+use ingestion_domain::Tick;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::filesystem::FileSystem;
+
+/// Writes each failed batch to its own JSON Lines file in `dir`, one tick
+/// per line, named `{symbol}_{UTC timestamp}_{id}.jsonl`. Files appear
+/// atomically, so a re-ingest tool never sees a half-written batch.
+pub struct JsonlDeadLetterSink {
+    dir: PathBuf,
+    fs: Arc<dyn FileSystem>,
+}
+
+impl JsonlDeadLetterSink {
+    pub fn new(dir: PathBuf, fs: Arc<dyn FileSystem>) -> Self {
+        Self { dir, fs }
+    }
+
+    /// Reads back a batch written by [`DeadLetterSink::write_batch`].
+    pub fn read_batch(fs: &dyn FileSystem, path: &Path) -> io::Result<Vec<Tick>> {
+        let mut ticks = Vec::new();
+        for line in BufReader::new(fs.open(path)?).lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            ticks.push(serde_json::from_str(&line).map_err(io::Error::other)?);
+        }
+        Ok(ticks)
+    }
+
+    fn batch_path(&self, symbol: &str) -> PathBuf {
+        let id = Uuid::new_v4().simple().to_string();
+        self.dir.join(format!(
+            "{}_{}_{}.jsonl",
+            symbol,
+            Utc::now().format("%Y%m%dT%H%M%S%3f"),
+            &id[..8]
+        ))
+    }
+}
+
+#[async_trait]
+impl DeadLetterSink for JsonlDeadLetterSink {
+    async fn write_batch(&self, symbol: &str, ticks: &[Tick]) -> Result<(), RepositoryError> {
+        let path = self.batch_path(symbol);
+        let temp = path.with_extension("jsonl.tmp");
+        let mut writer = self.fs.create(&temp)?;
+        for tick in ticks {
+            serde_json::to_writer(&mut writer, tick)
+                .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        drop(writer);
+        self.fs.rename(&temp, &path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::InMemoryFileSystem;
+    use chrono::TimeZone;
+    use ingestion_domain::test_support::sample_tick;
+
+    #[tokio::test]
+    async fn batch_round_trips_through_its_file() {
+        let fs = InMemoryFileSystem::new();
+        let sink =
+            JsonlDeadLetterSink::new(PathBuf::from("/data/dead_letter"), Arc::new(fs.clone()));
+        let at = |minute| Utc.with_ymd_and_hms(2025, 1, 2, 10, minute, 0).unwrap();
+        let ticks = vec![sample_tick("NQ", at(0)), sample_tick("NQ", at(1))];
+
+        sink.write_batch("NQ", &ticks).await.unwrap();
+
+        let paths = fs.paths();
+        assert_eq!(paths.len(), 1);
+        let name = paths[0].file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("NQ_") && name.ends_with(".jsonl"));
+        assert_eq!(
+            JsonlDeadLetterSink::read_batch(&fs, &paths[0]).unwrap(),
+            ticks
+        );
+    }
+}
@@ -0,0 +1,381 @@
+use crate::filesystem::FileSystem;
+use crate::repositories::naming::ParquetFileName;
+use crate::repositories::parquet::{ParquetWriter, PriceFormat};
+use arrow::array::{
+    Array, ArrayRef, Decimal128Array, Decimal128Builder, ListArray, ListBuilder, RecordBatch,
+    StringArray, TimestampMicrosecondArray, UInt32Array, UInt32Builder,
+};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use async_trait::async_trait;
+use chrono::{DateTime, Timelike, Utc};
+use ingestion_application::ports::{DepthRepository, RepositoryError};
+use ingestion_domain::{DepthLevel, MarketDepth};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use rust_decimal::Decimal;
+use shaku::Component;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Format of the level price lists: the tick files' default.
+fn price_format() -> PriceFormat {
+    PriceFormat::default()
+}
+
+/// Writes depth snapshots to hourly files named like tick files, one row per
+/// snapshot with each side's levels as list columns. `output_dir` must not be
+/// the tick directory, or gap detection would read depth files as ticks.
+#[derive(Component)]
+#[shaku(interface = DepthRepository)]
+pub struct ParquetDepthRepository {
+    #[shaku(inject)]
+    fs: Arc<dyn FileSystem>,
+
+    output_dir: PathBuf,
+    writer: Arc<Mutex<Option<ParquetWriter>>>,
+    current_hour: Arc<Mutex<Option<DateTime<Utc>>>>,
+}
+
+impl ParquetDepthRepository {
+    pub fn new(output_dir: PathBuf, fs: Arc<dyn FileSystem>) -> Self {
+        Self {
+            fs,
+            output_dir,
+            writer: Arc::new(Mutex::new(None)),
+            current_hour: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn create_schema() -> Arc<Schema> {
+        let price_list = || {
+            DataType::List(Arc::new(Field::new(
+                "item",
+                price_format().data_type(),
+                true,
+            )))
+        };
+        let size_list = || DataType::List(Arc::new(Field::new("item", DataType::UInt32, true)));
+
+        Arc::new(Schema::new(vec![
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+                false,
+            ),
+            Field::new("symbol", DataType::Utf8, false),
+            Field::new("bid_prices", price_list(), false),
+            Field::new("bid_sizes", size_list(), false),
+            Field::new("ask_prices", price_list(), false),
+            Field::new("ask_sizes", size_list(), false),
+        ]))
+    }
+
+    fn same_hour(a: DateTime<Utc>, b: DateTime<Utc>) -> bool {
+        a.date_naive() == b.date_naive() && a.hour() == b.hour()
+    }
+
+    async fn rotate_writer(
+        &self,
+        symbol: &str,
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let mut writer_guard = self.writer.lock().await;
+        if let Some(writer) = writer_guard.take() {
+            writer
+                .close()
+                .map_err(|e| RepositoryError::FileRotationError(e.to_string()))?;
+        }
+
+        let file_path = self
+            .output_dir
+            .join(ParquetFileName::for_timestamp(symbol, timestamp).file_name());
+        info!("Creating new depth file: {}", file_path.display());
+        let file = self
+            .fs
+            .create_locked(&file_path)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::WouldBlock => {
+                    RepositoryError::FileLocked(file_path.display().to_string())
+                }
+                _ => RepositoryError::IoError(e),
+            })?;
+
+        let writer = ArrowWriter::try_new(file, Self::create_schema(), None)
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+        *writer_guard = Some(writer);
+        *self.current_hour.lock().await = Some(timestamp);
+        Ok(())
+    }
+
+    async fn write_segment(&self, snapshots: &[MarketDepth]) -> Result<(), RepositoryError> {
+        let first = &snapshots[0];
+        let last_hour = *self.current_hour.lock().await;
+        if !last_hour.is_some_and(|last| Self::same_hour(last, first.timestamp())) {
+            self.rotate_writer(first.symbol(), first.timestamp())
+                .await?;
+        }
+
+        let batch = Self::snapshots_to_record_batch(snapshots)?;
+        let mut writer_guard = self.writer.lock().await;
+        let writer = writer_guard.as_mut().ok_or_else(|| {
+            RepositoryError::SerializationError("Writer not initialized".to_string())
+        })?;
+        writer
+            .write(&batch)
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+        info!("Wrote {} depth snapshots to parquet", snapshots.len());
+        Ok(())
+    }
+
+    fn snapshots_to_record_batch(
+        snapshots: &[MarketDepth],
+    ) -> Result<RecordBatch, RepositoryError> {
+        let timestamps: Vec<i64> = snapshots
+            .iter()
+            .map(|s| s.timestamp().timestamp_micros())
+            .collect();
+        let symbols: Vec<&str> = snapshots.iter().map(|s| s.symbol()).collect();
+
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(TimestampMicrosecondArray::from(timestamps).with_timezone("UTC")),
+            Arc::new(StringArray::from(symbols)),
+            Arc::new(price_list(snapshots.iter().map(MarketDepth::bids))?),
+            Arc::new(size_list(snapshots.iter().map(MarketDepth::bids))),
+            Arc::new(price_list(snapshots.iter().map(MarketDepth::asks))?),
+            Arc::new(size_list(snapshots.iter().map(MarketDepth::asks))),
+        ];
+
+        RecordBatch::try_new(Self::create_schema(), arrays)
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl DepthRepository for ParquetDepthRepository {
+    async fn save_batch(&self, snapshots: Vec<MarketDepth>) -> Result<(), RepositoryError> {
+        if snapshots.is_empty() {
+            warn!("Attempted to save empty depth batch, skipping");
+            return Ok(());
+        }
+
+        for segment in snapshots.chunk_by(|a, b| Self::same_hour(a.timestamp(), b.timestamp())) {
+            self.write_segment(segment).await?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), RepositoryError> {
+        if let Some(writer) = self.writer.lock().await.as_mut() {
+            writer
+                .flush()
+                .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> Result<(), RepositoryError> {
+        if let Some(writer) = self.writer.lock().await.take() {
+            writer
+                .close()
+                .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+            info!("Shutdown: Closed depth writer");
+        }
+        Ok(())
+    }
+}
+
+fn price_list<'a>(
+    sides: impl Iterator<Item = &'a [DepthLevel]>,
+) -> Result<ListArray, RepositoryError> {
+    let format = price_format();
+    let values = Decimal128Builder::new()
+        .with_precision_and_scale(format.precision(), format.scale() as i8)
+        .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+    let mut builder = ListBuilder::new(values);
+    for levels in sides {
+        for level in levels {
+            builder
+                .values()
+                .append_value(format.to_unscaled(level.price)?);
+        }
+        builder.append(true);
+    }
+    Ok(builder.finish())
+}
+
+fn size_list<'a>(sides: impl Iterator<Item = &'a [DepthLevel]>) -> ListArray {
+    let mut builder = ListBuilder::new(UInt32Builder::new());
+    for levels in sides {
+        for level in levels {
+            builder.values().append_value(level.size);
+        }
+        builder.append(true);
+    }
+    builder.finish()
+}
+
+/// Reads depth snapshots back out of files written by [`ParquetDepthRepository`].
+pub struct ParquetDepthReader;
+
+impl ParquetDepthReader {
+    pub fn read_file(path: &Path) -> Result<Vec<MarketDepth>, RepositoryError> {
+        let file = File::open(path)?;
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .and_then(|builder| builder.build())
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+
+        let mut snapshots = Vec::new();
+        for batch in reader {
+            let batch = batch.map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+            snapshots.extend(Self::record_batch_to_snapshots(&batch)?);
+        }
+        Ok(snapshots)
+    }
+
+    fn record_batch_to_snapshots(batch: &RecordBatch) -> Result<Vec<MarketDepth>, RepositoryError> {
+        let invalid = |message: String| RepositoryError::SerializationError(message);
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .ok_or_else(|| invalid(format!("Missing column '{}'", name)))
+        };
+        let timestamps = column("timestamp")?
+            .as_any()
+            .downcast_ref::<TimestampMicrosecondArray>()
+            .ok_or_else(|| invalid("Mistyped column 'timestamp'".to_string()))?;
+        let symbols = column("symbol")?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| invalid("Mistyped column 'symbol'".to_string()))?;
+        let lists = |name: &str| {
+            column(name)?
+                .as_any()
+                .downcast_ref::<ListArray>()
+                .cloned()
+                .ok_or_else(|| invalid(format!("Mistyped column '{}'", name)))
+        };
+        let (bid_prices, bid_sizes) = (lists("bid_prices")?, lists("bid_sizes")?);
+        let (ask_prices, ask_sizes) = (lists("ask_prices")?, lists("ask_sizes")?);
+
+        (0..batch.num_rows())
+            .map(|row| {
+                let timestamp =
+                    DateTime::from_timestamp_micros(timestamps.value(row)).ok_or_else(|| {
+                        invalid(format!("Invalid timestamp {}", timestamps.value(row)))
+                    })?;
+                MarketDepth::new(
+                    timestamp,
+                    symbols.value(row).to_string(),
+                    levels(&bid_prices, &bid_sizes, row)?,
+                    levels(&ask_prices, &ask_sizes, row)?,
+                )
+                .map_err(|e| invalid(e.to_string()))
+            })
+            .collect()
+    }
+}
+
+fn levels(
+    prices: &ListArray,
+    sizes: &ListArray,
+    row: usize,
+) -> Result<Vec<DepthLevel>, RepositoryError> {
+    let prices = prices.value(row);
+    let sizes = sizes.value(row);
+    let (prices, sizes) = match (
+        prices.as_any().downcast_ref::<Decimal128Array>(),
+        sizes.as_any().downcast_ref::<UInt32Array>(),
+    ) {
+        (Some(prices), Some(sizes)) if prices.len() == sizes.len() => (prices, sizes),
+        _ => {
+            return Err(RepositoryError::SerializationError(format!(
+                "Mismatched depth levels in row {}",
+                row
+            )))
+        }
+    };
+
+    Ok((0..prices.len())
+        .map(|idx| {
+            DepthLevel::new(
+                Decimal::from_i128_with_scale(prices.value(idx), prices.scale() as u32),
+                sizes.value(idx),
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{InMemoryFileSystem, StdFileSystem};
+    use chrono::TimeZone;
+
+    fn snapshot(minute: u32, depth: usize) -> MarketDepth {
+        let bids = (0..depth)
+            .map(|level| {
+                DepthLevel::new(
+                    Decimal::new(1_600_025, 2) - Decimal::from(level),
+                    10 + level as u32,
+                )
+            })
+            .collect();
+        let asks = (0..depth)
+            .map(|level| {
+                DepthLevel::new(
+                    Decimal::new(1_600_050, 2) + Decimal::from(level),
+                    20 + level as u32,
+                )
+            })
+            .collect();
+        MarketDepth::new(
+            Utc.with_ymd_and_hms(2025, 1, 2, 10, minute, 0).unwrap(),
+            "NQ".to_string(),
+            bids,
+            asks,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn depth_levels_round_trip() {
+        let dir = std::env::temp_dir().join(format!("parquet-depth-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let repository = ParquetDepthRepository::new(dir.clone(), Arc::new(StdFileSystem));
+        let snapshots = vec![snapshot(0, 3), snapshot(1, 5), snapshot(2, 0)];
+
+        repository.save_batch(snapshots.clone()).await.unwrap();
+        repository.shutdown().await.unwrap();
+
+        let read = ParquetDepthReader::read_file(&dir.join("NQ_20250102_10.parquet")).unwrap();
+        assert_eq!(read, snapshots);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn price_overflowing_the_format_is_an_error() {
+        let repository = ParquetDepthRepository::new(
+            PathBuf::from("/data"),
+            Arc::new(InMemoryFileSystem::new()),
+        );
+        let level = DepthLevel::new(Decimal::new(1_000_000, 0), 1);
+        let overflowing = MarketDepth::new(
+            Utc.with_ymd_and_hms(2025, 1, 2, 10, 0, 0).unwrap(),
+            "NQ".to_string(),
+            vec![level],
+            vec![level],
+        )
+        .unwrap();
+
+        // 1,000,000.0000 needs 11 digits at Decimal128(10, 4).
+        let result = repository.save_batch(vec![overflowing]).await;
+
+        assert!(matches!(
+            result,
+            Err(RepositoryError::SerializationError(_))
+        ));
+    }
+}
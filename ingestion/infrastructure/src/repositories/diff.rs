@@ -0,0 +1,221 @@
+use crate::repositories::naming::ParquetFileName;
+use crate::repositories::reader::ParquetTickReader;
+use chrono::NaiveDate;
+use ingestion_application::ports::RepositoryError;
+use ingestion_domain::{ticks_checksum, DateRange, Tick};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// A day on which two data directories disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DayDifference {
+    MissingInA {
+        date: NaiveDate,
+        rows_in_b: usize,
+    },
+    MissingInB {
+        date: NaiveDate,
+        rows_in_a: usize,
+    },
+    RowCount {
+        date: NaiveDate,
+        a: usize,
+        b: usize,
+    },
+    /// Same row count, different tick content.
+    Checksum {
+        date: NaiveDate,
+        a: u64,
+        b: u64,
+    },
+}
+
+impl DayDifference {
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            Self::MissingInA { date, .. }
+            | Self::MissingInB { date, .. }
+            | Self::RowCount { date, .. }
+            | Self::Checksum { date, .. } => *date,
+        }
+    }
+}
+
+impl fmt::Display for DayDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingInA { date, rows_in_b } => {
+                write!(f, "{}: missing in A ({} rows in B)", date, rows_in_b)
+            }
+            Self::MissingInB { date, rows_in_a } => {
+                write!(f, "{}: missing in B ({} rows in A)", date, rows_in_a)
+            }
+            Self::RowCount { date, a, b } => write!(f, "{}: {} rows in A, {} in B", date, a, b),
+            Self::Checksum { date, a, b } => {
+                write!(f, "{}: checksums differ ({:016x} vs {:016x})", date, a, b)
+            }
+        }
+    }
+}
+
+/// Compares `symbol`'s files in directories `a` and `b` day by day over
+/// `range`, by presence and row count and, if `compare_checksums`, by tick
+/// content. Days are those in the file names. Returns the days that differ,
+/// in date order.
+pub fn diff_directories(
+    a: &Path,
+    b: &Path,
+    symbol: &str,
+    range: &DateRange,
+    compare_checksums: bool,
+) -> Result<Vec<DayDifference>, RepositoryError> {
+    let days_a = read_days(a, symbol, range)?;
+    let mut days_b = read_days(b, symbol, range)?;
+
+    let mut differences = Vec::new();
+    for (date, ticks_a) in days_a {
+        let Some(ticks_b) = days_b.remove(&date) else {
+            differences.push(DayDifference::MissingInB {
+                date,
+                rows_in_a: ticks_a.len(),
+            });
+            continue;
+        };
+        if ticks_a.len() != ticks_b.len() {
+            differences.push(DayDifference::RowCount {
+                date,
+                a: ticks_a.len(),
+                b: ticks_b.len(),
+            });
+        } else if compare_checksums {
+            let (sum_a, sum_b) = (ticks_checksum(&ticks_a), ticks_checksum(&ticks_b));
+            if sum_a != sum_b {
+                differences.push(DayDifference::Checksum {
+                    date,
+                    a: sum_a,
+                    b: sum_b,
+                });
+            }
+        }
+    }
+    differences.extend(
+        days_b
+            .into_iter()
+            .map(|(date, ticks)| DayDifference::MissingInA {
+                date,
+                rows_in_b: ticks.len(),
+            }),
+    );
+    differences.sort_by_key(DayDifference::date);
+    Ok(differences)
+}
+
+/// `symbol`'s ticks in `dir` by file date, each day sorted by timestamp.
+fn read_days(
+    dir: &Path,
+    symbol: &str,
+    range: &DateRange,
+) -> Result<BTreeMap<NaiveDate, Vec<Tick>>, RepositoryError> {
+    let mut days: BTreeMap<NaiveDate, Vec<Tick>> = BTreeMap::new();
+    for path in ParquetTickReader::new(dir.to_path_buf()).symbol_files(symbol)? {
+        let Some(date) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(ParquetFileName::parse)
+            .map(|name| name.date)
+            .filter(|date| range.contains(*date))
+        else {
+            continue;
+        };
+        days.entry(date).or_default().extend(
+            ParquetTickReader::read_file(&path)?
+                .into_iter()
+                .filter(|tick| tick.symbol() == symbol),
+        );
+    }
+    for ticks in days.values_mut() {
+        ticks.sort_by_key(Tick::sort_key);
+    }
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::StdFileSystem;
+    use crate::repositories::ParquetTickRepository;
+    use chrono::{TimeZone, Utc};
+    use ingestion_application::TickRepository;
+    use ingestion_domain::test_support::sample_tick;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, day).unwrap()
+    }
+
+    fn ticks(day: u32, count: u32) -> Vec<Tick> {
+        (0..count)
+            .map(|minute| {
+                sample_tick(
+                    "NQ",
+                    Utc.with_ymd_and_hms(2025, 1, day, 10, minute, 0).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    async fn write_fixture(batches: Vec<Vec<Tick>>) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("diff-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let repo = ParquetTickRepository::new(dir.clone(), Arc::new(StdFileSystem));
+        for batch in batches {
+            repo.save_batch(batch).await.unwrap();
+        }
+        repo.shutdown().await.unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn reports_days_that_differ() {
+        let mut changed = ticks(6, 3);
+        changed[2] = sample_tick("NQ", changed[2].timestamp() + chrono::Duration::seconds(1));
+        let a = write_fixture(vec![ticks(2, 3), ticks(3, 3), ticks(6, 3), ticks(7, 2)]).await;
+        let b = write_fixture(vec![ticks(2, 3), ticks(3, 2), ticks(6, 3), ticks(8, 1)]).await;
+        let c = write_fixture(vec![ticks(2, 3), ticks(3, 3), changed, ticks(7, 2)]).await;
+        let range = DateRange::new(date(1), date(31)).unwrap();
+
+        let differences = diff_directories(&a, &b, "NQ", &range, true).unwrap();
+        let with_content = diff_directories(&a, &c, "NQ", &range, true).unwrap();
+        let counts_only = diff_directories(&a, &c, "NQ", &range, false).unwrap();
+        for dir in [a, b, c] {
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+
+        assert_eq!(
+            differences,
+            vec![
+                DayDifference::RowCount {
+                    date: date(3),
+                    a: 3,
+                    b: 2
+                },
+                DayDifference::MissingInB {
+                    date: date(7),
+                    rows_in_a: 2
+                },
+                DayDifference::MissingInA {
+                    date: date(8),
+                    rows_in_b: 1
+                },
+            ]
+        );
+        assert_eq!(with_content.len(), 1);
+        assert!(matches!(
+            with_content[0],
+            DayDifference::Checksum { date: d, .. } if d == date(6)
+        ));
+        assert!(counts_only.is_empty());
+    }
+}
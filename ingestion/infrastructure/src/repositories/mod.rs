@@ -1,3 +1,13 @@
+pub mod dead_letter;
+pub mod depth;
+pub mod diff;
+pub mod naming;
 pub mod parquet;
+pub mod reader;
 
-pub use parquet::ParquetTickRepository;
+pub use dead_letter::JsonlDeadLetterSink;
+pub use depth::{ParquetDepthReader, ParquetDepthRepository};
+pub use diff::{diff_directories, DayDifference};
+pub use naming::ParquetFileName;
+pub use parquet::{FileDateMode, FileRotation, ParquetTickRepository, PriceFormat};
+pub use reader::ParquetTickReader;
@@ -0,0 +1,299 @@
+use chrono::{DateTime, NaiveDate, Timelike, Utc};
+
+const EXTENSION: &str = ".parquet";
+
+/// Name of an hourly tick file, `{symbol}_{YYYYMMDD}_{HH}.parquet`, of a
+/// sub-hour segment, `{symbol}_{YYYYMMDD}_{HHMM}.parquet`, of a daily one,
+/// `{symbol}_{YYYYMMDD}.parquet` (`hour` is `None`), or of one part of a
+/// size-rotated day, `{symbol}_{YYYYMMDD}_p{NNN}.parquet`. An hour or
+/// segment on a UTC date other than the file's date (an evening session
+/// filed under its trade date) is prefixed with that UTC date,
+/// `{symbol}_{YYYYMMDD}_{utcYYYYMMDD}{HH}.parquet`.
+///
+/// Parsing splits from the right, so the last `_`-segments are always the
+/// date and hour and everything before them is the symbol. Symbols such as
+/// `NQ_H5` round-trip unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParquetFileName {
+    pub symbol: String,
+    pub date: NaiveDate,
+    pub hour: Option<u32>,
+    /// Start minute of a segment that doesn't begin on the hour, or of any
+    /// segment under sub-hour rotation; `hour` is then set too.
+    pub minute: Option<u32>,
+    /// Set only on size-rotated files; `hour` is then `None`.
+    pub part: Option<u32>,
+    /// UTC date of `hour` when it differs from `date`; `hour` is then set.
+    pub utc_date: Option<NaiveDate>,
+}
+
+impl ParquetFileName {
+    pub fn for_timestamp(symbol: &str, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            date: timestamp.date_naive(),
+            hour: Some(timestamp.hour()),
+            minute: None,
+            part: None,
+            utc_date: None,
+        }
+    }
+
+    /// File for the segment starting at `hour:minute` on `date`.
+    pub fn segment(symbol: &str, date: NaiveDate, hour: u32, minute: u32) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            date,
+            hour: Some(hour),
+            minute: Some(minute),
+            part: None,
+            utc_date: None,
+        }
+    }
+
+    pub fn daily(symbol: &str, date: NaiveDate) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            date,
+            hour: None,
+            minute: None,
+            part: None,
+            utc_date: None,
+        }
+    }
+
+    pub fn part(symbol: &str, date: NaiveDate, part: u32) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            date,
+            hour: None,
+            minute: None,
+            part: Some(part),
+            utc_date: None,
+        }
+    }
+
+    pub fn parse(filename: &str) -> Option<Self> {
+        let stem = filename.strip_suffix(EXTENSION)?;
+        Self::parse_hourly(stem)
+            .or_else(|| Self::parse_part(stem))
+            .or_else(|| Self::parse_daily(stem))
+    }
+
+    fn parse_hourly(stem: &str) -> Option<Self> {
+        let mut parts = stem.rsplitn(3, '_');
+        let time_str = parts.next()?;
+        let date_str = parts.next()?;
+        let symbol = parts.next().filter(|symbol| !symbol.is_empty())?;
+
+        if !matches!(time_str.len(), 2 | 4 | 10 | 12)
+            || !time_str.bytes().all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        let date = parse_date(date_str)?;
+        let (utc_date, time_str) = match time_str.len() {
+            10 | 12 => (Some(parse_date(&time_str[..8])?), &time_str[8..]),
+            _ => (None, time_str),
+        };
+        let hour = time_str[..2]
+            .parse::<u32>()
+            .ok()
+            .filter(|hour| *hour < 24)?;
+        let minute = match time_str.get(2..).filter(|minute| !minute.is_empty()) {
+            Some(minute) => Some(minute.parse::<u32>().ok().filter(|minute| *minute < 60)?),
+            None => None,
+        };
+
+        Some(Self {
+            symbol: symbol.to_string(),
+            date,
+            hour: Some(hour),
+            minute,
+            part: None,
+            utc_date,
+        })
+    }
+
+    fn parse_part(stem: &str) -> Option<Self> {
+        let mut parts = stem.rsplitn(3, '_');
+        let part_str = parts.next()?.strip_prefix('p')?;
+        let date_str = parts.next()?;
+        let symbol = parts.next().filter(|symbol| !symbol.is_empty())?;
+
+        if part_str.len() < 3 || !part_str.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some(Self::part(
+            symbol,
+            parse_date(date_str)?,
+            part_str.parse().ok()?,
+        ))
+    }
+
+    fn parse_daily(stem: &str) -> Option<Self> {
+        let (symbol, date_str) = stem.rsplit_once('_')?;
+        if symbol.is_empty() {
+            return None;
+        }
+        Some(Self::daily(symbol, parse_date(date_str)?))
+    }
+
+    pub fn file_name(&self) -> String {
+        let utc_date = self
+            .utc_date
+            .map(|date| date.format("%Y%m%d").to_string())
+            .unwrap_or_default();
+        match (self.hour, self.part) {
+            (Some(hour), _) if self.minute.is_some() => format!(
+                "{}_{}_{}{:02}{:02}{}",
+                self.symbol,
+                self.date.format("%Y%m%d"),
+                utc_date,
+                hour,
+                self.minute.unwrap_or_default(),
+                EXTENSION
+            ),
+            (Some(hour), _) => format!(
+                "{}_{}_{}{:02}{}",
+                self.symbol,
+                self.date.format("%Y%m%d"),
+                utc_date,
+                hour,
+                EXTENSION
+            ),
+            (None, Some(part)) => format!(
+                "{}_{}_p{:03}{}",
+                self.symbol,
+                self.date.format("%Y%m%d"),
+                part,
+                EXTENSION
+            ),
+            (None, None) => format!(
+                "{}_{}{}",
+                self.symbol,
+                self.date.format("%Y%m%d"),
+                EXTENSION
+            ),
+        }
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    if value.len() != 8 {
+        return None;
+    }
+    NaiveDate::parse_from_str(value, "%Y%m%d").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, d).unwrap()
+    }
+
+    #[test]
+    fn round_trips_plain_and_underscore_symbols() {
+        let timestamp = Utc.with_ymd_and_hms(2025, 1, 2, 7, 30, 0).unwrap();
+        for symbol in ["NQ", "NQ_H5", "MES_Z5_CONT"] {
+            let name = ParquetFileName::for_timestamp(symbol, timestamp);
+            let file_name = name.file_name();
+
+            assert_eq!(file_name, format!("{}_20250102_07.parquet", symbol));
+            assert_eq!(ParquetFileName::parse(&file_name), Some(name));
+        }
+    }
+
+    #[test]
+    fn round_trips_segment_names() {
+        for symbol in ["NQ", "NQ_H5"] {
+            let name = ParquetFileName::segment(symbol, date(2), 9, 45);
+            let file_name = name.file_name();
+
+            assert_eq!(file_name, format!("{}_20250102_0945.parquet", symbol));
+            assert_eq!(ParquetFileName::parse(&file_name), Some(name));
+        }
+    }
+
+    #[test]
+    fn round_trips_daily_names() {
+        for symbol in ["NQ", "NQ_H5"] {
+            let name = ParquetFileName::daily(symbol, date(2));
+            let file_name = name.file_name();
+
+            assert_eq!(file_name, format!("{}_20250102.parquet", symbol));
+            assert_eq!(ParquetFileName::parse(&file_name), Some(name));
+        }
+    }
+
+    #[test]
+    fn round_trips_part_names() {
+        for symbol in ["NQ", "NQ_H5"] {
+            let name = ParquetFileName::part(symbol, date(2), 7);
+            let file_name = name.file_name();
+
+            assert_eq!(file_name, format!("{}_20250102_p007.parquet", symbol));
+            assert_eq!(ParquetFileName::parse(&file_name), Some(name));
+        }
+        assert_eq!(
+            ParquetFileName::parse("NQ_20250102_p1234.parquet").and_then(|name| name.part),
+            Some(1234)
+        );
+    }
+
+    #[test]
+    fn round_trips_names_on_another_utc_date() {
+        for (name, file_name) in [
+            (
+                ParquetFileName {
+                    utc_date: Some(date(2)),
+                    ..ParquetFileName::for_timestamp(
+                        "NQ_H5",
+                        Utc.with_ymd_and_hms(2025, 1, 3, 23, 0, 0).unwrap(),
+                    )
+                },
+                "NQ_H5_20250103_2025010223.parquet",
+            ),
+            (
+                ParquetFileName {
+                    utc_date: Some(date(2)),
+                    ..ParquetFileName::segment("NQ", date(3), 23, 45)
+                },
+                "NQ_20250103_202501022345.parquet",
+            ),
+        ] {
+            assert_eq!(name.file_name(), file_name);
+            assert_eq!(ParquetFileName::parse(file_name), Some(name));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_names() {
+        for file_name in [
+            "_20250102_00.parquet",
+            "NQ_2025011_00.parquet",
+            "NQ_20250101_7.parquet",
+            "NQ_20250101_24.parquet",
+            "NQ_20250101_10.csv",
+            "20250101_10.parquet",
+            "_20250102.parquet",
+            "NQ_2025010.parquet",
+            "NQ_20250102_p7.parquet",
+            "NQ_20250102_pabc.parquet",
+            "NQ_20250102_1060.parquet",
+            "NQ_20250102_2400.parquet",
+            "NQ_20250102_930.parquet",
+            "NQ_20250102_2025013223.parquet",
+            "NQ_20250102_2025010124.parquet",
+        ] {
+            assert_eq!(ParquetFileName::parse(file_name), None, "{}", file_name);
+        }
+        assert_eq!(
+            ParquetFileName::parse("NQ_20250101_10.parquet").map(|name| name.date),
+            Some(date(1))
+        );
+    }
+}
@@ -1,93 +1,857 @@
+use crate::filesystem::FileSystem;
+use crate::repositories::naming::ParquetFileName;
+use crate::repositories::reader::ParquetTickReader;
 use arrow::array::{
-    ArrayRef, Decimal128Array, RecordBatch, StringArray, TimestampMicrosecondArray, UInt32Array,
+    ArrayRef, Decimal128Array, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+    UInt32Array, UInt64Array,
 };
-use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit, DECIMAL128_MAX_PRECISION};
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDate, Timelike, Utc};
 use ingestion_application::ports::{RepositoryError, TickRepository};
-use ingestion_domain::Tick;
+use ingestion_domain::{Tick, TradingCalendar};
 use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::metadata::{KeyValue, ParquetMetaDataReader};
 use parquet::file::properties::WriterProperties;
-use rust_decimal::prelude::ToPrimitive;
+use parquet::schema::types::ColumnPath;
+use rust_decimal::{Decimal, RoundingStrategy};
 use shaku::Component;
-use std::fs::File;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 use tokio::sync::Mutex;
 use tracing::{info, warn};
 
+/// Arrow writer over whatever file handle the [`FileSystem`] hands out.
+pub type ParquetWriter = ArrowWriter<Box<dyn Write + Send>>;
+
+/// Footer key-value entries recording the price columns' decimal precision
+/// and scale, so readers can check them without inspecting the schema.
+pub const PRICE_PRECISION_KEY: &str = "ingest.price_precision";
+pub const PRICE_SCALE_KEY: &str = "ingest.price_scale";
+
+/// Batches larger than this are most likely a bug upstream, e.g. several
+/// days accumulated into one `save_batch` call.
+pub const DEFAULT_MAX_BATCH_TICKS: usize = 100_000;
+
+/// Rows a file must have buffered before a periodic flush closes its row
+/// group. Fewer stay buffered, so frequent flushes of a quiet feed do not
+/// leave the file full of tiny row groups.
+pub const DEFAULT_MIN_FLUSH_ROWS: usize = 65_536;
+
+/// Footer key-value entry naming the gateway that produced the file's ticks,
+/// as reported by `HistoricalDataGateway::source_id`.
+pub const SOURCE_KEY: &str = "ingest.source";
+
+/// Footer key-value entry on a zero-row file recording that the source had
+/// no data for that day. Gap detection counts the day as present.
+pub const NO_DATA_KEY: &str = "ingest.no_data";
+
+/// Optional nullable Int64 column holding each tick's receive latency in
+/// milliseconds.
+pub const RECV_LATENCY_COLUMN: &str = "recv_latency_ms";
+
+/// Nullable UInt64 column holding each tick's sequence number, if it has one.
+pub const SEQUENCE_COLUMN: &str = "sequence";
+
+/// Which date a tick's file is named after.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FileDateMode {
+    /// The tick's UTC calendar date.
+    #[default]
+    CalendarDate,
+    /// The session's trade date from the calendar, so an overnight session
+    /// lands in one file set. The hour in the name stays the UTC hour, and
+    /// hours on an earlier UTC date also carry that date, so an evening
+    /// hour never shares a name with the same hour of the trade date.
+    TradeDate(TradingCalendar),
+}
+
+impl FileDateMode {
+    fn date_for(&self, timestamp: DateTime<Utc>) -> NaiveDate {
+        match self {
+            Self::CalendarDate => timestamp.date_naive(),
+            Self::TradeDate(calendar) => calendar.trade_date(timestamp),
+        }
+    }
+}
+
+/// How much of a symbol's data one file holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FileRotation {
+    /// One file per hour, for live ingestion.
+    #[default]
+    Hourly,
+    /// One file per file date, for backfill: a full day's batch is written
+    /// with a single file open instead of 24.
+    Daily,
+    /// Per file date like `Daily`, but a new part file is started once the
+    /// open one reaches `max_bytes` (checked between writes, so a file ends
+    /// somewhat over). Keeps low-volume symbols from littering hour files.
+    BySize { max_bytes: u64 },
+    /// One file per `minutes`-long segment of the UTC day, counted from
+    /// midnight, e.g. 15 for four files an hour. Segments that don't start
+    /// on the hour are named `{HHMM}`. A day or more rotates like `Daily`.
+    Interval { minutes: u32 },
+}
+
+impl FileRotation {
+    /// Segment length for rotations that split the day, if any.
+    pub(crate) fn segment_minutes(self) -> Option<u32> {
+        match self {
+            FileRotation::Hourly => Some(60),
+            FileRotation::Interval { minutes } if minutes < MINUTES_PER_DAY => Some(minutes.max(1)),
+            _ => None,
+        }
+    }
+}
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Precision and scale of the `Decimal128` price columns. The default,
+/// `(10, 4)`, suits index futures but tops out below 1,000,000; higher-priced
+/// instruments need a wider precision such as `(18, 4)`, and FX typically
+/// needs a larger scale. Prices that don't fit are rejected with an error.
+/// The scale is capped at 28, the most `rust_decimal` can represent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceFormat {
+    precision: u8,
+    scale: u8,
+}
+
+impl Default for PriceFormat {
+    fn default() -> Self {
+        Self {
+            precision: 10,
+            scale: 4,
+        }
+    }
+}
+
+impl PriceFormat {
+    /// `precision` must be 1..=38 and at least `scale`, and `scale` at most
+    /// 28.
+    pub fn new(precision: u8, scale: u8) -> Result<Self, RepositoryError> {
+        if precision == 0
+            || precision > DECIMAL128_MAX_PRECISION
+            || scale > precision
+            || u32::from(scale) > Decimal::MAX_SCALE
+        {
+            return Err(RepositoryError::SerializationError(format!(
+                "invalid price format Decimal128({}, {})",
+                precision, scale
+            )));
+        }
+        Ok(Self { precision, scale })
+    }
+
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    pub(crate) fn data_type(&self) -> DataType {
+        DataType::Decimal128(self.precision, self.scale as i8)
+    }
+
+    /// Unscaled value of `price` at this scale, rounding extra decimal places
+    /// half-to-even. Exact: taken from the mantissa, never through `f64`.
+    /// Fails if the result needs more than `precision` digits, or more than
+    /// the 96-bit mantissa `rescale` can hold (it then lowers the scale
+    /// instead).
+    pub(crate) fn to_unscaled(self, price: Decimal) -> Result<i128, RepositoryError> {
+        let mut scaled = price
+            .round_dp_with_strategy(u32::from(self.scale), RoundingStrategy::MidpointNearestEven);
+        scaled.rescale(u32::from(self.scale));
+        let unscaled = scaled.mantissa();
+        if scaled.scale() != u32::from(self.scale)
+            || unscaled.unsigned_abs() >= 10u128.pow(u32::from(self.precision))
+        {
+            return Err(RepositoryError::SerializationError(format!(
+                "price {} does not fit Decimal128({}, {})",
+                price, self.precision, self.scale
+            )));
+        }
+        Ok(unscaled)
+    }
+}
+
+/// Footer key-value entry marking an hour file that was closed by `shutdown`
+/// before the hour ended. Gap detection treats that day as missing.
+pub const INCOMPLETE_HOUR_KEY: &str = "ingest.incomplete_hour";
+
+/// The file a symbol is currently being written to.
+pub struct OpenFile {
+    writer: ParquetWriter,
+    path: PathBuf,
+    /// Timestamp of the first tick written; fixes the file's period.
+    opened_for: DateTime<Utc>,
+    /// Log of every tick in the file, when write-ahead logging is on.
+    wal: Option<Box<dyn Write + Send>>,
+}
+
+/// Write-ahead log kept beside the Parquet file at `path` while it is open.
+fn wal_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".wal");
+    PathBuf::from(name)
+}
+
 #[derive(Component)]
 #[shaku(interface = TickRepository)]
 pub struct ParquetTickRepository {
+    #[shaku(inject)]
+    fs: Arc<dyn FileSystem>,
+
     output_dir: PathBuf,
-    writer: Arc<Mutex<Option<ArrowWriter<File>>>>,
-    current_hour: Arc<Mutex<Option<DateTime<Utc>>>>,
+    /// Take an advisory lock on each output file so a second writer targeting
+    /// the same file fails with `RepositoryError::FileLocked`.
+    lock_files: bool,
+    /// Dictionary-encode the `symbol` column, which holds one repeated value
+    /// in single-symbol files.
+    symbol_dictionary: bool,
+    /// Flag the file still open at `shutdown` as incomplete. Meant for live
+    /// ingestion, where shutdown usually lands mid-hour; backfill writes
+    /// whole days and leaves this off.
+    mark_incomplete_on_shutdown: bool,
+    /// Soft cap on `save_batch` size: larger batches log a warning and are
+    /// written in chunks of this many ticks, each its own row group. 0 is
+    /// treated as 1.
+    max_batch_ticks: usize,
+    /// `flush` closes a file's row group only once it buffers this many
+    /// rows; see [`DEFAULT_MIN_FLUSH_ROWS`]. Rows below it are written when
+    /// the file rotates or closes, and until then only a write-ahead log
+    /// protects them.
+    #[shaku(default = DEFAULT_MIN_FLUSH_ROWS)]
+    min_flush_rows: usize,
+    #[shaku(default)]
+    price_format: PriceFormat,
+    #[shaku(default)]
+    file_dates: FileDateMode,
+    #[shaku(default)]
+    rotation: FileRotation,
+    /// Adds the nullable [`RECV_LATENCY_COLUMN`]; ticks without a latency
+    /// (backfilled ones) store null.
+    #[shaku(default)]
+    recv_latency_column: bool,
+    /// Codec for every column of every file written. ZSTD suits long-term
+    /// archives; the default, Snappy, is cheaper to write.
+    compression: Compression,
+    /// Log each open file's ticks to `{file}.wal` before writing them, so
+    /// [`TickRepository::recover`] can restore a file whose writer died
+    /// before closing it. With a log, reopening a period's file keeps the
+    /// ticks already in it rather than truncating them.
+    #[shaku(default)]
+    write_ahead_log: bool,
+    /// Provenance recorded in files opened from now on; see [`SOURCE_KEY`].
+    source: Arc<RwLock<Option<String>>>,
+    /// Each symbol's open file, so batches for several symbols can
+    /// interleave without reopening files. Held for the whole of a write,
+    /// rotation included.
+    writers: Arc<Mutex<HashMap<String, OpenFile>>>,
+    /// Files this repository closed. Concurrent backfill writers finish days
+    /// out of order, so a day's over-returned ticks can reopen a file that
+    /// was already written; reopening one of these keeps its ticks.
+    closed_files: Arc<std::sync::Mutex<HashSet<PathBuf>>>,
 }
 
 impl ParquetTickRepository {
-    fn create_schema() -> Arc<Schema> {
-        Arc::new(Schema::new(vec![
+    pub fn new(output_dir: PathBuf, fs: Arc<dyn FileSystem>) -> Self {
+        Self {
+            fs,
+            output_dir,
+            lock_files: true,
+            symbol_dictionary: true,
+            mark_incomplete_on_shutdown: false,
+            max_batch_ticks: DEFAULT_MAX_BATCH_TICKS,
+            min_flush_rows: DEFAULT_MIN_FLUSH_ROWS,
+            price_format: PriceFormat::default(),
+            file_dates: FileDateMode::default(),
+            rotation: FileRotation::default(),
+            recv_latency_column: false,
+            compression: Compression::SNAPPY,
+            write_ahead_log: false,
+            source: Arc::new(RwLock::new(None)),
+            writers: Arc::new(Mutex::new(HashMap::new())),
+            closed_files: Arc::default(),
+        }
+    }
+
+    pub fn with_file_locking(mut self, enabled: bool) -> Self {
+        self.lock_files = enabled;
+        self
+    }
+
+    pub fn with_symbol_dictionary(mut self, enabled: bool) -> Self {
+        self.symbol_dictionary = enabled;
+        self
+    }
+
+    pub fn with_incomplete_marking(mut self, enabled: bool) -> Self {
+        self.mark_incomplete_on_shutdown = enabled;
+        self
+    }
+
+    pub fn with_max_batch_ticks(mut self, max_batch_ticks: usize) -> Self {
+        self.max_batch_ticks = max_batch_ticks.max(1);
+        self
+    }
+
+    pub fn with_min_flush_rows(mut self, min_flush_rows: usize) -> Self {
+        self.min_flush_rows = min_flush_rows;
+        self
+    }
+
+    pub fn with_file_date_mode(mut self, file_dates: FileDateMode) -> Self {
+        self.file_dates = file_dates;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: FileRotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_recv_latency_column(mut self, enabled: bool) -> Self {
+        self.recv_latency_column = enabled;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_price_format(mut self, price_format: PriceFormat) -> Self {
+        self.price_format = price_format;
+        self
+    }
+
+    pub fn with_write_ahead_log(mut self, enabled: bool) -> Self {
+        self.write_ahead_log = enabled;
+        self
+    }
+
+    /// [`Self::max_batch_ticks`], clamped here because the shaku parameter
+    /// bypasses [`Self::with_max_batch_ticks`].
+    fn batch_limit(&self) -> usize {
+        self.max_batch_ticks.max(1)
+    }
+
+    fn writer_properties(&self) -> WriterProperties {
+        let mut metadata = vec![
+            KeyValue::new(
+                PRICE_PRECISION_KEY.to_string(),
+                self.price_format.precision.to_string(),
+            ),
+            KeyValue::new(
+                PRICE_SCALE_KEY.to_string(),
+                self.price_format.scale.to_string(),
+            ),
+        ];
+        if let Some(source) = self.source.read().unwrap().as_ref() {
+            metadata.push(KeyValue::new(SOURCE_KEY.to_string(), source.clone()));
+        }
+
+        WriterProperties::builder()
+            .set_compression(self.compression)
+            .set_column_dictionary_enabled(ColumnPath::from("symbol"), self.symbol_dictionary)
+            .set_key_value_metadata(Some(metadata))
+            .build()
+    }
+
+    fn create_schema(&self) -> Arc<Schema> {
+        let price_type = self.price_format.data_type();
+        let mut fields = vec![
             Field::new(
                 "timestamp",
                 DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
                 false,
             ),
             Field::new("symbol", DataType::Utf8, false),
-            Field::new("bid_price", DataType::Decimal128(10, 4), false),
+            Field::new("bid_price", price_type.clone(), false),
             Field::new("bid_size", DataType::UInt32, false),
-            Field::new("ask_price", DataType::Decimal128(10, 4), false),
+            Field::new("ask_price", price_type.clone(), false),
             Field::new("ask_size", DataType::UInt32, false),
-            Field::new("last_price", DataType::Decimal128(10, 4), false),
+            Field::new("last_price", price_type.clone(), false),
             Field::new("last_size", DataType::UInt32, false),
-        ]))
+            Field::new(SEQUENCE_COLUMN, DataType::UInt64, true),
+        ];
+        if self.recv_latency_column {
+            fields.push(Field::new(RECV_LATENCY_COLUMN, DataType::Int64, true));
+        }
+        Arc::new(Schema::new(fields))
     }
 
+    /// Path of `symbol`'s file for `timestamp`; the first part under
+    /// [`FileRotation::BySize`].
     fn generate_file_path(&self, symbol: &str, timestamp: DateTime<Utc>) -> PathBuf {
-        let filename = format!("{}_{}.parquet", symbol, timestamp.format("%Y%m%d_%H"));
-        self.output_dir.join(filename)
+        self.part_file_path(symbol, timestamp, 0)
     }
 
+    fn part_file_path(&self, symbol: &str, timestamp: DateTime<Utc>, part: u32) -> PathBuf {
+        let date = self.file_dates.date_for(timestamp);
+        let name = match (self.rotation, self.segment_start(timestamp)) {
+            (FileRotation::BySize { .. }, _) => ParquetFileName::part(symbol, date, part),
+            (_, Some(minute_of_day)) => ParquetFileName {
+                utc_date: Some(timestamp.date_naive()).filter(|utc_date| *utc_date != date),
+                ..self.segment_name(symbol, date, minute_of_day)
+            },
+            (_, None) => ParquetFileName::daily(symbol, date),
+        };
+        self.output_dir.join(name.file_name())
+    }
+
+    /// Minute of the UTC day at which `timestamp`'s segment starts, for
+    /// rotations that split the day.
+    fn segment_start(&self, timestamp: DateTime<Utc>) -> Option<u32> {
+        let minutes = self.rotation.segment_minutes()?;
+        let minute_of_day = timestamp.hour() * 60 + timestamp.minute();
+        Some(minute_of_day / minutes * minutes)
+    }
+
+    /// Name of the segment starting `minute_of_day` minutes into `date`.
+    /// Rotations in whole hours keep the `{HH}` form.
+    fn segment_name(&self, symbol: &str, date: NaiveDate, minute_of_day: u32) -> ParquetFileName {
+        let (hour, minute) = (minute_of_day / 60, minute_of_day % 60);
+        match self.rotation.segment_minutes() {
+            Some(minutes) if minutes % 60 != 0 => {
+                ParquetFileName::segment(symbol, date, hour, minute)
+            }
+            _ => ParquetFileName {
+                hour: Some(hour),
+                ..ParquetFileName::daily(symbol, date)
+            },
+        }
+    }
+
+    /// Time-based boundaries only; see [`Self::size_limit_reached`].
     fn should_rotate(&self, current: DateTime<Utc>, last: Option<DateTime<Utc>>) -> bool {
-        match last {
-            None => true,
-            Some(last) => {
-                current.format("%Y%m%d%H").to_string() != last.format("%Y%m%d%H").to_string()
+        let Some(last) = last else {
+            return true;
+        };
+        let file_date_changed = self.file_dates.date_for(current) != self.file_dates.date_for(last);
+        match self.rotation.segment_minutes() {
+            // A session opening mid-segment splits it between trade dates.
+            Some(_) => {
+                file_date_changed
+                    || (current.date_naive(), self.segment_start(current))
+                        != (last.date_naive(), self.segment_start(last))
             }
+            None => file_date_changed,
         }
     }
 
-    async fn rotate_writer(
+    /// Whether `open` has reached [`FileRotation::BySize`]'s limit, counting
+    /// row groups written and data still buffered. A file without rows never
+    /// has, so a tiny limit still puts ticks in every part.
+    fn size_limit_reached(&self, open: &OpenFile) -> bool {
+        let FileRotation::BySize { max_bytes } = self.rotation else {
+            return false;
+        };
+        let writer = &open.writer;
+        let has_rows = writer.in_progress_rows() > 0 || !writer.flushed_row_groups().is_empty();
+        has_rows && (writer.bytes_written() + writer.in_progress_size()) as u64 >= max_bytes
+    }
+
+    /// First part number not yet used by `symbol`'s files on `date`, so a
+    /// restart never reopens (and truncates) an earlier part.
+    fn next_part(&self, symbol: &str, date: NaiveDate) -> Result<u32, RepositoryError> {
+        Ok(self
+            .symbol_files(symbol)?
+            .into_iter()
+            .filter(|(_, name)| name.date == date)
+            .filter_map(|(_, name)| name.part)
+            .max()
+            .map_or(0, |part| part + 1))
+    }
+
+    /// Closes `symbol`'s open file, if any, and opens the one for
+    /// `timestamp`. Other symbols' files stay open.
+    fn rotate_writer(
         &self,
+        files: &mut HashMap<String, OpenFile>,
         symbol: &str,
         timestamp: DateTime<Utc>,
     ) -> Result<(), RepositoryError> {
         // 關閉舊 writer
-        let mut writer_guard = self.writer.lock().await;
-        if let Some(writer) = writer_guard.take() {
-            writer
-                .close()
-                .map_err(|e| RepositoryError::FileRotationError(e.to_string()))?;
-            info!("Closed previous parquet file");
+        if let Some(open) = files.remove(symbol) {
+            self.close_file(open)?;
         }
 
-        let file_path = self.generate_file_path(symbol, timestamp);
+        let part = match self.rotation {
+            FileRotation::BySize { .. } => {
+                self.next_part(symbol, self.file_dates.date_for(timestamp))?
+            }
+            _ => 0,
+        };
+        let file_path = self.part_file_path(symbol, timestamp, part);
         info!("Creating new parquet file: {}", file_path.display());
+        let carried = if self.write_ahead_log || self.was_closed(&file_path) {
+            self.existing_ticks(&file_path)
+        } else {
+            Vec::new()
+        };
+
+        let file = if self.lock_files {
+            self.fs
+                .create_locked(&file_path)
+                .map_err(|e| match e.kind() {
+                    std::io::ErrorKind::WouldBlock => {
+                        RepositoryError::FileLocked(file_path.display().to_string())
+                    }
+                    _ => RepositoryError::IoError(e),
+                })?
+        } else {
+            self.fs.create(&file_path)?
+        };
+        let schema = self.create_schema();
+        let props = self.writer_properties();
+
+        let writer = ArrowWriter::try_new(file, schema, Some(props))
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+        let wal = if self.write_ahead_log {
+            Some(self.fs.create(&wal_path(&file_path))?)
+        } else {
+            None
+        };
+
+        let mut open = OpenFile {
+            writer,
+            path: file_path,
+            opened_for: timestamp,
+            wal,
+        };
+        if !carried.is_empty() {
+            info!(
+                "Keeping {} ticks already in {}",
+                carried.len(),
+                open.path.display()
+            );
+            self.append(&mut open, &carried)?;
+        }
+        files.insert(symbol.to_string(), open);
+        Ok(())
+    }
+
+    /// Whether this repository already closed, or replaced, the file at
+    /// `path`.
+    fn was_closed(&self, path: &Path) -> bool {
+        self.closed_files.lock().unwrap().contains(path)
+    }
+
+    fn record_closed(&self, path: PathBuf) {
+        self.closed_files.lock().unwrap().insert(path);
+    }
+
+    /// Ticks already in `path`, carried into the file reopened over it. A
+    /// missing or unreadable file has none.
+    fn existing_ticks(&self, path: &Path) -> Vec<Tick> {
+        match ParquetTickReader::read_file_from(self.fs.as_ref(), path) {
+            Ok(ticks) => ticks,
+            Err(RepositoryError::IoError(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Vec::new()
+            }
+            Err(e) => {
+                warn!("Not keeping unreadable {}: {}", path.display(), e);
+                Vec::new()
+            }
+        }
+    }
 
-        let file = File::create(&file_path)?;
-        let schema = Self::create_schema();
-        let props = WriterProperties::builder().build();
+    /// Logs `ticks` to `open`'s WAL, if it has one, then writes them to the
+    /// file.
+    fn append(&self, open: &mut OpenFile, ticks: &[Tick]) -> Result<(), RepositoryError> {
+        if let Some(wal) = open.wal.as_mut() {
+            let mut lines = Vec::new();
+            for tick in ticks {
+                serde_json::to_writer(&mut lines, tick)
+                    .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+                lines.push(b'\n');
+            }
+            wal.write_all(&lines)?;
+            wal.flush()?;
+        }
+        let batch = self.ticks_to_record_batch(ticks)?;
+        open.writer
+            .write(&batch)
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))
+    }
 
-        let new_writer = ArrowWriter::try_new(file, schema, Some(props))
+    /// Closes `open`, then removes its WAL, which the file now covers.
+    fn close_file(&self, open: OpenFile) -> Result<(), RepositoryError> {
+        let OpenFile {
+            writer, path, wal, ..
+        } = open;
+        writer
+            .close()
             .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+        if let Some(wal) = wal {
+            drop(wal);
+            self.fs.remove(&wal_path(&path))?;
+        }
+        info!("Closed parquet file {}", path.display());
+        self.record_closed(path);
+        Ok(())
+    }
+
+    /// Writes one symbol's ticks for one period.
+    async fn write_segment(&self, ticks: &[Tick]) -> Result<(), RepositoryError> {
+        let first_tick = &ticks[0];
+        let symbol = first_tick.symbol();
+        let timestamp = first_tick.timestamp();
+        let mut files = self.writers.lock().await;
+
+        // 檢查是否需要滾動
+        let period = files.get(symbol).map(|open| open.opened_for);
+        if self.should_rotate(timestamp, period) {
+            self.rotate_writer(&mut files, symbol, timestamp)?;
+        }
 
-        *writer_guard = Some(new_writer);
-        *self.current_hour.lock().await = Some(timestamp);
+        let chunked = ticks.len() > self.batch_limit();
+        for chunk in ticks.chunks(self.batch_limit()) {
+            if files
+                .get(symbol)
+                .is_some_and(|open| self.size_limit_reached(open))
+            {
+                self.rotate_writer(&mut files, symbol, chunk[0].timestamp())?;
+            }
+
+            // 寫入
+            if let Some(open) = files.get_mut(symbol) {
+                self.append(open, chunk)?;
+                if chunked {
+                    // Close the row group so buffered pages don't pile up.
+                    open.writer
+                        .flush()
+                        .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+                }
+                info!("Wrote {} ticks to parquet", chunk.len());
+            } else {
+                return Err(RepositoryError::SerializationError(
+                    "Writer not initialized".to_string(),
+                ));
+            }
+        }
 
         Ok(())
     }
 
-    fn ticks_to_record_batch(ticks: &[Tick]) -> Result<RecordBatch, RepositoryError> {
-        let schema = Self::create_schema();
+    /// Replays a write-ahead log left by a previous run: JSON Lines, one tick
+    /// per line, in arrival order. Call before the first `save_batch`.
+    ///
+    /// For each file the WAL's ticks belong to, the file's ticks are matched
+    /// against the logged ones by [`Tick::content_key`], which truncates to
+    /// the microseconds Parquet stores, as a multiset: a key the file holds
+    /// `n` times accounts for the first `n` logged ticks with it. The rest
+    /// are appended in arrival order by rewriting the file atomically. A file
+    /// without a footer (the writer was killed before closing it) counts as
+    /// holding nothing. The WAL is removed once every file is written.
+    /// Returns the number of ticks recovered.
+    pub fn recover_from_wal(&self, wal_path: &Path) -> Result<usize, RepositoryError> {
+        if let FileRotation::BySize { .. } = self.rotation {
+            return Err(RepositoryError::FileRotationError(
+                "WAL recovery is not supported with size-based rotation".to_string(),
+            ));
+        }
+        let contents = match self.fs.open(wal_path) {
+            Ok(mut file) => {
+                let mut contents = String::new();
+                file.read_to_string(&mut contents)?;
+                contents
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut by_file: BTreeMap<PathBuf, Vec<Tick>> = BTreeMap::new();
+        for line in contents.lines().filter(|line| !line.is_empty()) {
+            let tick: Tick = serde_json::from_str(line)
+                .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+            by_file
+                .entry(self.generate_file_path(tick.symbol(), tick.timestamp()))
+                .or_default()
+                .push(tick);
+        }
+
+        let mut recovered = 0;
+        for (path, mut logged) in by_file {
+            let persisted = match ParquetTickReader::read_file_from(self.fs.as_ref(), &path) {
+                Ok(ticks) => ticks,
+                Err(RepositoryError::IoError(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                    Vec::new()
+                }
+                Err(RepositoryError::IoError(e)) => return Err(e.into()),
+                Err(e) => {
+                    warn!(
+                        "Rewriting unreadable {} from the WAL: {}",
+                        path.display(),
+                        e
+                    );
+                    Vec::new()
+                }
+            };
+            let key = |tick: &Tick| {
+                let (micros, symbol) = tick.content_key();
+                (micros, symbol.to_string())
+            };
+            let mut unmatched: HashMap<(i64, String), usize> = HashMap::new();
+            for tick in &persisted {
+                *unmatched.entry(key(tick)).or_default() += 1;
+            }
+            logged.retain(|tick| match unmatched.get_mut(&key(tick)) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    false
+                }
+                _ => true,
+            });
+            if logged.is_empty() {
+                continue;
+            }
+            recovered += logged.len();
+            info!(
+                "Recovering {} ticks from the WAL into {}",
+                logged.len(),
+                path.display()
+            );
+            let mut ticks = persisted;
+            ticks.extend(logged);
+            self.write_file_atomically(&path, &ticks)?;
+        }
+
+        self.fs.remove(wal_path)?;
+        Ok(recovered)
+    }
+
+    /// Writes `ticks` (one file's worth) to a temporary sibling of `path`, then
+    /// renames it over `path`, so readers never see a half-written file.
+    fn write_file_atomically(&self, path: &Path, ticks: &[Tick]) -> Result<(), RepositoryError> {
+        let mut tmp_name = path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_name);
+
+        let batch = self.ticks_to_record_batch(ticks)?;
+        let file = self.fs.create(&tmp_path)?;
+        let mut writer =
+            ArrowWriter::try_new(file, self.create_schema(), Some(self.writer_properties()))
+                .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+        writer
+            .write(&batch)
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+        writer
+            .close()
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+
+        self.fs.rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Writes a zero-row file for `date` (the midnight segment when the
+    /// rotation splits the day) flagged with [`NO_DATA_KEY`].
+    fn write_no_data_marker(&self, symbol: &str, date: NaiveDate) -> Result<(), RepositoryError> {
+        let name = match self.rotation.segment_minutes() {
+            Some(_) => self.segment_name(symbol, date, 0),
+            None => ParquetFileName::daily(symbol, date),
+        };
+        let path = self.output_dir.join(name.file_name());
+        let mut tmp_name = path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_name);
+
+        let file = self.fs.create(&tmp_path)?;
+        let mut writer =
+            ArrowWriter::try_new(file, self.create_schema(), Some(self.writer_properties()))
+                .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+        writer
+            .append_key_value_metadata(KeyValue::new(NO_DATA_KEY.to_string(), "true".to_string()));
+        writer
+            .close()
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+
+        self.fs.rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// Existing hour and daily files for `symbol` on `date`.
+    fn day_files(&self, symbol: &str, date: NaiveDate) -> Result<Vec<PathBuf>, RepositoryError> {
+        Ok(self
+            .symbol_files(symbol)?
+            .into_iter()
+            .filter(|(_, name)| name.date == date)
+            .map(|(path, _)| path)
+            .collect())
+    }
+
+    /// Existing files for `symbol`, with their parsed names.
+    fn symbol_files(
+        &self,
+        symbol: &str,
+    ) -> Result<Vec<(PathBuf, ParquetFileName)>, RepositoryError> {
+        let files = match self.fs.read_dir(&self.output_dir) {
+            Ok(files) => files,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(files
+            .into_iter()
+            .filter_map(|path| {
+                let name = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(ParquetFileName::parse)?;
+                (name.symbol == symbol).then_some((path, name))
+            })
+            .collect())
+    }
+
+    /// Whether `path` holds no ticks and no marker worth keeping: a zero-byte
+    /// file, or a zero-row one without [`NO_DATA_KEY`]. Files that fail to
+    /// parse are kept; they may still be being written. So are files another
+    /// writer holds locked, which may just not have a row group yet.
+    fn is_prunable(&self, path: &Path) -> Result<bool, RepositoryError> {
+        if self.fs.is_locked(path)? {
+            info!("Not pruning {}, another writer holds it", path.display());
+            return Ok(false);
+        }
+        let mut contents = Vec::new();
+        self.fs.open(path)?.read_to_end(&mut contents)?;
+        if contents.is_empty() {
+            return Ok(true);
+        }
+        let metadata = match ParquetMetaDataReader::new().parse_and_finish(&Bytes::from(contents)) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                warn!("Not pruning unreadable file {}: {}", path.display(), e);
+                return Ok(false);
+            }
+        };
+        let file_metadata = metadata.file_metadata();
+        let no_data_marker = file_metadata
+            .key_value_metadata()
+            .is_some_and(|entries| entries.iter().any(|kv| kv.key == NO_DATA_KEY));
+        Ok(file_metadata.num_rows() == 0 && !no_data_marker)
+    }
+
+    fn ticks_to_record_batch(&self, ticks: &[Tick]) -> Result<RecordBatch, RepositoryError> {
+        let schema = self.create_schema();
+        let format = self.price_format;
+        let prices = |price: fn(&Tick) -> Decimal| -> Result<ArrayRef, RepositoryError> {
+            let unscaled = ticks
+                .iter()
+                .map(|t| format.to_unscaled(price(t)))
+                .collect::<Result<Vec<i128>, _>>()?;
+            let array = Decimal128Array::from(unscaled)
+                .with_precision_and_scale(format.precision, format.scale as i8)
+                .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+            Ok(Arc::new(array))
+        };
 
         let timestamps: Vec<i64> = ticks
             .iter()
@@ -96,49 +860,35 @@ impl ParquetTickRepository {
 
         let symbols: Vec<&str> = ticks.iter().map(|t| t.symbol()).collect();
 
-        let bid_prices: Vec<i128> = ticks
-            .iter()
-            .map(|t| (t.bid_price().to_f64().unwrap() * 10000.0) as i128)
-            .collect();
+        let bid_prices = prices(Tick::bid_price)?;
 
         let bid_sizes: Vec<u32> = ticks.iter().map(|t| t.bid_size()).collect();
 
-        let ask_prices: Vec<i128> = ticks
-            .iter()
-            .map(|t| (t.ask_price().to_f64().unwrap() * 10000.0) as i128)
-            .collect();
+        let ask_prices = prices(Tick::ask_price)?;
 
         let ask_sizes: Vec<u32> = ticks.iter().map(|t| t.ask_size()).collect();
 
-        let last_prices: Vec<i128> = ticks
-            .iter()
-            .map(|t| (t.last_price().to_f64().unwrap() * 10000.0) as i128)
-            .collect();
+        let last_prices = prices(Tick::last_price)?;
 
         let last_sizes: Vec<u32> = ticks.iter().map(|t| t.last_size()).collect();
 
-        let arrays: Vec<ArrayRef> = vec![
+        let mut arrays: Vec<ArrayRef> = vec![
             Arc::new(TimestampMicrosecondArray::from(timestamps).with_timezone("UTC")),
             Arc::new(StringArray::from(symbols)),
-            Arc::new(
-                Decimal128Array::from(bid_prices)
-                    .with_precision_and_scale(10, 4)
-                    .unwrap(),
-            ),
+            bid_prices,
             Arc::new(UInt32Array::from(bid_sizes)),
-            Arc::new(
-                Decimal128Array::from(ask_prices)
-                    .with_precision_and_scale(10, 4)
-                    .unwrap(),
-            ),
+            ask_prices,
             Arc::new(UInt32Array::from(ask_sizes)),
-            Arc::new(
-                Decimal128Array::from(last_prices)
-                    .with_precision_and_scale(10, 4)
-                    .unwrap(),
-            ),
+            last_prices,
             Arc::new(UInt32Array::from(last_sizes)),
+            Arc::new(UInt64Array::from(
+                ticks.iter().map(Tick::sequence).collect::<Vec<_>>(),
+            )),
         ];
+        if self.recv_latency_column {
+            let latencies: Vec<Option<i64>> = ticks.iter().map(Tick::recv_latency_ms).collect();
+            arrays.push(Arc::new(Int64Array::from(latencies)));
+        }
 
         RecordBatch::try_new(schema, arrays)
             .map_err(|e| RepositoryError::SerializationError(e.to_string()))
@@ -147,60 +897,1363 @@ impl ParquetTickRepository {
 
 #[async_trait]
 impl TickRepository for ParquetTickRepository {
+    /// Each run of consecutive same-hour ticks goes to that hour's file, so a
+    /// batch that crosses an hour (or day) boundary is split across files.
+    /// With [`FileRotation::Daily`] the runs are per file date instead. Each
+    /// symbol has its own open file, so batches for several symbols may
+    /// interleave freely.
     async fn save_batch(&self, ticks: Vec<Tick>) -> Result<(), RepositoryError> {
         if ticks.is_empty() {
             warn!("Attempted to save empty batch, skipping");
             return Ok(());
         }
+        if ticks.len() > self.batch_limit() {
+            warn!(
+                "Batch of {} ticks exceeds the {}-tick soft cap; writing in chunks",
+                ticks.len(),
+                self.batch_limit()
+            );
+        }
 
-        let first_tick = &ticks[0];
-        let symbol = first_tick.symbol();
-        let timestamp = first_tick.timestamp();
-
-        // 檢查是否需要滾動
-        let last_hour = *self.current_hour.lock().await;
-        if self.should_rotate(timestamp, last_hour) {
-            self.rotate_writer(symbol, timestamp).await?;
+        for segment in ticks.chunk_by(|a, b| {
+            a.symbol() == b.symbol() && !self.should_rotate(b.timestamp(), Some(a.timestamp()))
+        }) {
+            self.write_segment(segment).await?;
         }
+        Ok(())
+    }
 
-        // 轉換為 RecordBatch
-        let batch = Self::ticks_to_record_batch(&ticks)?;
+    async fn flush(&self) -> Result<(), RepositoryError> {
+        flush_writers(&mut *self.writers.lock().await, self.min_flush_rows)
+    }
 
-        // 寫入
-        let mut writer_guard = self.writer.lock().await;
-        if let Some(writer) = writer_guard.as_mut() {
-            writer
-                .write(&batch)
-                .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
-            info!("Wrote {} ticks to parquet", ticks.len());
-        } else {
-            return Err(RepositoryError::SerializationError(
-                "Writer not initialized".to_string(),
-            ));
+    /// Skips when a write or rotation holds the writers.
+    async fn try_flush(&self) -> Result<bool, RepositoryError> {
+        let Ok(mut files) = self.writers.try_lock() else {
+            return Ok(false);
+        };
+        flush_writers(&mut files, self.min_flush_rows)?;
+        Ok(true)
+    }
+
+    /// Closes every open file, reporting the first that fails to close.
+    async fn shutdown(&self) -> Result<(), RepositoryError> {
+        let mut result = Ok(());
+        for (_, mut open) in self.writers.lock().await.drain() {
+            if self.mark_incomplete_on_shutdown {
+                open.writer.append_key_value_metadata(KeyValue::new(
+                    INCOMPLETE_HOUR_KEY.to_string(),
+                    "true".to_string(),
+                ));
+            }
+            let closed = self.close_file(open);
+            if result.is_ok() {
+                result = closed;
+            }
         }
+        result
+    }
+
+    /// Replays every WAL left in the output directory by a writer that
+    /// never closed its file; see [`Self::recover_from_wal`].
+    async fn recover(&self) -> Result<usize, RepositoryError> {
+        let files = match self.fs.read_dir(&self.output_dir) {
+            Ok(files) => files,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        let mut recovered = 0;
+        for wal in files
+            .iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "wal"))
+        {
+            recovered += self.recover_from_wal(wal)?;
+        }
+        Ok(recovered)
+    }
+
+    /// Takes effect from the next file opened; the current hour's file keeps
+    /// the source it was opened with.
+    async fn set_source(&self, source: &str) {
+        *self.source.write().unwrap() = Some(source.to_string());
+    }
 
+    /// Leaves existing files alone: a day that already has ticks needs no
+    /// marker.
+    async fn mark_no_data(&self, symbol: &str, date: NaiveDate) -> Result<(), RepositoryError> {
+        if self.day_files(symbol, date)?.is_empty() {
+            self.write_no_data_marker(symbol, date)?;
+            info!("Marked {} {} as having no data", symbol, date);
+        }
         Ok(())
     }
 
-    async fn flush(&self) -> Result<(), RepositoryError> {
-        let mut writer_guard = self.writer.lock().await;
-        if let Some(writer) = writer_guard.as_mut() {
-            writer
-                .flush()
-                .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
-            info!("Flushed parquet writer");
+    /// Never touches a file this repository has open, nor any file covering
+    /// the current wall-clock hour, which live ingestion may have just
+    /// created.
+    async fn prune_empty(&self, symbol: &str) -> Result<usize, RepositoryError> {
+        let now = Utc::now();
+        let live_date = self.file_dates.date_for(now);
+        let open_path = self
+            .writers
+            .lock()
+            .await
+            .get(symbol)
+            .map(|open| open.path.clone());
+
+        let mut removed = 0;
+        for (path, name) in self.symbol_files(symbol)? {
+            let live = name.date == live_date && name.hour.is_none_or(|hour| hour == now.hour());
+            if live || open_path.as_ref() == Some(&path) || !self.is_prunable(&path)? {
+                continue;
+            }
+            self.fs.remove(&path)?;
+            info!("Pruned empty file {}", path.display());
+            removed += 1;
         }
+        Ok(removed)
+    }
+
+    /// Merges a backfilled day with the live hourly files written for the
+    /// same date into one daily file, then removes the hourly files.
+    ///
+    /// Live wins the overlap: a segment that has a live file takes its
+    /// ticks from that file alone, and the daily (backfill) file supplies
+    /// every other segment. Segments are this repository's rotation, which
+    /// must match the one live ingestion wrote with. Exact duplicate ticks
+    /// are dropped. Days without both kinds of file are left alone; a day a
+    /// file is open on is refused.
+    async fn reconcile_day(&self, symbol: &str, date: NaiveDate) -> Result<(), RepositoryError> {
+        let mut daily = None;
+        let mut live = BTreeSet::new();
+        for path in self.day_files(symbol, date)? {
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(ParquetFileName::parse);
+            match name.map(|name| (name.hour, name.part)) {
+                Some((Some(_), _)) => {
+                    live.insert(path);
+                }
+                Some((None, None)) => daily = Some(path),
+                _ => {}
+            }
+        }
+        let Some(daily) = daily else {
+            return Ok(());
+        };
+        if live.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(open) = self.writers.lock().await.get(symbol) {
+            if self.file_dates.date_for(open.opened_for) == date {
+                return Err(RepositoryError::FileLocked(open.path.display().to_string()));
+            }
+        }
+
+        let mut ticks: Vec<Tick> = ParquetTickReader::read_file_from(self.fs.as_ref(), &daily)?
+            .into_iter()
+            .filter(|tick| !live.contains(&self.generate_file_path(symbol, tick.timestamp())))
+            .collect();
+        for path in &live {
+            ticks.extend(ParquetTickReader::read_file_from(self.fs.as_ref(), path)?);
+        }
+        ticks.sort_by_key(Tick::sort_key);
+        let read = ticks.len();
+        let mut merged: Vec<Tick> = Vec::with_capacity(read);
+        for tick in ticks {
+            let duplicate = merged
+                .iter()
+                .rev()
+                .take_while(|kept| kept.timestamp() == tick.timestamp())
+                .any(|kept| *kept == tick);
+            if !duplicate {
+                merged.push(tick);
+            }
+        }
+
+        self.write_file_atomically(&daily, &merged)?;
+        for path in &live {
+            self.fs.remove(path)?;
+        }
+        info!(
+            "Reconciled {} {}: {} live file(s) merged, {} duplicate tick(s) dropped, {} ticks kept",
+            symbol,
+            date,
+            live.len(),
+            read - merged.len(),
+            merged.len()
+        );
         Ok(())
     }
 
-    async fn shutdown(&self) -> Result<(), RepositoryError> {
-        let mut writer_guard = self.writer.lock().await;
-        if let Some(writer) = writer_guard.take() {
-            writer
-                .close()
-                .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
-            info!("Shutdown: Closed parquet writer");
+    /// Rewrites each file of `date` that has ticks via write-then-rename and
+    /// removes the day's other files. Only each file is replaced atomically:
+    /// until the last rename, a reader listing an hourly day can find some
+    /// hours rewritten and others not. Ticks outside `date` are dropped
+    /// rather than clobbering a neighbouring day's files.
+    async fn replace_day(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        mut ticks: Vec<Tick>,
+    ) -> Result<(), RepositoryError> {
+        // Close the symbol's open file first so it cannot be appended to
+        // afterwards. Size-rotated days are rewritten as a single part.
+        if let Some(open) = self.writers.lock().await.remove(symbol) {
+            self.close_file(open)?;
         }
+
+        let dropped = ticks.len();
+        ticks.retain(|tick| self.file_dates.date_for(tick.timestamp()) == date);
+        if ticks.len() < dropped {
+            warn!(
+                "Dropping {} ticks outside {} while replacing {}",
+                dropped - ticks.len(),
+                date,
+                symbol
+            );
+        }
+        ticks.sort_by_key(Tick::sort_key);
+
+        let mut written = BTreeSet::new();
+        for segment in
+            ticks.chunk_by(|a, b| !self.should_rotate(b.timestamp(), Some(a.timestamp())))
+        {
+            let path = self.generate_file_path(symbol, segment[0].timestamp());
+            self.write_file_atomically(&path, segment)?;
+            self.record_closed(path.clone());
+            written.insert(path);
+        }
+
+        for stale in self.day_files(symbol, date)? {
+            if !written.contains(&stale) {
+                self.fs.remove(&stale)?;
+            }
+        }
+        info!(
+            "Replaced {} {} with {} ticks in {} files",
+            symbol,
+            date,
+            ticks.len(),
+            written.len()
+        );
         Ok(())
     }
 }
+
+/// Closes the row group of each file buffering at least `min_rows` rows.
+fn flush_writers(
+    files: &mut HashMap<String, OpenFile>,
+    min_rows: usize,
+) -> Result<(), RepositoryError> {
+    for open in files.values_mut() {
+        if open.writer.in_progress_rows() < min_rows.max(1) {
+            continue;
+        }
+        open.writer
+            .flush()
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+        info!("Flushed parquet writer for {}", open.path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{InMemoryFileSystem, StdFileSystem};
+    use chrono::TimeZone;
+    use ingestion_domain::test_support::sample_tick;
+    use parquet::basic::ZstdLevel;
+    use parquet::file::metadata::{FooterTail, ParquetMetaData, ParquetMetaDataReader};
+    use parquet::file::FOOTER_SIZE;
+    use rust_decimal::Decimal;
+    use std::path::Path;
+
+    fn tick(minute: u32) -> Tick {
+        tick_at(Utc.with_ymd_and_hms(2025, 1, 2, 10, minute, 0).unwrap())
+    }
+
+    fn tick_at(timestamp: DateTime<Utc>) -> Tick {
+        Tick::new(
+            timestamp,
+            "NQ".to_string(),
+            Decimal::new(16000, 0),
+            1,
+            Decimal::new(16001, 0),
+            1,
+            Decimal::new(16000, 0),
+            1,
+        )
+        .unwrap()
+    }
+
+    async fn assert_second_writer_is_locked_out(fs: Arc<dyn FileSystem>, dir: PathBuf) {
+        let first = ParquetTickRepository::new(dir.clone(), fs.clone());
+        let second = ParquetTickRepository::new(dir, fs);
+
+        first.save_batch(vec![tick(0)]).await.unwrap();
+        let result = second.save_batch(vec![tick(1)]).await;
+        assert!(
+            matches!(result, Err(RepositoryError::FileLocked(_))),
+            "expected FileLocked, got {:?}",
+            result
+        );
+
+        first.shutdown().await.unwrap();
+        second.save_batch(vec![tick(2)]).await.unwrap();
+        second.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn second_writer_on_same_file_fails_fast() {
+        let dir = std::env::temp_dir().join(format!("parquet-lock-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert_second_writer_is_locked_out(Arc::new(StdFileSystem), dir.clone()).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn in_memory_lock_is_released_on_shutdown() {
+        assert_second_writer_is_locked_out(
+            Arc::new(InMemoryFileSystem::new()),
+            PathBuf::from("/data"),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn locking_can_be_disabled() {
+        let fs: Arc<dyn FileSystem> = Arc::new(InMemoryFileSystem::new());
+        let first =
+            ParquetTickRepository::new(PathBuf::from("/data"), fs.clone()).with_file_locking(false);
+        let second =
+            ParquetTickRepository::new(PathBuf::from("/data"), fs).with_file_locking(false);
+
+        first.save_batch(vec![tick(0)]).await.unwrap();
+        second.save_batch(vec![tick(1)]).await.unwrap();
+    }
+
+    async fn write_hour(symbol_dictionary: bool) -> Vec<u8> {
+        let fs = InMemoryFileSystem::new();
+        // Uncompressed, so file size reflects the encoding alone.
+        let repo = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()))
+            .with_symbol_dictionary(symbol_dictionary)
+            .with_compression(Compression::UNCOMPRESSED);
+        repo.save_batch((0..60).map(tick).collect()).await.unwrap();
+        repo.shutdown().await.unwrap();
+        fs.contents(Path::new("/data/NQ_20250102_10.parquet"))
+            .unwrap()
+    }
+
+    fn footer_metadata(file: &[u8]) -> ParquetMetaData {
+        let footer: [u8; FOOTER_SIZE] = file[file.len() - FOOTER_SIZE..].try_into().unwrap();
+        let metadata_len = FooterTail::try_from(footer).unwrap().metadata_length();
+        let metadata_start = file.len() - FOOTER_SIZE - metadata_len;
+        ParquetMetaDataReader::decode_metadata(&file[metadata_start..file.len() - FOOTER_SIZE])
+            .unwrap()
+    }
+
+    fn symbol_uses_dictionary(file: &[u8]) -> bool {
+        let metadata = footer_metadata(file);
+        let row_group = metadata.row_group(0);
+        let symbol = row_group
+            .columns()
+            .iter()
+            .find(|column| column.column_path().string() == "symbol")
+            .unwrap();
+        symbol.dictionary_page_offset().is_some()
+    }
+
+    #[tokio::test]
+    async fn symbol_column_dictionary_toggle() {
+        let with_dictionary = write_hour(true).await;
+        let without_dictionary = write_hour(false).await;
+
+        assert!(symbol_uses_dictionary(&with_dictionary));
+        assert!(!symbol_uses_dictionary(&without_dictionary));
+        assert!(with_dictionary.len() < without_dictionary.len());
+    }
+
+    #[tokio::test]
+    async fn batch_spanning_midnight_is_split_by_hour() {
+        let fs = InMemoryFileSystem::new();
+        let repo = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()));
+        let batch = vec![
+            tick_at(Utc.with_ymd_and_hms(2025, 1, 2, 23, 58, 0).unwrap()),
+            tick_at(Utc.with_ymd_and_hms(2025, 1, 2, 23, 59, 0).unwrap()),
+            tick_at(Utc.with_ymd_and_hms(2025, 1, 3, 0, 30, 0).unwrap()),
+        ];
+
+        repo.save_batch(batch).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        assert_eq!(
+            fs.paths(),
+            vec![
+                PathBuf::from("/data/NQ_20250102_23.parquet"),
+                PathBuf::from("/data/NQ_20250103_00.parquet"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn replace_day_swaps_files_for_fresh_data() {
+        let dir = std::env::temp_dir().join(format!("parquet-replace-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let repo = ParquetTickRepository::new(dir.clone(), Arc::new(StdFileSystem));
+        let at = |day, hour, minute| Utc.with_ymd_and_hms(2025, 1, day, hour, minute, 0).unwrap();
+
+        repo.save_batch(vec![
+            tick_at(at(2, 10, 0)),
+            tick_at(at(2, 11, 0)),
+            tick_at(at(3, 9, 0)),
+        ])
+        .await
+        .unwrap();
+        repo.shutdown().await.unwrap();
+
+        let revised = vec![tick_at(at(2, 10, 5)), tick_at(at(2, 10, 6))];
+        repo.replace_day("NQ", date(2), revised.clone())
+            .await
+            .unwrap();
+
+        let file_names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            file_names,
+            vec!["NQ_20250102_10.parquet", "NQ_20250103_09.parquet"]
+        );
+        let replaced =
+            crate::repositories::reader::ParquetTickReader::read_file(&dir.join(&file_names[0]))
+                .unwrap();
+        assert_eq!(replaced, revised);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, day).unwrap()
+    }
+
+    #[tokio::test]
+    async fn scale_8_round_trips_full_precision() {
+        let dir = std::env::temp_dir().join(format!("parquet-scale-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let repo = ParquetTickRepository::new(dir.clone(), Arc::new(StdFileSystem))
+            .with_price_format(PriceFormat::new(18, 8).unwrap());
+        let fx_tick = Tick::new(
+            Utc.with_ymd_and_hms(2025, 1, 2, 10, 0, 0).unwrap(),
+            "EURUSD".to_string(),
+            Decimal::new(108_412_345, 8),
+            1,
+            Decimal::new(108_412_678, 8),
+            1,
+            Decimal::new(108_412_501, 8),
+            1,
+        )
+        .unwrap();
+
+        repo.save_batch(vec![fx_tick.clone()]).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        let path = dir.join("EURUSD_20250102_10.parquet");
+        let file = std::fs::read(&path).unwrap();
+        let ticks = crate::repositories::reader::ParquetTickReader::read_file(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(ticks, vec![fx_tick]);
+        let metadata = footer_metadata(&file);
+        let entries = metadata.file_metadata().key_value_metadata().unwrap();
+        let value = |key: &str| {
+            entries
+                .iter()
+                .find(|kv| kv.key == key)
+                .and_then(|kv| kv.value.clone())
+        };
+        assert_eq!(value(PRICE_PRECISION_KEY).as_deref(), Some("18"));
+        assert_eq!(value(PRICE_SCALE_KEY).as_deref(), Some("8"));
+    }
+
+    #[tokio::test]
+    async fn default_format_round_trips_exact_prices() {
+        let dir = std::env::temp_dir().join(format!("parquet-exact-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let repo = ParquetTickRepository::new(dir.clone(), Arc::new(StdFileSystem));
+        let at = |minute| Utc.with_ymd_and_hms(2025, 1, 2, 10, minute, 0).unwrap();
+        let price = |value: &str| value.parse::<Decimal>().unwrap();
+        let tick = |minute, last: &str| {
+            Tick::new(
+                at(minute),
+                "NQ".to_string(),
+                price("0.0001"),
+                1,
+                price("16000.1234"),
+                1,
+                price(last),
+                1,
+            )
+            .unwrap()
+        };
+
+        // A fifth decimal place rounds half-to-even.
+        repo.save_batch(vec![tick(0, "1.00005"), tick(1, "1.00015")])
+            .await
+            .unwrap();
+        repo.shutdown().await.unwrap();
+
+        let path = dir.join("NQ_20250102_10.parquet");
+        let ticks = crate::repositories::reader::ParquetTickReader::read_file(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let text = |value: Decimal| value.to_string();
+        assert_eq!(text(ticks[0].bid_price()), "0.0001");
+        assert_eq!(text(ticks[0].ask_price()), "16000.1234");
+        assert_eq!(text(ticks[0].last_price()), "1.0000");
+        assert_eq!(text(ticks[1].last_price()), "1.0002");
+    }
+
+    #[tokio::test]
+    async fn price_exceeding_precision_is_rejected() {
+        let repo =
+            ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(InMemoryFileSystem::new()))
+                .with_price_format(PriceFormat::new(6, 4).unwrap());
+
+        // 16000.0000 needs 9 digits at scale 4.
+        let result = repo.save_batch(vec![tick(0)]).await;
+
+        assert!(matches!(
+            result,
+            Err(RepositoryError::SerializationError(_))
+        ));
+        assert!(PriceFormat::new(39, 4).is_err());
+        assert!(PriceFormat::new(4, 6).is_err());
+    }
+
+    #[tokio::test]
+    async fn scale_is_capped_where_rust_decimal_stops_rescaling() {
+        assert!(PriceFormat::new(38, 28).is_ok());
+        assert!(PriceFormat::new(38, 29).is_err());
+        let repo =
+            ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(InMemoryFileSystem::new()))
+                .with_price_format(PriceFormat::new(38, 28).unwrap());
+
+        // 16000 at scale 28 needs 33 digits, beyond the 96-bit mantissa, so
+        // rescale would silently keep fewer decimal places.
+        let result = repo.save_batch(vec![tick(0)]).await;
+
+        assert!(matches!(
+            result,
+            Err(RepositoryError::SerializationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn price_overflowing_the_default_format_is_an_error_not_a_panic() {
+        let repo =
+            ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(InMemoryFileSystem::new()));
+        let overflowing = Tick::new(
+            Utc.with_ymd_and_hms(2025, 1, 2, 10, 0, 0).unwrap(),
+            "BRK.A".to_string(),
+            Decimal::new(1_000_000, 0),
+            1,
+            Decimal::new(1_000_001, 0),
+            1,
+            Decimal::new(1_000_000, 0),
+            1,
+        )
+        .unwrap();
+
+        // 1,000,000.0000 needs 11 digits at the default Decimal128(10, 4).
+        let result = repo.save_batch(vec![overflowing]).await;
+
+        match result {
+            Err(RepositoryError::SerializationError(message)) => {
+                assert!(
+                    message.contains("does not fit Decimal128(10, 4)"),
+                    "{}",
+                    message
+                )
+            }
+            other => panic!("expected a serialization error, got {:?}", other),
+        }
+        repo.save_batch(vec![tick(0)]).await.unwrap();
+        repo.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn source_id_is_written_to_file_metadata() {
+        let fs = InMemoryFileSystem::new();
+        let repo = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()));
+        repo.set_source("vendor-x").await;
+
+        repo.save_batch(vec![tick(0)]).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        let file = fs
+            .contents(Path::new("/data/NQ_20250102_10.parquet"))
+            .unwrap();
+        let metadata = footer_metadata(&file);
+        let source = metadata
+            .file_metadata()
+            .key_value_metadata()
+            .and_then(|entries| entries.iter().find(|kv| kv.key == SOURCE_KEY))
+            .and_then(|kv| kv.value.clone());
+        assert_eq!(source.as_deref(), Some("vendor-x"));
+    }
+
+    /// Captures log output written while the guard is alive.
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn oversized_batch_warns_and_is_written_in_chunks() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_max_level(tracing::Level::WARN)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let fs = InMemoryFileSystem::new();
+        let repo = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()))
+            .with_max_batch_ticks(10);
+        repo.save_batch((0..25).map(tick).collect()).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(
+            output.contains("Batch of 25 ticks exceeds the 10-tick soft cap"),
+            "missing warning in: {}",
+            output
+        );
+        let file = fs
+            .contents(Path::new("/data/NQ_20250102_10.parquet"))
+            .unwrap();
+        let metadata = footer_metadata(&file);
+        assert_eq!(metadata.file_metadata().num_rows(), 25);
+        assert_eq!(metadata.num_row_groups(), 3);
+    }
+
+    #[tokio::test]
+    async fn zero_max_batch_ticks_writes_one_tick_per_row_group() {
+        let fs = InMemoryFileSystem::new();
+        // As configured through the shaku parameter, skipping the builder.
+        let repo = ParquetTickRepository {
+            max_batch_ticks: 0,
+            ..ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()))
+        };
+
+        repo.save_batch((0..3).map(tick).collect()).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        let file = fs
+            .contents(Path::new("/data/NQ_20250102_10.parquet"))
+            .unwrap();
+        assert_eq!(footer_metadata(&file).num_row_groups(), 3);
+    }
+
+    #[tokio::test]
+    async fn overnight_session_files_under_its_trade_date() {
+        let fs = InMemoryFileSystem::new();
+        let calendar = TradingCalendar::new()
+            .with_session_open(chrono::NaiveTime::from_hms_opt(23, 0, 0).unwrap());
+        let repo = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()))
+            .with_file_date_mode(FileDateMode::TradeDate(calendar));
+        // Thursday 2 Jan's evening session is Friday 3 Jan's trade date.
+        repo.save_batch(vec![
+            tick_at(Utc.with_ymd_and_hms(2025, 1, 2, 22, 59, 0).unwrap()),
+            tick_at(Utc.with_ymd_and_hms(2025, 1, 2, 23, 0, 0).unwrap()),
+            tick_at(Utc.with_ymd_and_hms(2025, 1, 3, 1, 30, 0).unwrap()),
+            tick_at(Utc.with_ymd_and_hms(2025, 1, 3, 14, 0, 0).unwrap()),
+        ])
+        .await
+        .unwrap();
+        repo.shutdown().await.unwrap();
+
+        assert_eq!(
+            fs.paths(),
+            vec![
+                PathBuf::from("/data/NQ_20250102_22.parquet"),
+                PathBuf::from("/data/NQ_20250103_01.parquet"),
+                PathBuf::from("/data/NQ_20250103_14.parquet"),
+                PathBuf::from("/data/NQ_20250103_2025010223.parquet"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn session_opening_mid_hour_keeps_both_trade_dates_hour() {
+        let fs = InMemoryFileSystem::new();
+        let calendar = TradingCalendar::new()
+            .with_session_open(chrono::NaiveTime::from_hms_opt(23, 30, 0).unwrap());
+        let repo = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()))
+            .with_file_date_mode(FileDateMode::TradeDate(calendar));
+        let at = |day, minute| Utc.with_ymd_and_hms(2025, 1, day, 23, minute, 0).unwrap();
+
+        // Monday evening opens Tuesday's session; Tuesday 23:00-23:29 is
+        // still Tuesday's trade date, in the same UTC hour.
+        for batch in [vec![at(6, 45)], vec![at(7, 10), at(7, 40)]] {
+            repo.save_batch(batch.into_iter().map(tick_at).collect())
+                .await
+                .unwrap();
+        }
+        repo.shutdown().await.unwrap();
+
+        let read = |name: &str| {
+            ParquetTickReader::read_file_from(&fs, &PathBuf::from("/data").join(name))
+                .unwrap()
+                .iter()
+                .map(Tick::timestamp)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fs.paths().len(), 3);
+        assert_eq!(read("NQ_20250107_2025010623.parquet"), vec![at(6, 45)]);
+        assert_eq!(read("NQ_20250107_23.parquet"), vec![at(7, 10)]);
+        assert_eq!(read("NQ_20250108_2025010723.parquet"), vec![at(7, 40)]);
+    }
+
+    #[tokio::test]
+    async fn daily_rotation_writes_full_day_batch_to_one_file() {
+        let dir = std::env::temp_dir().join(format!("parquet-daily-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let repo = ParquetTickRepository::new(dir.clone(), Arc::new(StdFileSystem))
+            .with_rotation(FileRotation::Daily);
+        let day = |d: u32, hour: u32| {
+            (0..4).map(move |quarter| {
+                tick_at(
+                    Utc.with_ymd_and_hms(2025, 1, d, hour, quarter * 15, 0)
+                        .unwrap(),
+                )
+            })
+        };
+        let full_day: Vec<Tick> = (0..24).flat_map(|hour| day(2, hour)).collect();
+
+        repo.save_batch(full_day.clone()).await.unwrap();
+        repo.save_batch(day(3, 0).collect()).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        let reader = crate::repositories::reader::ParquetTickReader::new(dir.clone());
+        assert_eq!(
+            reader.symbol_files("NQ").unwrap(),
+            vec![
+                dir.join("NQ_20250102.parquet"),
+                dir.join("NQ_20250103.parquet")
+            ]
+        );
+        assert_eq!(
+            crate::repositories::reader::ParquetTickReader::read_file(
+                &dir.join("NQ_20250102.parquet")
+            )
+            .unwrap(),
+            full_day
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reopening_a_closed_day_keeps_its_ticks() {
+        let dir = std::env::temp_dir().join(format!("parquet-reopen-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let repo = ParquetTickRepository::new(dir.clone(), Arc::new(StdFileSystem))
+            .with_rotation(FileRotation::Daily);
+        let at = |day, hour| tick_at(Utc.with_ymd_and_hms(2025, 1, day, hour, 0, 0).unwrap());
+
+        // Day 2 is written first; day 1's batch then spills a tick into it.
+        repo.save_batch(vec![at(2, 10)]).await.unwrap();
+        repo.save_batch(vec![at(1, 10), at(2, 0)]).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        let mut day_two = crate::repositories::reader::ParquetTickReader::read_file(
+            &dir.join("NQ_20250102.parquet"),
+        )
+        .unwrap();
+        day_two.sort_by_key(Tick::sort_key);
+        assert_eq!(day_two, vec![at(2, 0), at(2, 10)]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn size_rotation_starts_new_parts_and_new_days() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        let dir = PathBuf::from("/data");
+        // Any rows exceed the limit: every write after a file's first rotates.
+        let repo = ParquetTickRepository::new(dir.clone(), fs.clone())
+            .with_rotation(FileRotation::BySize { max_bytes: 1 })
+            .with_max_batch_ticks(2);
+        let next_day = Utc.with_ymd_and_hms(2025, 1, 3, 9, 0, 0).unwrap();
+
+        repo.save_batch(vec![tick(0)]).await.unwrap();
+        repo.save_batch(vec![tick(1)]).await.unwrap();
+        // Chunked into two row groups; the second starts another part.
+        repo.save_batch(vec![tick(2), tick(3), tick(4)])
+            .await
+            .unwrap();
+        repo.save_batch(vec![tick_at(next_day)]).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        let mut names: Vec<String> = fs
+            .paths()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "NQ_20250102_p000.parquet",
+                "NQ_20250102_p001.parquet",
+                "NQ_20250102_p002.parquet",
+                "NQ_20250102_p003.parquet",
+                "NQ_20250103_p000.parquet",
+            ]
+        );
+        let rows = |name: &str| {
+            ParquetTickReader::read_file_from(fs.as_ref(), &dir.join(name))
+                .unwrap()
+                .len()
+        };
+        assert_eq!(rows("NQ_20250102_p002.parquet"), 2);
+        assert_eq!(rows("NQ_20250102_p003.parquet"), 1);
+
+        // A restarted writer continues after the last part on disk.
+        let restarted = ParquetTickRepository::new(dir.clone(), fs.clone())
+            .with_rotation(FileRotation::BySize { max_bytes: 1 });
+        restarted.save_batch(vec![tick(5)]).await.unwrap();
+        restarted.shutdown().await.unwrap();
+        assert_eq!(rows("NQ_20250102_p004.parquet"), 1);
+    }
+
+    #[tokio::test]
+    async fn size_rotation_keeps_small_writes_in_one_file() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        let repo = ParquetTickRepository::new(PathBuf::from("/data"), fs.clone()).with_rotation(
+            FileRotation::BySize {
+                max_bytes: 1024 * 1024,
+            },
+        );
+
+        for minute in 0..5 {
+            repo.save_batch(vec![tick(minute)]).await.unwrap();
+        }
+        repo.shutdown().await.unwrap();
+
+        assert_eq!(
+            fs.paths(),
+            vec![PathBuf::from("/data/NQ_20250102_p000.parquet")]
+        );
+    }
+
+    #[tokio::test]
+    async fn symbol_change_within_the_hour_starts_a_new_file() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        let dir = PathBuf::from("/data");
+        let repo = ParquetTickRepository::new(dir.clone(), fs.clone());
+        let at = |minute| Utc.with_ymd_and_hms(2025, 1, 2, 10, minute, 0).unwrap();
+        let mut nq: Vec<Tick> = (0..3).map(|minute| sample_tick("NQ", at(minute))).collect();
+        let es: Vec<Tick> = (3..5).map(|minute| sample_tick("ES", at(minute))).collect();
+
+        repo.save_batch(nq.clone()).await.unwrap();
+        repo.save_batch(es.clone()).await.unwrap();
+        // Back to NQ within the hour continues its still-open file.
+        nq.push(sample_tick("NQ", at(6)));
+        repo.save_batch(vec![nq[3].clone()]).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        let read =
+            |name: &str| ParquetTickReader::read_file_from(fs.as_ref(), &dir.join(name)).unwrap();
+        assert_eq!(fs.paths().len(), 2);
+        assert_eq!(read("NQ_20250102_10.parquet"), nq);
+        assert_eq!(read("ES_20250102_10.parquet"), es);
+    }
+
+    #[tokio::test]
+    async fn interleaved_symbols_across_hours_each_keep_their_files() {
+        let fs = Arc::new(InMemoryFileSystem::new());
+        let dir = PathBuf::from("/data");
+        let repo = ParquetTickRepository::new(dir.clone(), fs.clone());
+        let at = |hour, minute| Utc.with_ymd_and_hms(2025, 1, 2, hour, minute, 0).unwrap();
+        let batch = |ticks: &[(&str, u32, u32)]| -> Vec<Tick> {
+            ticks
+                .iter()
+                .map(|(symbol, hour, minute)| sample_tick(symbol, at(*hour, *minute)))
+                .collect()
+        };
+
+        // Multi-symbol batches, as one ingestion loop over several symbols
+        // would produce.
+        repo.save_batch(batch(&[("NQ", 10, 0), ("ES", 10, 1), ("NQ", 10, 2)]))
+            .await
+            .unwrap();
+        repo.save_batch(batch(&[("ES", 10, 30), ("NQ", 11, 0), ("ES", 10, 59)]))
+            .await
+            .unwrap();
+        repo.save_batch(batch(&[("ES", 11, 5), ("NQ", 11, 6)]))
+            .await
+            .unwrap();
+        repo.shutdown().await.unwrap();
+
+        let minutes = |name: &str| {
+            ParquetTickReader::read_file_from(fs.as_ref(), &dir.join(name))
+                .unwrap()
+                .iter()
+                .map(|tick| tick.timestamp().minute())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fs.paths().len(), 4);
+        assert_eq!(minutes("NQ_20250102_10.parquet"), vec![0, 2]);
+        assert_eq!(minutes("NQ_20250102_11.parquet"), vec![0, 6]);
+        assert_eq!(minutes("ES_20250102_10.parquet"), vec![1, 30, 59]);
+        assert_eq!(minutes("ES_20250102_11.parquet"), vec![5]);
+    }
+
+    #[tokio::test]
+    async fn reconcile_day_merges_backfill_and_live_with_live_winning() {
+        let fs = InMemoryFileSystem::new();
+        let at =
+            |hour: u32, minute: u32| Utc.with_ymd_and_hms(2025, 1, 2, hour, minute, 0).unwrap();
+        let priced = |timestamp, last: i64| {
+            Tick::new(
+                timestamp,
+                "NQ".to_string(),
+                Decimal::new(last - 25, 2),
+                1,
+                Decimal::new(last + 25, 2),
+                1,
+                Decimal::new(last, 2),
+                1,
+            )
+            .unwrap()
+        };
+        let backfill = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()))
+            .with_rotation(FileRotation::Daily);
+        backfill
+            .save_batch(vec![
+                priced(at(9, 0), 1_600_000),
+                priced(at(10, 0), 1_600_100),
+                priced(at(10, 30), 1_600_200),
+                priced(at(11, 0), 1_600_300),
+            ])
+            .await
+            .unwrap();
+        backfill.shutdown().await.unwrap();
+        // Live saw hour 10 differently, and delivered one tick twice.
+        let live = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()));
+        let live_hour = vec![
+            priced(at(10, 0), 1_600_100),
+            priced(at(10, 0), 1_600_100),
+            priced(at(10, 45), 1_600_150),
+        ];
+        live.save_batch(live_hour).await.unwrap();
+        live.shutdown().await.unwrap();
+
+        live.reconcile_day("NQ", at(0, 0).date_naive())
+            .await
+            .unwrap();
+
+        assert_eq!(fs.paths(), vec![PathBuf::from("/data/NQ_20250102.parquet")]);
+        let merged =
+            ParquetTickReader::read_file_from(&fs, Path::new("/data/NQ_20250102.parquet")).unwrap();
+        assert_eq!(
+            merged,
+            vec![
+                priced(at(9, 0), 1_600_000),
+                priced(at(10, 0), 1_600_100),
+                priced(at(10, 45), 1_600_150),
+                priced(at(11, 0), 1_600_300),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reconcile_day_keeps_backfill_outside_live_sub_hour_segments() {
+        let fs = InMemoryFileSystem::new();
+        let backfill = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()))
+            .with_rotation(FileRotation::Daily);
+        backfill
+            .save_batch(vec![tick(0), tick(20), tick(40)])
+            .await
+            .unwrap();
+        backfill.shutdown().await.unwrap();
+        // Live only wrote the 10:15 segment.
+        let live = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()))
+            .with_rotation(FileRotation::Interval { minutes: 15 });
+        live.save_batch(vec![tick(16)]).await.unwrap();
+        live.shutdown().await.unwrap();
+
+        live.reconcile_day("NQ", tick(0).timestamp().date_naive())
+            .await
+            .unwrap();
+
+        assert_eq!(fs.paths(), vec![PathBuf::from("/data/NQ_20250102.parquet")]);
+        assert_eq!(
+            ParquetTickReader::read_file_from(&fs, Path::new("/data/NQ_20250102.parquet")).unwrap(),
+            vec![tick(0), tick(16), tick(40)]
+        );
+    }
+
+    #[tokio::test]
+    async fn reconcile_day_refuses_the_day_being_written() {
+        let fs = InMemoryFileSystem::new();
+        fs.insert("/data/NQ_20250102.parquet", Vec::new());
+        let live = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()));
+        live.save_batch(vec![tick(5)]).await.unwrap();
+
+        let result = live
+            .reconcile_day("NQ", tick(5).timestamp().date_naive())
+            .await;
+
+        assert!(matches!(result, Err(RepositoryError::FileLocked(_))));
+    }
+
+    #[tokio::test]
+    async fn sequence_round_trips_and_orders_same_timestamp_ticks() {
+        let dir = std::env::temp_dir().join(format!("parquet-seq-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let repo = ParquetTickRepository::new(dir.clone(), Arc::new(StdFileSystem));
+        // Written out of sequence order, plus one tick without a sequence.
+        repo.save_batch(vec![
+            tick(0).with_sequence(2),
+            tick(0).with_sequence(1),
+            tick(1),
+        ])
+        .await
+        .unwrap();
+        repo.shutdown().await.unwrap();
+
+        let stored = ParquetTickReader::read_file(&dir.join("NQ_20250102_10.parquet")).unwrap();
+        let sorted = ParquetTickReader::new(dir.clone())
+            .read_symbol("NQ")
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let sequences = |ticks: &[Tick]| ticks.iter().map(Tick::sequence).collect::<Vec<_>>();
+        assert_eq!(sequences(&stored), vec![Some(2), Some(1), None]);
+        assert_eq!(sequences(&sorted), vec![Some(1), Some(2), None]);
+    }
+
+    #[tokio::test]
+    async fn wal_recovery_writes_only_the_unpersisted_tail() {
+        let fs = InMemoryFileSystem::new();
+        let dir = PathBuf::from("/data");
+        let wal = dir.join("ticks.wal");
+        let logged = vec![
+            tick(0).with_sequence(1),
+            tick(1).with_sequence(2),
+            tick(1).with_sequence(3),
+            tick(2).with_sequence(4),
+        ];
+        let mut lines = String::new();
+        for tick in &logged {
+            lines.push_str(&serde_json::to_string(tick).unwrap());
+            lines.push('\n');
+        }
+        fs.insert(&wal, lines.into_bytes());
+
+        // The first two made it to Parquet before the crash.
+        let repo = ParquetTickRepository::new(dir.clone(), Arc::new(fs.clone()));
+        repo.save_batch(logged[..2].to_vec()).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        let restarted = ParquetTickRepository::new(dir.clone(), Arc::new(fs.clone()));
+        assert_eq!(restarted.recover_from_wal(&wal).unwrap(), 2);
+
+        let stored =
+            ParquetTickReader::read_file_from(&fs, &dir.join("NQ_20250102_10.parquet")).unwrap();
+        assert_eq!(stored, logged);
+        assert!(fs.contents(&wal).is_none());
+        assert_eq!(restarted.recover_from_wal(&wal).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn wal_recovery_matches_persisted_ticks_at_microsecond_resolution() {
+        let fs = InMemoryFileSystem::new();
+        let dir = PathBuf::from("/data");
+        let wal = dir.join("ticks.wal");
+        let base = Utc.with_ymd_and_hms(2025, 1, 2, 10, 0, 0).unwrap();
+        let at = |nanos| tick_at(base + chrono::Duration::nanoseconds(nanos));
+        // Unsequenced, two of them within one microsecond.
+        let logged = [at(100), at(200), at(1_500), at(1_700)];
+        let lines: String = logged
+            .iter()
+            .map(|tick| serde_json::to_string(tick).unwrap() + "\n")
+            .collect();
+        fs.insert(&wal, lines.into_bytes());
+
+        // Parquet keeps microseconds, so the stored copies no longer equal
+        // the logged ones.
+        let repo = ParquetTickRepository::new(dir.clone(), Arc::new(fs.clone()));
+        repo.save_batch(logged[..3].to_vec()).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        assert_eq!(repo.recover_from_wal(&wal).unwrap(), 1);
+        let stored =
+            ParquetTickReader::read_file_from(&fs, &dir.join("NQ_20250102_10.parquet")).unwrap();
+        let micros = |ticks: &[Tick]| {
+            ticks
+                .iter()
+                .map(|tick| tick.timestamp().timestamp_micros() - base.timestamp_micros())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(micros(&stored), vec![0, 0, 1, 1]);
+    }
+
+    #[tokio::test]
+    async fn crashed_writer_is_recovered_from_its_wal_and_resumed() {
+        let fs = InMemoryFileSystem::new();
+        let dir = PathBuf::from("/data");
+        let path = dir.join("NQ_20250102_10.parquet");
+        let repo = |fs: &InMemoryFileSystem| {
+            ParquetTickRepository::new(dir.clone(), Arc::new(fs.clone())).with_write_ahead_log(true)
+        };
+
+        // Killed mid-hour: the file never got its footer.
+        let crashed = repo(&fs);
+        crashed.save_batch(vec![tick(0), tick(1)]).await.unwrap();
+        drop(crashed);
+        assert!(ParquetTickReader::read_file_from(&fs, &path).is_err());
+
+        let restarted = repo(&fs);
+        assert_eq!(restarted.recover().await.unwrap(), 2);
+        assert_eq!(
+            ParquetTickReader::read_file_from(&fs, &path).unwrap(),
+            vec![tick(0), tick(1)]
+        );
+        // Resuming in the same hour keeps the recovered ticks.
+        restarted.save_batch(vec![tick(2)]).await.unwrap();
+        restarted.shutdown().await.unwrap();
+
+        assert_eq!(
+            ParquetTickReader::read_file_from(&fs, &path).unwrap(),
+            vec![tick(0), tick(1), tick(2)]
+        );
+        assert_eq!(fs.paths(), vec![path]);
+    }
+
+    #[tokio::test]
+    async fn wal_recovery_rewrites_a_file_left_without_a_footer() {
+        let fs = InMemoryFileSystem::new();
+        let dir = PathBuf::from("/data");
+        let wal = dir.join("ticks.wal");
+        let logged = vec![tick(0), tick(1)];
+        let lines: String = logged
+            .iter()
+            .map(|tick| serde_json::to_string(tick).unwrap() + "\n")
+            .collect();
+        fs.insert(&wal, lines.into_bytes());
+        fs.insert(dir.join("NQ_20250102_10.parquet"), b"PAR1".to_vec());
+
+        let repo = ParquetTickRepository::new(dir.clone(), Arc::new(fs.clone()));
+        assert_eq!(repo.recover_from_wal(&wal).unwrap(), 2);
+        let stored =
+            ParquetTickReader::read_file_from(&fs, &dir.join("NQ_20250102_10.parquet")).unwrap();
+        assert_eq!(stored, logged);
+    }
+
+    #[tokio::test]
+    async fn zstd_compression_is_applied_and_reads_back() {
+        let fs = InMemoryFileSystem::new();
+        let repo = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()))
+            .with_compression(Compression::ZSTD(ZstdLevel::try_new(9).unwrap()));
+        let ticks: Vec<Tick> = (0..10).map(tick).collect();
+
+        repo.save_batch(ticks.clone()).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        let path = Path::new("/data/NQ_20250102_10.parquet");
+        let metadata = footer_metadata(&fs.contents(path).unwrap());
+        let row_group = metadata.row_group(0);
+        assert!(row_group
+            .columns()
+            .iter()
+            .all(|column| matches!(column.compression(), Compression::ZSTD(_))));
+        assert_eq!(ParquetTickReader::read_file_from(&fs, path).unwrap(), ticks);
+    }
+
+    #[tokio::test]
+    async fn recv_latency_column_is_opt_in() {
+        let fs = InMemoryFileSystem::new();
+        let plain = ParquetTickRepository::new(PathBuf::from("/plain"), Arc::new(fs.clone()));
+        plain.save_batch(vec![tick(1)]).await.unwrap();
+        plain.shutdown().await.unwrap();
+        let with_latency = ParquetTickRepository::new(PathBuf::from("/live"), Arc::new(fs.clone()))
+            .with_recv_latency_column(true);
+        with_latency
+            .save_batch(vec![tick(1).with_recv_latency_ms(42), tick(2)])
+            .await
+            .unwrap();
+        with_latency.shutdown().await.unwrap();
+
+        let has_column = |path: &str| {
+            footer_metadata(&fs.contents(Path::new(path)).unwrap())
+                .file_metadata()
+                .schema_descr()
+                .columns()
+                .iter()
+                .any(|column| column.name() == RECV_LATENCY_COLUMN)
+        };
+        assert!(!has_column("/plain/NQ_20250102_10.parquet"));
+        assert!(has_column("/live/NQ_20250102_10.parquet"));
+        let read =
+            ParquetTickReader::read_file_from(&fs, Path::new("/live/NQ_20250102_10.parquet"))
+                .unwrap();
+        assert_eq!(
+            read.iter().map(Tick::recv_latency_ms).collect::<Vec<_>>(),
+            vec![Some(42), None]
+        );
+    }
+
+    #[tokio::test]
+    async fn prune_empty_removes_only_empty_files() {
+        let fs = InMemoryFileSystem::new();
+        let repo = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()));
+        repo.save_batch(vec![tick(1)]).await.unwrap();
+        repo.shutdown().await.unwrap();
+        repo.mark_no_data("NQ", NaiveDate::from_ymd_opt(2025, 1, 4).unwrap())
+            .await
+            .unwrap();
+        let zero_rows = Path::new("/data/NQ_20250103_11.parquet");
+        ArrowWriter::try_new(fs.create(zero_rows).unwrap(), repo.create_schema(), None)
+            .unwrap()
+            .close()
+            .unwrap();
+        fs.create(Path::new("/data/NQ_20250103_12.parquet"))
+            .unwrap();
+        let now = Utc::now();
+        let live_hour = repo.generate_file_path("NQ", now);
+        fs.create(&live_hour).unwrap();
+        fs.create(Path::new("/data/ES_20250103_12.parquet"))
+            .unwrap();
+        // Another process's writer, which has not written its header yet.
+        let _held = fs
+            .create_locked(Path::new("/data/NQ_20250103_13.parquet"))
+            .unwrap();
+
+        assert_eq!(repo.prune_empty("NQ").await.unwrap(), 2);
+
+        let mut remaining = fs.paths();
+        remaining.sort();
+        let mut expected = vec![
+            PathBuf::from("/data/ES_20250103_12.parquet"),
+            PathBuf::from("/data/NQ_20250102_10.parquet"),
+            PathBuf::from("/data/NQ_20250103_13.parquet"),
+            PathBuf::from("/data/NQ_20250104_00.parquet"),
+            live_hour,
+        ];
+        expected.sort();
+        assert_eq!(remaining, expected);
+    }
+
+    #[tokio::test]
+    async fn flush_leaves_row_groups_below_the_threshold_buffered() {
+        let fs = InMemoryFileSystem::new();
+        let repo = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()))
+            .with_min_flush_rows(3);
+
+        for minute in [0, 10] {
+            repo.save_batch(vec![tick(minute), tick(minute + 1)])
+                .await
+                .unwrap();
+            repo.flush().await.unwrap();
+        }
+        repo.save_batch(vec![tick(20)]).await.unwrap();
+        repo.flush().await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        let file = fs
+            .contents(Path::new("/data/NQ_20250102_10.parquet"))
+            .unwrap();
+        let metadata = footer_metadata(&file);
+        assert_eq!(metadata.file_metadata().num_rows(), 5);
+        // The second flush closed the first four rows; close wrote the last.
+        let rows: Vec<i64> = metadata.row_groups().iter().map(|g| g.num_rows()).collect();
+        assert_eq!(rows, vec![4, 1]);
+    }
+
+    #[tokio::test]
+    async fn try_flush_skips_while_a_write_holds_the_writer() {
+        let fs = InMemoryFileSystem::new();
+        let repo = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs));
+        repo.save_batch(vec![tick(1)]).await.unwrap();
+
+        let held = repo.writers.lock().await;
+        assert!(!repo.try_flush().await.unwrap());
+        drop(held);
+
+        assert!(repo.try_flush().await.unwrap());
+    }
+
+    /// Names of the files written for ticks every five minutes over `hours`
+    /// hours from 2025-01-02 10:00, and the number of ticks read back.
+    async fn files_for_interval(minutes: u32, hours: u32) -> (Vec<String>, usize) {
+        let fs = InMemoryFileSystem::new();
+        let repo = ParquetTickRepository::new(PathBuf::from("/data"), Arc::new(fs.clone()))
+            .with_rotation(FileRotation::Interval { minutes });
+        let start = Utc.with_ymd_and_hms(2025, 1, 2, 10, 0, 0).unwrap();
+        let ticks: Vec<Tick> = (0..hours * 60)
+            .step_by(5)
+            .map(|minute| tick_at(start + chrono::Duration::minutes(minute.into())))
+            .collect();
+        repo.save_batch(ticks).await.unwrap();
+        repo.shutdown().await.unwrap();
+
+        let mut names = Vec::new();
+        let mut read = 0;
+        for path in fs.paths() {
+            let name = path.file_name().unwrap().to_str().unwrap().to_string();
+            if name.ends_with(".parquet") {
+                read += ParquetTickReader::read_file_from(&fs, &path).unwrap().len();
+                names.push(name);
+            }
+        }
+        names.sort();
+        (names, read)
+    }
+
+    #[tokio::test]
+    async fn fifteen_minute_interval_writes_four_files_per_hour() {
+        let (names, read) = files_for_interval(15, 2).await;
+        let expected: Vec<String> = [
+            "1000", "1015", "1030", "1045", "1100", "1115", "1130", "1145",
+        ]
+        .iter()
+        .map(|segment| format!("NQ_20250102_{}.parquet", segment))
+        .collect();
+        assert_eq!(names, expected);
+        assert_eq!(read, 24);
+        for name in &names {
+            let parsed = ParquetFileName::parse(name).unwrap();
+            assert!(parsed.hour.is_some() && parsed.minute.is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn hour_and_day_intervals_keep_hourly_and_daily_names() {
+        let (names, read) = files_for_interval(60, 2).await;
+        assert_eq!(names, ["NQ_20250102_10.parquet", "NQ_20250102_11.parquet"]);
+        assert_eq!(read, 24);
+
+        // 10:00 on the 2nd through 09:55 on the 3rd: one file per day.
+        let (names, read) = files_for_interval(24 * 60, 24).await;
+        assert_eq!(names, ["NQ_20250102.parquet", "NQ_20250103.parquet"]);
+        assert_eq!(read, 288);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::filesystem::FileSystem;
+use crate::repositories::naming::ParquetFileName;
+use crate::repositories::parquet::{
+    PRICE_SCALE_KEY, RECV_LATENCY_COLUMN, SEQUENCE_COLUMN, SOURCE_KEY,
+};
+use arrow::array::{
+    Array, Decimal128Array, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+    UInt32Array, UInt64Array,
+};
+use arrow::datatypes::DataType;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use ingestion_application::ports::RepositoryError;
+use ingestion_domain::Tick;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ProjectionMask;
+use parquet::file::reader::ChunkReader;
+use rust_decimal::Decimal;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Reads ticks back out of the files written by `ParquetTickRepository`.
+pub struct ParquetTickReader {
+    data_dir: PathBuf,
+}
+
+impl ParquetTickReader {
+    pub fn new(data_dir: PathBuf) -> Self {
+        Self { data_dir }
+    }
+
+    /// Reads every tick for `symbol` in the data directory, ordered by
+    /// timestamp and then sequence.
+    pub fn read_symbol(&self, symbol: &str) -> Result<Vec<Tick>, RepositoryError> {
+        let mut ticks = Vec::new();
+        for path in self.symbol_files(symbol)? {
+            ticks.extend(
+                Self::read_file(&path)?
+                    .into_iter()
+                    .filter(|tick| tick.symbol() == symbol),
+            );
+        }
+        ticks.sort_by_key(Tick::sort_key);
+        Ok(ticks)
+    }
+
+    pub fn symbol_files(&self, symbol: &str) -> Result<Vec<PathBuf>, RepositoryError> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.data_dir)? {
+            let path = entry?.path();
+            let matches = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(ParquetFileName::parse)
+                .is_some_and(|name| name.symbol == symbol);
+            if matches && path.is_file() {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Provider recorded in the file's footer, if any.
+    pub fn file_source(path: &Path) -> Result<Option<String>, RepositoryError> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+        Ok(builder
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .and_then(|entries| entries.iter().find(|kv| kv.key == SOURCE_KEY))
+            .and_then(|kv| kv.value.clone()))
+    }
+
+    /// Prices are read at the scale stored in the column type. Files that
+    /// also record [`PRICE_SCALE_KEY`] must agree with it.
+    pub fn read_file(path: &Path) -> Result<Vec<Tick>, RepositoryError> {
+        Self::read_parquet(File::open(path)?, path)
+    }
+
+    /// Like [`Self::read_file`], but reads through `fs`.
+    pub fn read_file_from(fs: &dyn FileSystem, path: &Path) -> Result<Vec<Tick>, RepositoryError> {
+        let mut contents = Vec::new();
+        fs.open(path)?.read_to_end(&mut contents)?;
+        Self::read_parquet(Bytes::from(contents), path)
+    }
+
+    /// Reads only the timestamp column of a file through `fs`, in file
+    /// order.
+    pub fn read_timestamps_from(
+        fs: &dyn FileSystem,
+        path: &Path,
+    ) -> Result<Vec<DateTime<Utc>>, RepositoryError> {
+        let mut contents = Vec::new();
+        fs.open(path)?.read_to_end(&mut contents)?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(contents))
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+        let mask = ProjectionMask::columns(builder.parquet_schema(), ["timestamp"]);
+        let reader = builder
+            .with_projection(mask)
+            .build()
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+
+        let mut timestamps = Vec::new();
+        for batch in reader {
+            let batch = batch.map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+            let column = column::<TimestampMicrosecondArray>(&batch, "timestamp")?;
+            for micros in column.values().iter() {
+                timestamps.push(DateTime::from_timestamp_micros(*micros).ok_or_else(|| {
+                    RepositoryError::SerializationError(format!("Invalid timestamp {}", micros))
+                })?);
+            }
+        }
+        Ok(timestamps)
+    }
+
+    fn read_parquet<R: ChunkReader + 'static>(
+        input: R,
+        path: &Path,
+    ) -> Result<Vec<Tick>, RepositoryError> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(input)
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+
+        let declared_scale = builder
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .and_then(|entries| entries.iter().find(|kv| kv.key == PRICE_SCALE_KEY))
+            .and_then(|kv| kv.value.as_deref())
+            .map(|value| value.parse::<i8>().ok());
+        if let Some(declared_scale) = declared_scale {
+            let column_scale =
+                builder
+                    .schema()
+                    .field_with_name("bid_price")
+                    .ok()
+                    .and_then(|field| match field.data_type() {
+                        DataType::Decimal128(_, scale) => Some(*scale),
+                        _ => None,
+                    });
+            if declared_scale != column_scale {
+                return Err(RepositoryError::SerializationError(format!(
+                    "{} declares price scale {:?} but its columns use {:?}",
+                    path.display(),
+                    declared_scale,
+                    column_scale
+                )));
+            }
+        }
+
+        let reader = builder
+            .build()
+            .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+
+        let mut ticks = Vec::new();
+        for batch in reader {
+            let batch = batch.map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+            ticks.extend(Self::record_batch_to_ticks(&batch)?);
+        }
+        Ok(ticks)
+    }
+
+    fn record_batch_to_ticks(batch: &RecordBatch) -> Result<Vec<Tick>, RepositoryError> {
+        let timestamps = column::<TimestampMicrosecondArray>(batch, "timestamp")?;
+        let symbols = column::<StringArray>(batch, "symbol")?;
+        let bid_prices = column::<Decimal128Array>(batch, "bid_price")?;
+        let bid_sizes = column::<UInt32Array>(batch, "bid_size")?;
+        let ask_prices = column::<Decimal128Array>(batch, "ask_price")?;
+        let ask_sizes = column::<UInt32Array>(batch, "ask_size")?;
+        let last_prices = column::<Decimal128Array>(batch, "last_price")?;
+        let last_sizes = column::<UInt32Array>(batch, "last_size")?;
+        // Only present in files written with the latency column enabled.
+        let latencies = match batch.column_by_name(RECV_LATENCY_COLUMN) {
+            Some(_) => Some(column::<Int64Array>(batch, RECV_LATENCY_COLUMN)?),
+            None => None,
+        };
+        // Absent from files written before sequence numbers were stored.
+        let sequences = match batch.column_by_name(SEQUENCE_COLUMN) {
+            Some(_) => Some(column::<UInt64Array>(batch, SEQUENCE_COLUMN)?),
+            None => None,
+        };
+
+        (0..batch.num_rows())
+            .map(|row| {
+                let timestamp =
+                    DateTime::from_timestamp_micros(timestamps.value(row)).ok_or_else(|| {
+                        RepositoryError::SerializationError(format!(
+                            "Invalid timestamp {}",
+                            timestamps.value(row)
+                        ))
+                    })?;
+                let tick = Tick::new(
+                    timestamp,
+                    symbols.value(row).to_string(),
+                    decimal_value(bid_prices, row),
+                    bid_sizes.value(row),
+                    decimal_value(ask_prices, row),
+                    ask_sizes.value(row),
+                    decimal_value(last_prices, row),
+                    last_sizes.value(row),
+                )
+                .map_err(|e| RepositoryError::SerializationError(e.to_string()))?;
+                let tick = match latencies {
+                    Some(latencies) if latencies.is_valid(row) => {
+                        tick.with_recv_latency_ms(latencies.value(row))
+                    }
+                    _ => tick,
+                };
+                Ok(match sequences {
+                    Some(sequences) if sequences.is_valid(row) => {
+                        tick.with_sequence(sequences.value(row))
+                    }
+                    _ => tick,
+                })
+            })
+            .collect()
+    }
+}
+
+fn column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> Result<&'a T, RepositoryError> {
+    batch
+        .column_by_name(name)
+        .and_then(|array| array.as_any().downcast_ref::<T>())
+        .ok_or_else(|| {
+            RepositoryError::SerializationError(format!("Missing or mistyped column '{}'", name))
+        })
+}
+
+fn decimal_value(array: &Decimal128Array, row: usize) -> Decimal {
+    Decimal::from_i128_with_scale(array.value(row), array.scale() as u32)
+}
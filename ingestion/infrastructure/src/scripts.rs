@@ -0,0 +1,86 @@
+use redis::{RedisError, Script};
+
+use crate::rate_limiting::RedisConnection;
+
+/// A Lua script now cached by Redis under `sha`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedScript {
+    pub name: &'static str,
+    pub sha: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScriptPreloadError {
+    #[error("could not connect to Redis to load scripts: {0}")]
+    Connection(#[source] RedisError),
+
+    #[error("Redis rejected the {name} script: {source}")]
+    Load {
+        name: &'static str,
+        #[source]
+        source: RedisError,
+    },
+
+    #[error("Redis returned SHA {actual} for the {name} script, expected {expected}")]
+    ShaMismatch {
+        name: &'static str,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// Every Lua script the Redis-backed components run, by name.
+pub fn lua_scripts() -> Vec<(&'static str, &'static Script)> {
+    let mut scripts = crate::rate_limiting::limiter::lua_scripts();
+    scripts.extend(crate::state::redis::lua_scripts());
+    scripts.extend(crate::state::gap_queue::lua_scripts());
+    scripts
+}
+
+/// `SCRIPT LOAD`s every script in [`lua_scripts`] and checks Redis hashed
+/// each to the SHA the client will invoke it by. Run at startup so a script
+/// that fails to compile stops the process at boot rather than mid-backfill,
+/// and the first real call doesn't pay for the load.
+pub async fn preload_scripts(
+    redis: &dyn RedisConnection,
+) -> Result<Vec<LoadedScript>, ScriptPreloadError> {
+    let mut conn = redis
+        .get_connection()
+        .await
+        .map_err(ScriptPreloadError::Connection)?;
+    let mut loaded = Vec::new();
+    for (name, script) in lua_scripts() {
+        let sha = script
+            .load_async(&mut conn)
+            .await
+            .map_err(|source| ScriptPreloadError::Load { name, source })?;
+        if sha != script.get_hash() {
+            return Err(ScriptPreloadError::ShaMismatch {
+                name,
+                expected: script.get_hash().to_string(),
+                actual: sha,
+            });
+        }
+        loaded.push(LoadedScript { name, sha });
+    }
+    Ok(loaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn scripts_have_unique_names_and_hashes() {
+        let scripts = lua_scripts();
+        let names: HashSet<_> = scripts.iter().map(|(name, _)| *name).collect();
+        let hashes: HashSet<_> = scripts
+            .iter()
+            .map(|(_, script)| script.get_hash())
+            .collect();
+
+        assert_eq!(names.len(), scripts.len());
+        assert_eq!(hashes.len(), scripts.len());
+    }
+}
@@ -0,0 +1,188 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use ingestion_application::gap_queue::{GapQueue, GapQueueError};
+use ingestion_domain::DateRange;
+use lazy_static::lazy_static;
+use redis::Script;
+use shaku::Component;
+
+use crate::rate_limiting::redis::{cluster_redirection, RedisConnection, ThrottledConnection};
+
+pub const GAP_QUEUE_KEY_PREFIX: &str = "ingest:gaps:";
+pub const DEFAULT_VISIBILITY_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+lazy_static! {
+    /// Queues each gap not already pending or claimed.
+    static ref ENQUEUE_SCRIPT: Script = Script::new(
+        r#"
+        for _, gap in ipairs(ARGV) do
+            if redis.call('SADD', KEYS[3], gap) == 1 then
+                redis.call('RPUSH', KEYS[1], gap)
+            end
+        end
+        return 0
+    "#
+    );
+
+    /// Returns expired claims to the pending list, then pops the next gap
+    /// and records its claim deadline, all in one atomic step. Deadlines
+    /// come from the Redis clock, so workers with skewed clocks neither
+    /// steal live claims nor hold on to dead ones.
+    static ref CLAIM_SCRIPT: Script = Script::new(
+        r#"
+        local redis_time = redis.call('TIME')
+        local now = redis_time[1] * 1000 + math.floor(redis_time[2] / 1000)
+        local expired = redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', now)
+        for _, gap in ipairs(expired) do
+            redis.call('ZREM', KEYS[2], gap)
+            redis.call('RPUSH', KEYS[1], gap)
+        end
+        local gap = redis.call('LPOP', KEYS[1])
+        if not gap then
+            return false
+        end
+        redis.call('ZADD', KEYS[2], now + tonumber(ARGV[1]), gap)
+        return gap
+    "#
+    );
+
+    /// Forgets a gap wherever it is: still claimed, or already returned to
+    /// the pending list because its claim expired.
+    static ref ACK_SCRIPT: Script = Script::new(
+        r#"
+        redis.call('ZREM', KEYS[2], ARGV[1])
+        redis.call('LREM', KEYS[1], 0, ARGV[1])
+        redis.call('SREM', KEYS[3], ARGV[1])
+        return 0
+    "#
+    );
+}
+
+/// Scripts the queue may run, named for [`crate::scripts::preload_scripts`].
+pub(crate) fn lua_scripts() -> Vec<(&'static str, &'static Script)> {
+    vec![
+        ("gap-queue-enqueue", &*ENQUEUE_SCRIPT),
+        ("gap-queue-claim", &*CLAIM_SCRIPT),
+        ("gap-queue-ack", &*ACK_SCRIPT),
+    ]
+}
+
+/// Keeps each symbol's pending gaps in a list (`ingest:gaps:{symbol}:pending`),
+/// in-flight claims in a sorted set scored by their deadline in ms
+/// (`ingest:gaps:{symbol}:claimed`), and every gap in either in a set
+/// (`ingest:gaps:{symbol}:queued`) so one is never queued twice.
+#[derive(Component)]
+#[shaku(interface = GapQueue)]
+pub struct RedisGapQueue {
+    #[shaku(inject)]
+    redis: Arc<dyn RedisConnection>,
+
+    #[shaku(default = DEFAULT_VISIBILITY_TIMEOUT)]
+    visibility_timeout: Duration,
+}
+
+#[async_trait]
+impl GapQueue for RedisGapQueue {
+    async fn enqueue_gaps(&self, symbol: &str, gaps: &[DateRange]) -> Result<(), GapQueueError> {
+        if gaps.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.connection().await?;
+        let mut invocation = ENQUEUE_SCRIPT.prepare_invoke();
+        invocation
+            .key(pending_key(symbol))
+            .key(claimed_key(symbol))
+            .key(queued_key(symbol));
+        for gap in gaps {
+            invocation.arg(encode_gap(gap));
+        }
+        invocation
+            .invoke_async(&mut conn)
+            .await
+            .map_err(redis_error)
+            .map(|_: i64| ())
+    }
+
+    async fn claim_next_gap(&self, symbol: &str) -> Result<Option<DateRange>, GapQueueError> {
+        let mut conn = self.connection().await?;
+        let claimed: Option<String> = CLAIM_SCRIPT
+            .key(pending_key(symbol))
+            .key(claimed_key(symbol))
+            .arg(self.visibility_timeout.as_millis() as u64)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(redis_error)?;
+        claimed.as_deref().map(decode_gap).transpose()
+    }
+
+    async fn ack_gap(&self, symbol: &str, gap: &DateRange) -> Result<(), GapQueueError> {
+        let mut conn = self.connection().await?;
+        ACK_SCRIPT
+            .key(pending_key(symbol))
+            .key(claimed_key(symbol))
+            .key(queued_key(symbol))
+            .arg(encode_gap(gap))
+            .invoke_async(&mut conn)
+            .await
+            .map_err(redis_error)
+            .map(|_: i64| ())
+    }
+}
+
+impl RedisGapQueue {
+    async fn connection(&self) -> Result<ThrottledConnection, GapQueueError> {
+        self.redis.get_connection().await.map_err(redis_error)
+    }
+}
+
+fn pending_key(symbol: &str) -> String {
+    format!("{}{}:pending", GAP_QUEUE_KEY_PREFIX, symbol)
+}
+
+fn claimed_key(symbol: &str) -> String {
+    format!("{}{}:claimed", GAP_QUEUE_KEY_PREFIX, symbol)
+}
+
+fn queued_key(symbol: &str) -> String {
+    format!("{}{}:queued", GAP_QUEUE_KEY_PREFIX, symbol)
+}
+
+fn encode_gap(gap: &DateRange) -> String {
+    format!("{}/{}", gap.start(), gap.end())
+}
+
+fn decode_gap(raw: &str) -> Result<DateRange, GapQueueError> {
+    let invalid = || GapQueueError::InvalidGap(raw.to_string());
+    let (start, end) = raw.split_once('/').ok_or_else(invalid)?;
+    let start = NaiveDate::parse_from_str(start, "%Y-%m-%d").map_err(|_| invalid())?;
+    let end = NaiveDate::parse_from_str(end, "%Y-%m-%d").map_err(|_| invalid())?;
+    DateRange::new(start, end).map_err(|_| invalid())
+}
+
+fn redis_error(err: redis::RedisError) -> GapQueueError {
+    match cluster_redirection(&err) {
+        Some(reason) => GapQueueError::ClusterNotSupported(reason),
+        None => GapQueueError::Backend(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gap_encoding_round_trips() {
+        let gap = DateRange::new(
+            NaiveDate::from_ymd_opt(2025, 1, 2).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 7).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(encode_gap(&gap), "2025-01-02/2025-01-07");
+        assert_eq!(decode_gap(&encode_gap(&gap)).unwrap(), gap);
+        assert!(decode_gap("2025-01-07/2025-01-02").is_err());
+        assert!(decode_gap("garbage").is_err());
+    }
+}
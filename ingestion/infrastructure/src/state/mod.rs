@@ -1,3 +1,5 @@
+pub mod gap_queue;
 pub mod redis;
 
+pub use gap_queue::RedisGapQueue;
 pub use redis::RedisJobStateRepository;
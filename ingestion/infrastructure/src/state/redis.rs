@@ -1,17 +1,20 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use ingestion_application::job_state::{
-    CriticalRange, JobInstanceId, JobState, JobStateError, JobStateRepository, JobStatus,
+    CriticalRange, JobAuditEntry, JobAuditEvent, JobInstanceId, JobState, JobStateError,
+    JobStateRepository, JobStatus, JOB_KEY_PREFIX,
 };
 use lazy_static::lazy_static;
-use redis::aio::MultiplexedConnection;
 use redis::Script;
 use shaku::Component;
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 
-use crate::rate_limiting::redis::RedisConnection;
+use crate::rate_limiting::redis::{
+    cluster_redirection, redis_db_from_env, RedisConnection, ThrottledConnection,
+};
 
 const FIELD_STATUS: &str = "status";
 const FIELD_JOB_INSTANCE_ID: &str = "job_instance_id";
@@ -20,8 +23,17 @@ const FIELD_END_TIME: &str = "end_time";
 const FIELD_HEARTBEAT_AT: &str = "heartbeat_at";
 const FIELD_CRITICAL_RANGES: &str = "critical_ranges";
 const FIELD_LAST_ERROR_TYPE: &str = "last_error_type";
+const FIELD_DAY_CHECKSUMS: &str = "day_checksums";
+const FIELD_TOTAL_TICKS: &str = "total_ticks";
+const FIELD_LAST_PROCESSED_DATE: &str = "last_processed_date";
 const FIELD_STATE: &str = "state";
 
+/// Audit trails live outside `ingest:job:*` so job scans never see them.
+pub const JOB_AUDIT_KEY_PREFIX: &str = "ingest:job-audit:";
+pub const DEFAULT_AUDIT_TRAIL_LEN: usize = 100;
+/// Selects the Redis database for job state, apart from other components.
+pub const JOB_STATE_REDIS_DB_ENV: &str = "JOB_STATE_REDIS_DB";
+
 lazy_static! {
     static ref CHECK_AND_SET_SCRIPT: Script = Script::new(
         r#"
@@ -39,6 +51,40 @@ lazy_static! {
         return 1
     "#
     );
+    /// Like `CHECK_AND_SET_SCRIPT`, but refuses (-2) keys that only hold the
+    /// legacy `state` blob, so a single-field write never produces a partial
+    /// hash that `get` would ignore.
+    static ref CHECK_AND_SET_FIELDS_SCRIPT: Script = Script::new(
+        r#"
+        local expected = ARGV[1]
+        local current = redis.call('HGET', KEYS[1], 'job_instance_id')
+        if not current then
+            return -1
+        end
+        if current ~= expected then
+            return 0
+        end
+        if redis.call('HEXISTS', KEYS[1], 'status') == 0 then
+            return -2
+        end
+        for i = 2, #ARGV, 2 do
+            redis.call('HSET', KEYS[1], ARGV[i], ARGV[i + 1])
+        end
+        return 1
+    "#
+    );
+}
+
+/// Scripts the repository may run, named for
+/// [`crate::scripts::preload_scripts`].
+pub(crate) fn lua_scripts() -> Vec<(&'static str, &'static Script)> {
+    vec![
+        ("job-state-check-and-set", &*CHECK_AND_SET_SCRIPT),
+        (
+            "job-state-check-and-set-fields",
+            &*CHECK_AND_SET_FIELDS_SCRIPT,
+        ),
+    ]
 }
 
 #[derive(Component)]
@@ -46,6 +92,21 @@ lazy_static! {
 pub struct RedisJobStateRepository {
     #[shaku(inject)]
     redis: Arc<dyn RedisConnection>,
+
+    /// Most recent audit entries kept per job.
+    #[shaku(default = DEFAULT_AUDIT_TRAIL_LEN)]
+    audit_trail_len: usize,
+
+    /// Seconds a job (and its audit trail) is kept after it completes or
+    /// fails; `None` keeps it until deleted. Cancelled jobs never expire:
+    /// their cursor is what the next run resumes from.
+    #[shaku(default)]
+    terminal_ttl_secs: Option<u64>,
+
+    /// Redis database for job state and audit keys; `None` shares the
+    /// `REDIS_URL` database.
+    #[shaku(default = redis_db_from_env(JOB_STATE_REDIS_DB_ENV))]
+    redis_db: Option<i64>,
 }
 
 #[async_trait]
@@ -60,6 +121,9 @@ impl JobStateRepository for RedisJobStateRepository {
             heartbeat_at,
             critical_ranges,
             last_error_type,
+            day_checksums,
+            total_ticks,
+            last_processed_date,
             legacy_state,
         ): (
             Option<String>,
@@ -70,6 +134,9 @@ impl JobStateRepository for RedisJobStateRepository {
             Option<String>,
             Option<String>,
             Option<String>,
+            Option<u64>,
+            Option<String>,
+            Option<String>,
         ) = redis::cmd("HMGET")
             .arg(job_key)
             .arg(FIELD_STATUS)
@@ -79,10 +146,13 @@ impl JobStateRepository for RedisJobStateRepository {
             .arg(FIELD_HEARTBEAT_AT)
             .arg(FIELD_CRITICAL_RANGES)
             .arg(FIELD_LAST_ERROR_TYPE)
+            .arg(FIELD_DAY_CHECKSUMS)
+            .arg(FIELD_TOTAL_TICKS)
+            .arg(FIELD_LAST_PROCESSED_DATE)
             .arg(FIELD_STATE)
             .query_async(&mut conn)
             .await
-            .map_err(|e| JobStateError::Backend(e.to_string()))?;
+            .map_err(redis_error)?;
 
         if let (
             Some(status_raw),
@@ -105,6 +175,10 @@ impl JobStateRepository for RedisJobStateRepository {
                 heartbeat_at: parse_heartbeat(heartbeat)?,
                 critical_ranges: parse_critical_ranges(critical_ranges)?,
                 last_error_type: parse_last_error(last_error_type),
+                day_checksums: parse_day_checksums(day_checksums)?,
+                // Absent on hashes written before these fields existed.
+                total_ticks: total_ticks.unwrap_or_default(),
+                last_processed_date: parse_last_processed_date(last_processed_date)?,
             }));
         }
 
@@ -122,7 +196,21 @@ impl JobStateRepository for RedisJobStateRepository {
     }
 
     async fn upsert(&self, job_key: &str, state: &JobState) -> Result<(), JobStateError> {
-        self.write_full_state(job_key, state).await
+        self.write_full_state(job_key, state).await?;
+        self.append_audit(job_key, JobAuditEvent::Status(state.status.clone()))
+            .await?;
+        self.apply_terminal_ttl(job_key, &state.status).await
+    }
+
+    async fn delete(&self, job_key: &str) -> Result<(), JobStateError> {
+        let mut conn = self.connection().await?;
+        redis::cmd("DEL")
+            .arg(job_key)
+            .arg(audit_key(job_key))
+            .query_async(&mut conn)
+            .await
+            .map_err(redis_error)
+            .map(|_: i32| ())
     }
 
     async fn update_cursor(
@@ -131,8 +219,13 @@ impl JobStateRepository for RedisJobStateRepository {
         job_instance_id: &JobInstanceId,
         cursor: i64,
     ) -> Result<(), JobStateError> {
-        self.update_with(job_key, job_instance_id, |state| state.cursor = cursor)
-            .await
+        self.update_field(
+            job_key,
+            job_instance_id,
+            (FIELD_CURSOR, cursor.to_string()),
+            |state| state.cursor = cursor,
+        )
+        .await
     }
 
     async fn update_status(
@@ -142,10 +235,16 @@ impl JobStateRepository for RedisJobStateRepository {
         status: JobStatus,
     ) -> Result<(), JobStateError> {
         let status_clone = status.clone();
-        self.update_with(job_key, job_instance_id, move |state| {
-            state.status = status_clone.clone();
-        })
-        .await
+        self.update_field(
+            job_key,
+            job_instance_id,
+            (FIELD_STATUS, status.as_str().to_string()),
+            move |state| state.status = status_clone.clone(),
+        )
+        .await?;
+        self.append_audit(job_key, JobAuditEvent::Status(status.clone()))
+            .await?;
+        self.apply_terminal_ttl(job_key, &status).await
     }
 
     async fn heartbeat(
@@ -154,9 +253,15 @@ impl JobStateRepository for RedisJobStateRepository {
         job_instance_id: &JobInstanceId,
         heartbeat_at: DateTime<Utc>,
     ) -> Result<(), JobStateError> {
-        self.update_with(job_key, job_instance_id, |state| {
-            state.heartbeat_at = heartbeat_at;
-        })
+        self.update_field(
+            job_key,
+            job_instance_id,
+            (
+                FIELD_HEARTBEAT_AT,
+                heartbeat_at.timestamp_millis().to_string(),
+            ),
+            |state| state.heartbeat_at = heartbeat_at,
+        )
         .await
     }
 
@@ -166,19 +271,235 @@ impl JobStateRepository for RedisJobStateRepository {
         job_instance_id: &JobInstanceId,
         message: &str,
     ) -> Result<(), JobStateError> {
-        self.update_with(job_key, job_instance_id, |state| {
-            state.last_error_type = Some(message.to_string());
-        })
+        self.update_field(
+            job_key,
+            job_instance_id,
+            (FIELD_LAST_ERROR_TYPE, message.to_string()),
+            |state| state.last_error_type = Some(message.to_string()),
+        )
+        .await?;
+        self.append_audit(job_key, JobAuditEvent::Error(message.to_string()))
+            .await
+    }
+
+    async fn update_day_checksums(
+        &self,
+        job_key: &str,
+        job_instance_id: &JobInstanceId,
+        day_checksums: &BTreeMap<NaiveDate, u64>,
+    ) -> Result<(), JobStateError> {
+        let payload = serde_json::to_string(day_checksums)
+            .map_err(|e| JobStateError::Backend(e.to_string()))?;
+        self.update_field(
+            job_key,
+            job_instance_id,
+            (FIELD_DAY_CHECKSUMS, payload),
+            |state| state.day_checksums = day_checksums.clone(),
+        )
+        .await
+    }
+
+    async fn update_progress(
+        &self,
+        job_key: &str,
+        job_instance_id: &JobInstanceId,
+        total_ticks: u64,
+        last_processed_date: NaiveDate,
+    ) -> Result<(), JobStateError> {
+        self.update_fields(
+            job_key,
+            job_instance_id,
+            &[
+                (FIELD_TOTAL_TICKS, total_ticks.to_string()),
+                (FIELD_LAST_PROCESSED_DATE, last_processed_date.to_string()),
+            ],
+            |state| {
+                state.total_ticks = total_ticks;
+                state.last_processed_date = Some(last_processed_date);
+            },
+        )
         .await
     }
+
+    async fn audit_trail(&self, job_key: &str) -> Result<Vec<JobAuditEntry>, JobStateError> {
+        let mut conn = self.connection().await?;
+        let entries: Vec<String> = redis::cmd("LRANGE")
+            .arg(audit_key(job_key))
+            .arg(0)
+            .arg(-1)
+            .query_async(&mut conn)
+            .await
+            .map_err(redis_error)?;
+        entries
+            .iter()
+            .map(|raw| {
+                serde_json::from_str(raw)
+                    .map_err(|e| JobStateError::Backend(format!("Invalid audit entry: {}", e)))
+            })
+            .collect()
+    }
+
+    async fn find_by_status(
+        &self,
+        status: JobStatus,
+    ) -> Result<Vec<(String, JobState)>, JobStateError> {
+        Ok(self
+            .list_jobs(&format!("{}*", JOB_KEY_PREFIX))
+            .await?
+            .into_iter()
+            .filter(|(_, state)| state.status == status)
+            .collect())
+    }
+
+    /// Walks the key space with `SCAN` rather than `KEYS`, so a large
+    /// instance is never blocked. Only `ingest:job:*` keys are read, even
+    /// for a broader pattern.
+    async fn list_jobs(&self, pattern: &str) -> Result<Vec<(String, JobState)>, JobStateError> {
+        let mut conn = self.connection().await?;
+        let mut keys = Vec::new();
+        let mut cursor: u64 = 0;
+        loop {
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(pattern)
+                .arg("COUNT")
+                .arg(100)
+                .query_async(&mut conn)
+                .await
+                .map_err(redis_error)?;
+            keys.extend(batch);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        let mut jobs = Vec::new();
+        for key in keys {
+            if !key.starts_with(JOB_KEY_PREFIX) {
+                continue;
+            }
+            // A key can expire or be removed between SCAN and HMGET.
+            if let Some(state) = self.get(&key).await? {
+                jobs.push((key, state));
+            }
+        }
+        Ok(jobs)
+    }
 }
 
 impl RedisJobStateRepository {
-    async fn connection(&self) -> Result<MultiplexedConnection, JobStateError> {
+    async fn connection(&self) -> Result<ThrottledConnection, JobStateError> {
         self.redis
-            .get_connection()
+            .get_connection_to(self.redis_db)
+            .await
+            .map_err(redis_error)
+    }
+
+    /// Appends to the job's audit list and trims it to the newest
+    /// `audit_trail_len` entries in one transaction.
+    async fn append_audit(&self, job_key: &str, event: JobAuditEvent) -> Result<(), JobStateError> {
+        let entry = JobAuditEntry {
+            at: Utc::now(),
+            event,
+        };
+        let payload =
+            serde_json::to_string(&entry).map_err(|e| JobStateError::Backend(e.to_string()))?;
+        let key = audit_key(job_key);
+        let keep = self.audit_trail_len.max(1) as isize;
+        let mut conn = self.connection().await?;
+        redis::pipe()
+            .atomic()
+            .cmd("RPUSH")
+            .arg(&key)
+            .arg(payload)
+            .ignore()
+            .cmd("LTRIM")
+            .arg(&key)
+            .arg(-keep)
+            .arg(-1)
+            .ignore()
+            .query_async(&mut conn)
+            .await
+            .map_err(redis_error)
+    }
+
+    /// With a terminal TTL configured, sets the job and its audit trail to
+    /// expire once `status` is terminal, and clears the expiry when a
+    /// finished job is picked up again. A cancelled job is kept for resuming.
+    async fn apply_terminal_ttl(
+        &self,
+        job_key: &str,
+        status: &JobStatus,
+    ) -> Result<(), JobStateError> {
+        let Some(ttl) = self.terminal_ttl_secs else {
+            return Ok(());
+        };
+        let mut pipe = redis::pipe();
+        for key in [job_key.to_string(), audit_key(job_key)] {
+            if status.is_terminal() && *status != JobStatus::Cancelled {
+                pipe.cmd("EXPIRE").arg(key).arg(ttl).ignore();
+            } else {
+                pipe.cmd("PERSIST").arg(key).ignore();
+            }
+        }
+        let mut conn = self.connection().await?;
+        pipe.query_async(&mut conn).await.map_err(redis_error)
+    }
+
+    /// Writes one field behind the instance-id check. A heartbeat used to
+    /// resend every field plus the JSON `state` blob (~300 bytes for a job
+    /// without critical ranges, growing with them); now it sends a single
+    /// ~13-byte timestamp. The blob is only refreshed by `upsert` and is read
+    /// solely as a fallback for keys written before the per-field layout;
+    /// such keys still go through the full read-modify-write.
+    async fn update_field<F>(
+        &self,
+        job_key: &str,
+        job_instance_id: &JobInstanceId,
+        field: (&str, String),
+        updater: F,
+    ) -> Result<(), JobStateError>
+    where
+        F: FnMut(&mut JobState),
+    {
+        self.update_fields(job_key, job_instance_id, &[field], updater)
+            .await
+    }
+
+    /// Like [`Self::update_field`], for fields that change together.
+    async fn update_fields<F>(
+        &self,
+        job_key: &str,
+        job_instance_id: &JobInstanceId,
+        fields: &[(&str, String)],
+        updater: F,
+    ) -> Result<(), JobStateError>
+    where
+        F: FnMut(&mut JobState),
+    {
+        let mut conn = self.connection().await?;
+        let mut invocation = CHECK_AND_SET_FIELDS_SCRIPT.prepare_invoke();
+        invocation.key(job_key).arg(job_instance_id);
+        for (field, value) in fields {
+            invocation.arg(*field).arg(value);
+        }
+        let result: i32 = invocation
+            .invoke_async(&mut conn)
             .await
-            .map_err(|e| JobStateError::Backend(e.to_string()))
+            .map_err(redis_error)?;
+
+        match result {
+            1 => Ok(()),
+            0 => Err(JobStateError::StaleInstance(job_key.to_string())),
+            -1 => Err(JobStateError::NotFound(job_key.to_string())),
+            -2 => self.update_with(job_key, job_instance_id, updater).await,
+            _ => Err(JobStateError::Backend(format!(
+                "Unexpected script result {}",
+                result
+            ))),
+        }
     }
 
     async fn update_with<F>(
@@ -222,7 +543,7 @@ impl RedisJobStateRepository {
         let result: i32 = script_invocation
             .invoke_async(&mut conn)
             .await
-            .map_err(|e| JobStateError::Backend(e.to_string()))?;
+            .map_err(redis_error)?;
 
         match result {
             1 => Ok(()),
@@ -246,7 +567,7 @@ impl RedisJobStateRepository {
 
         cmd.query_async(&mut conn)
             .await
-            .map_err(|e| JobStateError::Backend(e.to_string()))
+            .map_err(redis_error)
             .map(|_: i32| ())
     }
 }
@@ -273,6 +594,19 @@ fn state_field_values(state: &JobState) -> Result<Vec<(Cow<'static, str>, String
             Cow::from(FIELD_LAST_ERROR_TYPE),
             state.last_error_type.clone().unwrap_or_default(),
         ),
+        (
+            Cow::from(FIELD_DAY_CHECKSUMS),
+            serde_json::to_string(&state.day_checksums)
+                .map_err(|e| JobStateError::Backend(e.to_string()))?,
+        ),
+        (Cow::from(FIELD_TOTAL_TICKS), state.total_ticks.to_string()),
+        (
+            Cow::from(FIELD_LAST_PROCESSED_DATE),
+            state
+                .last_processed_date
+                .map(|date| date.to_string())
+                .unwrap_or_default(),
+        ),
         (
             Cow::from(FIELD_STATE),
             serde_json::to_string(state).map_err(|e| JobStateError::Backend(e.to_string()))?,
@@ -280,6 +614,21 @@ fn state_field_values(state: &JobState) -> Result<Vec<(Cow<'static, str>, String
     ])
 }
 
+fn audit_key(job_key: &str) -> String {
+    format!(
+        "{}{}",
+        JOB_AUDIT_KEY_PREFIX,
+        job_key.strip_prefix(JOB_KEY_PREFIX).unwrap_or(job_key)
+    )
+}
+
+fn redis_error(err: redis::RedisError) -> JobStateError {
+    match cluster_redirection(&err) {
+        Some(reason) => JobStateError::ClusterNotSupported(reason),
+        None => JobStateError::Backend(err.to_string()),
+    }
+}
+
 fn parse_status(raw: &str) -> Result<JobStatus, JobStateError> {
     JobStatus::from_str(raw)
         .ok_or_else(|| JobStateError::Backend(format!("Unrecognized job status value '{}'", raw)))
@@ -299,6 +648,26 @@ fn parse_critical_ranges(payload: Option<String>) -> Result<Vec<CriticalRange>,
     }
 }
 
+fn parse_last_processed_date(payload: Option<String>) -> Result<Option<NaiveDate>, JobStateError> {
+    match payload {
+        None => Ok(None),
+        Some(raw) if raw.is_empty() => Ok(None),
+        Some(raw) => raw
+            .parse()
+            .map(Some)
+            .map_err(|e| JobStateError::Backend(format!("Invalid last_processed_date: {}", e))),
+    }
+}
+
+fn parse_day_checksums(payload: Option<String>) -> Result<BTreeMap<NaiveDate, u64>, JobStateError> {
+    match payload {
+        None => Ok(BTreeMap::new()),
+        Some(raw) if raw.is_empty() => Ok(BTreeMap::new()),
+        Some(raw) => serde_json::from_str(&raw)
+            .map_err(|e| JobStateError::Backend(format!("Invalid day_checksums: {}", e))),
+    }
+}
+
 fn parse_last_error(value: Option<String>) -> Option<String> {
     match value {
         Some(raw) if raw.is_empty() => None,
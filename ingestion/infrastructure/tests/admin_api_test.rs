@@ -0,0 +1,358 @@
+#![cfg(feature = "admin-api")]
+
+use async_trait::async_trait;
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use chrono::{DateTime, NaiveDate, Utc};
+use ingestion_application::backfill_service::{
+    BackfillError, BackfillOptions, BackfillPlan, BackfillReport, BackfillService, PlanReason,
+    PlannedDay,
+};
+use ingestion_application::{
+    key_matches, JobInstanceId, JobKey, JobState, JobStateError, JobStateRepository, JobStatus,
+};
+use ingestion_domain::DateRange;
+use ingestion_infrastructure::admin;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tower::ServiceExt;
+
+#[derive(Default)]
+struct InMemoryJobs {
+    states: Mutex<HashMap<String, JobState>>,
+}
+
+#[async_trait]
+impl JobStateRepository for InMemoryJobs {
+    async fn get(&self, job_key: &str) -> Result<Option<JobState>, JobStateError> {
+        Ok(self.states.lock().await.get(job_key).cloned())
+    }
+
+    async fn upsert(&self, job_key: &str, state: &JobState) -> Result<(), JobStateError> {
+        self.states
+            .lock()
+            .await
+            .insert(job_key.to_string(), state.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, job_key: &str) -> Result<(), JobStateError> {
+        self.states.lock().await.remove(job_key);
+        Ok(())
+    }
+
+    async fn update_cursor(
+        &self,
+        _job_key: &str,
+        _job_instance_id: &JobInstanceId,
+        _cursor: i64,
+    ) -> Result<(), JobStateError> {
+        Ok(())
+    }
+
+    async fn update_status(
+        &self,
+        job_key: &str,
+        _job_instance_id: &JobInstanceId,
+        status: JobStatus,
+    ) -> Result<(), JobStateError> {
+        if let Some(state) = self.states.lock().await.get_mut(job_key) {
+            state.status = status;
+        }
+        Ok(())
+    }
+
+    async fn heartbeat(
+        &self,
+        _job_key: &str,
+        _job_instance_id: &JobInstanceId,
+        _heartbeat_at: DateTime<Utc>,
+    ) -> Result<(), JobStateError> {
+        Ok(())
+    }
+
+    async fn save_error(
+        &self,
+        _job_key: &str,
+        _job_instance_id: &JobInstanceId,
+        _message: &str,
+    ) -> Result<(), JobStateError> {
+        Ok(())
+    }
+
+    async fn update_day_checksums(
+        &self,
+        _job_key: &str,
+        _job_instance_id: &JobInstanceId,
+        _day_checksums: &BTreeMap<NaiveDate, u64>,
+    ) -> Result<(), JobStateError> {
+        Ok(())
+    }
+
+    async fn update_progress(
+        &self,
+        _job_key: &str,
+        _job_instance_id: &JobInstanceId,
+        _total_ticks: u64,
+        _last_processed_date: NaiveDate,
+    ) -> Result<(), JobStateError> {
+        Ok(())
+    }
+
+    async fn find_by_status(
+        &self,
+        status: JobStatus,
+    ) -> Result<Vec<(String, JobState)>, JobStateError> {
+        Ok(self
+            .states
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, state)| state.status == status)
+            .map(|(key, state)| (key.clone(), state.clone()))
+            .collect())
+    }
+
+    async fn list_jobs(&self, pattern: &str) -> Result<Vec<(String, JobState)>, JobStateError> {
+        Ok(self
+            .states
+            .lock()
+            .await
+            .iter()
+            .filter(|(key, _)| key_matches(pattern, key))
+            .map(|(key, state)| (key.clone(), state.clone()))
+            .collect())
+    }
+}
+
+/// Marks the job running, then waits for cancellation and marks it failed.
+struct WaitingBackfill {
+    jobs: Arc<InMemoryJobs>,
+}
+
+#[async_trait]
+impl BackfillService for WaitingBackfill {
+    async fn backfill_range(
+        &self,
+        symbol: &str,
+        range: DateRange,
+    ) -> Result<BackfillReport, BackfillError> {
+        self.backfill_range_with_options(symbol, range, BackfillOptions::default())
+            .await
+    }
+
+    async fn backfill_range_with_options(
+        &self,
+        symbol: &str,
+        range: DateRange,
+        options: BackfillOptions,
+    ) -> Result<BackfillReport, BackfillError> {
+        let key = JobKey::new(symbol, range.start()).to_string();
+        let state = JobState::new("instance".to_string(), JobStatus::Running, 0, 0, Utc::now());
+        self.jobs.upsert(&key, &state).await?;
+        if let Some(cancel) = options.cancel {
+            cancel.cancelled().await;
+        }
+        // As the real service reports a cancelled run: a partial report, and
+        // the job left resumable.
+        self.jobs
+            .update_status(&key, &state.job_instance_id, JobStatus::Cancelled)
+            .await?;
+        Ok(BackfillReport {
+            symbol: symbol.to_string(),
+            range,
+            days_processed: 0,
+            total_ticks: 0,
+            failed_days: Vec::new(),
+            days_no_data: Vec::new(),
+            day_outcomes: BTreeMap::new(),
+            invalid_ticks: BTreeMap::new(),
+            cancelled: true,
+            lifetime_ticks: 0,
+            last_processed_date: None,
+        })
+    }
+
+    async fn backfill_dates(
+        &self,
+        _symbol: &str,
+        _dates: Vec<NaiveDate>,
+    ) -> Result<BackfillReport, BackfillError> {
+        Err(BackfillError::NoDatesRequested)
+    }
+
+    /// Plans every day of the range as a gap.
+    async fn plan(&self, symbol: &str, range: DateRange) -> Result<BackfillPlan, BackfillError> {
+        let days = range
+            .start()
+            .iter_days()
+            .take_while(|date| *date <= range.end())
+            .map(|date| PlannedDay {
+                date,
+                reason: PlanReason::Gap,
+            })
+            .collect();
+        Ok(BackfillPlan {
+            symbol: symbol.to_string(),
+            resume_from: range.start(),
+            range,
+            days,
+            estimated_duration: Duration::ZERO,
+        })
+    }
+}
+
+const TOKEN: &str = "test-token";
+
+fn app() -> Router {
+    let jobs = Arc::new(InMemoryJobs::default());
+    let backfill = Arc::new(WaitingBackfill { jobs: jobs.clone() });
+    admin::router(
+        backfill,
+        jobs,
+        admin::AdminConfig {
+            max_days: Some(5),
+            ..admin::AdminConfig::new(TOKEN.to_string())
+        },
+    )
+}
+
+async fn send(app: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    send_as(app, Some(TOKEN), method, uri, body).await
+}
+
+async fn send_as(
+    app: &Router,
+    token: Option<&str>,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    let request = match body {
+        Some(body) => request
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let value = if bytes.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&bytes).unwrap()
+    };
+    (status, value)
+}
+
+async fn wait_for_status(app: &Router, key: &str, status: &str) -> Value {
+    for _ in 0..100 {
+        let (_, job) = send(app, "GET", &format!("/jobs/{}", key), None).await;
+        if job["state"]["status"] == status {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("{} never reached {}", key, status);
+}
+
+fn start_request() -> Value {
+    json!({ "symbol": "NQ", "start_date": "2025-01-02", "end_date": "2025-01-03" })
+}
+
+#[tokio::test]
+async fn started_backfill_can_be_listed_inspected_and_cancelled() {
+    let app = app();
+
+    let (status, body) = send(&app, "POST", "/backfill", Some(start_request())).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let key = body["job_key"].as_str().unwrap().to_string();
+    assert_eq!(key, "ingest:job:NQ:2025-01-02");
+
+    let job = wait_for_status(&app, &key, "RUNNING").await;
+    assert_eq!(job["running"], true);
+    let (status, _) = send(&app, "POST", "/backfill", Some(start_request())).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, listed) = send(&app, "GET", "/jobs?status=RUNNING", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["job_key"], key);
+
+    let (status, _) = send(&app, "POST", &format!("/jobs/{}/cancel", key), None).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    wait_for_status(&app, &key, "CANCELLED").await;
+    for _ in 0..100 {
+        let (_, job) = send(&app, "GET", &format!("/jobs/{}", key), None).await;
+        if job["running"] == false {
+            assert_eq!(job["report"]["cancelled"], true);
+            assert_eq!(job["error"], Value::Null);
+            let (_, listed) = send(&app, "GET", "/jobs?status=RUNNING", None).await;
+            assert!(listed.as_array().unwrap().is_empty());
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("cancelled backfill never finished");
+}
+
+#[tokio::test]
+async fn rejects_bad_requests_and_unknown_jobs() {
+    let app = app();
+
+    let reversed = json!({ "symbol": "NQ", "start_date": "2025-01-03", "end_date": "2025-01-02" });
+    let (status, body) = send(&app, "POST", "/backfill", Some(reversed)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].is_string());
+
+    let (status, _) = send(&app, "GET", "/jobs/ingest:job:ES:2025-01-02", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, "POST", "/jobs/ingest:job:ES:2025-01-02/cancel", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn refuses_ranges_over_max_days_unless_allowed() {
+    let app = app();
+    let wide = json!({ "symbol": "NQ", "start_date": "2025-01-02", "end_date": "2025-01-10" });
+
+    let (status, body) = send(&app, "POST", "/backfill", Some(wide.clone())).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(
+        body["error"].as_str().unwrap().contains("9 days"),
+        "{}",
+        body
+    );
+    let (status, _) = send(&app, "GET", "/jobs/ingest:job:NQ:2025-01-02", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let mut allowed = wide;
+    allowed["allow_large_range"] = json!(true);
+    let (status, _) = send(&app, "POST", "/backfill", Some(allowed)).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+}
+
+#[tokio::test]
+async fn requests_without_the_token_are_unauthorized() {
+    let app = app();
+
+    for token in [None, Some("wrong-token")] {
+        let (status, body) = send_as(&app, token, "GET", "/jobs", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(body["error"].is_string());
+        let (status, _) = send_as(&app, token, "POST", "/backfill", Some(start_request())).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    let (status, listed) = send(&app, "GET", "/jobs", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(listed.as_array().unwrap().is_empty());
+}
@@ -0,0 +1,23 @@
+use ingestion_infrastructure::rate_limiting::IbRateLimiterConfig;
+use std::env;
+
+#[test]
+fn env_file_values_reach_rate_limiter_config() {
+    let dir = env::temp_dir().join(format!("env-file-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let env_file = dir.join(".env");
+    std::fs::write(
+        &env_file,
+        "IB_RATE_LIMIT_CONTRACT_LIMIT=11\nIB_RATE_LIMIT_DUPLICATE_LIMIT=9\n",
+    )
+    .unwrap();
+    // Already set in the process, so the file must not replace it.
+    env::set_var("IB_RATE_LIMIT_DUPLICATE_LIMIT", "3");
+
+    dotenvy::from_path(&env_file).unwrap();
+    let config = IbRateLimiterConfig::from_env();
+
+    assert_eq!(config.contract_window.limit, 11);
+    assert_eq!(config.duplicate_request_window.limit, 3);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
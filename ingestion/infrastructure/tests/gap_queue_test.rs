@@ -0,0 +1,173 @@
+use chrono::NaiveDate;
+use ingestion_application::gap_queue::GapQueue;
+use ingestion_domain::DateRange;
+use ingestion_infrastructure::rate_limiting::redis::RedisConnectionManager;
+use ingestion_infrastructure::state::gap_queue::{RedisGapQueueParameters, GAP_QUEUE_KEY_PREFIX};
+use ingestion_infrastructure::state::RedisGapQueue;
+use shaku::{module, HasComponent};
+use std::collections::HashSet;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+module! {
+    TestModule {
+        components = [
+            RedisConnectionManager,
+            RedisGapQueue,
+        ],
+        providers = []
+    }
+}
+
+fn setup(visibility_timeout: Duration) -> Arc<dyn GapQueue> {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/2".to_string());
+    env::set_var("REDIS_URL", &redis_url);
+    let module = TestModule::builder()
+        .with_component_parameters::<RedisGapQueue>(RedisGapQueueParameters { visibility_timeout })
+        .build();
+    module.resolve()
+}
+
+/// A fresh symbol per test keeps runs independent without cleanup.
+fn unique_symbol() -> String {
+    format!("T{}", Uuid::new_v4().simple())
+}
+
+fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 1, d).unwrap()
+}
+
+fn gaps(count: u32) -> Vec<DateRange> {
+    (1..=count).map(|d| DateRange::single_day(day(d))).collect()
+}
+
+async fn delete_keys(symbol: &str) {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/2".to_string());
+    let client = redis::Client::open(redis_url).expect("open redis client");
+    let mut conn = client
+        .get_multiplexed_async_connection()
+        .await
+        .expect("connect redis");
+    let _: () = redis::cmd("DEL")
+        .arg(format!("{}{}:pending", GAP_QUEUE_KEY_PREFIX, symbol))
+        .arg(format!("{}{}:claimed", GAP_QUEUE_KEY_PREFIX, symbol))
+        .arg(format!("{}{}:queued", GAP_QUEUE_KEY_PREFIX, symbol))
+        .query_async(&mut conn)
+        .await
+        .expect("delete keys");
+}
+
+#[tokio::test]
+async fn concurrent_claimers_receive_disjoint_gaps() {
+    let queue = setup(Duration::from_secs(60));
+    let symbol = unique_symbol();
+    queue
+        .enqueue_gaps(&symbol, &gaps(20))
+        .await
+        .expect("enqueue");
+
+    let claim_all = |queue: Arc<dyn GapQueue>, symbol: String| async move {
+        let mut claimed = Vec::new();
+        while let Some(gap) = queue.claim_next_gap(&symbol).await.expect("claim") {
+            claimed.push(gap);
+            tokio::task::yield_now().await;
+        }
+        claimed
+    };
+    let (first, second) = tokio::join!(
+        tokio::spawn(claim_all(queue.clone(), symbol.clone())),
+        tokio::spawn(claim_all(queue.clone(), symbol.clone())),
+    );
+    let (first, second) = (first.unwrap(), second.unwrap());
+
+    let first_set: HashSet<_> = first.iter().map(DateRange::start).collect();
+    let second_set: HashSet<_> = second.iter().map(DateRange::start).collect();
+    assert!(first_set.is_disjoint(&second_set));
+    assert_eq!(first.len() + second.len(), 20);
+    assert_eq!(first_set.len() + second_set.len(), 20);
+
+    delete_keys(&symbol).await;
+}
+
+#[tokio::test]
+async fn unacknowledged_gap_is_requeued_after_visibility_timeout() {
+    let queue = setup(Duration::from_millis(200));
+    let symbol = unique_symbol();
+    queue
+        .enqueue_gaps(&symbol, &gaps(2))
+        .await
+        .expect("enqueue");
+
+    let acked = queue.claim_next_gap(&symbol).await.unwrap().unwrap();
+    queue.ack_gap(&symbol, &acked).await.expect("ack");
+    let failed = queue.claim_next_gap(&symbol).await.unwrap().unwrap();
+    assert_eq!(queue.claim_next_gap(&symbol).await.unwrap(), None);
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    assert_eq!(queue.claim_next_gap(&symbol).await.unwrap(), Some(failed));
+    assert_eq!(queue.claim_next_gap(&symbol).await.unwrap(), None);
+
+    delete_keys(&symbol).await;
+}
+
+#[tokio::test]
+async fn a_gap_already_queued_or_claimed_is_not_queued_again() {
+    let queue = setup(Duration::from_secs(60));
+    let symbol = unique_symbol();
+    queue
+        .enqueue_gaps(&symbol, &gaps(2))
+        .await
+        .expect("enqueue");
+    let claimed = queue.claim_next_gap(&symbol).await.unwrap().unwrap();
+
+    queue
+        .enqueue_gaps(&symbol, &gaps(3))
+        .await
+        .expect("enqueue");
+
+    let mut rest = Vec::new();
+    while let Some(gap) = queue.claim_next_gap(&symbol).await.unwrap() {
+        rest.push(gap);
+    }
+    assert_eq!(claimed, DateRange::single_day(day(1)));
+    assert_eq!(
+        rest,
+        vec![DateRange::single_day(day(2)), DateRange::single_day(day(3))]
+    );
+
+    // Once filled, a gap may be queued again.
+    queue.ack_gap(&symbol, &claimed).await.expect("ack");
+    queue
+        .enqueue_gaps(&symbol, &gaps(1))
+        .await
+        .expect("enqueue");
+    assert_eq!(queue.claim_next_gap(&symbol).await.unwrap(), Some(claimed));
+
+    delete_keys(&symbol).await;
+}
+
+#[tokio::test]
+async fn ack_after_the_claim_expired_still_removes_the_gap() {
+    let queue = setup(Duration::from_millis(200));
+    let symbol = unique_symbol();
+    queue
+        .enqueue_gaps(&symbol, &gaps(2))
+        .await
+        .expect("enqueue");
+    let slow = queue.claim_next_gap(&symbol).await.unwrap().unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    // Another worker's claim returns the expired gap to the pending list.
+    let other = queue.claim_next_gap(&symbol).await.unwrap().unwrap();
+    queue.ack_gap(&symbol, &slow).await.expect("ack");
+    queue.ack_gap(&symbol, &other).await.expect("ack");
+
+    assert_eq!(queue.claim_next_gap(&symbol).await.unwrap(), None);
+
+    delete_keys(&symbol).await;
+}
@@ -0,0 +1,290 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, TimeZone, Utc};
+use ingestion_application::rate_limiter::{RateLimitScope, RateLimiter, RateLimiterError};
+use ingestion_application::{HistoricalDataError, HistoricalDataGateway};
+use ingestion_infrastructure::gateways::ib::{IbHistoricalConfig, IbHistoricalDataGateway};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+
+const TRADES: &str = include_str!("fixtures/ib/nq_20250102_trades.txt");
+const BID_ASK: &str = include_str!("fixtures/ib/nq_20250102_bid_ask.txt");
+const PACING_VIOLATION: &str = include_str!("fixtures/ib/pacing_violation.txt");
+const NO_DATA: &str = include_str!("fixtures/ib/no_data.txt");
+const CONNECTION_LOST: &str = include_str!("fixtures/ib/connection_lost.txt");
+
+/// Position of `whatToShow` in a `reqHistoricalData` message.
+const WHAT_TO_SHOW: usize = 19;
+
+fn date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 1, 2).unwrap()
+}
+
+/// Records the scope of every slot taken.
+#[derive(Default)]
+struct RecordingRateLimiter {
+    scopes: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl RateLimiter for RecordingRateLimiter {
+    async fn acquire(&self, scope: &RateLimitScope) -> Result<(), RateLimiterError> {
+        self.scopes.lock().unwrap().push(scope.contract_key());
+        Ok(())
+    }
+}
+
+async fn write_fields(stream: &mut TcpStream, fields: &[&str]) {
+    let mut payload = Vec::new();
+    for field in fields {
+        payload.extend_from_slice(field.as_bytes());
+        payload.push(0);
+    }
+    stream.write_u32(payload.len() as u32).await.unwrap();
+    stream.write_all(&payload).await.unwrap();
+}
+
+async fn read_frame(stream: &mut TcpStream) -> Option<Vec<u8>> {
+    let len = stream.read_u32().await.ok()?;
+    let mut payload = vec![0; len as usize];
+    stream.read_exact(&mut payload).await.ok()?;
+    Some(payload)
+}
+
+fn split_fields(payload: &[u8]) -> Vec<String> {
+    let mut fields: Vec<String> = String::from_utf8_lossy(payload)
+        .split('\0')
+        .map(str::to_string)
+        .collect();
+    fields.pop();
+    fields
+}
+
+/// A TWS stand-in on a local port. It completes the handshake, then
+/// answers each historical data request with the recorded messages for its
+/// bar type, and keeps the requests it received.
+async fn fake_tws(
+    replies: HashMap<&'static str, &'static str>,
+) -> (u16, Arc<Mutex<Vec<Vec<String>>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let received = requests.clone();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut greeting = [0; 4];
+        stream.read_exact(&mut greeting).await.unwrap();
+        assert_eq!(&greeting, b"API\0");
+        let versions = read_frame(&mut stream).await.unwrap();
+        assert!(versions.starts_with(b"v100.."));
+        write_fields(&mut stream, &["176", "20250102 10:00:00 UTC"]).await;
+        let start_api = split_fields(&read_frame(&mut stream).await.unwrap());
+        assert_eq!(start_api[0], "71");
+        write_fields(
+            &mut stream,
+            &[
+                "4",
+                "2",
+                "-1",
+                "2104",
+                "Market data farm connection is OK:usfarm",
+                "",
+            ],
+        )
+        .await;
+
+        while let Some(payload) = read_frame(&mut stream).await {
+            let request = split_fields(&payload);
+            let reply = replies[request[WHAT_TO_SHOW].as_str()];
+            let req_id = request[1].clone();
+            received.lock().unwrap().push(request);
+            for line in reply.lines().filter(|line| !line.starts_with('#')) {
+                let line = line.replace("$REQ_ID", &req_id);
+                let fields: Vec<&str> = line.split('|').collect();
+                write_fields(&mut stream, &fields).await;
+            }
+        }
+    });
+    (port, requests)
+}
+
+fn gateway(port: u16, limiter: Arc<RecordingRateLimiter>) -> IbHistoricalDataGateway {
+    IbHistoricalDataGateway::new(
+        IbHistoricalConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            client_id: 7,
+            sec_type: "FUT".to_string(),
+            exchange: "CME".to_string(),
+            currency: "USD".to_string(),
+            contract_month: "202503".to_string(),
+            use_rth: false,
+            request_timeout: Duration::from_secs(5),
+            max_history_days: 100_000,
+        },
+        limiter,
+    )
+}
+
+#[tokio::test]
+async fn recorded_bars_become_one_tick_per_quoted_minute() {
+    let (port, requests) =
+        fake_tws(HashMap::from([("TRADES", TRADES), ("BID_ASK", BID_ASK)])).await;
+    let limiter = Arc::new(RecordingRateLimiter::default());
+    let gateway = gateway(port, limiter.clone());
+
+    let ticks = gateway.fetch_historical_ticks("NQ", date()).await.unwrap();
+
+    // 14:31 has a trade bar but no quote bar, and 14:33 the reverse.
+    let summary: Vec<_> = ticks
+        .iter()
+        .map(|tick| {
+            (
+                tick.timestamp(),
+                tick.bid_price(),
+                tick.ask_price(),
+                tick.last_price(),
+                tick.last_size(),
+            )
+        })
+        .collect();
+    let at = |minute| Utc.with_ymd_and_hms(2025, 1, 2, 14, minute, 0).unwrap();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                at(30),
+                Decimal::new(2151325, 2),
+                Decimal::new(2151375, 2),
+                Decimal::new(215135, 1),
+                184
+            ),
+            (
+                at(32),
+                Decimal::new(2151575, 2),
+                Decimal::new(2151625, 2),
+                Decimal::from(21516),
+                203
+            ),
+        ]
+    );
+    assert_eq!(
+        *limiter.scopes.lock().unwrap(),
+        vec!["NQ:CME:TRADES", "NQ:CME:BID_ASK"]
+    );
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    for (request, what_to_show) in requests.iter().zip(["TRADES", "BID_ASK"]) {
+        assert_eq!(request[0], "20");
+        assert_eq!(request[3], "NQ");
+        assert_eq!(request[5], "202503");
+        assert_eq!(request[12], "");
+        assert_eq!(request[15], "20250103-00:00:00");
+        assert_eq!(request[16], "1 min");
+        assert_eq!(request[WHAT_TO_SHOW], what_to_show);
+    }
+}
+
+#[tokio::test]
+async fn a_resolved_contract_is_requested_by_local_symbol() {
+    let (port, requests) =
+        fake_tws(HashMap::from([("TRADES", TRADES), ("BID_ASK", BID_ASK)])).await;
+    let limiter = Arc::new(RecordingRateLimiter::default());
+    let gateway = gateway(port, limiter.clone());
+
+    let fetched = gateway
+        .fetch_contract_ticks_cancellable("NQH5", date(), &CancellationToken::new())
+        .await
+        .unwrap();
+
+    assert_eq!(fetched.ticks.len(), 2);
+    assert_eq!(
+        *limiter.scopes.lock().unwrap(),
+        vec!["NQH5:CME:TRADES", "NQH5:CME:BID_ASK"]
+    );
+    // The local symbol names the month, so the configured one must not
+    // contradict it.
+    for request in requests.lock().unwrap().iter() {
+        assert_eq!(request[3], "");
+        assert_eq!(request[4], "FUT");
+        assert_eq!(request[5], "");
+        assert_eq!(request[9], "CME");
+        assert_eq!(request[12], "NQH5");
+    }
+}
+
+#[tokio::test]
+async fn pacing_violation_is_a_rate_limit_error() {
+    let (port, _) = fake_tws(HashMap::from([("TRADES", PACING_VIOLATION)])).await;
+    let gateway = gateway(port, Arc::new(RecordingRateLimiter::default()));
+
+    let result = gateway.fetch_historical_ticks("NQ", date()).await;
+
+    // Long enough for IB's ten-minute pacing window to pass.
+    assert!(
+        matches!(
+            result,
+            Err(HistoricalDataError::RateLimitExceeded { retry_after: Some(wait) })
+                if wait >= Duration::from_secs(600)
+        ),
+        "{:?}",
+        result
+    );
+}
+
+#[tokio::test]
+async fn losing_the_ib_connection_fails_the_pending_request() {
+    let (port, _) = fake_tws(HashMap::from([("TRADES", CONNECTION_LOST)])).await;
+    let gateway = gateway(port, Arc::new(RecordingRateLimiter::default()));
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(1),
+        gateway.fetch_historical_ticks("NQ", date()),
+    )
+    .await
+    .expect("the request must fail rather than wait for its timeout");
+
+    assert!(
+        matches!(&result, Err(HistoricalDataError::GatewayError(msg)) if msg.contains("1100")),
+        "{:?}",
+        result
+    );
+}
+
+#[tokio::test]
+async fn no_data_reply_is_data_not_available() {
+    let (port, _) = fake_tws(HashMap::from([("TRADES", NO_DATA)])).await;
+    let gateway = gateway(port, Arc::new(RecordingRateLimiter::default()));
+
+    let result = gateway.fetch_historical_ticks("NQ", date()).await;
+
+    assert!(
+        matches!(result, Err(HistoricalDataError::DataNotAvailable(day)) if day == date()),
+        "{:?}",
+        result
+    );
+}
+
+#[tokio::test]
+async fn the_connection_is_reused_across_fetches() {
+    let (port, requests) =
+        fake_tws(HashMap::from([("TRADES", TRADES), ("BID_ASK", BID_ASK)])).await;
+    let gateway = gateway(port, Arc::new(RecordingRateLimiter::default()));
+
+    // The stand-in accepts a single connection, so a reconnect would fail.
+    for _ in 0..2 {
+        gateway.fetch_historical_ticks("NQ", date()).await.unwrap();
+    }
+
+    let ids: Vec<String> = requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|request| request[1].clone())
+        .collect();
+    assert_eq!(ids, vec!["1", "2", "3", "4"]);
+}
@@ -1,10 +1,14 @@
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 use ingestion_application::job_state::{
-    JobInstanceId, JobState, JobStateError, JobStateRepository, JobStatus,
+    JobAuditEvent, JobInstanceId, JobState, JobStateError, JobStateRepository, JobStatus,
 };
 use ingestion_infrastructure::rate_limiting::redis::RedisConnectionManager;
+use ingestion_infrastructure::state::redis::{
+    RedisJobStateRepositoryParameters, JOB_AUDIT_KEY_PREFIX,
+};
 use ingestion_infrastructure::state::RedisJobStateRepository;
 use shaku::{module, HasComponent};
+use std::collections::HashMap;
 use std::env;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -43,6 +47,128 @@ async fn upsert_and_fetch_job_state() {
     assert!(fetched.last_error_type.is_none());
 }
 
+#[tokio::test]
+async fn cancelled_status_round_trips() {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/2".to_string());
+    env::set_var("REDIS_URL", &redis_url);
+    let module = TestModule::builder().build();
+
+    let repo: Arc<dyn JobStateRepository> = module.resolve();
+    let job_key = "ingest:job:YM:2024-01-01".to_string();
+    delete_key(&redis_url, &job_key).await;
+
+    let mut state = sample_state();
+    state.status = JobStatus::Cancelled;
+    repo.upsert(&job_key, &state).await.expect("upsert");
+    let fetched = repo.get(&job_key).await.expect("get").expect("state");
+    assert_eq!(fetched.status, JobStatus::Cancelled);
+    assert_eq!(
+        hash_fields(&redis_url, &job_key).await["status"],
+        "CANCELLED"
+    );
+
+    // States written before the per-field layout live in one JSON blob.
+    delete_key(&redis_url, &job_key).await;
+    let client = redis::Client::open(redis_url.as_str()).expect("open redis client");
+    let mut conn = client
+        .get_multiplexed_async_connection()
+        .await
+        .expect("connect redis");
+    let _: () = redis::cmd("HSET")
+        .arg(&job_key)
+        .arg("state")
+        .arg(serde_json::to_string(&state).unwrap())
+        .query_async(&mut conn)
+        .await
+        .expect("write legacy state");
+    let legacy = repo.get(&job_key).await.expect("get").expect("state");
+    assert_eq!(legacy.status, JobStatus::Cancelled);
+}
+
+#[tokio::test]
+async fn lifetime_totals_round_trip_and_accumulate() {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/2".to_string());
+    env::set_var("REDIS_URL", &redis_url);
+    let module = TestModule::builder().build();
+
+    let repo: Arc<dyn JobStateRepository> = module.resolve();
+    let job_key = "ingest:job:RTY:2024-01-01".to_string();
+    delete_key(&redis_url, &job_key).await;
+    let date = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+
+    let fresh = sample_state();
+    repo.upsert(&job_key, &fresh).await.expect("upsert");
+    let fetched = repo.get(&job_key).await.expect("get").expect("state");
+    assert_eq!(fetched.total_ticks, 0);
+    assert_eq!(fetched.last_processed_date, None);
+
+    // First run: two days.
+    repo.update_progress(&job_key, &fresh.job_instance_id, 120, date(2))
+        .await
+        .expect("first run progress");
+    // Second run under a new instance carries the totals forward.
+    let mut resumed = repo.get(&job_key).await.expect("get").expect("state");
+    resumed.job_instance_id = Uuid::new_v4().to_string();
+    repo.upsert(&job_key, &resumed)
+        .await
+        .expect("upsert resumed");
+    repo.update_progress(&job_key, &resumed.job_instance_id, 200, date(3))
+        .await
+        .expect("second run progress");
+
+    let fetched = repo.get(&job_key).await.expect("get").expect("state");
+    assert_eq!(fetched.total_ticks, 200);
+    assert_eq!(fetched.last_processed_date, Some(date(3)));
+    assert!(matches!(
+        repo.update_progress(&job_key, &fresh.job_instance_id, 1, date(4))
+            .await,
+        Err(JobStateError::StaleInstance(_))
+    ));
+}
+
+#[tokio::test]
+async fn list_jobs_returns_every_matching_job() {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/2".to_string());
+    env::set_var("REDIS_URL", &redis_url);
+    let module = TestModule::builder().build();
+
+    let repo: Arc<dyn JobStateRepository> = module.resolve();
+    let symbol = format!("L{}", &Uuid::new_v4().simple().to_string()[..8]);
+    let jobs = [
+        ("2024-01-01", JobStatus::Running),
+        ("2024-02-01", JobStatus::Completed),
+        ("2024-03-01", JobStatus::Failed),
+    ];
+    for (start, status) in &jobs {
+        let job_key = format!("ingest:job:{}:{}", symbol, start);
+        delete_key(&redis_url, &job_key).await;
+        let mut state = sample_state();
+        state.status = status.clone();
+        repo.upsert(&job_key, &state).await.expect("upsert");
+    }
+
+    let listed: HashMap<String, JobStatus> = repo
+        .list_jobs("ingest:job:*")
+        .await
+        .expect("list jobs")
+        .into_iter()
+        .map(|(key, state)| (key, state.status))
+        .collect();
+    for (start, status) in &jobs {
+        let job_key = format!("ingest:job:{}:{}", symbol, start);
+        assert_eq!(listed.get(&job_key), Some(status), "{}", job_key);
+    }
+
+    let narrowed = repo
+        .list_jobs(&format!("ingest:job:{}:2024-01-*", symbol))
+        .await
+        .expect("list jobs");
+    assert_eq!(narrowed.len(), 1);
+}
+
 #[tokio::test]
 async fn update_cursor_enforces_instance_id() {
     let redis_url =
@@ -133,3 +259,256 @@ async fn delete_key(redis_url: &str, job_key: &str) {
 fn stale_instance() -> JobInstanceId {
     format!("stale-{}", Uuid::new_v4())
 }
+
+#[tokio::test]
+async fn heartbeat_only_touches_heartbeat_field() {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/2".to_string());
+    env::set_var("REDIS_URL", &redis_url);
+    let module = TestModule::builder().build();
+
+    let repo: Arc<dyn JobStateRepository> = module.resolve();
+    let job_key = "ingest:job:RTY:2024-04-01".to_string();
+    delete_key(&redis_url, &job_key).await;
+
+    let mut state = sample_state();
+    state.cursor = 777;
+    state.last_error_type = Some("RateLimited".to_string());
+    repo.upsert(&job_key, &state).await.expect("upsert");
+    let before = hash_fields(&redis_url, &job_key).await;
+
+    let heartbeat_at = state.heartbeat_at + chrono::Duration::seconds(30);
+    repo.heartbeat(&job_key, &state.job_instance_id, heartbeat_at)
+        .await
+        .expect("heartbeat");
+    let after = hash_fields(&redis_url, &job_key).await;
+
+    let changed: Vec<&String> = after
+        .iter()
+        .filter(|(field, value)| before.get(*field) != Some(value))
+        .map(|(field, _)| field)
+        .collect();
+    assert_eq!(changed, vec!["heartbeat_at"]);
+    assert_eq!(
+        after["heartbeat_at"],
+        heartbeat_at.timestamp_millis().to_string()
+    );
+
+    let fetched = repo.get(&job_key).await.unwrap().unwrap();
+    assert_eq!(fetched.cursor, 777);
+    assert_eq!(fetched.last_error_type.as_deref(), Some("RateLimited"));
+    assert_eq!(
+        fetched.heartbeat_at.timestamp_millis(),
+        heartbeat_at.timestamp_millis()
+    );
+
+    let stale_error = repo
+        .heartbeat(&job_key, &stale_instance(), heartbeat_at)
+        .await
+        .expect_err("stale instance must fail");
+    assert!(matches!(stale_error, JobStateError::StaleInstance(_)));
+}
+
+async fn hash_fields(redis_url: &str, job_key: &str) -> HashMap<String, String> {
+    let client = redis::Client::open(redis_url).expect("open redis client");
+    let mut conn = client
+        .get_multiplexed_async_connection()
+        .await
+        .expect("connect redis");
+    redis::cmd("HGETALL")
+        .arg(job_key)
+        .query_async(&mut conn)
+        .await
+        .expect("read hash")
+}
+
+#[tokio::test]
+async fn audit_trail_retains_every_error_in_order() {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/2".to_string());
+    env::set_var("REDIS_URL", &redis_url);
+    let module = TestModule::builder().build();
+
+    let repo: Arc<dyn JobStateRepository> = module.resolve();
+    let job_key = "ingest:job:YM:2024-05-01".to_string();
+    delete_key(&redis_url, &job_key).await;
+    delete_key(
+        &redis_url,
+        &format!("{}YM:2024-05-01", JOB_AUDIT_KEY_PREFIX),
+    )
+    .await;
+
+    let state = sample_state();
+    let instance = state.job_instance_id.clone();
+    repo.upsert(&job_key, &state).await.expect("upsert");
+    repo.save_error(&job_key, &instance, "2024-05-02: API rate limit exceeded")
+        .await
+        .unwrap();
+    repo.update_status(&job_key, &instance, JobStatus::Failed)
+        .await
+        .unwrap();
+    repo.upsert(&job_key, &state).await.expect("retry");
+    repo.save_error(&job_key, &instance, "2024-05-06: IO error: disk full")
+        .await
+        .unwrap();
+
+    let events: Vec<JobAuditEvent> = repo
+        .audit_trail(&job_key)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.event)
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            JobAuditEvent::Status(JobStatus::Running),
+            JobAuditEvent::Error("2024-05-02: API rate limit exceeded".to_string()),
+            JobAuditEvent::Status(JobStatus::Failed),
+            JobAuditEvent::Status(JobStatus::Running),
+            JobAuditEvent::Error("2024-05-06: IO error: disk full".to_string()),
+        ]
+    );
+    // Job scans must not trip over the audit list.
+    assert!(repo
+        .find_by_status(JobStatus::Running)
+        .await
+        .unwrap()
+        .iter()
+        .any(|(key, _)| key == &job_key));
+}
+
+#[tokio::test]
+async fn audit_trail_keeps_only_the_newest_entries() {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/2".to_string());
+    env::set_var("REDIS_URL", &redis_url);
+    let module = TestModule::builder()
+        .with_component_parameters::<RedisJobStateRepository>(RedisJobStateRepositoryParameters {
+            audit_trail_len: 3,
+            terminal_ttl_secs: None,
+            redis_db: None,
+        })
+        .build();
+
+    let repo: Arc<dyn JobStateRepository> = module.resolve();
+    let job_key = "ingest:job:ZN:2024-06-03".to_string();
+    delete_key(&redis_url, &job_key).await;
+    delete_key(
+        &redis_url,
+        &format!("{}ZN:2024-06-03", JOB_AUDIT_KEY_PREFIX),
+    )
+    .await;
+
+    let state = sample_state();
+    repo.upsert(&job_key, &state).await.expect("upsert");
+    for attempt in 1..=5 {
+        repo.save_error(
+            &job_key,
+            &state.job_instance_id,
+            &format!("attempt {}", attempt),
+        )
+        .await
+        .unwrap();
+    }
+
+    let events: Vec<JobAuditEvent> = repo
+        .audit_trail(&job_key)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.event)
+        .collect();
+    assert_eq!(
+        events,
+        ["attempt 3", "attempt 4", "attempt 5"]
+            .map(|message| JobAuditEvent::Error(message.to_string()))
+            .to_vec()
+    );
+}
+
+#[tokio::test]
+async fn delete_removes_the_job_and_ignores_missing_keys() {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/2".to_string());
+    env::set_var("REDIS_URL", &redis_url);
+    let module = TestModule::builder().build();
+
+    let repo: Arc<dyn JobStateRepository> = module.resolve();
+    let job_key = "ingest:job:HG:2024-07-01".to_string();
+    let state = sample_state();
+    repo.upsert(&job_key, &state).await.expect("upsert");
+
+    repo.delete(&job_key).await.expect("delete");
+    assert!(repo.get(&job_key).await.unwrap().is_none());
+    assert!(repo.audit_trail(&job_key).await.unwrap().is_empty());
+
+    repo.delete(&job_key).await.expect("deleting a missing key");
+}
+
+#[tokio::test]
+async fn terminal_jobs_expire_after_the_configured_ttl() {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/2".to_string());
+    env::set_var("REDIS_URL", &redis_url);
+    let module = TestModule::builder()
+        .with_component_parameters::<RedisJobStateRepository>(RedisJobStateRepositoryParameters {
+            audit_trail_len: 3,
+            terminal_ttl_secs: Some(1),
+            redis_db: None,
+        })
+        .build();
+
+    let repo: Arc<dyn JobStateRepository> = module.resolve();
+    let job_key = "ingest:job:SI:2024-07-01".to_string();
+    repo.delete(&job_key).await.unwrap();
+    let state = sample_state();
+    repo.upsert(&job_key, &state).await.expect("upsert");
+    repo.update_status(&job_key, &state.job_instance_id, JobStatus::Completed)
+        .await
+        .expect("finalize");
+    // A heartbeat after finalizing must not clear the expiry.
+    repo.heartbeat(&job_key, &state.job_instance_id, Utc::now())
+        .await
+        .unwrap();
+    assert!(repo.get(&job_key).await.unwrap().is_some());
+
+    tokio::time::sleep(std::time::Duration::from_millis(2100)).await;
+
+    assert!(repo.get(&job_key).await.unwrap().is_none());
+    assert!(repo.audit_trail(&job_key).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn cancelled_jobs_are_kept_for_resuming() {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/2".to_string());
+    env::set_var("REDIS_URL", &redis_url);
+    let module = TestModule::builder()
+        .with_component_parameters::<RedisJobStateRepository>(RedisJobStateRepositoryParameters {
+            audit_trail_len: 3,
+            terminal_ttl_secs: Some(1),
+            redis_db: None,
+        })
+        .build();
+
+    let repo: Arc<dyn JobStateRepository> = module.resolve();
+    let job_key = "ingest:job:HG:2024-07-01".to_string();
+    repo.delete(&job_key).await.unwrap();
+    let state = sample_state();
+    repo.upsert(&job_key, &state).await.expect("upsert");
+    repo.update_status(&job_key, &state.job_instance_id, JobStatus::Cancelled)
+        .await
+        .expect("cancel");
+
+    tokio::time::sleep(std::time::Duration::from_millis(2100)).await;
+
+    let kept = repo
+        .get(&job_key)
+        .await
+        .unwrap()
+        .expect("cancelled job kept");
+    assert_eq!(kept.status, JobStatus::Cancelled);
+    assert_eq!(kept.cursor, state.cursor);
+    repo.delete(&job_key).await.unwrap();
+}
@@ -0,0 +1,164 @@
+use chrono::{DateTime, TimeZone, Utc};
+use futures::StreamExt;
+use ingestion_application::ports::{GatewayError, MarketDataGateway};
+use ingestion_application::services::{IngestionService, IngestionServiceImpl};
+use ingestion_application::TickRepository;
+use ingestion_domain::test_support::sample_tick;
+use ingestion_domain::Tick;
+use ingestion_infrastructure::repositories::FileRotation;
+use ingestion_infrastructure::{
+    ParquetReplayGateway, ParquetTickReader, ParquetTickRepository, StdFileSystem,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}-{}", name, uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn at(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 2, hour, minute, second)
+        .unwrap()
+}
+
+async fn write_ticks(dir: &Path, ticks: Vec<Tick>) {
+    let repository = ParquetTickRepository::new(dir.to_path_buf(), Arc::new(StdFileSystem));
+    repository.save_batch(ticks).await.unwrap();
+    repository.shutdown().await.unwrap();
+}
+
+async fn replay(gateway: &ParquetReplayGateway, symbol: &str) -> Vec<Tick> {
+    gateway
+        .subscribe(symbol)
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+        .await
+}
+
+#[tokio::test]
+async fn replays_written_ticks_in_timestamp_order() {
+    let dir = temp_dir("parquet-replay");
+    // Two hourly files for NQ, saved out of order, and one for ES.
+    let nq = vec![
+        sample_tick("NQ", at(10, 0, 0)),
+        sample_tick("NQ", at(10, 30, 0)),
+        sample_tick("NQ", at(11, 0, 0)),
+        sample_tick("NQ", at(11, 15, 0)),
+    ];
+    write_ticks(&dir, vec![nq[2].clone(), nq[3].clone()]).await;
+    write_ticks(&dir, vec![nq[0].clone(), nq[1].clone()]).await;
+    write_ticks(&dir, vec![sample_tick("ES", at(10, 45, 0))]).await;
+    let gateway = ParquetReplayGateway::new(dir.clone(), None);
+
+    assert_eq!(replay(&gateway, "NQ").await, nq);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn live_files_win_the_hours_they_cover_over_the_daily_file() {
+    let dir = temp_dir("parquet-replay-overlap");
+    let backfilled = vec![
+        sample_tick("NQ", at(9, 0, 0)),
+        sample_tick("NQ", at(10, 0, 0)),
+        sample_tick("NQ", at(10, 30, 0)),
+        sample_tick("NQ", at(11, 15, 0)),
+    ];
+    let daily = ParquetTickRepository::new(dir.clone(), Arc::new(StdFileSystem))
+        .with_rotation(FileRotation::Daily);
+    daily.save_batch(backfilled.clone()).await.unwrap();
+    daily.shutdown().await.unwrap();
+    // Hour 10 was also captured live, without the 10:30 tick.
+    let live = vec![
+        sample_tick("NQ", at(10, 0, 0)),
+        sample_tick("NQ", at(10, 45, 0)),
+    ];
+    write_ticks(&dir, live.clone()).await;
+    let gateway = ParquetReplayGateway::new(dir.clone(), None);
+
+    assert_eq!(
+        replay(&gateway, "NQ").await,
+        vec![
+            backfilled[0].clone(),
+            live[0].clone(),
+            live[1].clone(),
+            backfilled[3].clone(),
+        ]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn ingestion_stores_replayed_ticks_unchanged() {
+    let source = temp_dir("parquet-replay-source");
+    let target = temp_dir("parquet-replay-target");
+    let ticks: Vec<Tick> = (0..4)
+        .map(|quarter| sample_tick("NQ", at(10, quarter * 15, 0)))
+        .collect();
+    write_ticks(&source, ticks.clone()).await;
+    let service = IngestionServiceImpl::new(
+        Arc::new(ParquetReplayGateway::new(source.clone(), None)),
+        Arc::new(ParquetTickRepository::new(
+            target.clone(),
+            Arc::new(StdFileSystem),
+        )),
+        2,
+        Duration::from_secs(3600),
+    );
+
+    service.run("NQ").await.unwrap();
+
+    assert_eq!(
+        ParquetTickReader::new(target.clone())
+            .read_symbol("NQ")
+            .unwrap(),
+        // Unchanged but for the arrival sequence ingestion assigns.
+        ticks
+            .into_iter()
+            .zip(0..)
+            .map(|(tick, sequence)| tick.with_sequence(sequence))
+            .collect::<Vec<_>>()
+    );
+    std::fs::remove_dir_all(&source).unwrap();
+    std::fs::remove_dir_all(&target).unwrap();
+}
+
+#[tokio::test]
+async fn replay_speed_scales_the_recorded_spacing() {
+    let dir = temp_dir("parquet-replay-speed");
+    let ticks = vec![
+        sample_tick("NQ", at(10, 0, 0)),
+        sample_tick("NQ", at(10, 0, 1)),
+        sample_tick("NQ", at(10, 0, 2)),
+    ];
+    write_ticks(&dir, ticks.clone()).await;
+    // Two seconds of data at ten times the recorded pace.
+    let gateway = ParquetReplayGateway::new(dir.clone(), Some(10.0));
+
+    let started = Instant::now();
+    let replayed = replay(&gateway, "NQ").await;
+
+    assert_eq!(replayed, ticks);
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn unreadable_files_are_stream_errors() {
+    let dir = temp_dir("parquet-replay-corrupt");
+    std::fs::write(dir.join("NQ_20250102_10.parquet"), b"not parquet").unwrap();
+    let gateway = ParquetReplayGateway::new(dir.clone(), None);
+
+    let result = gateway.subscribe("NQ").await;
+
+    assert!(
+        matches!(result, Err(GatewayError::StreamError(_))),
+        "{:?}",
+        result.err()
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,4 +1,5 @@
-use ingestion_application::rate_limiter::RateLimiter;
+use ingestion_application::rate_limiter::{RateLimitScope, RateLimiter, RateLimiterError};
+use ingestion_infrastructure::rate_limiting::algorithm::RateLimitAlgorithm;
 use ingestion_infrastructure::rate_limiting::limiter::{
     IbRateLimiter, IbRateLimiterConfig, IbRateLimiterParameters, RateLimitWindow,
 };
@@ -40,14 +41,6 @@ async fn setup_test_module(config: IbRateLimiterConfig) -> TestModule {
     module
 }
 
-fn windows(config: &IbRateLimiterConfig) -> [&RateLimitWindow; 3] {
-    [
-        &config.ten_minute_window,
-        &config.contract_window,
-        &config.duplicate_request_window,
-    ]
-}
-
 async fn clear_rate_limit_keys(
     redis_connection: &Arc<dyn RedisConnection>,
     config: &IbRateLimiterConfig,
@@ -58,11 +51,19 @@ async fn clear_rate_limit_keys(
         .expect("failed to acquire Redis connection");
 
     let mut del_cmd = redis::cmd("DEL");
-    for window in windows(config) {
+    for (_, window) in config.windows() {
         del_cmd.arg(format!(
             "rate_limit:ib:historical:{}:{}s",
             config.account_id, window.duration_secs
         ));
+        for scope in [nq(), es()] {
+            del_cmd.arg(format!(
+                "rate_limit:ib:historical:{}:{}:{}s",
+                config.account_id,
+                scope.contract_key(),
+                window.duration_secs
+            ));
+        }
     }
 
     let _: () = del_cmd
@@ -71,12 +72,22 @@ async fn clear_rate_limit_keys(
         .expect("failed to delete rate limiter keys");
 }
 
+fn nq() -> RateLimitScope {
+    RateLimitScope::contract("NQ")
+}
+
+fn es() -> RateLimitScope {
+    RateLimitScope::contract("ES")
+}
+
 fn test_config(account_id: String) -> IbRateLimiterConfig {
     IbRateLimiterConfig {
         account_id,
         ten_minute_window: RateLimitWindow::new(20, 10),
         contract_window: RateLimitWindow::new(3, 2),
         duplicate_request_window: RateLimitWindow::new(2, 1),
+        algorithm: RateLimitAlgorithm::SlidingWindowLog,
+        redis_db: None,
     }
 }
 
@@ -88,7 +99,7 @@ async fn test_rate_limiter_allows_requests_within_limit() {
     let limiter: Arc<dyn RateLimiter> = module.resolve();
 
     let start = Instant::now();
-    limiter.acquire().await.unwrap();
+    limiter.acquire(&nq()).await.unwrap();
     let duration = start.elapsed();
     assert!(
         duration < Duration::from_millis(100),
@@ -97,7 +108,7 @@ async fn test_rate_limiter_allows_requests_within_limit() {
     );
 
     let start = Instant::now();
-    limiter.acquire().await.unwrap();
+    limiter.acquire(&nq()).await.unwrap();
     let duration = start.elapsed();
     assert!(
         duration < Duration::from_millis(100),
@@ -117,9 +128,9 @@ async fn test_rate_limiter_blocks_requests_exceeding_short_limit() {
     let limiter: Arc<dyn RateLimiter> = module.resolve();
 
     let start = Instant::now();
-    limiter.acquire().await.unwrap();
-    limiter.acquire().await.unwrap();
-    limiter.acquire().await.unwrap();
+    limiter.acquire(&nq()).await.unwrap();
+    limiter.acquire(&nq()).await.unwrap();
+    limiter.acquire(&nq()).await.unwrap();
     let duration = start.elapsed();
 
     assert!(
@@ -145,13 +156,13 @@ async fn test_rate_limiter_resets_after_window() {
     let module = setup_test_module(config).await;
     let limiter: Arc<dyn RateLimiter> = module.resolve();
 
-    limiter.acquire().await.unwrap();
-    limiter.acquire().await.unwrap();
+    limiter.acquire(&nq()).await.unwrap();
+    limiter.acquire(&nq()).await.unwrap();
 
     sleep(Duration::from_millis(1_100)).await;
 
     let start = Instant::now();
-    limiter.acquire().await.unwrap();
+    limiter.acquire(&nq()).await.unwrap();
     let duration = start.elapsed();
 
     assert!(
@@ -174,11 +185,11 @@ async fn test_rate_limiter_respects_multiple_windows() {
     let limiter: Arc<dyn RateLimiter> = module.resolve();
 
     for _ in 0..3 {
-        limiter.acquire().await.unwrap();
+        limiter.acquire(&nq()).await.unwrap();
     }
 
     let start = Instant::now();
-    limiter.acquire().await.unwrap();
+    limiter.acquire(&nq()).await.unwrap();
     let duration = start.elapsed();
     assert!(
         duration >= Duration::from_secs(3),
@@ -191,3 +202,167 @@ async fn test_rate_limiter_respects_multiple_windows() {
         duration
     );
 }
+
+#[tokio::test]
+async fn test_rate_limiter_reports_wait_for_exhausted_window() {
+    let account_id = format!("test-estimate-{}", Uuid::new_v4());
+    let config = IbRateLimiterConfig {
+        ten_minute_window: RateLimitWindow::new(100, 60),
+        contract_window: RateLimitWindow::new(3, 2),
+        duplicate_request_window: RateLimitWindow::new(100, 1),
+        ..test_config(account_id)
+    };
+    let module = setup_test_module(config).await;
+    let limiter: Arc<dyn RateLimiter> = module.resolve();
+
+    assert_eq!(limiter.estimated_wait(&nq()).await.unwrap(), Duration::ZERO);
+
+    for _ in 0..3 {
+        limiter.acquire(&nq()).await.unwrap();
+    }
+
+    let estimate = limiter.estimated_wait(&nq()).await.unwrap();
+    assert!(
+        estimate > Duration::from_millis(1_500) && estimate <= Duration::from_secs(2),
+        "Estimate should be close to the 2 second window, but was {:?}",
+        estimate
+    );
+
+    let waited = limiter.acquire_with_estimate(&nq()).await.unwrap();
+    assert!(
+        waited >= Duration::from_millis(1_500) && waited < Duration::from_millis(2_600),
+        "Reported wait should be close to the 2 second window, but was {:?}",
+        waited
+    );
+}
+
+#[tokio::test]
+async fn test_alternative_algorithms_refuse_once_exhausted() {
+    for algorithm in [
+        RateLimitAlgorithm::FixedWindowCounter,
+        RateLimitAlgorithm::TokenBucket,
+    ] {
+        let account_id = format!("test-{}-{}", algorithm, Uuid::new_v4());
+        let config = IbRateLimiterConfig {
+            ten_minute_window: RateLimitWindow::new(100, 60),
+            contract_window: RateLimitWindow::new(3, 10),
+            duplicate_request_window: RateLimitWindow::new(100, 10),
+            algorithm,
+            ..test_config(account_id)
+        };
+        let module = setup_test_module(config).await;
+        let limiter: Arc<dyn RateLimiter> = module.resolve();
+
+        for _ in 0..3 {
+            assert!(
+                limiter.try_acquire(&nq()).await.unwrap(),
+                "{algorithm} denied early"
+            );
+        }
+        assert!(
+            !limiter.try_acquire(&nq()).await.unwrap(),
+            "{algorithm} admitted past the limit"
+        );
+        assert_eq!(limiter.estimated_wait(&nq()).await.unwrap(), Duration::ZERO);
+    }
+}
+
+#[tokio::test]
+async fn different_contracts_do_not_share_the_contract_window() {
+    let account_id = format!("test-contracts-{}", Uuid::new_v4());
+    let config = IbRateLimiterConfig {
+        ten_minute_window: RateLimitWindow::new(100, 60),
+        contract_window: RateLimitWindow::new(2, 2),
+        duplicate_request_window: RateLimitWindow::new(100, 1),
+        ..test_config(account_id)
+    };
+    let module = setup_test_module(config).await;
+    let limiter: Arc<dyn RateLimiter> = module.resolve();
+
+    assert!(limiter.try_acquire(&nq()).await.unwrap());
+    assert!(limiter.try_acquire(&nq()).await.unwrap());
+    assert!(
+        !limiter.try_acquire(&nq()).await.unwrap(),
+        "NQ admitted past its contract window"
+    );
+
+    for _ in 0..2 {
+        assert!(
+            limiter.try_acquire(&es()).await.unwrap(),
+            "ES was held back by NQ's contract window"
+        );
+    }
+    assert!(!limiter.try_acquire(&es()).await.unwrap());
+}
+
+#[tokio::test]
+async fn contracts_share_the_account_window() {
+    let account_id = format!("test-account-{}", Uuid::new_v4());
+    let config = IbRateLimiterConfig {
+        ten_minute_window: RateLimitWindow::new(3, 60),
+        contract_window: RateLimitWindow::new(100, 2),
+        duplicate_request_window: RateLimitWindow::new(100, 1),
+        ..test_config(account_id)
+    };
+    let module = setup_test_module(config).await;
+    let limiter: Arc<dyn RateLimiter> = module.resolve();
+
+    assert!(limiter.try_acquire(&nq()).await.unwrap());
+    assert!(limiter.try_acquire(&nq()).await.unwrap());
+    assert!(limiter.try_acquire(&es()).await.unwrap());
+    assert!(!limiter.try_acquire(&es()).await.unwrap());
+}
+
+#[tokio::test]
+async fn try_acquire_refuses_a_saturated_window_without_waiting() {
+    let account_id = format!("test-try-{}", Uuid::new_v4());
+    let config = IbRateLimiterConfig {
+        duplicate_request_window: RateLimitWindow::new(2, 1),
+        ..test_config(account_id)
+    };
+    let module = setup_test_module(config).await;
+    let limiter: Arc<dyn RateLimiter> = module.resolve();
+
+    limiter.acquire(&nq()).await.unwrap();
+    limiter.acquire(&nq()).await.unwrap();
+
+    let start = Instant::now();
+    let admitted = limiter.try_acquire(&nq()).await.unwrap();
+    let duration = start.elapsed();
+
+    assert!(!admitted, "try_acquire admitted past the 1 second window");
+    assert!(
+        duration < Duration::from_millis(100),
+        "try_acquire should not wait out the window, but took {:?}",
+        duration
+    );
+}
+
+#[tokio::test]
+async fn acquire_with_timeout_gives_up_on_a_saturated_window() {
+    let account_id = format!("test-timeout-{}", Uuid::new_v4());
+    let config = IbRateLimiterConfig {
+        duplicate_request_window: RateLimitWindow::new(1, 10),
+        ..test_config(account_id)
+    };
+    let module = setup_test_module(config).await;
+    let limiter: Arc<dyn RateLimiter> = module.resolve();
+
+    limiter.acquire(&nq()).await.unwrap();
+
+    let start = Instant::now();
+    let result = limiter
+        .acquire_with_timeout(&nq(), Duration::from_millis(100))
+        .await;
+
+    assert!(
+        matches!(result, Err(RateLimiterError::Timeout(timeout)) if timeout == Duration::from_millis(100)),
+        "expected a timeout, got {:?}",
+        result
+    );
+    assert!(
+        start.elapsed() < Duration::from_secs(1),
+        "timeout took {:?}",
+        start.elapsed()
+    );
+}
@@ -1,11 +1,13 @@
 use redis::aio::MultiplexedConnection;
 use redis::Script;
 use std::env;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use uuid::Uuid;
 
 const LUA_SOURCE: &str = include_str!("../src/rate_limiting/limiter.lua");
+const ESTIMATE_SOURCE: &str = include_str!("../src/rate_limiting/estimate.lua");
+const TOKEN_BUCKET_SOURCE: &str = include_str!("../src/rate_limiting/token_bucket.lua");
 
 #[tokio::test]
 async fn lua_script_blocks_requests_within_short_window() {
@@ -18,12 +20,12 @@ async fn lua_script_blocks_requests_within_short_window() {
 
     clear_keys(&mut conn, &keys).await;
 
-    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await, 1);
-    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await, 0);
+    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await.0, 1);
+    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await.0, 0);
 
     // Wait for 1s window + 100ms buffer to ensure expiry.
     sleep(Duration::from_millis(1_100)).await;
-    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await, 1);
+    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await.0, 1);
 }
 
 #[tokio::test]
@@ -37,15 +39,82 @@ async fn lua_script_enforces_contract_window_limits() {
 
     clear_keys(&mut conn, &keys).await;
 
-    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await, 1);
-    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await, 1);
-    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await, 1);
+    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await.0, 1);
+    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await.0, 1);
+    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await.0, 1);
     // Fourth request should block because the 3-per-2s window is saturated.
-    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await, 0);
+    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await.0, 0);
 
     // Wait for 2s window + 200ms buffer.
     sleep(Duration::from_millis(2_200)).await;
-    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await, 1);
+    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await.0, 1);
+}
+
+#[tokio::test]
+async fn lua_script_reports_time_until_the_window_frees() {
+    let mut conn = redis_connection().await;
+    let script = Script::new(LUA_SOURCE);
+    // (limit, duration_secs): 100 req/60s, 3 req/2s, 10 req/1s
+    let windows = [(100, 60), (3, 2), (10, 1)];
+    let account_id = format!("test-lua-retry-{}", Uuid::new_v4());
+    let keys = window_keys(&account_id, &windows);
+
+    clear_keys(&mut conn, &keys).await;
+
+    let first = Instant::now();
+    for _ in 0..3 {
+        assert_eq!(invoke(&script, &keys, &windows, &mut conn).await, (1, 0));
+    }
+    sleep(Duration::from_millis(300)).await;
+
+    let (allowed, retry_after_ms) = invoke(&script, &keys, &windows, &mut conn).await;
+    // The first entry ages out of the 2s window 2s after it was admitted.
+    let remainder = 2_000 - first.elapsed().as_millis() as i64;
+    assert_eq!(allowed, 0);
+    assert!(
+        (retry_after_ms - remainder).abs() <= 50,
+        "retry_after {}ms, window remainder {}ms",
+        retry_after_ms,
+        remainder
+    );
+
+    sleep(Duration::from_millis(retry_after_ms as u64 + 20)).await;
+    assert_eq!(invoke(&script, &keys, &windows, &mut conn).await.0, 1);
+}
+
+#[tokio::test]
+async fn lua_script_denies_a_zero_limit_for_the_window_length() {
+    let mut conn = redis_connection().await;
+    let script = Script::new(LUA_SOURCE);
+    // (limit, duration_secs): 100 req/60s, 0 req/2s, 10 req/1s
+    let windows = [(100, 60), (0, 2), (10, 1)];
+    let account_id = format!("test-lua-zero-{}", Uuid::new_v4());
+    let keys = window_keys(&account_id, &windows);
+
+    clear_keys(&mut conn, &keys).await;
+
+    assert_eq!(
+        invoke(&script, &keys, &windows, &mut conn).await,
+        (0, 2_000)
+    );
+    assert_eq!(
+        estimate(&Script::new(ESTIMATE_SOURCE), &keys, &windows, &mut conn).await,
+        2_000
+    );
+
+    // Token-bucket windows are hashes, so they need keys of their own.
+    let bucket_keys = window_keys(&format!("{}-bucket", account_id), &windows);
+    clear_keys(&mut conn, &bucket_keys).await;
+    assert_eq!(
+        invoke(
+            &Script::new(TOKEN_BUCKET_SOURCE),
+            &bucket_keys,
+            &windows,
+            &mut conn
+        )
+        .await,
+        (0, 2_000)
+    );
 }
 
 async fn clear_keys(conn: &mut MultiplexedConnection, keys: &[String; 3]) {
@@ -75,6 +144,28 @@ async fn redis_connection() -> MultiplexedConnection {
 }
 
 /// Generates keys in the form `rate_limit:lua_test:{account_id}:{duration}s`.
+/// Runs estimate.lua, which takes the same keys and windows as limiter.lua
+/// without the request id.
+async fn estimate(
+    script: &Script,
+    keys: &[String; 3],
+    windows: &[(usize, u64); 3],
+    conn: &mut MultiplexedConnection,
+) -> i64 {
+    let mut invocation = script.prepare_invoke();
+    for key in keys {
+        invocation.key(key);
+    }
+    for (limit, duration) in windows {
+        invocation.arg(*limit);
+        invocation.arg(*duration);
+    }
+    invocation
+        .invoke_async(conn)
+        .await
+        .expect("estimate script invocation failed")
+}
+
 fn window_keys(account_id: &str, windows: &[(usize, u64); 3]) -> [String; 3] {
     std::array::from_fn(|idx| format!("rate_limit:lua_test:{}:{}s", account_id, windows[idx].1))
 }
@@ -84,7 +175,7 @@ async fn invoke(
     keys: &[String; 3],
     windows: &[(usize, u64); 3],
     conn: &mut MultiplexedConnection,
-) -> i32 {
+) -> (i32, i64) {
     let request_id = Uuid::new_v4().to_string();
     let mut invocation = script.prepare_invoke();
 
@@ -97,8 +188,9 @@ async fn invoke(
     }
     invocation.arg(&request_id);
 
-    invocation
+    let (allowed, retry_after_ms): (i32, i64) = invocation
         .invoke_async(conn)
         .await
-        .expect("lua script invocation failed")
+        .expect("lua script invocation failed");
+    (allowed, retry_after_ms)
 }
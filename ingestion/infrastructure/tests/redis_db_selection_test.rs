@@ -0,0 +1,104 @@
+use chrono::Utc;
+use ingestion_application::job_state::{JobState, JobStateRepository, JobStatus};
+use ingestion_application::rate_limiter::{RateLimitScope, RateLimiter};
+use ingestion_infrastructure::rate_limiting::algorithm::RateLimitAlgorithm;
+use ingestion_infrastructure::rate_limiting::limiter::{
+    IbRateLimiter, IbRateLimiterConfig, IbRateLimiterParameters, RateLimitWindow,
+};
+use ingestion_infrastructure::rate_limiting::redis::RedisConnectionManager;
+use ingestion_infrastructure::state::redis::RedisJobStateRepositoryParameters;
+use ingestion_infrastructure::state::RedisJobStateRepository;
+use redis::aio::MultiplexedConnection;
+use shaku::{module, HasComponent};
+use std::env;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const RATE_LIMIT_DB: i64 = 3;
+const JOB_STATE_DB: i64 = 4;
+
+module! {
+    TestModule {
+        components = [
+            RedisConnectionManager,
+            IbRateLimiter,
+            RedisJobStateRepository,
+        ],
+        providers = []
+    }
+}
+
+#[tokio::test]
+async fn components_write_to_their_configured_databases() {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/0".to_string());
+    env::set_var("REDIS_URL", &redis_url);
+    let account_id = format!("test-db-{}", Uuid::new_v4());
+    let module = TestModule::builder()
+        .with_component_parameters::<IbRateLimiter>(IbRateLimiterParameters {
+            config: IbRateLimiterConfig {
+                account_id: account_id.clone(),
+                ten_minute_window: RateLimitWindow::new(10, 60),
+                contract_window: RateLimitWindow::new(10, 2),
+                duplicate_request_window: RateLimitWindow::new(10, 1),
+                algorithm: RateLimitAlgorithm::SlidingWindowLog,
+                redis_db: Some(RATE_LIMIT_DB),
+            },
+        })
+        .with_component_parameters::<RedisJobStateRepository>(RedisJobStateRepositoryParameters {
+            audit_trail_len: 3,
+            terminal_ttl_secs: None,
+            redis_db: Some(JOB_STATE_DB),
+        })
+        .build();
+
+    let limiter: Arc<dyn RateLimiter> = module.resolve();
+    let repo: Arc<dyn JobStateRepository> = module.resolve();
+    let job_key = format!("ingest:job:{}:2024-01-02", account_id);
+    let rate_limit_key = format!("rate_limit:ib:historical:{}:60s", account_id);
+
+    limiter
+        .acquire(&RateLimitScope::contract("NQ"))
+        .await
+        .unwrap();
+    let state = JobState::new(
+        Uuid::new_v4().to_string(),
+        JobStatus::Running,
+        0,
+        0,
+        Utc::now(),
+    );
+    repo.upsert(&job_key, &state).await.unwrap();
+
+    let mut rate_limit_db = connection(&redis_url, RATE_LIMIT_DB).await;
+    let mut job_state_db = connection(&redis_url, JOB_STATE_DB).await;
+    assert!(exists(&mut rate_limit_db, &rate_limit_key).await);
+    assert!(!exists(&mut rate_limit_db, &job_key).await);
+    assert!(exists(&mut job_state_db, &job_key).await);
+    assert!(!exists(&mut job_state_db, &rate_limit_key).await);
+    assert!(repo.get(&job_key).await.unwrap().is_some());
+
+    repo.delete(&job_key).await.unwrap();
+}
+
+async fn connection(redis_url: &str, db: i64) -> MultiplexedConnection {
+    let mut conn = redis::Client::open(redis_url)
+        .expect("failed to open redis client")
+        .get_multiplexed_async_connection()
+        .await
+        .expect("failed to connect to redis");
+    let _: () = redis::cmd("SELECT")
+        .arg(db)
+        .query_async(&mut conn)
+        .await
+        .expect("failed to select database");
+    conn
+}
+
+async fn exists(conn: &mut MultiplexedConnection, key: &str) -> bool {
+    redis::cmd("EXISTS")
+        .arg(key)
+        .query_async(conn)
+        .await
+        .expect("EXISTS failed")
+}
@@ -0,0 +1,45 @@
+use ingestion_infrastructure::rate_limiting::redis::RedisConnectionManager;
+use ingestion_infrastructure::scripts::{lua_scripts, preload_scripts};
+use ingestion_infrastructure::RedisConnection;
+use shaku::{module, HasComponent};
+use std::env;
+use std::sync::Arc;
+
+module! {
+    TestModule {
+        components = [RedisConnectionManager],
+        providers = []
+    }
+}
+
+fn setup() -> Arc<dyn RedisConnection> {
+    let redis_url =
+        env::var("REDIS_URL_TEST").unwrap_or_else(|_| "redis://127.0.0.1:6379/2".to_string());
+    env::set_var("REDIS_URL", &redis_url);
+    TestModule::builder().build().resolve()
+}
+
+#[tokio::test]
+async fn preloads_every_script_with_stable_shas() {
+    let redis = setup();
+
+    let first = preload_scripts(redis.as_ref()).await.unwrap();
+    let second = preload_scripts(redis.as_ref()).await.unwrap();
+
+    assert_eq!(first.len(), lua_scripts().len());
+    assert_eq!(first, second);
+    for (loaded, (name, script)) in first.iter().zip(lua_scripts()) {
+        assert_eq!(loaded.name, name);
+        assert_eq!(loaded.sha, script.get_hash());
+    }
+
+    let mut conn = redis.get_connection().await.unwrap();
+    let shas: Vec<&str> = first.iter().map(|loaded| loaded.sha.as_str()).collect();
+    let cached: Vec<bool> = redis::cmd("SCRIPT")
+        .arg("EXISTS")
+        .arg(&shas)
+        .query_async(&mut conn)
+        .await
+        .unwrap();
+    assert!(cached.iter().all(|exists| *exists));
+}
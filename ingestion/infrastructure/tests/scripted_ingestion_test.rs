@@ -0,0 +1,92 @@
+use chrono::{TimeZone, Utc};
+use ingestion_application::services::{IngestionService, IngestionServiceImpl};
+use ingestion_application::TickRepository;
+use ingestion_domain::test_support::sample_tick;
+use ingestion_domain::Tick;
+use ingestion_infrastructure::gateways::scripted::ScriptedMarketDataGateway;
+use ingestion_infrastructure::{ParquetTickReader, ParquetTickRepository, StdFileSystem};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn persists_scripted_ticks_in_order() {
+    let dir = std::env::temp_dir().join(format!("scripted-ingestion-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // Seven ticks over three hours, so the run rotates files and
+    // leaves a partial batch for the end-of-stream flush.
+    let script: Vec<Tick> = [
+        (10, 58),
+        (10, 59),
+        (11, 0),
+        (11, 1),
+        (11, 2),
+        (12, 0),
+        (12, 1),
+    ]
+    .into_iter()
+    .map(|(hour, minute)| {
+        sample_tick(
+            "NQ",
+            Utc.with_ymd_and_hms(2025, 1, 2, hour, minute, 0).unwrap(),
+        )
+    })
+    .collect();
+    let gateway = Arc::new(ScriptedMarketDataGateway::new(
+        script.clone(),
+        Duration::from_millis(1),
+    ));
+    let repository = Arc::new(ParquetTickRepository::new(
+        dir.clone(),
+        Arc::new(StdFileSystem),
+    ));
+    let service = IngestionServiceImpl::new(gateway, repository, 3, Duration::from_secs(3600));
+
+    service.run("NQ").await.unwrap();
+
+    let reader = ParquetTickReader::new(dir.clone());
+    assert_eq!(reader.symbol_files("NQ").unwrap().len(), 3);
+    // Ingestion numbers the ticks in arrival order.
+    let sequenced: Vec<Tick> = script
+        .into_iter()
+        .zip(0..)
+        .map(|(tick, sequence)| tick.with_sequence(sequence))
+        .collect();
+    assert_eq!(reader.read_symbol("NQ").unwrap(), sequenced);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn recv_latency_is_stored_for_live_ticks_only() {
+    let dir = std::env::temp_dir().join(format!("recv-latency-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let at = |hour| Utc.with_ymd_and_hms(2025, 1, 2, hour, 0, 0).unwrap();
+    let repository = Arc::new(
+        ParquetTickRepository::new(dir.clone(), Arc::new(StdFileSystem))
+            .with_recv_latency_column(true),
+    );
+
+    // Backfill writes hour 9 straight to the repository.
+    repository
+        .save_batch(vec![sample_tick("NQ", at(9))])
+        .await
+        .unwrap();
+    let gateway = Arc::new(ScriptedMarketDataGateway::new(
+        vec![sample_tick("NQ", at(10)), sample_tick("NQ", at(11))],
+        Duration::ZERO,
+    ));
+    let service =
+        IngestionServiceImpl::new(gateway, repository.clone(), 10, Duration::from_secs(3600))
+            .with_recv_latency(true);
+    service.run("NQ").await.unwrap();
+
+    let ticks = ParquetTickReader::new(dir.clone())
+        .read_symbol("NQ")
+        .unwrap();
+    let latencies: Vec<Option<i64>> = ticks.iter().map(Tick::recv_latency_ms).collect();
+    assert_eq!(latencies[0], None);
+    // Received "now" for ticks stamped in 2025: well over a day late.
+    assert!(latencies[1..]
+        .iter()
+        .all(|latency| latency.is_some_and(|ms| ms > 86_400_000)));
+    std::fs::remove_dir_all(&dir).unwrap();
+}